@@ -0,0 +1,59 @@
+//! Benchmarks for writing and parsing documents with many strings, where most of the time goes
+//! into escaping and unescaping text.
+//!
+//! Run with `cargo bench`.
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use serde_xmlrpc::{Document, EscapePolicy, Value, WriteOptions};
+
+const STRINGS: usize = 10_000;
+
+/// A response with an array of strings, all of which either need escaping or don't.
+fn strings(escapes: bool) -> Vec<Value> {
+    let text = if escapes {
+        "<item id=\"42\">Tom & Jerry's</item>"
+    } else {
+        "the quick brown fox jumps over the lazy dog"
+    };
+    vec![Value::Array(vec![Value::from(text); STRINGS])]
+}
+
+fn write(c: &mut Criterion) {
+    let mut group = c.benchmark_group("write");
+    group.throughput(Throughput::Elements(STRINGS as u64));
+
+    for &(name, escapes) in [("plain", false), ("escaped", true)].iter() {
+        let params = strings(escapes);
+        for &policy in [EscapePolicy::Full, EscapePolicy::Minimal].iter() {
+            let options = WriteOptions::default().escape(policy);
+            group.bench_function(format!("{}/{:?}", name, policy), |b| {
+                b.iter(|| {
+                    options
+                        .response_to_string(black_box(params.clone()).into_iter())
+                        .unwrap()
+                })
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Elements(STRINGS as u64));
+
+    for &(name, escapes) in [("plain", false), ("escaped", true)].iter() {
+        let body = serde_xmlrpc::response_to_string(strings(escapes).into_iter()).unwrap();
+        group.bench_function(name, |b| {
+            b.iter(|| serde_xmlrpc::response_from_str::<Vec<String>>(black_box(&body)).unwrap())
+        });
+        group.bench_function(format!("{}/value", name), |b| {
+            b.iter(|| black_box(&body).parse::<Document>().unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, write, parse);
+criterion_main!(benches);
@@ -0,0 +1,294 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{FnArg, Ident, ItemTrait, LitStr, Pat, Signature, Token, TraitItem};
+
+use crate::RenameRule;
+
+/// What to generate for an interface.
+struct Options {
+    client: bool,
+    server: bool,
+    namespace: Option<String>,
+    rename_all: Option<RenameRule>,
+}
+
+impl Options {
+    fn parse(args: TokenStream) -> syn::Result<Self> {
+        let mut options = Options {
+            client: false,
+            server: false,
+            namespace: None,
+            rename_all: None,
+        };
+
+        let parser = syn::meta::parser(|meta| {
+            if meta.path.is_ident("client") {
+                options.client = true;
+            } else if meta.path.is_ident("server") {
+                options.server = true;
+            } else if meta.path.is_ident("namespace") {
+                options.namespace = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("rename_all") {
+                options.rename_all = Some(RenameRule::parse(&meta.value()?.parse()?)?);
+            } else {
+                return Err(meta.error(
+                    "expected client, server, namespace = \"...\" or rename_all = \"...\"",
+                ));
+            }
+            Ok(())
+        });
+        syn::parse::Parser::parse2(parser, args)?;
+
+        if !options.client && !options.server {
+            options.client = true;
+            options.server = true;
+        }
+        Ok(options)
+    }
+
+    /// The name of the XML-RPC method for a trait method without an explicit name.
+    fn method_name(&self, ident: &Ident) -> String {
+        let ident = ident.to_string();
+        let ident = ident.trim_start_matches("r#");
+        let name = match self.rename_all {
+            Some(rule) => rule.apply(ident),
+            None => ident.to_string(),
+        };
+        match &self.namespace {
+            Some(namespace) => format!("{}.{}", namespace, name),
+            None => name,
+        }
+    }
+}
+
+/// A method of the interface.
+struct Method {
+    /// The signature with the arguments renamed to `arg0`, `arg1` and so on.
+    sig: Signature,
+    name: String,
+    args: Vec<Ident>,
+}
+
+pub(crate) fn expand(args: TokenStream, mut item: ItemTrait) -> syn::Result<TokenStream> {
+    let options = Options::parse(args)?;
+
+    let mut methods = Vec::new();
+    for trait_item in &mut item.items {
+        let method = match trait_item {
+            TraitItem::Fn(method) => method,
+            item => {
+                return Err(syn::Error::new_spanned(
+                    item,
+                    "xmlrpc_interface traits can only contain methods",
+                ))
+            }
+        };
+
+        let mut rename = None;
+        let mut error = None;
+        method.attrs.retain(|attr| {
+            if !attr.path().is_ident("xmlrpc") {
+                return true;
+            }
+            let parsed = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown xmlrpc method attribute"))
+                }
+            });
+            if let Err(err) = parsed {
+                error = Some(err);
+            }
+            false
+        });
+        if let Some(err) = error {
+            return Err(err);
+        }
+        let name = rename.unwrap_or_else(|| options.method_name(&method.sig.ident));
+
+        methods.push(parse_method(&method.sig, name)?);
+    }
+
+    if options.server {
+        server(&mut item, &methods);
+    }
+    let client = if options.client {
+        client(&item, &methods)
+    } else {
+        quote!()
+    };
+
+    Ok(quote! {
+        #item
+        #client
+    })
+}
+
+fn parse_method(sig: &Signature, name: String) -> syn::Result<Method> {
+    if !sig.generics.params.is_empty() || sig.asyncness.is_some() {
+        return Err(syn::Error::new_spanned(
+            sig,
+            "xmlrpc_interface methods can't be generic or async, use the generated async client \
+             for calling asynchronously",
+        ));
+    }
+
+    let takes_ref_self = match sig.inputs.first() {
+        Some(FnArg::Receiver(receiver)) => {
+            receiver.reference.is_some() && receiver.mutability.is_none()
+        }
+        _ => false,
+    };
+    if !takes_ref_self {
+        return Err(syn::Error::new_spanned(
+            sig,
+            "xmlrpc_interface methods must take &self",
+        ));
+    }
+
+    let mut sig = sig.clone();
+    let mut args = Vec::new();
+    for input in sig.inputs.iter_mut() {
+        if let FnArg::Typed(arg) = input {
+            let ident = format_ident!("arg{}", args.len());
+            *arg.pat = Pat::Verbatim(quote!(#ident));
+            args.push(ident);
+        }
+    }
+
+    Ok(Method { sig, name, args })
+}
+
+/// Adds `into_dispatcher` to the trait.
+fn server(item: &mut ItemTrait, methods: &[Method]) {
+    let registrations = methods.iter().map(|method| {
+        let ident = &method.sig.ident;
+        let name = &method.name;
+        let args = &method.args;
+        let types = method.sig.inputs.iter().filter_map(|input| match input {
+            FnArg::Typed(arg) => Some(&arg.ty),
+            FnArg::Receiver(_) => None,
+        });
+        quote! {
+            let service = ::std::sync::Arc::clone(&this);
+            dispatcher.register(#name, move |#(#args: #types),*| service.#ident(#(#args),*));
+        }
+    });
+
+    item.items.push(syn::parse_quote! {
+        /// Registers the methods of `self` with a new dispatcher.
+        fn into_dispatcher(self) -> ::serde_xmlrpc::server::Dispatcher
+        where
+            Self: ::std::marker::Sized + ::std::marker::Send + ::std::marker::Sync + 'static,
+        {
+            let this = ::std::sync::Arc::new(self);
+            let mut dispatcher = ::serde_xmlrpc::server::Dispatcher::new();
+            #(#registrations)*
+            dispatcher
+        }
+    });
+}
+
+/// Generates the blocking and async clients.
+fn client(item: &ItemTrait, methods: &[Method]) -> TokenStream {
+    let vis = &item.vis;
+    let trait_ident = &item.ident;
+    let client = format_ident!("{}Client", trait_ident);
+    let async_client = format_ident!("{}AsyncClient", trait_ident);
+
+    let client_doc = LitStr::new(
+        &format!(
+            "Implements [`{}`] by calling a server through a [`Caller`](::serde_xmlrpc::Caller).",
+            trait_ident
+        ),
+        Span::call_site(),
+    );
+    let async_client_doc = LitStr::new(
+        &format!(
+            "Calls the methods of [`{}`] through an \
+             [`AsyncCaller`](::serde_xmlrpc::AsyncCaller).",
+            trait_ident
+        ),
+        Span::call_site(),
+    );
+
+    let params = |method: &Method| {
+        let args = &method.args;
+        if args.is_empty() {
+            quote!([(); 0])
+        } else {
+            quote!((#(#args,)*))
+        }
+    };
+
+    let blocking_methods = methods.iter().map(|method| {
+        let sig = &method.sig;
+        let name = &method.name;
+        let params = params(method);
+        quote! {
+            #sig {
+                ::serde_xmlrpc::Caller::call(&self.caller, #name, #params)
+                    .map_err(::std::convert::From::from)
+            }
+        }
+    });
+
+    let async_methods = methods.iter().map(|method| {
+        let mut sig = method.sig.clone();
+        sig.asyncness = Some(<Token![async]>::default());
+        let name = &method.name;
+        let params = params(method);
+        quote! {
+            pub #sig {
+                ::serde_xmlrpc::AsyncCaller::call(&self.caller, #name, #params)
+                    .await
+                    .map_err(::std::convert::From::from)
+            }
+        }
+    });
+
+    let constructors = |ident: &Ident| {
+        quote! {
+            impl<C> #ident<C> {
+                pub fn new(caller: C) -> Self {
+                    #ident { caller }
+                }
+
+                /// Returns the caller the calls are made through.
+                pub fn into_inner(self) -> C {
+                    self.caller
+                }
+            }
+        }
+    };
+    let client_constructors = constructors(&client);
+    let async_client_constructors = constructors(&async_client);
+
+    quote! {
+        #[doc = #client_doc]
+        #[derive(Clone, Debug)]
+        #vis struct #client<C> {
+            caller: C,
+        }
+
+        #client_constructors
+
+        impl<C: ::serde_xmlrpc::Caller> #trait_ident for #client<C> {
+            #(#blocking_methods)*
+        }
+
+        #[doc = #async_client_doc]
+        #[derive(Clone, Debug)]
+        #vis struct #async_client<C> {
+            caller: C,
+        }
+
+        #async_client_constructors
+
+        impl<C: ::serde_xmlrpc::AsyncCaller> #async_client<C> {
+            #(#async_methods)*
+        }
+    }
+}
@@ -0,0 +1,428 @@
+//! Derive macros for [`serde_xmlrpc`](https://docs.rs/serde_xmlrpc). Use them through its
+//! `derive` feature rather than depending on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Expr, Fields, ItemTrait, LitStr};
+
+mod interface;
+
+/// Implements `XmlRpcType` for a struct with named fields, converting it to and from a
+/// `<struct>` with a member per field.
+///
+/// Each field's type must implement `XmlRpcType` itself, unless one of the attributes below
+/// says how to convert it. Missing members are read as `<nil/>`, so `Option` fields may be left
+/// out. The `xmlrpc` attributes are independent of serde's, so a type can derive both with
+/// different names on each side.
+///
+/// On the struct:
+///
+/// * `#[xmlrpc(rename_all = "...")]` names the members after the fields in `camelCase`,
+///   `PascalCase`, `snake_case`, `SCREAMING_SNAKE_CASE`, `kebab-case`,
+///   `SCREAMING-KEBAB-CASE`, `lowercase` or `UPPERCASE`.
+/// * `#[xmlrpc(deny_unknown_members)]` fails to read structs with other members.
+///
+/// On a field:
+///
+/// * `#[xmlrpc(rename = "name")]` sets the member name.
+/// * `#[xmlrpc(datetime_format = EXPR)]` writes an `XmlRpcDateTime` or
+///   `Option<XmlRpcDateTime>` in the given `DateTimeFormat`, whatever the `WriteOptions` say.
+/// * `#[xmlrpc(bytes = "base64")]` or `#[xmlrpc(bytes = "array")]` writes a `Vec<u8>` or
+///   `Option<Vec<u8>>` as `<base64>` or as an array of `<int>`s. Either is accepted when
+///   reading.
+/// * `#[xmlrpc(default)]` uses `Default::default()` for a missing member.
+/// * `#[xmlrpc(skip)]` leaves the field out, and reads it as `Default::default()`.
+///
+/// ```
+/// use serde_xmlrpc::{DateTimeFormat, XmlRpcDateTime, XmlRpcStruct, XmlRpcType};
+///
+/// #[derive(Debug, PartialEq, XmlRpcStruct)]
+/// #[xmlrpc(rename_all = "camelCase")]
+/// struct Post {
+///     #[xmlrpc(rename = "postid")]
+///     post_id: String,
+///     post_title: String,
+///     #[xmlrpc(datetime_format = DateTimeFormat::EXTENDED)]
+///     date_created: XmlRpcDateTime,
+///     #[xmlrpc(bytes = "base64")]
+///     thumbnail: Option<Vec<u8>>,
+/// }
+///
+/// let post = Post {
+///     post_id: "1".into(),
+///     post_title: "Hello".into(),
+///     date_created: XmlRpcDateTime::new(2024, 5, 1, 12, 0, 0).unwrap(),
+///     thumbnail: None,
+/// };
+///
+/// let xml = serde_xmlrpc::value_to_string(post.to_value()).unwrap();
+/// assert!(xml.contains("<name>postTitle</name>"));
+/// assert!(xml.contains("<dateTime.iso8601>2024-05-01T12:00:00</dateTime.iso8601>"));
+///
+/// let value = serde_xmlrpc::value_from_str(&xml).unwrap();
+/// assert_eq!(Post::from_value(&value).unwrap(), post);
+/// ```
+#[proc_macro_derive(XmlRpcStruct, attributes(xmlrpc))]
+pub fn derive_xmlrpc_struct(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Turns a trait into a typed XML-RPC interface, with a client implementing it and a dispatcher
+/// serving any implementation of it.
+///
+/// Each method of the trait becomes an XML-RPC method. Its name is worked out from the name of
+/// the Rust method with these arguments of the attribute:
+///
+/// * `rename_all = "..."` converts the name to one of the cases `XmlRpcStruct` knows, such as
+///   `camelCase`, so `get_posts` is called as `getPosts`.
+/// * `namespace = "..."` puts the name in a namespace, so with `namespace = "wp"` as well the
+///   method is `wp.getPosts`.
+///
+/// `#[xmlrpc(rename = "...")]` on a method sets its whole name instead, such as
+/// `system.methodHelp`, without the namespace.
+///
+/// Methods must take `&self` and owned arguments, which are the params of the call, and return
+/// a `Result` whose error converts from `serde_xmlrpc::Error` and into a `Fault`, such as
+/// `Fault` itself.
+///
+/// With `#[xmlrpc_interface(client)]`, or with no arguments, it generates:
+///
+/// * `FooClient<C>`, which implements the trait `Foo` by calling through a `Caller`, such as a
+///   `BlockingClient`.
+/// * `FooAsyncClient<C>`, with an `async` version of each method, calling through an
+///   `AsyncCaller` such as a `Client`.
+///
+/// With `#[xmlrpc_interface(server)]`, or with no arguments, the trait gets an
+/// `into_dispatcher` method which registers each method of an implementation with a new
+/// `Dispatcher`. The `derive` feature of `serde_xmlrpc` turns on its `server` feature, which has no
+/// dependencies of its own, so the default expansion always builds.
+///
+/// ```
+/// use serde_xmlrpc::{xmlrpc_interface, Fault};
+///
+/// #[xmlrpc_interface(namespace = "math", rename_all = "camelCase")]
+/// pub trait Calculator {
+///     fn add(&self, a: i32, b: i32) -> Result<i32, Fault>;
+///
+///     fn checked_div(&self, a: i32, b: i32) -> Result<i32, Fault>;
+///
+///     #[xmlrpc(rename = "system.version")]
+///     fn version(&self) -> Result<String, Fault>;
+/// }
+///
+/// struct Service;
+///
+/// impl Calculator for Service {
+///     fn add(&self, a: i32, b: i32) -> Result<i32, Fault> {
+///         Ok(a + b)
+///     }
+///
+///     fn checked_div(&self, a: i32, b: i32) -> Result<i32, Fault> {
+///         a.checked_div(b).ok_or_else(|| Fault::new(1, "division by zero"))
+///     }
+///
+///     fn version(&self) -> Result<String, Fault> {
+///         Ok("1.0".into())
+///     }
+/// }
+///
+/// let dispatcher = Service.into_dispatcher();
+/// assert_eq!(
+///     dispatcher.methods().collect::<Vec<_>>(),
+///     ["math.add", "math.checkedDiv", "system.version"]
+/// );
+///
+/// // A dispatcher is a `Caller` too, which makes calls without a server.
+/// let client = CalculatorClient::new(dispatcher);
+/// assert_eq!(client.add(1, 2), Ok(3));
+/// assert_eq!(client.checked_div(1, 0).unwrap_err().fault_code, 1);
+/// ```
+#[proc_macro_attribute]
+pub fn xmlrpc_interface(args: TokenStream, input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as ItemTrait);
+    interface::expand(args.into(), item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// How member names are derived from field names.
+#[derive(Clone, Copy)]
+enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    const RULES: [(&'static str, RenameRule); 8] = [
+        ("lowercase", RenameRule::Lower),
+        ("UPPERCASE", RenameRule::Upper),
+        ("PascalCase", RenameRule::Pascal),
+        ("camelCase", RenameRule::Camel),
+        ("snake_case", RenameRule::Snake),
+        ("SCREAMING_SNAKE_CASE", RenameRule::ScreamingSnake),
+        ("kebab-case", RenameRule::Kebab),
+        ("SCREAMING-KEBAB-CASE", RenameRule::ScreamingKebab),
+    ];
+
+    fn parse(lit: &LitStr) -> syn::Result<Self> {
+        let name = lit.value();
+        RenameRule::RULES
+            .iter()
+            .find(|(rule, _)| *rule == name)
+            .map(|(_, rule)| *rule)
+            .ok_or_else(|| {
+                let names: Vec<&str> = RenameRule::RULES.iter().map(|(rule, _)| *rule).collect();
+                syn::Error::new(
+                    lit.span(),
+                    format!(
+                        "unknown rename_all rule, expected one of {}",
+                        names.join(", ")
+                    ),
+                )
+            })
+    }
+
+    /// Renames a field, which is expected to be in snake case.
+    fn apply(self, field: &str) -> String {
+        let pascal = || {
+            field
+                .split('_')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => first.to_uppercase().chain(chars).collect(),
+                        None => String::new(),
+                    }
+                })
+                .collect::<String>()
+        };
+
+        match self {
+            RenameRule::Lower | RenameRule::Snake => field.to_ascii_lowercase(),
+            RenameRule::Upper => field.to_ascii_uppercase(),
+            RenameRule::Pascal => pascal(),
+            RenameRule::Camel => {
+                let pascal = pascal();
+                let mut chars = pascal.chars();
+                match chars.next() {
+                    Some(first) => first.to_lowercase().chain(chars).collect(),
+                    None => String::new(),
+                }
+            }
+            RenameRule::ScreamingSnake => field.to_ascii_uppercase(),
+            RenameRule::Kebab => field.replace('_', "-"),
+            RenameRule::ScreamingKebab => field.replace('_', "-").to_ascii_uppercase(),
+        }
+    }
+}
+
+/// How a field is converted.
+enum Encoding {
+    /// With the field type's own `XmlRpcType` implementation.
+    Plain,
+    /// As a `<dateTime.iso8601>` written in the given `DateTimeFormat`.
+    DateTime(Expr),
+    /// As `<base64>`, or as an array of `<int>`s if `false`.
+    Bytes { base64: bool },
+}
+
+struct Field {
+    ident: syn::Ident,
+    name: String,
+    encoding: Encoding,
+    default: bool,
+    skip: bool,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(unsupported(&input)),
+        },
+        _ => return Err(unsupported(&input)),
+    };
+
+    let mut rename_all = None;
+    let mut deny_unknown = false;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("xmlrpc"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename_all") {
+                rename_all = Some(RenameRule::parse(&meta.value()?.parse()?)?);
+                Ok(())
+            } else if meta.path.is_ident("deny_unknown_members") {
+                deny_unknown = true;
+                Ok(())
+            } else {
+                Err(meta.error("unknown xmlrpc container attribute"))
+            }
+        })?;
+    }
+
+    let fields = fields
+        .iter()
+        .map(|field| parse_field(field, rename_all))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let ident = &input.ident;
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param
+            .bounds
+            .push(syn::parse_quote!(::serde_xmlrpc::XmlRpcType));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let private = quote!(::serde_xmlrpc::private);
+    let writes = fields.iter().filter(|field| !field.skip).map(|field| {
+        let ident = &field.ident;
+        let name = &field.name;
+        let value = match &field.encoding {
+            Encoding::Plain => quote!(::serde_xmlrpc::XmlRpcType::to_value(&self.#ident)),
+            Encoding::DateTime(format) => {
+                quote!(#private::DateTimeField::to_value(&self.#ident, #format))
+            }
+            Encoding::Bytes { base64 } => {
+                quote!(#private::BytesField::to_value(&self.#ident, #base64))
+            }
+        };
+        quote!(members.insert(::std::string::String::from(#name), #value);)
+    });
+
+    let reads = fields.iter().map(|field| {
+        let ident = &field.ident;
+        let name = &field.name;
+        if field.skip {
+            return quote!(#ident: ::std::default::Default::default());
+        }
+
+        let read = match &field.encoding {
+            Encoding::Plain => quote!(::serde_xmlrpc::XmlRpcType::from_value),
+            Encoding::DateTime(_) => quote!(#private::DateTimeField::from_value),
+            Encoding::Bytes { .. } => quote!(#private::BytesField::from_value),
+        };
+        if field.default {
+            quote!(#ident: #private::member_or_default(members, #name, #read)?)
+        } else {
+            quote!(#ident: #private::member(members, #name, #read)?)
+        }
+    });
+
+    let check_unknown = if deny_unknown {
+        let names = fields
+            .iter()
+            .filter(|field| !field.skip)
+            .map(|field| &field.name);
+        quote!(#private::deny_unknown(members, &[#(#names),*])?;)
+    } else {
+        quote!()
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::serde_xmlrpc::XmlRpcType for #ident #ty_generics #where_clause {
+            fn to_value(&self) -> ::serde_xmlrpc::Value {
+                let mut members = ::serde_xmlrpc::Map::new();
+                #(#writes)*
+                ::serde_xmlrpc::Value::Struct(members)
+            }
+
+            fn from_value(value: &::serde_xmlrpc::Value) -> ::serde_xmlrpc::Result<Self> {
+                let members = #private::members(value)?;
+                #check_unknown
+                ::std::result::Result::Ok(#ident {
+                    #(#reads,)*
+                })
+            }
+        }
+    })
+}
+
+fn parse_field(field: &syn::Field, rename_all: Option<RenameRule>) -> syn::Result<Field> {
+    let ident = field.ident.clone().expect("named fields have names");
+    let unraw = ident.to_string().trim_start_matches("r#").to_string();
+    let mut parsed = Field {
+        name: match rename_all {
+            Some(rule) => rule.apply(&unraw),
+            None => unraw,
+        },
+        ident,
+        encoding: Encoding::Plain,
+        default: false,
+        skip: false,
+    };
+
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("xmlrpc"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                parsed.name = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("datetime_format") {
+                set_encoding(
+                    &mut parsed.encoding,
+                    Encoding::DateTime(meta.value()?.parse()?),
+                )
+                .map_err(|msg| meta.error(msg))?;
+            } else if meta.path.is_ident("bytes") {
+                let lit: LitStr = meta.value()?.parse()?;
+                let base64 = match lit.value().as_str() {
+                    "base64" => true,
+                    "array" => false,
+                    _ => {
+                        return Err(syn::Error::new(
+                            lit.span(),
+                            "expected bytes = \"base64\" or bytes = \"array\"",
+                        ))
+                    }
+                };
+                set_encoding(&mut parsed.encoding, Encoding::Bytes { base64 })
+                    .map_err(|msg| meta.error(msg))?;
+            } else if meta.path.is_ident("default") {
+                parsed.default = true;
+            } else if meta.path.is_ident("skip") {
+                parsed.skip = true;
+            } else {
+                return Err(meta.error("unknown xmlrpc field attribute"));
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(parsed)
+}
+
+fn set_encoding(encoding: &mut Encoding, new: Encoding) -> Result<(), &'static str> {
+    match encoding {
+        Encoding::Plain => {
+            *encoding = new;
+            Ok(())
+        }
+        _ => Err("only one of datetime_format and bytes can be used on a field"),
+    }
+}
+
+fn unsupported(input: &DeriveInput) -> syn::Error {
+    syn::Error::new(
+        Span::call_site(),
+        format!(
+            "XmlRpcStruct can only be derived for structs with named fields, not {}",
+            input.ident
+        ),
+    )
+}
@@ -0,0 +1,75 @@
+use serde_xmlrpc::{Value, Values, XmlRpcStruct, XmlRpcType};
+
+#[derive(Debug, Default, PartialEq, XmlRpcStruct)]
+#[xmlrpc(rename_all = "SCREAMING-KEBAB-CASE", deny_unknown_members)]
+struct Record {
+    r#type: String,
+    user_id: i32,
+    #[xmlrpc(rename = "tags")]
+    labels: Vec<String>,
+    #[xmlrpc(bytes = "array")]
+    checksum: Vec<u8>,
+    #[xmlrpc(default)]
+    retries: i64,
+    #[xmlrpc(skip)]
+    cached: bool,
+}
+
+#[derive(Debug, PartialEq, XmlRpcStruct)]
+struct Page<T> {
+    items: Vec<T>,
+    next: Option<String>,
+}
+
+#[test]
+fn members() {
+    let record = Record {
+        r#type: "a".into(),
+        user_id: 7,
+        labels: vec!["x".into()],
+        checksum: vec![1, 255],
+        retries: 0,
+        cached: true,
+    };
+
+    let value = record.to_value();
+    assert_eq!(value.pointer("/TYPE"), Some(&Value::from("a")));
+    assert_eq!(value.pointer("/USER-ID"), Some(&Value::Int(7)));
+    assert_eq!(value.pointer("/tags/0"), Some(&Value::from("x")));
+    assert_eq!(value.pointer("/CHECKSUM/1"), Some(&Value::Int(255)));
+    assert_eq!(value.pointer("/CACHED"), None);
+
+    let read = Record::from_value(&value).unwrap();
+    assert_eq!(
+        read,
+        Record {
+            cached: false,
+            ..record
+        }
+    );
+
+    let page = Page {
+        items: vec![1, 2],
+        next: None,
+    };
+    let params = Values::new().param(&page);
+    assert_eq!(params.get::<Page<i32>>(0).unwrap(), page);
+}
+
+#[test]
+fn errors() {
+    let mut value = Record::default().to_value();
+    value.entry("EXTRA").or_insert(1);
+    let err = Record::from_value(&value).unwrap_err();
+    assert!(err.to_string().contains("unknown field `EXTRA`"));
+
+    let mut value = Value::Nil;
+    value.entry("items").or_insert(vec![Value::from("a")]);
+    let err = Page::<i32>::from_value(&value).unwrap_err();
+    assert!(err.to_string().ends_with("at /items"), "{}", err);
+
+    let value = Value::Struct(Default::default());
+    let err = Page::<i32>::from_value(&value).unwrap_err();
+    assert!(err.to_string().contains("missing field `items`"), "{}", err);
+    assert!(Page::<i32>::from_value(&Value::Nil).is_err());
+}
@@ -0,0 +1,87 @@
+use std::future::Future;
+use std::sync::Mutex;
+
+use serde_xmlrpc::server::AsyncDispatcher;
+use serde_xmlrpc::{xmlrpc_interface, Error, Fault};
+
+#[xmlrpc_interface]
+trait Counter {
+    fn get(&self) -> Result<i64, Fault>;
+
+    #[xmlrpc(rename = "counter.add")]
+    fn add(&self, by: i64, labels: Vec<String>) -> Result<i64, Fault>;
+
+    fn reset(&self) -> Result<(), Error>;
+}
+
+#[xmlrpc_interface(client, namespace = "wp", rename_all = "camelCase")]
+trait Blog {
+    fn get_posts(&self, blog_id: i32) -> Result<Vec<String>, Error>;
+
+    #[xmlrpc(rename = "system.methodHelp")]
+    fn method_help(&self, method: String) -> Result<String, Error>;
+}
+
+#[derive(Default)]
+struct Service(Mutex<i64>);
+
+impl Counter for Service {
+    fn get(&self) -> Result<i64, Fault> {
+        Ok(*self.0.lock().unwrap())
+    }
+
+    fn add(&self, by: i64, labels: Vec<String>) -> Result<i64, Fault> {
+        if labels.is_empty() {
+            return Err(Fault::new(7, "no labels"));
+        }
+        let mut count = self.0.lock().unwrap();
+        *count += by;
+        Ok(*count)
+    }
+
+    fn reset(&self) -> Result<(), Error> {
+        *self.0.lock().unwrap() = 0;
+        Ok(())
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+#[test]
+fn blocking_client() {
+    let dispatcher = Service::default().into_dispatcher();
+    assert_eq!(
+        dispatcher.methods().collect::<Vec<_>>(),
+        ["counter.add", "get", "reset"]
+    );
+
+    let client = CounterClient::new(dispatcher);
+    assert_eq!(client.add(2, vec!["a".into()]), Ok(2));
+    assert_eq!(client.add(3, vec!["b".into()]), Ok(5));
+    assert_eq!(client.add(1, vec![]).unwrap_err().fault_code, 7);
+    client.reset().unwrap();
+    assert_eq!(client.get(), Ok(0));
+
+    let client = BlogClient::new(client.into_inner());
+    let err = client.get_posts(1).unwrap_err();
+    assert!(matches!(err, Error::Fault(f) if f == Fault::method_not_found("wp.getPosts")));
+    let err = client.method_help("get".into()).unwrap_err();
+    assert!(matches!(err, Error::Fault(f) if f == Fault::method_not_found("system.methodHelp")));
+}
+
+#[test]
+fn async_client() {
+    let mut dispatcher = AsyncDispatcher::new();
+    dispatcher.register("counter.add", |by: i64, labels: Vec<String>| async move {
+        Ok::<_, Fault>(by * labels.len() as i64)
+    });
+
+    let client = CounterAsyncClient::new(dispatcher);
+    assert_eq!(block_on(client.add(2, vec!["a".into(), "b".into()])), Ok(4));
+    assert_eq!(block_on(client.get()).unwrap_err().fault_code, -32601);
+}
@@ -27,7 +27,7 @@ fn main() {
     // an error message if anything went wrong
     // and a list of tuples of (topic name, topic data type)
     let (_status_code, _error_msg, topics) =
-        serde_xmlrpc::response_from_str::<(i8, String, Vec<(String, String)>)>(&response).unwrap();
+        serde_xmlrpc::response_from_str::<(i8, String, Vec<(String, String)>)>(response).unwrap();
 
     println!("Ros reported the following registered topics and types: {topics:?}");
 
@@ -0,0 +1,12 @@
+//! Parses arbitrary bytes as a document, which must fail cleanly rather than panic. Anything
+//! which parses must also be writable.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use serde_xmlrpc::ParseOptions;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(document) = ParseOptions::default().document_from_slice(data) {
+        document.to_xml().unwrap();
+    }
+});
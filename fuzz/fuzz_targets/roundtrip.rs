@@ -0,0 +1,13 @@
+//! Writes an arbitrary value, parses it back and writes it again, which must reproduce the first
+//! document exactly.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use serde_xmlrpc::{ParseOptions, Value, WriteOptions};
+
+fuzz_target!(|value: Value| {
+    let options = WriteOptions::default();
+    let body = options.value_to_string(value).unwrap();
+    let parsed = ParseOptions::lenient().value_from_str(&body).unwrap();
+    assert_eq!(options.value_to_string(parsed).unwrap(), body);
+});
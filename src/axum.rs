@@ -0,0 +1,215 @@
+//! Serving XML-RPC with [`axum`](https://docs.rs/axum).
+//!
+//! [`XmlRpc`] extracts a [`MethodCall`] from a request body and turns a
+//! [`MethodResponse`](crate::MethodResponse) into a `text/xml` response, so a
+//! [`Dispatcher`](crate::server::Dispatcher) only needs a single route:
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use axum::{extract::State, routing::post, Router};
+//! use serde_xmlrpc::axum::XmlRpc;
+//! use serde_xmlrpc::server::Dispatcher;
+//! use serde_xmlrpc::{Fault, MethodCall, MethodResponse};
+//!
+//! async fn rpc(
+//!     State(dispatcher): State<Arc<Dispatcher>>,
+//!     XmlRpc(call): XmlRpc<MethodCall>,
+//! ) -> XmlRpc<MethodResponse> {
+//!     XmlRpc(dispatcher.call(call))
+//! }
+//!
+//! let mut dispatcher = Dispatcher::new();
+//! dispatcher.register("add", |a: i32, b: i32| Ok::<_, Fault>(a + b));
+//!
+//! let app: Router = Router::new()
+//!     .route("/RPC2", post(rpc))
+//!     .with_state(Arc::new(dispatcher));
+//! ```
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use ::axum::body::{Bytes, HttpBody};
+use ::axum::extract::rejection::BytesRejection;
+use ::axum::extract::FromRequest;
+use ::axum::http::{header, HeaderValue, Request, StatusCode};
+use ::axum::response::{IntoResponse, Response};
+use ::axum::{async_trait, BoxError};
+
+use crate::{Document, Error, MethodCall, ParseOptions};
+
+/// An XML-RPC message in a request or response body.
+///
+/// As an extractor, `XmlRpc<MethodCall>` parses the request body with the default
+/// [`ParseOptions`]. The request must have an XML content type such as `text/xml`. See
+/// [`XmlRpcRejection`] for what happens when it can't be extracted.
+///
+/// As a response, `XmlRpc<T>` writes `T` with the default [`WriteOptions`](crate::WriteOptions)
+/// and a `text/xml` content type. `T` can be a [`MethodResponse`](crate::MethodResponse) or
+/// anything else which converts into a [`Document`]. If it can't be written, the response is a
+/// `500 Internal Server Error` with the error as its body.
+#[derive(Clone, Debug, PartialEq)]
+pub struct XmlRpc<T>(pub T);
+
+#[async_trait]
+impl<S, B> FromRequest<S, B> for XmlRpc<MethodCall>
+where
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+    S: Send + Sync,
+{
+    type Rejection = XmlRpcRejection;
+
+    async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        if !has_xml_content_type(&req) {
+            return Err(XmlRpcRejection::MissingXmlContentType);
+        }
+
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(XmlRpcRejection::BodyError)?;
+        let call = ParseOptions::default()
+            .document_from_slice(&body)
+            .and_then(MethodCall::try_from)
+            .map_err(XmlRpcRejection::InvalidCall)?;
+
+        Ok(XmlRpc(call))
+    }
+}
+
+/// Accepts `text/xml`, which the spec requires, as well as `application/xml` and `+xml` types.
+fn has_xml_content_type<B>(req: &Request<B>) -> bool {
+    match req.headers().get(header::CONTENT_TYPE) {
+        Some(content_type) => content_type
+            .to_str()
+            .is_ok_and(crate::http::is_xml_content_type),
+        None => false,
+    }
+}
+
+impl<T> IntoResponse for XmlRpc<T>
+where
+    T: Into<Document>,
+{
+    fn into_response(self) -> Response {
+        match self.0.into().to_xml() {
+            Ok(body) => (
+                [(header::CONTENT_TYPE, HeaderValue::from_static("text/xml"))],
+                body,
+            )
+                .into_response(),
+            Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+        }
+    }
+}
+
+/// Why an [`XmlRpc`] couldn't be extracted from a request.
+///
+/// A body which isn't a valid `<methodCall>` is answered with the fault picked by
+/// [`Fault::from_request_error`](crate::Fault::from_request_error), the same as
+/// [`Dispatcher::handle`](crate::server::Dispatcher::handle) does, so XML-RPC clients see the
+/// failure as a fault. The other failures are answered with an HTTP error status.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum XmlRpcRejection {
+    /// The request didn't have an XML content type. Answered with `415 Unsupported Media Type`.
+    MissingXmlContentType,
+    /// The body couldn't be read.
+    BodyError(BytesRejection),
+    /// The body wasn't a valid `<methodCall>`.
+    InvalidCall(Error),
+}
+
+impl fmt::Display for XmlRpcRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XmlRpcRejection::MissingXmlContentType => {
+                f.write_str("expected request with `Content-Type: text/xml`")
+            }
+            XmlRpcRejection::BodyError(err) => err.fmt(f),
+            XmlRpcRejection::InvalidCall(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for XmlRpcRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            XmlRpcRejection::MissingXmlContentType => None,
+            XmlRpcRejection::BodyError(err) => Some(err),
+            XmlRpcRejection::InvalidCall(err) => Some(err),
+        }
+    }
+}
+
+impl IntoResponse for XmlRpcRejection {
+    fn into_response(self) -> Response {
+        match self {
+            XmlRpcRejection::MissingXmlContentType => {
+                (StatusCode::UNSUPPORTED_MEDIA_TYPE, self.to_string()).into_response()
+            }
+            XmlRpcRejection::BodyError(err) => err.into_response(),
+            XmlRpcRejection::InvalidCall(err) => {
+                XmlRpc(crate::server::parse_error(err)).into_response()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::axum::body::Body;
+
+    use super::*;
+    use crate::util::block_on;
+    use crate::{Fault, MethodResponse, Value};
+
+    fn extract(content_type: &str, body: &str) -> Result<XmlRpc<MethodCall>, XmlRpcRejection> {
+        let req = Request::builder()
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        block_on(XmlRpc::<MethodCall>::from_request(req, &()))
+    }
+
+    fn body(response: Response) -> String {
+        let body = block_on(Bytes::from_request(Request::new(response.into_body()), &())).unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn extract_call() {
+        let request = crate::request_to_string("add", vec![1.into(), 2.into()]).unwrap();
+        let XmlRpc(call) = extract("text/xml; charset=utf-8", &request).unwrap();
+        assert_eq!(call.method_name, "add");
+        assert_eq!(call.params.as_slice(), &[Value::Int(1), Value::Int(2)]);
+
+        assert!(extract("application/xml", &request).is_ok());
+        assert!(matches!(
+            extract("application/json", &request),
+            Err(XmlRpcRejection::MissingXmlContentType)
+        ));
+
+        let response = extract("text/xml", "<methodCall>")
+            .unwrap_err()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(matches!(
+            body(response).parse::<MethodResponse>().unwrap(),
+            MethodResponse::Fault(Fault {
+                fault_code: -32700,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn response() {
+        let response = XmlRpc(MethodResponse::Success(vec![Value::Int(3)].into())).into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/xml");
+        assert_eq!(crate::response_from_str::<i32>(&body(response)).unwrap(), 3);
+    }
+}
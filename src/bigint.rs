@@ -0,0 +1,147 @@
+//! Serialize [`num_bigint::BigInt`] and [`BigUint`](num_bigint::BigUint) without losing
+//! precision.
+//!
+//! Use the module with `#[serde(with = "serde_xmlrpc::bigint")]`, which works for both types.
+//! Integers are written as a `<string>` of their digits, or as Apache XML-RPC's
+//! `<ex:biginteger>` with
+//! [`WriteOptions::big_number_extension`](crate::WriteOptions::big_number_extension). Either is
+//! read back, as are the integer types.
+//!
+//! ```
+//! use num_bigint::BigInt;
+//! use serde::{Deserialize, Serialize};
+//! use serde_xmlrpc::WriteOptions;
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Account {
+//!     #[serde(with = "serde_xmlrpc::bigint")]
+//!     balance: BigInt,
+//! }
+//!
+//! let account = Account {
+//!     balance: "-123456789012345678901234567890".parse().unwrap(),
+//! };
+//! let options = WriteOptions::apache();
+//! let body = options.value_to_string(options.to_value(&account).unwrap());
+//! assert!(body.unwrap().contains(">-123456789012345678901234567890</ex:biginteger>"));
+//!
+//! let body = serde_xmlrpc::value_to_string(serde_xmlrpc::to_value(&account).unwrap()).unwrap();
+//! assert!(body.contains("<string>-123456789012345678901234567890</string>"));
+//! let value = serde_xmlrpc::value_from_str(&body).unwrap();
+//! assert_eq!(serde_xmlrpc::from_value::<Account>(value).unwrap(), account);
+//! ```
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use serde::de::{self, Visitor};
+use serde::{Deserializer, Serializer};
+
+use crate::value::BIGINTEGER_TOKEN;
+
+/// Serializes an integer as its digits.
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: fmt::Display,
+    S: Serializer,
+{
+    serializer.serialize_newtype_struct(BIGINTEGER_TOKEN, &value.to_string())
+}
+
+/// Deserializes an integer from its digits or an integer type.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr + From<u64> + TryFrom<i64>,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(BigIntVisitor(PhantomData))
+}
+
+struct BigIntVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for BigIntVisitor<T>
+where
+    T: FromStr + From<u64> + TryFrom<i64>,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an integer")
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        T::try_from(v).map_err(|_| E::invalid_value(de::Unexpected::Signed(v), &self))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(T::from(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        T::from_str(v).map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::{BigInt, BigUint};
+    use serde::{Deserialize, Serialize};
+
+    use crate::value::Deserializer;
+    use crate::{Value, WriteOptions};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Signed {
+        #[serde(with = "super")]
+        n: BigInt,
+    }
+
+    #[test]
+    fn roundtrip() {
+        let big = Signed {
+            n: "-98765432109876543210987654321".parse().unwrap(),
+        };
+        for options in [WriteOptions::default(), WriteOptions::apache()].iter() {
+            let value = options.to_value(&big).unwrap();
+            let body = options.response_to_string(std::iter::once(value)).unwrap();
+            assert_eq!(crate::response_from_str::<Signed>(&body).unwrap(), big);
+
+            // Values keep the extension type.
+            let value = crate::response_from_str::<Value>(&body).unwrap();
+            assert_eq!(crate::from_value::<Signed>(value.clone()).unwrap(), big);
+            assert_eq!(
+                options.response_to_string(std::iter::once(value)).unwrap(),
+                body
+            );
+        }
+    }
+
+    #[test]
+    fn other_types() {
+        let read = |xml: &str| {
+            let value = crate::value_from_str(xml)?;
+            super::deserialize::<BigInt, _>(Deserializer::from_value(value)).map(|n| n.to_string())
+        };
+        assert_eq!(read("<value><int>-7</int></value>").unwrap(), "-7");
+        assert_eq!(read("<value><i8>-7</i8></value>").unwrap(), "-7");
+        assert!(read("<value>1.5</value>").is_err());
+
+        let read = |xml: &str| {
+            let value = crate::value_from_str(xml)?;
+            super::deserialize::<BigUint, _>(Deserializer::from_value(value)).map(|n| n.to_string())
+        };
+        assert_eq!(read("<value><i4>7</i4></value>").unwrap(), "7");
+        assert!(read("<value><int>-7</int></value>").is_err());
+    }
+}
@@ -0,0 +1,101 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::Result;
+
+/// Something which can make calls, such as a client, for code which works with any of them.
+///
+/// This is implemented by `BlockingClient` with the `blocking` feature, and by `Dispatcher` with
+/// the `server` feature, which calls its handlers directly without writing any XML. The typed
+/// clients generated by `#[xmlrpc_interface]` make their calls through it.
+pub trait Caller {
+    /// Calls `method` with `params`, converted as for [`Values::from_params`], and converts the
+    /// param of the response to `R`.
+    ///
+    /// [`Values::from_params`]: crate::Values::from_params
+    fn call<P, R>(&self, method: &str, params: P) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned;
+}
+
+/// The future returned by an [`AsyncCaller`].
+pub type CallFuture<'a, R> = Pin<Box<dyn Future<Output = Result<R>> + Send + 'a>>;
+
+/// The async version of [`Caller`], implemented by `Client` with the `client` feature and by
+/// `AsyncDispatcher` with the `async` feature.
+pub trait AsyncCaller {
+    /// Calls `method` with `params` and resolves to the param of the response, converted to `R`.
+    fn call<'a, P, R>(&'a self, method: &'a str, params: P) -> CallFuture<'a, R>
+    where
+        P: Serialize + Send + 'a,
+        R: DeserializeOwned + 'a;
+}
+
+#[cfg(feature = "blocking")]
+impl Caller for crate::client::BlockingClient {
+    fn call<P, R>(&self, method: &str, params: P) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        crate::client::BlockingClient::call(self, method, params)
+    }
+}
+
+#[cfg(feature = "client")]
+impl AsyncCaller for crate::client::Client {
+    fn call<'a, P, R>(&'a self, method: &'a str, params: P) -> CallFuture<'a, R>
+    where
+        P: Serialize + Send + 'a,
+        R: DeserializeOwned + 'a,
+    {
+        Box::pin(crate::client::Client::call(self, method, params))
+    }
+}
+
+/// Converts the params of a call made to a dispatcher.
+#[cfg(feature = "server")]
+fn method_call<P: Serialize>(method: &str, params: &P) -> Result<crate::MethodCall> {
+    Ok(crate::MethodCall {
+        method_name: method.into(),
+        params: crate::Values::from_params(params)?,
+    })
+}
+
+/// Converts the response of a dispatcher, as a client would.
+#[cfg(feature = "server")]
+fn response_param<R: DeserializeOwned>(response: crate::MethodResponse) -> Result<R> {
+    let param = response.into_result()?.into_vec().into_iter().next();
+    crate::from_value(param.unwrap_or(crate::Value::Nil))
+}
+
+#[cfg(feature = "server")]
+impl Caller for crate::server::Dispatcher {
+    fn call<P, R>(&self, method: &str, params: P) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        response_param(crate::server::Dispatcher::call(
+            self,
+            method_call(method, &params)?,
+        ))
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncCaller for crate::server::AsyncDispatcher {
+    fn call<'a, P, R>(&'a self, method: &'a str, params: P) -> CallFuture<'a, R>
+    where
+        P: Serialize + Send + 'a,
+        R: DeserializeOwned + 'a,
+    {
+        let response = method_call(method, &params)
+            .map(|call| crate::server::AsyncDispatcher::call(self, call));
+        Box::pin(async move { response_param(response?.await) })
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Map;
+
+/// The extensions a peer supports, as returned by `system.getCapabilities`.
+///
+/// Each capability is keyed by name and describes the spec it implements. The ones this crate
+/// knows about have their own fields while any others are kept in `other`.
+///
+/// ```
+/// use serde_xmlrpc::{Capabilities, Capability};
+///
+/// let capabilities = Capabilities::new();
+/// assert_eq!(capabilities.nil, Some(Capability::nil()));
+/// assert!(capabilities.supports("faults_interop"));
+/// assert!(!capabilities.supports("introspection"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    from = "BTreeMap<String, Capability>",
+    into = "BTreeMap<String, Capability>"
+)]
+pub struct Capabilities {
+    /// Standard fault codes, see the [fault code interoperability spec].
+    ///
+    /// [fault code interoperability spec]: http://xmlrpc-epi.sourceforge.net/specs/rfc.fault_codes.php
+    pub faults_interop: Option<Capability>,
+    /// The `system.listMethods`, `system.methodSignature` and `system.methodHelp` methods.
+    pub introspection: Option<Capability>,
+    /// The `<nil/>` value.
+    pub nil: Option<Capability>,
+    /// Any other capabilities, by name.
+    pub other: Map<String, Capability>,
+}
+
+impl Capabilities {
+    /// Returns the capabilities this crate honors on its own: the standard fault codes and
+    /// `<nil/>`. Introspection is added by a dispatcher which has it attached.
+    pub fn new() -> Self {
+        Capabilities {
+            faults_interop: Some(Capability::faults_interop()),
+            introspection: None,
+            nil: Some(Capability::nil()),
+            other: Map::new(),
+        }
+    }
+
+    /// Returns whether the capability called `name` is supported.
+    pub fn supports(&self, name: &str) -> bool {
+        match name {
+            "faults_interop" => self.faults_interop.is_some(),
+            "introspection" => self.introspection.is_some(),
+            "nil" => self.nil.is_some(),
+            name => self.other.contains_key(name),
+        }
+    }
+}
+
+impl From<BTreeMap<String, Capability>> for Capabilities {
+    fn from(mut capabilities: BTreeMap<String, Capability>) -> Self {
+        Capabilities {
+            faults_interop: capabilities.remove("faults_interop"),
+            introspection: capabilities.remove("introspection"),
+            nil: capabilities.remove("nil"),
+            other: capabilities.into_iter().collect(),
+        }
+    }
+}
+
+impl From<Capabilities> for BTreeMap<String, Capability> {
+    fn from(capabilities: Capabilities) -> Self {
+        let mut map: BTreeMap<_, _> = capabilities.other.into_iter().collect();
+        let known = [
+            ("faults_interop", capabilities.faults_interop),
+            ("introspection", capabilities.introspection),
+            ("nil", capabilities.nil),
+        ];
+        for (name, capability) in known.iter().cloned() {
+            if let Some(capability) = capability {
+                map.insert(name.to_string(), capability);
+            }
+        }
+        map
+    }
+}
+
+/// The spec a capability implements.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capability {
+    /// Where the spec can be found.
+    pub spec_url: String,
+    /// The version of the spec, usually the date it was published as `YYYYMMDD`.
+    pub spec_version: i32,
+}
+
+impl Capability {
+    pub fn new(spec_url: impl Into<String>, spec_version: i32) -> Self {
+        Capability {
+            spec_url: spec_url.into(),
+            spec_version,
+        }
+    }
+
+    /// The `faults_interop` capability.
+    pub fn faults_interop() -> Self {
+        Capability::new(
+            "http://xmlrpc-epi.sourceforge.net/specs/rfc.fault_codes.php",
+            20010516,
+        )
+    }
+
+    /// The `introspection` capability.
+    pub fn introspection() -> Self {
+        Capability::new("http://xmlrpc-c.sourceforge.net/introspection.html", 1)
+    }
+
+    /// The `nil` capability.
+    pub fn nil() -> Self {
+        Capability::new("http://www.ontosys.com/xml-rpc/extensions.php", 20010518)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capabilities_roundtrip() {
+        let mut capabilities = Capabilities::new();
+        capabilities
+            .other
+            .insert("json".into(), Capability::new("http://example.com/json", 1));
+
+        let body =
+            crate::response_to_string(vec![crate::to_value(&capabilities).unwrap()].into_iter())
+                .unwrap();
+        assert!(body.contains("<name>specUrl</name>"));
+        assert!(!body.contains("<name>introspection</name>"));
+
+        let parsed: Capabilities = crate::response_from_str(&body).unwrap();
+        assert_eq!(parsed, capabilities);
+        assert!(parsed.supports("json"));
+    }
+}
@@ -0,0 +1,633 @@
+use std::fmt;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::batch::{multicall_param, multicall_results, AsyncBatch, Call};
+use super::http::{read_response, request_head, transport_error, Session, MAX_HEAD_LEN};
+use super::pool::PoolOptions;
+use super::retry::{attempt_timeout, timed_out, RetryPolicy};
+use super::tls::TlsOptions;
+use super::{AsyncTransport, TransportFuture};
+use crate::http::{check_content_type, check_length, check_status, CONTENT_TYPE};
+use crate::trace::CallSpan;
+use crate::{Capabilities, ParseOptions, Result, Value, Values, WriteOptions};
+
+/// Makes calls to an XML-RPC server at a fixed URL.
+///
+/// Params are passed as a tuple with one element per param, or as any other value to pass a
+/// single param. See [`Values::from_params`]. A `<fault>` response is returned as
+/// [`Error::Fault`](crate::Error::Fault) and HTTP failures as
+/// [`Error::TransportError`](crate::Error::TransportError), checked as described in
+/// [`crate::http`].
+///
+/// ```no_run
+/// # async fn run() -> serde_xmlrpc::Result<()> {
+/// use serde_xmlrpc::client::Client;
+///
+/// let client = Client::new("http://localhost:8080/RPC2");
+/// let sum = client.call::<(i32, i32), i32>("add", (1, 2)).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Calls can also be made over a Unix socket with [`Client::unix`], over any other stream with
+/// [`call_stream`], or with any [`AsyncTransport`].
+#[derive(Clone, Debug)]
+pub struct Client {
+    backend: Backend,
+    max_response_len: Option<u64>,
+    timeout: Option<Duration>,
+    deadline: Option<Duration>,
+    retry: RetryPolicy,
+    session: Session,
+    parse_options: ParseOptions,
+    pub(super) write_options: WriteOptions,
+}
+
+/// The options of a `reqwest::Client` created by [`Client::new`].
+#[derive(Clone, Debug, Default)]
+struct HttpOptions {
+    pool: PoolOptions,
+    tls: TlsOptions,
+}
+
+/// How a [`Client`] connects to its server.
+#[derive(Clone)]
+enum Backend {
+    Http {
+        /// The client, or the error creating it from `options`, which every call fails with.
+        http: std::result::Result<reqwest::Client, Arc<reqwest::Error>>,
+        url: String,
+        /// The options `http` was created with, or `None` if it was given.
+        options: Option<HttpOptions>,
+    },
+    #[cfg(unix)]
+    Unix {
+        socket: PathBuf,
+        path: String,
+    },
+    Custom(Arc<dyn AsyncTransport + Send + Sync>),
+}
+
+impl fmt::Debug for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backend::Http { http, url, options } => f
+                .debug_struct("Http")
+                .field("http", http)
+                .field("url", url)
+                .field("options", options)
+                .finish(),
+            #[cfg(unix)]
+            Backend::Unix { socket, path } => f
+                .debug_struct("Unix")
+                .field("socket", socket)
+                .field("path", path)
+                .finish(),
+            Backend::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+impl Client {
+    /// Creates a client with a `reqwest::Client` which keeps connections open as the default
+    /// [`PoolOptions`] say and verifies servers as the default [`TlsOptions`] do.
+    ///
+    /// If the `reqwest::Client` can't be created, for example because the TLS backend fails to
+    /// start, every call fails with [`Error::TransportError`](crate::Error::TransportError).
+    pub fn new(url: impl Into<String>) -> Self {
+        let options = HttpOptions::default();
+        Client::from_backend(Backend::Http {
+            http: http_client(&options),
+            url: url.into(),
+            options: Some(options),
+        })
+    }
+
+    /// Creates a client which sends its requests through `http`, for example to configure
+    /// timeouts or authentication.
+    pub fn with_client(http: reqwest::Client, url: impl Into<String>) -> Self {
+        Client::from_backend(Backend::Http {
+            http: Ok(http),
+            url: url.into(),
+            options: None,
+        })
+    }
+
+    /// Creates a client which connects to the Unix socket at `socket` and posts its calls to
+    /// `path`, such as supervisord's `/var/run/supervisor.sock` and `/RPC2`.
+    #[cfg(unix)]
+    pub fn unix(socket: impl Into<PathBuf>, path: impl Into<String>) -> Self {
+        Client::from_backend(Backend::Unix {
+            socket: socket.into(),
+            path: path.into(),
+        })
+    }
+
+    /// Creates a client which sends its calls with `transport`. See [`AsyncTransport`].
+    pub fn with_transport(transport: impl AsyncTransport + Send + Sync + 'static) -> Self {
+        Client::from_backend(Backend::Custom(Arc::new(transport)))
+    }
+
+    fn from_backend(backend: Backend) -> Self {
+        Client {
+            backend,
+            max_response_len: None,
+            timeout: None,
+            deadline: None,
+            retry: RetryPolicy::default(),
+            session: Session::default(),
+            parse_options: ParseOptions::default(),
+            write_options: WriteOptions::default(),
+        }
+    }
+
+    /// Sets how connections are kept open between calls, replacing the `reqwest::Client` created
+    /// by [`Client::new`]. A client given to [`Client::with_client`] is left as it is, and calls
+    /// over a Unix socket always use a new connection. As with [`Client::new`], failing to create
+    /// the `reqwest::Client` makes every call fail.
+    pub fn pool(mut self, pool: PoolOptions) -> Self {
+        self.update_http(|options| options.pool = pool);
+        self
+    }
+
+    /// Sets the root certificates, client certificate and verification of `https://` servers,
+    /// replacing the `reqwest::Client` created by [`Client::new`]. A client given to
+    /// [`Client::with_client`] is left as it is. As with [`Client::new`], failing to create the
+    /// `reqwest::Client`, such as with a client certificate the TLS backend rejects, makes every
+    /// call fail.
+    pub fn tls(mut self, tls: TlsOptions) -> Self {
+        self.update_http(|options| options.tls = tls);
+        self
+    }
+
+    /// Changes the options of a `reqwest::Client` created by [`Client::new`] and creates it
+    /// again with them.
+    fn update_http(&mut self, update: impl FnOnce(&mut HttpOptions)) {
+        if let Backend::Http {
+            http,
+            options: Some(options),
+            ..
+        } = &mut self.backend
+        {
+            update(options);
+            *http = http_client(options);
+        }
+    }
+
+    /// Limits how long each attempt at a call may take, from sending the request to reading the
+    /// whole response. The default is to wait as long as the `reqwest::Client` does.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Limits how long a call may take in all, including its retries and the waits between them.
+    /// The default is no limit.
+    pub fn deadline(mut self, deadline: Option<Duration>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Retries failed calls as `policy` allows. The default is never to retry.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Limits the body of a response to `limit` bytes, failing with
+    /// [`HttpError::TooLarge`](crate::http::HttpError::TooLarge) when the server sends more. The
+    /// default is no limit.
+    pub fn max_response_len(mut self, limit: Option<u64>) -> Self {
+        self.max_response_len = limit;
+        self
+    }
+
+    /// Sends the `username` and `password` with each call using HTTP Basic authentication.
+    pub fn basic_auth(mut self, username: &str, password: &str) -> Self {
+        self.session.basic_auth(username, password);
+        self
+    }
+
+    /// Sends `token` with each call in an `Authorization: Bearer` header.
+    pub fn bearer_auth(mut self, token: &str) -> Self {
+        self.session.bearer_auth(token);
+        self
+    }
+
+    /// Stores the cookies responses set and sends them with later calls, for servers which keep
+    /// a session after a login call. Clones of the client share the cookies. Off by default.
+    pub fn cookie_store(mut self, enable: bool) -> Self {
+        self.session.cookie_store(enable);
+        self
+    }
+
+    /// Sets the options responses are parsed with.
+    pub fn parse_options(mut self, options: ParseOptions) -> Self {
+        self.parse_options = options;
+        self
+    }
+
+    /// Sets the options requests are written with.
+    pub fn write_options(mut self, options: WriteOptions) -> Self {
+        self.write_options = options;
+        self
+    }
+
+    /// Calls `method` and converts the param of the response to `R`.
+    pub async fn call<P, R>(&self, method: &str, params: P) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let params = Values::from_params_with(&params, &self.write_options)?;
+        self.call_values(method, params).await
+    }
+
+    /// Calls `method` with params which are already converted.
+    async fn call_values<R: DeserializeOwned>(&self, method: &str, params: Values) -> Result<R> {
+        let span = CallSpan::client(method, params.len());
+        let body = self
+            .write_options
+            .request_to_string(method, params.into_vec())?;
+        span.request(body.len());
+
+        let body = body.into_bytes();
+        let deadline = self.deadline.map(|deadline| Instant::now() + deadline);
+        let mut retries = 0;
+        let result = loop {
+            // Only the error is kept across the wait, so `R` needn't be `Send`.
+            let response = self.attempt(body.clone(), deadline).await;
+            let err = match response.and_then(|body| {
+                span.response(body.len());
+                self.parse_options.response_from_slice(&body)
+            }) {
+                Err(err) if self.retry.should_retry(method, &err, retries) => err,
+                result => break result,
+            };
+            let backoff = self.retry.backoff_for(retries);
+            if deadline.is_some_and(|deadline| Instant::now() + backoff >= deadline) {
+                break Err(err);
+            }
+            if !backoff.is_zero() {
+                tokio::time::sleep(backoff).await;
+            }
+            retries += 1;
+        };
+        span.finish(&result);
+        result
+    }
+
+    /// Posts `body`, failing with a timeout after the client's timeout or at `deadline`.
+    async fn attempt(&self, body: Vec<u8>, deadline: Option<Instant>) -> Result<Vec<u8>> {
+        match attempt_timeout(self.timeout, deadline)? {
+            Some(timeout) => tokio::time::timeout(timeout, self.post(body))
+                .await
+                .unwrap_or_else(|_| Err(timed_out())),
+            None => self.post(body).await,
+        }
+    }
+
+    /// Sends `body` in a POST request and returns the body of the response.
+    async fn post(&self, body: Vec<u8>) -> Result<Vec<u8>> {
+        match &self.backend {
+            Backend::Http { http, url, .. } => {
+                let http = http
+                    .as_ref()
+                    .map_err(|err| transport_error(Arc::clone(err)))?;
+                let mut request = http
+                    .post(url)
+                    .header(reqwest::header::CONTENT_TYPE, CONTENT_TYPE);
+                for (name, value) in self.session.headers()? {
+                    request = request.header(name, value);
+                }
+                let mut response = request.body(body).send().await.map_err(transport_error)?;
+                for set_cookie in response.headers().get_all(reqwest::header::SET_COOKIE) {
+                    if let Ok(set_cookie) = set_cookie.to_str() {
+                        self.session.set_cookie(set_cookie);
+                    }
+                }
+                check_status(response.status().as_u16())?;
+                let content_type = response.headers().get(reqwest::header::CONTENT_TYPE);
+                check_content_type(content_type.map(|ty| ty.to_str().unwrap_or_default()))?;
+                if let Some(length) = response.content_length() {
+                    check_length(length, self.max_response_len)?;
+                }
+
+                // Read in chunks so a body without a length is cut off at the limit.
+                let mut body = Vec::new();
+                while let Some(chunk) = response.chunk().await.map_err(transport_error)? {
+                    body.extend_from_slice(&chunk);
+                    check_length(body.len() as u64, self.max_response_len)?;
+                }
+                Ok(body)
+            }
+            #[cfg(unix)]
+            Backend::Unix { socket, path } => {
+                let stream = tokio::net::UnixStream::connect(socket)
+                    .await
+                    .map_err(transport_error)?;
+                post(
+                    stream,
+                    path,
+                    &body,
+                    self.max_response_len,
+                    Some(&self.session),
+                )
+                .await
+            }
+            Backend::Custom(transport) => {
+                let response = transport.send(&body).await?;
+                check_length(response.len() as u64, self.max_response_len)?;
+                Ok(response)
+            }
+        }
+    }
+
+    /// Starts a batch of calls which are sent together, with `system.multicall` or one request
+    /// after another. See [`AsyncBatch`].
+    pub fn batch(&self) -> AsyncBatch<'_> {
+        AsyncBatch::new(self)
+    }
+
+    /// Sends `calls` with `system.multicall`.
+    pub(super) async fn multicall(&self, calls: &[Call]) -> Result<Vec<Result<Value>>> {
+        let response = self
+            .call("system.multicall", (multicall_param(calls),))
+            .await?;
+        multicall_results(response, calls.len())
+    }
+
+    /// Sends `calls` one after another. `reqwest` keeps the connection open between them.
+    pub(super) async fn pipeline(&self, calls: Vec<Call>) -> Vec<Result<Value>> {
+        let mut results = Vec::with_capacity(calls.len());
+        for (method, params) in calls {
+            results.push(self.call_values(&method, params).await);
+        }
+        results
+    }
+
+    /// Lists the methods the server offers with `system.listMethods`.
+    pub async fn list_methods(&self) -> Result<Vec<String>> {
+        self.call("system.listMethods", [(); 0]).await
+    }
+
+    /// Asks for the signatures of `method` with `system.methodSignature`. Each signature is the
+    /// return type followed by the types of the params. The list is empty if the server doesn't
+    /// know them.
+    pub async fn method_signature(&self, method: &str) -> Result<Vec<Vec<String>>> {
+        let signatures: super::Signatures = self.call("system.methodSignature", (method,)).await?;
+        signatures.into_vec()
+    }
+
+    /// Asks for the help of `method` with `system.methodHelp`.
+    pub async fn method_help(&self, method: &str) -> Result<String> {
+        self.call("system.methodHelp", (method,)).await
+    }
+
+    /// Asks which extensions the server supports with `system.getCapabilities`.
+    pub async fn get_capabilities(&self) -> Result<Capabilities> {
+        self.call("system.getCapabilities", [(); 0]).await
+    }
+}
+
+/// Posts the body over the client's own connection, so a client can be wrapped by another
+/// transport.
+impl AsyncTransport for Client {
+    fn send<'a>(&'a self, body: &'a [u8]) -> TransportFuture<'a> {
+        Box::pin(self.post(body.to_vec()))
+    }
+}
+
+/// Calls `method` over an already connected `stream`, such as a TLS stream or an in-process pipe,
+/// by posting it to `path`. The stream is read until the server closes it.
+///
+/// ```no_run
+/// # async fn run() -> serde_xmlrpc::Result<()> {
+/// let stream = tokio::net::TcpStream::connect("localhost:8080").await.unwrap();
+/// let sum: i32 = serde_xmlrpc::client::call_stream(stream, "/RPC2", "add", (1, 2)).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn call_stream<S, P, R>(stream: S, path: &str, method: &str, params: P) -> Result<R>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    P: Serialize,
+    R: DeserializeOwned,
+{
+    let params = Values::from_params(&params)?;
+    let body = crate::request_to_string(method, params.into_vec())?;
+    let body = post(stream, path, body.as_bytes(), None, None).await?;
+    ParseOptions::default().response_from_slice(&body)
+}
+
+/// A `reqwest::Client` which keeps connections open and verifies servers as `options` say, or
+/// the error creating it.
+fn http_client(options: &HttpOptions) -> std::result::Result<reqwest::Client, Arc<reqwest::Error>> {
+    let pool = &options.pool;
+    let max_idle = if pool.enabled() { pool.max_idle } else { 0 };
+    let builder = reqwest::Client::builder()
+        .pool_max_idle_per_host(max_idle)
+        .pool_idle_timeout(pool.idle_timeout);
+    options.tls.apply(builder).build().map_err(Arc::new)
+}
+
+/// Sends `body` in a POST request over `stream` and returns the body of the response, which may
+/// be at most `limit` bytes. The credentials and cookies of `session` are sent if given.
+async fn post<S>(
+    mut stream: S,
+    path: &str,
+    body: &[u8],
+    limit: Option<u64>,
+    session: Option<&Session>,
+) -> Result<Vec<u8>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let headers = match session {
+        Some(session) => session.headers()?,
+        None => Vec::new(),
+    };
+    let head = request_head(path, "localhost", body.len(), &headers, false);
+    stream
+        .write_all(head.as_bytes())
+        .await
+        .map_err(transport_error)?;
+    stream.write_all(body).await.map_err(transport_error)?;
+    stream.flush().await.map_err(transport_error)?;
+
+    let mut response = Vec::new();
+    match limit {
+        Some(limit) => {
+            (&mut stream)
+                .take(limit.saturating_add(MAX_HEAD_LEN))
+                .read_to_end(&mut response)
+                .await
+        }
+        None => stream.read_to_end(&mut response).await,
+    }
+    .map_err(transport_error)?;
+
+    read_response(&response, limit, session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::block_on;
+    use crate::{Error, Fault, Value};
+
+    /// Calls over an in-memory stream which answers with `response`, returning the result and
+    /// the request that was sent.
+    fn call_with<R: DeserializeOwned>(response: &str) -> (Result<R>, String) {
+        let mut request = Vec::new();
+        let stream = tokio::io::join(response.as_bytes(), &mut request);
+        let result = block_on(call_stream(stream, "/RPC2", "add", (1, 2)));
+        (result, String::from_utf8(request).unwrap())
+    }
+
+    fn http_response(body: &str) -> String {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    }
+
+    #[test]
+    fn stream_call() {
+        let body = crate::response_to_string(vec![Value::Int(3)].into_iter()).unwrap();
+        let (sum, request) = call_with::<i32>(&http_response(&body));
+        assert_eq!(sum.unwrap(), 3);
+        assert!(request.starts_with("POST /RPC2 HTTP/1.1\r\nHost: localhost\r\n"));
+        assert!(request.ends_with(
+            "<methodName>add</methodName><params>\
+             <param><value><int>1</int></value></param>\
+             <param><value><int>2</int></value></param>\
+             </params></methodCall>"
+        ));
+
+        let body = crate::result_to_string::<()>(&Err(Fault::new(7, "nope"))).unwrap();
+        let (err, _) = call_with::<i32>(&http_response(&body));
+        assert!(matches!(
+            err,
+            Err(Error::Fault(Fault { fault_code: 7, .. }))
+        ));
+
+        let (err, _) = call_with::<i32>("HTTP/1.1 404 Not Found\r\n\r\n");
+        assert!(matches!(err, Err(Error::TransportError(_))));
+    }
+
+    #[test]
+    fn custom_transport() {
+        struct Replay(String);
+
+        impl AsyncTransport for Replay {
+            fn send<'a>(&'a self, body: &'a [u8]) -> TransportFuture<'a> {
+                assert!(String::from_utf8_lossy(body).contains("system.listMethods"));
+                Box::pin(async move { Ok(self.0.clone().into_bytes()) })
+            }
+        }
+
+        let body =
+            crate::response_to_string(vec![Value::Array(vec![Value::from("add")])].into_iter())
+                .unwrap();
+        let client = Client::with_transport(Replay(body));
+        assert_eq!(block_on(client.list_methods()).unwrap(), ["add"]);
+        assert_eq!(format!("{:?}", client.backend), "Custom(..)");
+    }
+
+    #[test]
+    fn pool() {
+        let pooled = |client: &Client| match &client.backend {
+            Backend::Http { options, .. } => options.as_ref().map(|options| options.pool),
+            _ => None,
+        };
+        let client = Client::new("http://localhost/RPC2").pool(PoolOptions::disabled());
+        assert_eq!(pooled(&client), Some(PoolOptions::disabled()));
+
+        let client = Client::with_client(reqwest::Client::new(), "http://localhost/RPC2")
+            .pool(PoolOptions::disabled());
+        assert_eq!(pooled(&client), None);
+    }
+
+    #[test]
+    fn tls() {
+        let insecure = |client: &Client| match &client.backend {
+            Backend::Http { options, .. } => options
+                .as_ref()
+                .map(|options| options.tls.accept_invalid_certs),
+            _ => None,
+        };
+        let tls = TlsOptions::new().danger_accept_invalid_certs(true);
+        let client = Client::new("https://localhost/RPC2")
+            .tls(tls.clone())
+            .pool(PoolOptions::disabled());
+        assert_eq!(insecure(&client), Some(true));
+
+        let client = Client::with_client(reqwest::Client::new(), "https://localhost/RPC2").tls(tls);
+        assert_eq!(insecure(&client), None);
+    }
+
+    #[test]
+    fn retry() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Fails with a 503 the first `failures` times.
+        struct Flaky {
+            failures: usize,
+            attempts: AtomicUsize,
+        }
+
+        impl AsyncTransport for Flaky {
+            fn send<'a>(&'a self, _body: &'a [u8]) -> TransportFuture<'a> {
+                let attempt = self.attempts.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async move {
+                    if attempt < self.failures {
+                        return Err(crate::http::HttpError::Status(503).into());
+                    }
+                    crate::response_to_string(vec![Value::Int(3)].into_iter())
+                        .map(String::into_bytes)
+                })
+            }
+        }
+
+        let policy = RetryPolicy::new(2)
+            .backoff(Duration::ZERO, Duration::ZERO)
+            .idempotent("add");
+        let flaky = |failures| Flaky {
+            failures,
+            attempts: AtomicUsize::new(0),
+        };
+
+        let client = Client::with_transport(flaky(2)).retry(policy.clone());
+        assert_eq!(block_on(client.call::<_, i32>("add", (1, 2))).unwrap(), 3);
+
+        let client = Client::with_transport(flaky(3)).retry(policy.clone());
+        assert!(block_on(client.call::<_, i32>("add", (1, 2))).is_err());
+
+        let client = Client::with_transport(flaky(1)).retry(policy);
+        assert!(block_on(client.call::<_, i32>("sub", (1, 2))).is_err());
+    }
+
+    #[test]
+    fn http_client_error() {
+        // native-tls can't require TLS 1.3, so building the client fails.
+        let err = reqwest::Client::builder()
+            .min_tls_version(reqwest::tls::Version::TLS_1_3)
+            .build()
+            .unwrap_err();
+        let client = Client::from_backend(Backend::Http {
+            http: Err(Arc::new(err)),
+            url: "http://localhost:8080/RPC2".into(),
+            options: Some(HttpOptions::default()),
+        });
+        let err = block_on(client.call::<_, i32>("add", (1, 2))).unwrap_err();
+        assert!(matches!(err, Error::TransportError(_)), "{:?}", err);
+    }
+}
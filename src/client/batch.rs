@@ -0,0 +1,327 @@
+use serde::de::Error as _;
+use serde::Serialize;
+
+use crate::{Error, Fault, Map, Result, Value, Values, WriteOptions};
+
+/// How the calls of a batch are sent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BatchMode {
+    /// Tries `system.multicall` and falls back to [`Pipelined`](BatchMode::Pipelined) requests
+    /// if the server answers it with a fault, as servers without it do.
+    #[default]
+    Auto,
+    /// Sends all the calls in a single `system.multicall` request.
+    Multicall,
+    /// Sends each call in its own request, over as few connections as the server allows.
+    ///
+    /// `BlockingClient` pipelines the requests over a kept-alive connection. `Client` sends them
+    /// one after another, reusing the connection of its `reqwest::Client`.
+    Pipelined,
+}
+
+/// A call to send, with its converted params.
+pub(super) type Call = (String, Values);
+
+/// The calls queued in a batch, with their params converted up front so the caller's values
+/// aren't borrowed.
+#[derive(Debug, Default)]
+pub(super) struct Calls {
+    pub(super) mode: BatchMode,
+    calls: Vec<(String, Result<Values>)>,
+}
+
+impl Calls {
+    pub(super) fn push<P: Serialize>(&mut self, method: &str, params: &P, options: &WriteOptions) {
+        let params = Values::from_params_with(params, options);
+        self.calls.push((method.into(), params));
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    /// Splits off the calls to send. The results of the calls whose params couldn't be converted
+    /// are already filled in.
+    pub(super) fn split(self) -> (Vec<Option<Result<Value>>>, Vec<Call>) {
+        let mut results = Vec::with_capacity(self.calls.len());
+        let mut pending = Vec::with_capacity(self.calls.len());
+        for (method, params) in self.calls {
+            match params {
+                Ok(params) => {
+                    results.push(None);
+                    pending.push((method, params));
+                }
+                Err(err) => results.push(Some(Err(err))),
+            }
+        }
+        (results, pending)
+    }
+}
+
+/// Fills in the results left out by [`Calls::split`] with those of the calls sent, in order.
+pub(super) fn merge(
+    results: Vec<Option<Result<Value>>>,
+    sent: Vec<Result<Value>>,
+) -> Vec<Result<Value>> {
+    let mut sent = sent.into_iter();
+    results
+        .into_iter()
+        .map(|result| match result {
+            Some(result) => result,
+            None => sent
+                .next()
+                .unwrap_or_else(|| Err(Error::custom("missing result in batch"))),
+        })
+        .collect()
+}
+
+/// The param of a `system.multicall` request, a struct with the `methodName` and `params` of
+/// each call.
+pub(super) fn multicall_param(calls: &[Call]) -> Value {
+    let calls = calls
+        .iter()
+        .map(|(method, params)| {
+            let mut call = Map::new();
+            call.insert("methodName".into(), Value::String(method.clone()));
+            call.insert("params".into(), Value::Array(params.to_vec()));
+            Value::Struct(call)
+        })
+        .collect();
+    Value::Array(calls)
+}
+
+/// Splits the response to a `system.multicall` request of `len` calls. Each call either
+/// succeeded with an array of its single param, or failed with a fault struct.
+pub(super) fn multicall_results(response: Value, len: usize) -> Result<Vec<Result<Value>>> {
+    let results = match response {
+        Value::Array(results) if results.len() == len => results,
+        Value::Array(results) => {
+            return Err(Error::custom(format_args!(
+                "system.multicall returned {} results for {} calls",
+                results.len(),
+                len
+            )))
+        }
+        _ => return Err(Error::custom("system.multicall didn't return an array")),
+    };
+
+    results
+        .into_iter()
+        .map(|result| match result {
+            Value::Array(mut values) if values.len() == 1 => Ok(Ok(values.remove(0))),
+            value @ Value::Struct(_) => Ok(Err(Error::Fault(crate::from_value::<Fault>(value)?))),
+            _ => Err(Error::custom(
+                "expected an array of one value or a fault in system.multicall results",
+            )),
+        })
+        .collect()
+}
+
+/// Calls queued on a [`BlockingClient`](super::BlockingClient) to be sent together with
+/// [`send`](Batch::send).
+///
+/// ```no_run
+/// use serde_xmlrpc::client::BlockingClient;
+///
+/// let client = BlockingClient::new("http://localhost:11311/");
+/// let results = client
+///     .batch()
+///     .call("getPid", ("/node",))
+///     .call("getUri", ("/node",))
+///     .send()
+///     .unwrap();
+/// for result in results {
+///     println!("{:?}", result);
+/// }
+/// ```
+#[cfg(feature = "blocking")]
+#[derive(Debug)]
+#[must_use = "a batch does nothing until it's sent"]
+pub struct Batch<'a> {
+    client: &'a super::BlockingClient,
+    calls: Calls,
+}
+
+#[cfg(feature = "blocking")]
+impl<'a> Batch<'a> {
+    pub(super) fn new(client: &'a super::BlockingClient) -> Self {
+        Batch {
+            client,
+            calls: Calls::default(),
+        }
+    }
+
+    /// Queues a call of `method`. The params are converted now with the client's write options,
+    /// and a failure is returned as the result of this call.
+    pub fn call<P: Serialize>(mut self, method: &str, params: P) -> Self {
+        self.calls.push(method, &params, &self.client.write_options);
+        self
+    }
+
+    /// Sets how the calls are sent, [`BatchMode::Auto`] by default.
+    pub fn mode(mut self, mode: BatchMode) -> Self {
+        self.calls.mode = mode;
+        self
+    }
+
+    /// The number of calls queued.
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.len() == 0
+    }
+
+    /// Sends the calls and returns the param or error of each, in the order they were queued.
+    /// Failing to send them at all, such as failing to connect, is returned as a single error.
+    pub fn send(self) -> Result<Vec<Result<Value>>> {
+        let mode = self.calls.mode;
+        let (results, pending) = self.calls.split();
+        if pending.is_empty() {
+            return Ok(merge(results, Vec::new()));
+        }
+
+        let sent = match mode {
+            BatchMode::Auto => match self.client.multicall(&pending) {
+                Err(Error::Fault(_)) => self.client.pipeline(pending)?,
+                sent => sent?,
+            },
+            BatchMode::Multicall => self.client.multicall(&pending)?,
+            BatchMode::Pipelined => self.client.pipeline(pending)?,
+        };
+        Ok(merge(results, sent))
+    }
+}
+
+/// Calls queued on a [`Client`](super::Client) to be sent together with
+/// [`send`](AsyncBatch::send).
+///
+/// ```no_run
+/// # async fn run() -> serde_xmlrpc::Result<()> {
+/// use serde_xmlrpc::client::Client;
+///
+/// let client = Client::new("http://localhost:11311/");
+/// let results = client
+///     .batch()
+///     .call("getPid", ("/node",))
+///     .call("getUri", ("/node",))
+///     .send()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "client")]
+#[derive(Debug)]
+#[must_use = "a batch does nothing until it's sent"]
+pub struct AsyncBatch<'a> {
+    client: &'a super::Client,
+    calls: Calls,
+}
+
+#[cfg(feature = "client")]
+impl<'a> AsyncBatch<'a> {
+    pub(super) fn new(client: &'a super::Client) -> Self {
+        AsyncBatch {
+            client,
+            calls: Calls::default(),
+        }
+    }
+
+    /// Queues a call of `method`. The params are converted now with the client's write options,
+    /// and a failure is returned as the result of this call.
+    pub fn call<P: Serialize>(mut self, method: &str, params: P) -> Self {
+        self.calls.push(method, &params, &self.client.write_options);
+        self
+    }
+
+    /// Sets how the calls are sent, [`BatchMode::Auto`] by default.
+    pub fn mode(mut self, mode: BatchMode) -> Self {
+        self.calls.mode = mode;
+        self
+    }
+
+    /// The number of calls queued.
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.len() == 0
+    }
+
+    /// Sends the calls and returns the param or error of each, in the order they were queued.
+    /// Failing to send them at all, such as failing to connect, is returned as a single error.
+    pub async fn send(self) -> Result<Vec<Result<Value>>> {
+        let mode = self.calls.mode;
+        let (results, pending) = self.calls.split();
+        if pending.is_empty() {
+            return Ok(merge(results, Vec::new()));
+        }
+
+        let sent = match mode {
+            BatchMode::Auto => match self.client.multicall(&pending).await {
+                Err(Error::Fault(_)) => self.client.pipeline(pending).await,
+                sent => sent?,
+            },
+            BatchMode::Multicall => self.client.multicall(&pending).await?,
+            BatchMode::Pipelined => self.client.pipeline(pending).await,
+        };
+        Ok(merge(results, sent))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multicall() {
+        let calls = vec![
+            (
+                "add".to_string(),
+                Values::from(vec![Value::Int(1), Value::Int(2)]),
+            ),
+            ("ping".to_string(), Values::new()),
+        ];
+        let param = multicall_param(&calls);
+        let xml = crate::request_to_string("system.multicall", vec![param]).unwrap();
+        assert!(xml.contains("<name>methodName</name><value><string>add</string></value>"));
+
+        let mut fault = Map::new();
+        fault.insert("faultCode".into(), Value::Int(-32601));
+        fault.insert("faultString".into(), Value::String("no ping".into()));
+        let response = Value::Array(vec![
+            Value::Array(vec![Value::Int(3)]),
+            Value::Struct(fault),
+        ]);
+        let results = multicall_results(response.clone(), 2).unwrap();
+        assert_eq!(results[0].as_ref().unwrap(), &Value::Int(3));
+        match &results[1] {
+            Err(Error::Fault(fault)) => assert_eq!(fault.fault_code, -32601),
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        assert!(multicall_results(response, 3).is_err());
+        assert!(multicall_results(Value::Int(1), 1).is_err());
+        assert!(multicall_results(Value::Array(vec![Value::Int(1)]), 1).is_err());
+    }
+
+    #[test]
+    fn merge_results() {
+        let mut calls = Calls::default();
+        let options = WriteOptions::default();
+        calls.push("a", &(1,), &options);
+        let mut unkeyable = std::collections::BTreeMap::new();
+        unkeyable.insert((1, 2), 3);
+        calls.push("b", &unkeyable, &options);
+        calls.push("c", &(), &options);
+
+        let (results, pending) = calls.split();
+        assert_eq!(pending.len(), 2);
+        let merged = merge(results, vec![Ok(Value::Int(1)), Ok(Value::Nil)]);
+        assert_eq!(merged.len(), 3);
+        assert!(merged[1].is_err());
+        assert_eq!(merged[2].as_ref().unwrap(), &Value::Nil);
+    }
+}
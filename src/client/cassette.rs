@@ -0,0 +1,345 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+
+use super::http::transport_error;
+use super::Transport;
+#[cfg(feature = "client")]
+use super::{AsyncTransport, TransportFuture};
+use crate::{ParseOptions, Result, Value, WriteOptions};
+
+/// When a [`Cassette`] sends calls on to the server it wraps.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RecordMode {
+    /// Replays the calls already recorded and records the others. This is the default, so the
+    /// first run against the server records the cassette and later runs replay it.
+    #[default]
+    Once,
+    /// Only replays, failing calls which weren't recorded, for runs which mustn't reach the
+    /// server such as on CI.
+    Replay,
+    /// Sends every call and records it again, replacing the cassette, to refresh recordings of a
+    /// server which changed.
+    Record,
+}
+
+/// A call read from a request body, its method name and params.
+type Call = (String, Vec<Value>);
+
+/// What a [`Cassette`] does with a call.
+enum Lookup {
+    /// Answers it with a recorded response.
+    Replay(Vec<u8>),
+    /// Sends it on and records the response.
+    Record(Call),
+}
+
+/// A recorded call. The params are canonicalized, so the key of a call doesn't depend on how it
+/// was written.
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    method: String,
+    params: Vec<Value>,
+    /// The body of the response, as a string if it's UTF-8 or `base64` otherwise.
+    response: Value,
+    #[serde(skip)]
+    key: String,
+}
+
+impl Entry {
+    fn new(call: Call, response: &[u8]) -> Result<Self> {
+        let (method, mut params) = call;
+        params.iter_mut().for_each(Value::canonicalize);
+        let response = match std::str::from_utf8(response) {
+            Ok(response) => Value::String(response.into()),
+            Err(_) => Value::Base64(response.to_vec()),
+        };
+        let key = key(&method, &params)?;
+        Ok(Entry {
+            method,
+            params,
+            response,
+            key,
+        })
+    }
+
+    fn response(&self) -> Vec<u8> {
+        match &self.response {
+            Value::Base64(response) => response.clone(),
+            response => response.as_str().unwrap_or_default().as_bytes().to_vec(),
+        }
+    }
+}
+
+/// What a call is matched by, its method name and its params written canonically.
+fn key(method: &str, params: &[Value]) -> Result<String> {
+    WriteOptions::canonical().request_to_string(method, params.to_vec())
+}
+
+#[derive(Debug, Default)]
+struct Tape {
+    entries: Vec<Entry>,
+    /// How many times each key has been replayed in this run.
+    replayed: Vec<(String, usize)>,
+}
+
+impl Tape {
+    /// The response recorded for the call with `key`. A call made more often than it was
+    /// recorded gets its responses in order, then the last one again.
+    fn replay(&mut self, key: &str) -> Option<Vec<u8>> {
+        let count = match self
+            .replayed
+            .iter_mut()
+            .find(|(replayed, _)| replayed == key)
+        {
+            Some((_, count)) => count,
+            None => {
+                self.replayed.push((key.to_string(), 0));
+                &mut self.replayed.last_mut()?.1
+            }
+        };
+        let mut recorded = self.entries.iter().filter(|entry| entry.key == key);
+        let entry = recorded
+            .clone()
+            .nth(*count)
+            .or_else(|| recorded.next_back())?;
+        *count += 1;
+        Some(entry.response())
+    }
+}
+
+/// A transport which records the calls sent through it to a file and replays them in later
+/// runs, for deterministic tests against real servers. Calls are matched by method name and
+/// canonicalized params, see [`Value::canonicalize`].
+///
+/// The cassette file holds an XML-RPC `<array>` with a struct for each call, so it can be
+/// checked in and reviewed like any other fixture. Each recording is saved right away.
+///
+/// ```no_run
+/// # fn run() -> serde_xmlrpc::Result<()> {
+/// use serde_xmlrpc::client::{BlockingClient, Cassette, RecordMode};
+///
+/// let live = BlockingClient::new("http://rpc.example.com/RPC2");
+/// let mode = match std::env::var("RECORD") {
+///     Ok(_) => RecordMode::Record,
+///     Err(_) => RecordMode::Once,
+/// };
+/// let cassette = Cassette::new(live, "tests/cassettes/example.xml")?.mode(mode);
+/// let client = BlockingClient::with_transport(cassette);
+/// let version: String = client.call("version", ())?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// A [`Client`](super::Client) is wrapped the same way with `Client::with_transport`.
+#[derive(Debug)]
+pub struct Cassette<T> {
+    inner: T,
+    path: PathBuf,
+    mode: RecordMode,
+    tape: Mutex<Tape>,
+}
+
+impl<T> Cassette<T> {
+    /// Wraps `inner`, replaying the calls recorded in the file at `path` if it exists.
+    pub fn new(inner: T, path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let entries = if path.exists() {
+            load(&path)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Cassette {
+            inner,
+            path,
+            mode: RecordMode::default(),
+            tape: Mutex::new(Tape {
+                entries,
+                replayed: Vec::new(),
+            }),
+        })
+    }
+
+    /// Sets when calls are sent on to `inner`, [`RecordMode::Once`] by default.
+    /// [`RecordMode::Record`] forgets the calls recorded before.
+    pub fn mode(self, mode: RecordMode) -> Self {
+        if mode == RecordMode::Record {
+            *self.tape() = Tape::default();
+        }
+        Cassette { mode, ..self }
+    }
+
+    /// The path of the cassette file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Unwraps the transport.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn tape(&self) -> MutexGuard<'_, Tape> {
+        self.tape.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Whether the call in `body` is replayed or sent on, as the mode says.
+    fn lookup(&self, body: &[u8]) -> Result<Lookup> {
+        let call = ParseOptions::default().request_from_slice(body)?;
+        let mut params = call.1.clone();
+        params.iter_mut().for_each(Value::canonicalize);
+        let key = key(&call.0, &params)?;
+
+        if self.mode != RecordMode::Record {
+            if let Some(response) = self.tape().replay(&key) {
+                return Ok(Lookup::Replay(response));
+            }
+        }
+        if self.mode == RecordMode::Replay {
+            return Err(transport_error(format!(
+                "no recording of a call of {} with these params in {}",
+                call.0,
+                self.path.display()
+            )));
+        }
+        Ok(Lookup::Record(call))
+    }
+
+    /// Records `response` to the call and saves the cassette.
+    fn record(&self, call: Call, response: &[u8]) -> Result<()> {
+        let entry = Entry::new(call, response)?;
+        let mut tape = self.tape();
+        tape.entries.push(entry);
+        save(&self.path, &tape.entries)
+    }
+}
+
+fn load(path: &Path) -> Result<Vec<Entry>> {
+    let text = fs::read_to_string(path).map_err(transport_error)?;
+    let value = ParseOptions::default()
+        .max_text_len(None)
+        .value_from_str(&text)?;
+    let mut entries: Vec<Entry> = crate::from_value(value)?;
+    for entry in &mut entries {
+        entry.key = key(&entry.method, &entry.params)?;
+    }
+    Ok(entries)
+}
+
+fn save(path: &Path, entries: &[Entry]) -> Result<()> {
+    let text = WriteOptions::default().value_to_string(crate::to_value(entries)?)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(transport_error)?;
+    }
+    fs::write(path, text).map_err(transport_error)
+}
+
+impl<T: Transport> Transport for Cassette<T> {
+    fn send(&self, body: &[u8]) -> Result<Vec<u8>> {
+        let call = match self.lookup(body)? {
+            Lookup::Replay(response) => return Ok(response),
+            Lookup::Record(call) => call,
+        };
+        let response = self.inner.send(body)?;
+        self.record(call, &response)?;
+        Ok(response)
+    }
+}
+
+#[cfg(feature = "client")]
+impl<T: AsyncTransport + Sync> AsyncTransport for Cassette<T> {
+    fn send<'a>(&'a self, body: &'a [u8]) -> TransportFuture<'a> {
+        Box::pin(async move {
+            let call = match self.lookup(body)? {
+                Lookup::Replay(response) => return Ok(response),
+                Lookup::Record(call) => call,
+            };
+            let response = self.inner.send(body).await?;
+            self.record(call, &response)?;
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// A cassette path of its own for each test.
+    fn cassette_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("serde_xmlrpc-{}", std::process::id()));
+        let path = dir.join(format!("{}.xml", name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    /// A server which answers each call with the number of calls so far.
+    fn counter(calls: &AtomicUsize) -> impl Fn(&[u8]) -> Result<Vec<u8>> + '_ {
+        move |_body: &[u8]| {
+            let count = calls.fetch_add(1, Ordering::SeqCst) as i32 + 1;
+            Ok(crate::response_to_string(vec![Value::Int(count)].into_iter())?.into_bytes())
+        }
+    }
+
+    fn call<T: Transport>(transport: &T, params: Vec<Value>) -> i32 {
+        let body = crate::request_to_string("count", params).unwrap();
+        let response = transport.send(body.as_bytes()).unwrap();
+        crate::ParseOptions::default()
+            .response_from_slice(&response)
+            .unwrap()
+    }
+
+    #[test]
+    fn record_replay() {
+        let path = cassette_path("record_replay");
+        let calls = AtomicUsize::new(0);
+
+        let cassette = Cassette::new(counter(&calls), &path).unwrap();
+        assert_eq!(call(&cassette, vec![Value::Int(1)]), 1);
+        assert_eq!(call(&cassette, vec![Value::Int(1)]), 1);
+        assert_eq!(call(&cassette, vec![Value::Int(2)]), 2);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Replayed from the file, with params matched canonically.
+        let cassette = Cassette::new(counter(&calls), &path)
+            .unwrap()
+            .mode(RecordMode::Replay);
+        assert_eq!(call(&cassette, vec![Value::Int64(2)]), 2);
+        assert_eq!(call(&cassette, vec![Value::Int(1)]), 1);
+        let body = crate::request_to_string("count", vec![Value::Int(3)]).unwrap();
+        assert!(cassette.send(body.as_bytes()).is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let cassette = Cassette::new(counter(&calls), &path)
+            .unwrap()
+            .mode(RecordMode::Record);
+        assert_eq!(call(&cassette, vec![Value::Int(1)]), 3);
+        assert_eq!(call(&cassette, vec![Value::Int(1)]), 4);
+        let cassette = Cassette::new(counter(&calls), &path).unwrap();
+        assert_eq!(call(&cassette, vec![Value::Int(1)]), 3);
+        assert_eq!(call(&cassette, vec![Value::Int(1)]), 4);
+        assert_eq!(call(&cassette, vec![Value::Int(1)]), 4);
+        assert_eq!(call(&cassette, vec![Value::Int(2)]), 5);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn binary_response() {
+        let path = cassette_path("binary_response");
+        let cassette = Cassette::new(|_body: &[u8]| Ok(vec![0xff, 0x00]), &path).unwrap();
+        let body = crate::request_to_string("raw", vec![]).unwrap();
+        assert_eq!(cassette.send(body.as_bytes()).unwrap(), [0xff, 0x00]);
+
+        let cassette = Cassette::new(|_body: &[u8]| Ok(Vec::new()), &path)
+            .unwrap()
+            .mode(RecordMode::Replay);
+        assert_eq!(cassette.send(body.as_bytes()).unwrap(), [0xff, 0x00]);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,318 @@
+//! The parts of HTTP/1.1 the clients speak themselves, over connections which aren't made by an
+//! HTTP library such as Unix sockets.
+
+use std::sync::{Arc, Mutex};
+
+use base64::prelude::*;
+
+use crate::http::{check_content_type, check_length, check_status};
+use crate::{Error, Result};
+
+/// How much of a response may be headers on top of the body size limit.
+pub(super) const MAX_HEAD_LEN: u64 = 64 * 1024;
+
+pub(super) fn transport_error(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Error {
+    Error::TransportError(err.into())
+}
+
+/// Cookies by name, in the order they were first set.
+type Cookies = Vec<(String, String)>;
+
+/// The credentials and cookies a client sends with each call.
+#[derive(Clone, Debug, Default)]
+pub(super) struct Session {
+    authorization: Option<String>,
+    cookies: Option<Arc<Mutex<Cookies>>>,
+}
+
+impl Session {
+    pub(super) fn basic_auth(&mut self, username: &str, password: &str) {
+        let credentials = BASE64_STANDARD.encode(format!("{}:{}", username, password));
+        self.authorization = Some(format!("Basic {}", credentials));
+    }
+
+    pub(super) fn bearer_auth(&mut self, token: &str) {
+        self.authorization = Some(format!("Bearer {}", token));
+    }
+
+    /// Starts with an empty cookie store, or drops the store.
+    pub(super) fn cookie_store(&mut self, enable: bool) {
+        self.cookies = if enable { Some(Arc::default()) } else { None };
+    }
+
+    /// The headers to send with a call.
+    pub(super) fn headers(&self) -> Result<Vec<(&'static str, String)>> {
+        let mut headers = Vec::new();
+        if let Some(authorization) = &self.authorization {
+            if authorization.contains(['\r', '\n']) {
+                return Err(transport_error("invalid Authorization header"));
+            }
+            headers.push(("Authorization", authorization.clone()));
+        }
+        if let Some(cookies) = &self.cookies {
+            let cookies = cookies.lock().unwrap_or_else(|e| e.into_inner());
+            if !cookies.is_empty() {
+                let cookies: Vec<_> = cookies
+                    .iter()
+                    .map(|(name, value)| format!("{}={}", name, value))
+                    .collect();
+                headers.push(("Cookie", cookies.join("; ")));
+            }
+        }
+        Ok(headers)
+    }
+
+    /// Remembers the cookie of a `Set-Cookie` header, if cookies are stored. A client only talks
+    /// to one endpoint, so the domain and path are ignored, and a cookie is only removed when it
+    /// is set with `Max-Age=0`.
+    pub(super) fn set_cookie(&self, set_cookie: &str) {
+        let cookies = match &self.cookies {
+            Some(cookies) => cookies,
+            None => return,
+        };
+        let mut parts = set_cookie.split(';');
+        let (name, value) = match parts.next().and_then(|pair| pair.split_once('=')) {
+            Some((name, value)) if !name.trim().is_empty() => (name.trim(), value.trim()),
+            _ => return,
+        };
+        let expired = parts.any(|attribute| match attribute.split_once('=') {
+            Some((key, age)) => {
+                key.trim().eq_ignore_ascii_case("max-age")
+                    && age.trim().parse::<i64>().is_ok_and(|age| age <= 0)
+            }
+            None => false,
+        });
+
+        let mut cookies = cookies.lock().unwrap_or_else(|e| e.into_inner());
+        cookies.retain(|(existing, _)| existing != name);
+        if !expired {
+            cookies.push((name.to_string(), value.to_string()));
+        }
+    }
+}
+
+/// The head of a POST request with a body of `len` bytes and the extra `headers`. Unless
+/// `keep_alive`, the connection is closed after the response, so everything read until then is
+/// the response.
+pub(super) fn request_head(
+    path: &str,
+    host: &str,
+    len: usize,
+    headers: &[(&str, String)],
+    keep_alive: bool,
+) -> String {
+    #[cfg(feature = "gzip")]
+    let accept_encoding = format!("Accept-Encoding: {}\r\n", crate::http::ACCEPT_ENCODING);
+    #[cfg(not(feature = "gzip"))]
+    let accept_encoding = "";
+
+    let mut head = format!(
+        "POST {} HTTP/1.1\r\n\
+         Host: {}\r\n\
+         User-Agent: serde_xmlrpc\r\n\
+         Content-Type: text/xml\r\n\
+         Content-Length: {}\r\n\
+         {}\
+         Connection: {}\r\n",
+        path,
+        host,
+        len,
+        accept_encoding,
+        if keep_alive { "keep-alive" } else { "close" }
+    );
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    head
+}
+
+/// Where a response read from a kept-alive connection ends.
+#[cfg(feature = "blocking")]
+pub(super) struct Framing {
+    /// The length of the response, including its head.
+    pub(super) len: usize,
+    /// Whether the server keeps the connection open after it.
+    pub(super) keep_alive: bool,
+}
+
+/// Finds the end of the first response in `buf`, or returns `None` if it hasn't all been read
+/// yet. A response with neither a length nor chunks ends with the connection, so it's never
+/// complete before then.
+#[cfg(feature = "blocking")]
+pub(super) fn response_framing(buf: &[u8]) -> Result<Option<Framing>> {
+    let malformed = || transport_error("malformed HTTP response");
+    let split = match buf.windows(4).position(|w| w == b"\r\n\r\n") {
+        Some(split) => split + 4,
+        None => return Ok(None),
+    };
+    let head = std::str::from_utf8(&buf[..split]).map_err(|_| malformed())?;
+
+    let mut lines = head.split("\r\n");
+    let status = lines.next().unwrap_or_default();
+    let mut keep_alive = status.starts_with("HTTP/1.1 ");
+    let mut chunked = false;
+    let mut length = None;
+    for line in lines {
+        let (name, value) = match line.find(':') {
+            Some(idx) => (line[..idx].trim(), line[idx + 1..].trim()),
+            None => continue,
+        };
+        if name.eq_ignore_ascii_case("connection") {
+            for option in value.split(',') {
+                if option.trim().eq_ignore_ascii_case("close") {
+                    keep_alive = false;
+                } else if option.trim().eq_ignore_ascii_case("keep-alive") {
+                    keep_alive = true;
+                }
+            }
+        } else if name.eq_ignore_ascii_case("transfer-encoding") {
+            chunked = value.eq_ignore_ascii_case("chunked");
+        } else if name.eq_ignore_ascii_case("content-length") {
+            length = Some(value.parse::<usize>().map_err(|_| malformed())?);
+        }
+    }
+
+    let len = if chunked {
+        match chunked_len(&buf[split..])? {
+            Some(len) => split + len,
+            None => return Ok(None),
+        }
+    } else {
+        match length {
+            Some(length) if buf.len() - split >= length => split + length,
+            _ => return Ok(None),
+        }
+    };
+    Ok(Some(Framing { len, keep_alive }))
+}
+
+/// The length of a chunked body at the start of `body`, including its trailers, or `None` if it
+/// hasn't all been read yet.
+#[cfg(feature = "blocking")]
+fn chunked_len(body: &[u8]) -> Result<Option<usize>> {
+    let malformed = || transport_error("malformed chunked HTTP response");
+    let mut pos = 0;
+
+    loop {
+        let line_end = match body[pos..].windows(2).position(|w| w == b"\r\n") {
+            Some(line_end) => pos + line_end,
+            None => return Ok(None),
+        };
+        let size = std::str::from_utf8(&body[pos..line_end]).map_err(|_| malformed())?;
+        let size = size.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16).map_err(|_| malformed())?;
+        pos = line_end + 2;
+
+        if size == 0 {
+            // The trailers end with an empty line.
+            let rest = &body[pos..];
+            return Ok(if rest.starts_with(b"\r\n") {
+                Some(pos + 2)
+            } else {
+                rest.windows(4)
+                    .position(|w| w == b"\r\n\r\n")
+                    .map(|end| pos + end + 4)
+            });
+        }
+        pos = match size.checked_add(2).and_then(|size| pos.checked_add(size)) {
+            Some(end) if end <= body.len() => end,
+            _ => return Ok(None),
+        };
+    }
+}
+
+/// Checks an HTTP response with the [`crate::http`] checks, with a body of at most `limit` bytes,
+/// and returns its body. Cookies are stored in `session`.
+pub(super) fn read_response(
+    response: &[u8],
+    limit: Option<u64>,
+    session: Option<&Session>,
+) -> Result<Vec<u8>> {
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| transport_error("malformed HTTP response"))?;
+    let head = std::str::from_utf8(&response[..split])
+        .map_err(|_| transport_error("malformed HTTP response headers"))?;
+    let body = &response[split + 4..];
+
+    let mut lines = head.split("\r\n");
+    let status = lines.next().unwrap_or_default();
+    let code = status.split(' ').nth(1).unwrap_or_default();
+    let code = code
+        .parse()
+        .map_err(|_| transport_error("malformed HTTP status line"))?;
+    check_status(code)?;
+
+    let mut chunked = false;
+    let mut length = None;
+    #[cfg(feature = "gzip")]
+    let mut encoding = "";
+    for line in lines {
+        let (name, value) = match line.find(':') {
+            Some(idx) => (line[..idx].trim(), line[idx + 1..].trim()),
+            None => continue,
+        };
+        if name.eq_ignore_ascii_case("transfer-encoding") {
+            chunked = value.eq_ignore_ascii_case("chunked");
+        } else if name.eq_ignore_ascii_case("content-length") {
+            length = value.parse::<usize>().ok();
+        } else if name.eq_ignore_ascii_case("content-type") {
+            check_content_type(Some(value))?;
+        } else if name.eq_ignore_ascii_case("set-cookie") {
+            if let Some(session) = session {
+                session.set_cookie(value);
+            }
+        }
+        #[cfg(feature = "gzip")]
+        if name.eq_ignore_ascii_case("content-encoding") {
+            encoding = value;
+        }
+    }
+
+    if let Some(length) = length {
+        check_length(length as u64, limit)?;
+    }
+
+    let body = if chunked {
+        dechunk(body)?
+    } else {
+        match length {
+            Some(length) if length <= body.len() => body[..length].to_vec(),
+            Some(_) => return Err(transport_error("HTTP response ended early")),
+            None => body.to_vec(),
+        }
+    };
+    #[cfg(feature = "gzip")]
+    let body = crate::http::decode_body(encoding, &body, limit)?;
+    check_length(body.len() as u64, limit)?;
+    Ok(body)
+}
+
+/// Joins the chunks of a body sent with `Transfer-Encoding: chunked`.
+fn dechunk(mut body: &[u8]) -> Result<Vec<u8>> {
+    let malformed = || transport_error("malformed chunked HTTP response");
+    let mut ret = Vec::new();
+
+    loop {
+        let line_end = body
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or_else(malformed)?;
+        let size = std::str::from_utf8(&body[..line_end]).map_err(|_| malformed())?;
+        // Chunk extensions follow a `;` and can be ignored.
+        let size = size.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16).map_err(|_| malformed())?;
+        body = &body[line_end + 2..];
+
+        if size == 0 {
+            return Ok(ret);
+        }
+        if body.len() < size + 2 {
+            return Err(malformed());
+        }
+        ret.extend_from_slice(&body[..size]);
+        body = &body[size + 2..];
+    }
+}
@@ -0,0 +1,84 @@
+//! XML-RPC clients over HTTP.
+//!
+//! With the `client` feature, `Client` makes calls asynchronously using `reqwest`. With the
+//! `blocking` feature, `BlockingClient` offers the same interface on top of the standard library
+//! alone, for programs which don't want an async runtime.
+//!
+//! Both clients can also call servers listening on a Unix socket, such as supervisord, and
+//! `BlockingClient::with_connector` and `call_stream` make calls over any other stream.
+//!
+//! Both clients keep connections open between calls to save a handshake for each, as set with
+//! [`PoolOptions`].
+//!
+//! `Client` verifies `https://` servers against the system's root certificates. [`TlsOptions`]
+//! add the certificate of a self-signed server or an internal CA, or present a client
+//! certificate. `BlockingClient` only speaks plain HTTP, and takes TLS streams from
+//! `BlockingClient::with_connector`.
+//!
+//! Failed calls can be retried with a [`RetryPolicy`], and `deadline` limits how long a call may
+//! take with all its retries.
+//!
+//! Calls which don't depend on each other can be queued in a [`Batch`] or [`AsyncBatch`] and sent
+//! together, with `system.multicall` when the server has it, saving a round trip for each.
+//!
+//! For anything else, such as another HTTP stack, retries or recorded responses, both clients
+//! take a [`Transport`] or [`AsyncTransport`] which only moves the encoded bytes. With the
+//! `test-util` feature, a `Cassette` transport records the calls made to a real server and replays
+//! them in later test runs.
+//!
+//! With the `tracing` feature, each call is recorded in a `call` span under the
+//! `serde_xmlrpc::client` target, with the method name, param count, body sizes, duration and
+//! outcome.
+
+#[cfg(feature = "client")]
+mod async_client;
+mod batch;
+#[cfg(feature = "blocking")]
+mod blocking;
+#[cfg(feature = "test-util")]
+mod cassette;
+mod http;
+mod pool;
+mod retry;
+#[cfg(feature = "client")]
+mod tls;
+mod transport;
+
+#[cfg(feature = "client")]
+pub use async_client::{call_stream, Client};
+#[cfg(feature = "client")]
+pub use batch::AsyncBatch;
+#[cfg(feature = "blocking")]
+pub use batch::Batch;
+pub use batch::BatchMode;
+#[cfg(feature = "blocking")]
+pub use blocking::BlockingClient;
+#[cfg(feature = "test-util")]
+pub use cassette::{Cassette, RecordMode};
+pub use pool::PoolOptions;
+pub use retry::RetryPolicy;
+#[cfg(feature = "client")]
+pub use tls::TlsOptions;
+pub use transport::{AsyncTransport, Transport, TransportFuture};
+
+/// The result of `system.methodSignature`, which is `"undef"` when the server doesn't know the
+/// signatures.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum Signatures {
+    Known(Vec<Vec<String>>),
+    Unknown(String),
+}
+
+impl Signatures {
+    fn into_vec(self) -> crate::Result<Vec<Vec<String>>> {
+        match self {
+            Signatures::Known(signatures) => Ok(signatures),
+            Signatures::Unknown(ref s) if s == "undef" => Ok(Vec::new()),
+            Signatures::Unknown(s) => Err(serde::de::Error::invalid_value(
+                serde::de::Unexpected::Str(&s),
+                &"an array of signatures or \"undef\"",
+            )),
+        }
+    }
+}
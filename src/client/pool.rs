@@ -0,0 +1,163 @@
+#[cfg(feature = "blocking")]
+use std::fmt;
+#[cfg(feature = "blocking")]
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+#[cfg(feature = "blocking")]
+use std::time::Instant;
+
+/// How a client keeps connections open between calls, so repeated calls to the same server skip
+/// the handshake, set with `BlockingClient::pool` or `Client::pool`.
+///
+/// `BlockingClient` pools its TCP and Unix socket connections, but not streams from a
+/// connector. `Client` passes the options on to the `reqwest::Client` it creates, so they don't
+/// apply to one given to `Client::with_client`, nor to Unix sockets.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use serde_xmlrpc::client::PoolOptions;
+///
+/// let options = PoolOptions::new()
+///     .max_idle(2)
+///     .idle_timeout(Some(Duration::from_secs(10)));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolOptions {
+    pub(crate) keep_alive: bool,
+    pub(crate) max_idle: usize,
+    pub(crate) idle_timeout: Option<Duration>,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        PoolOptions::new()
+    }
+}
+
+impl PoolOptions {
+    /// Keeps up to 8 connections open while they're idle, for up to 90 seconds.
+    pub const fn new() -> Self {
+        PoolOptions {
+            keep_alive: true,
+            max_idle: 8,
+            idle_timeout: Some(Duration::from_secs(90)),
+        }
+    }
+
+    /// Closes each connection after its call, as servers which can't keep connections open
+    /// need.
+    pub const fn disabled() -> Self {
+        PoolOptions {
+            keep_alive: false,
+            max_idle: 0,
+            idle_timeout: None,
+        }
+    }
+
+    /// Asks the server to keep connections open with HTTP/1.1 keep-alive. Without it, each
+    /// connection is closed after its call.
+    pub const fn keep_alive(mut self, enable: bool) -> Self {
+        self.keep_alive = enable;
+        self
+    }
+
+    /// Sets the most connections kept open while idle. Connections beyond that are closed once
+    /// their call is done.
+    pub const fn max_idle(mut self, max: usize) -> Self {
+        self.max_idle = max;
+        self
+    }
+
+    /// Closes connections which have been idle for longer than `timeout`, or never if `None`.
+    pub const fn idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Whether connections are kept open at all.
+    pub(crate) const fn enabled(&self) -> bool {
+        self.keep_alive && self.max_idle > 0
+    }
+}
+
+/// The idle connections of a client, shared by its clones.
+#[cfg(feature = "blocking")]
+pub(super) struct Pool<T> {
+    idle: Arc<Mutex<Vec<(T, Instant)>>>,
+}
+
+#[cfg(feature = "blocking")]
+impl<T> Pool<T> {
+    /// Takes the most recently used connection which hasn't been idle for too long.
+    pub(super) fn take(&self, options: &PoolOptions) -> Option<T> {
+        let mut idle = self.idle.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(timeout) = options.idle_timeout {
+            idle.retain(|(_, since)| since.elapsed() < timeout);
+        }
+        idle.pop().map(|(connection, _)| connection)
+    }
+
+    /// Keeps `connection` for a later call, unless the pool is full.
+    pub(super) fn put(&self, connection: T, options: &PoolOptions) {
+        let mut idle = self.idle.lock().unwrap_or_else(|err| err.into_inner());
+        if idle.len() < options.max_idle {
+            idle.push((connection, Instant::now()));
+        }
+    }
+
+    #[cfg(test)]
+    pub(super) fn len(&self) -> usize {
+        self.idle.lock().unwrap().len()
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<T> Clone for Pool<T> {
+    fn clone(&self) -> Self {
+        Pool {
+            idle: Arc::clone(&self.idle),
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<T> Default for Pool<T> {
+    fn default() -> Self {
+        Pool {
+            idle: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<T> fmt::Debug for Pool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let idle = self.idle.lock().map_or(0, |idle| idle.len());
+        f.debug_struct("Pool").field("idle", &idle).finish()
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "blocking")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pool() {
+        let options = PoolOptions::new().max_idle(2);
+        let pool = Pool::default();
+        pool.put(1, &options);
+        pool.put(2, &options);
+        pool.put(3, &options);
+        assert_eq!(pool.take(&options), Some(2));
+        assert_eq!(pool.clone().take(&options), Some(1));
+        assert_eq!(pool.take(&options), None);
+
+        pool.put(1, &options);
+        let expired = options.idle_timeout(Some(Duration::ZERO));
+        assert_eq!(pool.take(&expired), None);
+        assert!(!PoolOptions::disabled().enabled());
+        assert!(!PoolOptions::new().keep_alive(false).enabled());
+    }
+}
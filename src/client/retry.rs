@@ -0,0 +1,207 @@
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+
+use super::http::transport_error;
+use crate::http::HttpError;
+use crate::Error;
+
+/// The introspection methods, which only read, so retrying them is always safe.
+const INTROSPECTION: [&str; 4] = [
+    "system.listMethods",
+    "system.methodSignature",
+    "system.methodHelp",
+    "system.getCapabilities",
+];
+
+/// When a client retries a failed call, set with `BlockingClient::retry` or `Client::retry`.
+///
+/// A call is retried when it failed in a way another attempt might not, which is a transport
+/// error such as a refused connection or a timeout, a `429`, `502`, `503` or `504` status, or a
+/// fault with one of the [`fault_code`](RetryPolicy::fault_code)s. Retrying could run a method
+/// twice, so only the introspection methods and those marked
+/// [`idempotent`](RetryPolicy::idempotent) are retried unless
+/// [`all_methods`](RetryPolicy::all_methods) is set.
+///
+/// The first retry waits for the initial backoff, and each one after that twice as long as the
+/// last, up to the maximum.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use serde_xmlrpc::client::RetryPolicy;
+///
+/// // This server faults with code 503 while it's starting up.
+/// let policy = RetryPolicy::new(3)
+///     .backoff(Duration::from_millis(50), Duration::from_secs(1))
+///     .idempotent("supervisor.getProcessInfo")
+///     .fault_code(503);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    pub(crate) max_retries: u32,
+    pub(crate) initial_backoff: Duration,
+    pub(crate) max_backoff: Duration,
+    pub(crate) idempotent: BTreeSet<String>,
+    pub(crate) all_methods: bool,
+    pub(crate) fault_codes: BTreeSet<i32>,
+}
+
+impl Default for RetryPolicy {
+    /// Never retries.
+    fn default() -> Self {
+        RetryPolicy::new(0)
+    }
+}
+
+impl RetryPolicy {
+    /// Retries a call up to `max_retries` times, starting with a backoff of 100 milliseconds and
+    /// waiting at most 10 seconds between attempts.
+    pub fn new(max_retries: u32) -> Self {
+        RetryPolicy {
+            max_retries,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            idempotent: BTreeSet::new(),
+            all_methods: false,
+            fault_codes: BTreeSet::new(),
+        }
+    }
+
+    /// Sets how many times a call is retried.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the wait before the first retry and the most to wait between attempts.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Marks `method` as safe to call more than once, so it's retried.
+    pub fn idempotent(mut self, method: impl Into<String>) -> Self {
+        self.idempotent.insert(method.into());
+        self
+    }
+
+    /// Retries every method, whether or not it's idempotent. Off by default.
+    pub fn all_methods(mut self, enable: bool) -> Self {
+        self.all_methods = enable;
+        self
+    }
+
+    /// Retries faults with `code`, for servers which fault when they're busy or not ready yet.
+    /// Other faults are returned right away.
+    pub fn fault_code(mut self, code: i32) -> Self {
+        self.fault_codes.insert(code);
+        self
+    }
+
+    /// Whether a call of `method` which failed with `err` after `retries` retries is retried.
+    pub(crate) fn should_retry(&self, method: &str, err: &Error, retries: u32) -> bool {
+        if retries >= self.max_retries {
+            return false;
+        }
+        if !self.all_methods
+            && !self.idempotent.contains(method)
+            && !INTROSPECTION.contains(&method)
+        {
+            return false;
+        }
+
+        match err {
+            Error::Fault(fault) => self.fault_codes.contains(&fault.fault_code),
+            Error::TransportError(err) => match err.downcast_ref::<HttpError>() {
+                Some(HttpError::Status(status)) => matches!(status, 429 | 502 | 503 | 504),
+                Some(_) => false,
+                None => true,
+            },
+            _ => false,
+        }
+    }
+
+    /// How long to wait before retry number `retries`, counting from zero.
+    pub(crate) fn backoff_for(&self, retries: u32) -> Duration {
+        let factor = 2u32.checked_pow(retries).unwrap_or(u32::MAX);
+        self.initial_backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+}
+
+/// The time left until `deadline`, or an error if it has passed.
+pub(super) fn remaining(deadline: Instant) -> crate::Result<Duration> {
+    match deadline.checked_duration_since(Instant::now()) {
+        Some(remaining) if remaining > Duration::ZERO => Ok(remaining),
+        _ => Err(timed_out()),
+    }
+}
+
+/// How long the next attempt at a call may take, given the `timeout` of each attempt and the
+/// `deadline` of the whole call.
+pub(super) fn attempt_timeout(
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+) -> crate::Result<Option<Duration>> {
+    Ok(match deadline {
+        Some(deadline) => {
+            let remaining = remaining(deadline)?;
+            Some(timeout.map_or(remaining, |timeout| timeout.min(remaining)))
+        }
+        None => timeout,
+    })
+}
+
+/// The error for a call which ran out of time.
+pub(super) fn timed_out() -> Error {
+    transport_error(std::io::Error::new(
+        std::io::ErrorKind::TimedOut,
+        "the call timed out",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Fault;
+
+    #[test]
+    fn should_retry() {
+        let policy = RetryPolicy::new(2).idempotent("get").fault_code(7);
+        let offline = transport_error("offline");
+
+        assert!(policy.should_retry("get", &offline, 0));
+        assert!(policy.should_retry("get", &offline, 1));
+        assert!(!policy.should_retry("get", &offline, 2));
+        assert!(policy.should_retry("system.listMethods", &offline, 0));
+        assert!(!policy.should_retry("set", &offline, 0));
+        assert!(policy
+            .clone()
+            .all_methods(true)
+            .should_retry("set", &offline, 0));
+
+        assert!(policy.should_retry("get", &HttpError::Status(503).into(), 0));
+        assert!(!policy.should_retry("get", &HttpError::Status(500).into(), 0));
+        let too_large = HttpError::TooLarge {
+            length: 2,
+            limit: 1,
+        };
+        assert!(!policy.should_retry("get", &too_large.into(), 0));
+
+        assert!(policy.should_retry("get", &Error::Fault(Fault::new(7, "busy")), 0));
+        assert!(!policy.should_retry("get", &Error::Fault(Fault::new(8, "bad")), 0));
+        assert!(!RetryPolicy::default().should_retry("get", &offline, 0));
+    }
+
+    #[test]
+    fn backoff() {
+        let policy =
+            RetryPolicy::new(10).backoff(Duration::from_millis(100), Duration::from_secs(1));
+        assert_eq!(policy.backoff_for(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff_for(2), Duration::from_millis(400));
+        assert_eq!(policy.backoff_for(4), Duration::from_secs(1));
+        assert_eq!(policy.backoff_for(40), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,79 @@
+//! Traits for supplying the clients' transports, which send an encoded request and return the
+//! encoded response.
+
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::Result;
+
+/// Sends the body of a request and returns the body of the response, for a
+/// [`BlockingClient`](super::BlockingClient) made with
+/// [`with_transport`](super::BlockingClient::with_transport).
+///
+/// The client encodes the call and decodes the response, so a transport only moves bytes. This
+/// allows another HTTP stack, retries, or recorded responses in tests. A transport should return
+/// failures to reach the server as [`Error::TransportError`](crate::Error::TransportError).
+///
+/// Closures taking the body are transports:
+///
+/// ```
+/// use serde_xmlrpc::client::BlockingClient;
+/// use serde_xmlrpc::Value;
+///
+/// let client = BlockingClient::with_transport(|_body: &[u8]| {
+///     let response = serde_xmlrpc::response_to_string(vec![Value::Int(3)].into_iter())?;
+///     Ok(response.into_bytes())
+/// });
+/// assert_eq!(client.call::<_, i32>("add", (1, 2)).unwrap(), 3);
+/// ```
+pub trait Transport {
+    /// Sends `body` and returns the body of the response.
+    fn send(&self, body: &[u8]) -> Result<Vec<u8>>;
+}
+
+impl<F> Transport for F
+where
+    F: Fn(&[u8]) -> Result<Vec<u8>>,
+{
+    fn send(&self, body: &[u8]) -> Result<Vec<u8>> {
+        self(body)
+    }
+}
+
+/// The future returned by an [`AsyncTransport`].
+pub type TransportFuture<'a> = Pin<Box<dyn Future<Output = Result<Vec<u8>>> + Send + 'a>>;
+
+/// The async version of [`Transport`], for a `Client` made with `Client::with_transport`.
+///
+/// ```
+/// use serde_xmlrpc::client::{AsyncTransport, TransportFuture};
+///
+/// /// Retries failed requests once.
+/// struct Retry<T>(T);
+///
+/// impl<T: AsyncTransport + Sync> AsyncTransport for Retry<T> {
+///     fn send<'a>(&'a self, body: &'a [u8]) -> TransportFuture<'a> {
+///         Box::pin(async move {
+///             match self.0.send(body).await {
+///                 Err(serde_xmlrpc::Error::TransportError(_)) => self.0.send(body).await,
+///                 result => result,
+///             }
+///         })
+///     }
+/// }
+/// ```
+pub trait AsyncTransport {
+    /// Sends `body` and resolves to the body of the response.
+    fn send<'a>(&'a self, body: &'a [u8]) -> TransportFuture<'a>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closure_transport() {
+        let echo = |body: &[u8]| Ok(body.to_vec());
+        assert_eq!(Transport::send(&echo, b"<value/>").unwrap(), b"<value/>");
+    }
+}
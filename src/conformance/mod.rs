@@ -0,0 +1,214 @@
+//! A corpus of XML-RPC documents with the bytes this crate writes for each, for checking that
+//! another parser or writer behaves the same.
+//!
+//! Each [`Case`] pairs a document as some implementation writes it, such as the examples of the
+//! spec or the output of Python's `xmlrpc.client`, with a golden file holding what this crate
+//! writes for it with the default [`WriteOptions`](crate::WriteOptions). [`check`] runs a parser
+//! and a writer over the whole corpus and returns where they differ from this crate, so a change
+//! of XML backend or a fork can show it reads every document the same way and writes the same
+//! bytes.
+//!
+//! ```
+//! use serde_xmlrpc::conformance;
+//! use serde_xmlrpc::Document;
+//!
+//! let mismatches = conformance::check(|input| input.parse(), Document::to_xml);
+//! assert!(mismatches.is_empty(), "{:?}", mismatches);
+//! ```
+//!
+//! The golden files are part of this crate's behavior: a change to the bytes written for any of
+//! them is a change to what peers receive.
+
+use std::fmt;
+
+use crate::{Document, ParseOptions, Result};
+
+/// Where the document of a [`Case`] comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// The examples of the XML-RPC specification.
+    Spec,
+    /// Python's `xmlrpc.client`, with `allow_none`.
+    Python,
+    /// Apache XML-RPC for Java, with its extensions enabled.
+    Apache,
+    /// PHP's phpxmlrpc library.
+    Php,
+    /// Forms the spec allows but implementations rarely write, such as empty values.
+    EdgeCase,
+}
+
+/// A document of the corpus.
+#[derive(Clone, Copy, Debug)]
+pub struct Case {
+    name: &'static str,
+    source: Source,
+    input: &'static str,
+    golden: &'static str,
+}
+
+macro_rules! case {
+    ($name:literal, $source:ident) => {
+        Case {
+            name: $name,
+            source: Source::$source,
+            input: include_str!(concat!("corpus/", $name, ".xml")),
+            golden: include_str!(concat!("corpus/", $name, ".golden.xml")),
+        }
+    };
+}
+
+/// Every document of the corpus.
+pub const CASES: &[Case] = &[
+    case!("spec-call", Spec),
+    case!("spec-response", Spec),
+    case!("spec-fault", Spec),
+    case!("spec-scalars", Spec),
+    case!("spec-compound", Spec),
+    case!("python-call", Python),
+    case!("python-fault", Python),
+    case!("apache-response", Apache),
+    case!("php-response", Php),
+    case!("no-params", EdgeCase),
+    case!("empty-values", EdgeCase),
+    case!("cdata", EdgeCase),
+    case!("extensions", EdgeCase),
+];
+
+impl Case {
+    /// The name of the case, which is also the name of its files.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn source(&self) -> Source {
+        self.source
+    }
+
+    /// The document as its source writes it.
+    pub fn input(&self) -> &'static str {
+        self.input
+    }
+
+    /// What this crate writes for the document with the default
+    /// [`WriteOptions`](crate::WriteOptions).
+    pub fn golden(&self) -> &'static str {
+        self.golden
+    }
+
+    /// The document as this crate reads it with the default [`ParseOptions`].
+    pub fn document(&self) -> Document {
+        ParseOptions::default()
+            .document_from_str(self.input)
+            .unwrap_or_else(|err| panic!("conformance case {} doesn't parse: {}", self.name, err))
+    }
+
+    /// Checks that `parse` reads the input and the golden file as the same document as this
+    /// crate does, and that `write` writes that document as the golden file.
+    pub fn check<P, W>(&self, parse: P, write: W) -> Vec<Mismatch>
+    where
+        P: Fn(&str) -> Result<Document>,
+        W: Fn(&Document) -> Result<String>,
+    {
+        let expected = self.document();
+        let mut mismatches = Vec::new();
+        let mut mismatch = |problem: String| {
+            mismatches.push(Mismatch {
+                case: self.name,
+                problem,
+            })
+        };
+
+        for (file, text) in [("input", self.input), ("golden file", self.golden)] {
+            match parse(text) {
+                Ok(document) if document == expected => {}
+                Ok(document) => mismatch(format!("read the {} as {:?}", file, document)),
+                Err(err) => mismatch(format!("failed to read the {}: {}", file, err)),
+            }
+        }
+        match write(&expected) {
+            Ok(written) if written == self.golden => {}
+            Ok(written) => mismatch(format!("wrote {}", written)),
+            Err(err) => mismatch(format!("failed to write: {}", err)),
+        }
+        mismatches
+    }
+}
+
+/// A way a parser or writer differs from this crate on a [`Case`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The name of the case.
+    pub case: &'static str,
+    /// What differs.
+    pub problem: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.case, self.problem)
+    }
+}
+
+/// Runs [`Case::check`] on every case of the corpus and returns the mismatches, which are empty
+/// if `parse` and `write` behave like this crate's defaults.
+pub fn check<P, W>(parse: P, write: W) -> Vec<Mismatch>
+where
+    P: Fn(&str) -> Result<Document>,
+    W: Fn(&Document) -> Result<String>,
+{
+    CASES
+        .iter()
+        .flat_map(|case| case.check(&parse, &write))
+        .collect()
+}
+
+/// Panics with a list of the mismatches if `parse` and `write` don't behave like this crate's
+/// defaults on every case of the corpus. See [`check`].
+pub fn assert_conforms<P, W>(parse: P, write: W)
+where
+    P: Fn(&str) -> Result<Document>,
+    W: Fn(&Document) -> Result<String>,
+{
+    let mismatches = check(parse, write);
+    if !mismatches.is_empty() {
+        let list: Vec<String> = mismatches.iter().map(Mismatch::to_string).collect();
+        panic!(
+            "{} conformance mismatches:\n{}",
+            mismatches.len(),
+            list.join("\n")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::Error as _;
+
+    use super::*;
+    use crate::WriteOptions;
+
+    #[test]
+    fn defaults_conform() {
+        assert_conforms(|input| input.parse(), Document::to_xml);
+        assert_conforms(
+            |input| ParseOptions::lenient().document_from_str(input),
+            |document| WriteOptions::lenient().document_to_string(document),
+        );
+    }
+
+    #[test]
+    fn mismatches() {
+        let failing = |_: &str| Err(crate::Error::custom("unsupported"));
+        assert_eq!(check(failing, Document::to_xml).len(), 2 * CASES.len());
+
+        let padded = |document: &Document| Ok(format!("{}\n", document.to_xml()?));
+        let mismatches = check(|input| input.parse(), padded);
+        assert_eq!(mismatches.len(), CASES.len());
+        assert!(mismatches[0].to_string().starts_with("spec-call: wrote"));
+
+        let cdata = CASES.iter().find(|case| case.name() == "cdata").unwrap();
+        assert_eq!(cdata.source(), Source::EdgeCase);
+        assert!(cdata.input().contains("<![CDATA["));
+    }
+}
@@ -0,0 +1,95 @@
+//! Conversions between [`XmlRpcDateTime`] and `chrono`'s types.
+//!
+//! A naive date/time converts with an offset of zero. Converting back fails for dates which
+//! don't exist, such as February 31st, and for leap seconds.
+
+use core::convert::TryFrom;
+
+use ::chrono::{
+    DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc,
+};
+
+use super::XmlRpcDateTime;
+use crate::Error;
+
+impl From<NaiveDateTime> for XmlRpcDateTime {
+    fn from(dt: NaiveDateTime) -> Self {
+        XmlRpcDateTime {
+            year: dt.year(),
+            month: dt.month(),
+            day: dt.day(),
+            hour: dt.hour(),
+            minute: dt.minute(),
+            // chrono keeps leap seconds in the nanoseconds.
+            second: dt.second() + dt.nanosecond() / 1_000_000_000,
+            millisecond: dt.nanosecond() % 1_000_000_000 / 1_000_000,
+            offset_minutes: 0,
+        }
+    }
+}
+
+impl<Tz: TimeZone> From<DateTime<Tz>> for XmlRpcDateTime {
+    fn from(dt: DateTime<Tz>) -> Self {
+        use ::chrono::Offset;
+
+        let offset = dt.offset().fix().local_minus_utc() / 60;
+        XmlRpcDateTime {
+            offset_minutes: offset,
+            ..XmlRpcDateTime::from(dt.naive_local())
+        }
+    }
+}
+
+/// Takes the wall-clock time and drops the offset.
+impl TryFrom<XmlRpcDateTime> for NaiveDateTime {
+    type Error = Error;
+
+    fn try_from(dt: XmlRpcDateTime) -> Result<Self, Self::Error> {
+        let date = NaiveDate::from_ymd_opt(dt.year, dt.month, dt.day);
+        let time = NaiveTime::from_hms_milli_opt(dt.hour, dt.minute, dt.second, dt.millisecond);
+        match (date, time) {
+            (Some(date), Some(time)) => Ok(date.and_time(time)),
+            _ => Err(dt.out_of_range()),
+        }
+    }
+}
+
+impl TryFrom<XmlRpcDateTime> for DateTime<FixedOffset> {
+    type Error = Error;
+
+    fn try_from(dt: XmlRpcDateTime) -> Result<Self, Self::Error> {
+        let naive = NaiveDateTime::try_from(dt)?;
+        FixedOffset::east_opt(dt.offset_minutes * 60)
+            .and_then(|offset| offset.from_local_datetime(&naive).single())
+            .ok_or_else(|| dt.out_of_range())
+    }
+}
+
+/// Converts to UTC using the offset, so a value without a timezone is taken as UTC.
+impl TryFrom<XmlRpcDateTime> for DateTime<Utc> {
+    type Error = Error;
+
+    fn try_from(dt: XmlRpcDateTime) -> Result<Self, Self::Error> {
+        DateTime::<FixedOffset>::try_from(dt).map(|dt| dt.with_timezone(&Utc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chrono_roundtrip() {
+        let dt: XmlRpcDateTime = "2024-03-09T07:05:04.020-05:30".parse().unwrap();
+        let chrono = DateTime::<FixedOffset>::try_from(dt).unwrap();
+        assert_eq!(chrono.offset().local_minus_utc(), -330 * 60);
+        assert_eq!(chrono.timestamp_subsec_millis(), 20);
+        assert_eq!(XmlRpcDateTime::from(chrono), dt);
+
+        let utc = DateTime::<Utc>::try_from(dt).unwrap();
+        assert_eq!((utc.hour(), utc.minute()), (12, 35));
+
+        let invalid: XmlRpcDateTime = "2023-02-29T00:00:00".parse().unwrap();
+        assert!(NaiveDateTime::try_from(invalid).is_err());
+    }
+}
@@ -0,0 +1,328 @@
+//! The [`XmlRpcDateTime`] type for `<dateTime.iso8601>` values.
+
+use core::fmt;
+use core::str::FromStr;
+
+use crate::{DateTimeFormat, Error, ParseOptions};
+
+#[cfg(feature = "chrono")]
+mod chrono;
+mod parse;
+#[cfg(feature = "time")]
+mod time;
+
+pub(crate) use parse::parse_datetime;
+
+/// A `<dateTime.iso8601>` value: a calendar date and wall-clock time with millisecond precision,
+/// and the offset from UTC it was given in.
+///
+/// XML-RPC dates often don't say which timezone they're in. Those are read with an offset of
+/// zero, the same as UTC, so check with your peer what its dates mean.
+///
+/// Converts to and from [`iso8601::DateTime`], and to and from the `chrono` and `time` crates'
+/// types with the `chrono` and `time` features enabled.
+///
+/// ```
+/// use serde_xmlrpc::XmlRpcDateTime;
+///
+/// let dt: XmlRpcDateTime = "19980717T14:08:55".parse().unwrap();
+/// assert_eq!((dt.year(), dt.month(), dt.day()), (1998, 7, 17));
+/// assert_eq!(dt.to_string(), "1998-07-17T14:08:55.000Z");
+///
+/// let dt = XmlRpcDateTime::new(1998, 7, 17, 14, 8, 55)
+///     .and_then(|dt| dt.with_offset_minutes(120))
+///     .unwrap();
+/// assert_eq!(dt.to_string(), "1998-07-17T14:08:55.000+02:00");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct XmlRpcDateTime {
+    pub(crate) year: i32,
+    pub(crate) month: u32,
+    pub(crate) day: u32,
+    pub(crate) hour: u32,
+    pub(crate) minute: u32,
+    pub(crate) second: u32,
+    pub(crate) millisecond: u32,
+    pub(crate) offset_minutes: i32,
+}
+
+impl XmlRpcDateTime {
+    /// Returns the date/time with an offset of zero, or `None` if a field is out of range or the
+    /// day doesn't exist in the month, such as February 29th outside leap years. A `second` of 60
+    /// is accepted for leap seconds.
+    pub fn new(
+        year: i32,
+        month: u32,
+        day: u32,
+        hour: u32,
+        minute: u32,
+        second: u32,
+    ) -> Option<Self> {
+        let in_range = (1..=12).contains(&month)
+            && (1..=days_in_month(year, month)).contains(&day)
+            && hour < 24
+            && minute < 60
+            && second <= 60;
+        if !in_range {
+            return None;
+        }
+
+        Some(XmlRpcDateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            millisecond: 0,
+            offset_minutes: 0,
+        })
+    }
+
+    /// Sets the milliseconds. Returns `None` if `millisecond` is 1000 or more.
+    pub fn with_millisecond(mut self, millisecond: u32) -> Option<Self> {
+        if millisecond >= 1000 {
+            return None;
+        }
+        self.millisecond = millisecond;
+        Some(self)
+    }
+
+    /// Sets the offset from UTC in minutes, positive east of Greenwich. The wall-clock time stays
+    /// the same. Returns `None` if the offset is a day or more.
+    pub fn with_offset_minutes(mut self, offset_minutes: i32) -> Option<Self> {
+        if offset_minutes.abs() >= 24 * 60 {
+            return None;
+        }
+        self.offset_minutes = offset_minutes;
+        Some(self)
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    /// The month, from 1 to 12.
+    pub fn month(&self) -> u32 {
+        self.month
+    }
+
+    /// The day of the month, from 1.
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+
+    pub fn hour(&self) -> u32 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u32 {
+        self.minute
+    }
+
+    /// The second, which can be 60 for a leap second.
+    pub fn second(&self) -> u32 {
+        self.second
+    }
+
+    pub fn millisecond(&self) -> u32 {
+        self.millisecond
+    }
+
+    /// The offset from UTC in minutes, positive east of Greenwich. Zero if the value didn't have
+    /// a timezone.
+    pub fn offset_minutes(&self) -> i32 {
+        self.offset_minutes
+    }
+
+    /// Returns the same instant with an offset of zero.
+    ///
+    /// ```
+    /// use serde_xmlrpc::XmlRpcDateTime;
+    ///
+    /// let dt: XmlRpcDateTime = "1998-07-17T01:08:55+02:00".parse().unwrap();
+    /// assert_eq!(dt.to_utc().to_string(), "1998-07-16T23:08:55.000Z");
+    /// ```
+    pub fn to_utc(&self) -> XmlRpcDateTime {
+        if self.offset_minutes == 0 {
+            return *self;
+        }
+
+        let minutes = days_from_civil(self.year, self.month, self.day) * 24 * 60
+            + i64::from(self.hour * 60 + self.minute)
+            - i64::from(self.offset_minutes);
+        let (year, month, day) = civil_from_days(minutes.div_euclid(24 * 60));
+        let minutes = minutes.rem_euclid(24 * 60);
+
+        XmlRpcDateTime {
+            year,
+            month,
+            day,
+            hour: (minutes / 60) as u32,
+            minute: (minutes % 60) as u32,
+            offset_minutes: 0,
+            ..*self
+        }
+    }
+
+    /// The error for a date/time which doesn't exist in a calendar library's types.
+    #[cfg(any(feature = "chrono", feature = "time"))]
+    fn out_of_range(&self) -> Error {
+        use alloc::string::ToString;
+
+        crate::DecodingError::DateTimeDecodeError(self.to_string()).into()
+    }
+}
+
+/// Parses the formats [`ParseOptions::lenient`] accepts, such as `19980717T14:08:55` or
+/// `1998-07-17T14:08:55.250+02:00`.
+impl FromStr for XmlRpcDateTime {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_datetime(s, &ParseOptions::lenient())
+    }
+}
+
+/// Writes extended ISO 8601 with milliseconds and the offset, such as
+/// `1998-07-17T14:08:55.000Z`. Use [`DateTimeFormat`] for other forms.
+impl fmt::Display for XmlRpcDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = DateTimeFormat::EXTENDED.timezone(true).fraction(true);
+        f.write_str(&format.format(self))
+    }
+}
+
+/// Week and ordinal dates are converted to the calendar date they fall on.
+impl From<iso8601::DateTime> for XmlRpcDateTime {
+    fn from(dt: iso8601::DateTime) -> Self {
+        let (year, month, day) = match dt.date {
+            iso8601::Date::YMD { year, month, day } => (year, month, day),
+            iso8601::Date::Week { year, ww, d } => {
+                // Week 1 is the week with January 4th in it, and weeks start on Monday.
+                let jan4 = days_from_civil(year, 1, 4);
+                let monday = jan4 - (jan4 + 3).rem_euclid(7);
+                civil_from_days(monday + (i64::from(ww) - 1) * 7 + i64::from(d) - 1)
+            }
+            iso8601::Date::Ordinal { year, ddd } => {
+                civil_from_days(days_from_civil(year, 1, 1) + i64::from(ddd) - 1)
+            }
+        };
+
+        XmlRpcDateTime {
+            year,
+            month,
+            day,
+            hour: dt.time.hour,
+            minute: dt.time.minute,
+            second: dt.time.second,
+            millisecond: dt.time.millisecond,
+            offset_minutes: dt.time.tz_offset_hours * 60 + dt.time.tz_offset_minutes,
+        }
+    }
+}
+
+impl From<XmlRpcDateTime> for iso8601::DateTime {
+    fn from(dt: XmlRpcDateTime) -> Self {
+        iso8601::DateTime {
+            date: iso8601::Date::YMD {
+                year: dt.year,
+                month: dt.month,
+                day: dt.day,
+            },
+            time: iso8601::Time {
+                hour: dt.hour,
+                minute: dt.minute,
+                second: dt.second,
+                millisecond: dt.millisecond,
+                tz_offset_hours: dt.offset_minutes / 60,
+                tz_offset_minutes: dt.offset_minutes % 60,
+            },
+        }
+    }
+}
+
+/// The number of days in `month` of `year` in the proleptic Gregorian calendar.
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 in the proleptic Gregorian calendar.
+fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year as i32, month as u32, day as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iso8601_dates() {
+        let ymd: XmlRpcDateTime = "2024-03-09T07:05:04.020-05:30".parse().unwrap();
+        let iso = iso8601::DateTime::from(ymd);
+        assert_eq!(iso, "2024-03-09T07:05:04.020-05:30".parse().unwrap());
+        assert_eq!(XmlRpcDateTime::from(iso), ymd);
+
+        // 2020-W53-4 and 2020-366 are both the last day of 2020.
+        for text in ["2020-W53-4T00:00:00Z", "2020-366T00:00:00Z"].iter() {
+            let dt = XmlRpcDateTime::from(text.parse::<iso8601::DateTime>().unwrap());
+            assert_eq!((dt.year(), dt.month(), dt.day()), (2020, 12, 31));
+        }
+        let dt = XmlRpcDateTime::from("2021-W01-1T00:00:00Z".parse::<iso8601::DateTime>().unwrap());
+        assert_eq!((dt.year(), dt.month(), dt.day()), (2021, 1, 4));
+    }
+
+    #[test]
+    fn ranges() {
+        assert!(XmlRpcDateTime::new(2024, 2, 31, 23, 59, 60).is_none());
+        assert!(XmlRpcDateTime::new(2024, 2, 29, 23, 59, 60).is_some());
+        assert!(XmlRpcDateTime::new(2023, 2, 29, 0, 0, 0).is_none());
+        assert!(XmlRpcDateTime::new(2000, 2, 29, 0, 0, 0).is_some());
+        assert!(XmlRpcDateTime::new(1900, 2, 29, 0, 0, 0).is_none());
+        assert!(XmlRpcDateTime::new(2024, 4, 31, 0, 0, 0).is_none());
+        assert!(XmlRpcDateTime::new(2024, 12, 31, 0, 0, 0).is_some());
+        assert!(XmlRpcDateTime::new(2024, 13, 1, 0, 0, 0).is_none());
+        assert!(XmlRpcDateTime::new(2024, 1, 1, 24, 0, 0).is_none());
+
+        let dt = XmlRpcDateTime::new(2024, 1, 1, 0, 0, 0).unwrap();
+        assert!(dt.with_millisecond(1000).is_none());
+        assert!(dt.with_offset_minutes(-24 * 60).is_none());
+    }
+
+    #[test]
+    fn utc() {
+        let dt: XmlRpcDateTime = "2024-03-01T00:30:59.250+05:45".parse().unwrap();
+        assert_eq!(dt.to_utc().to_string(), "2024-02-29T18:45:59.250Z");
+        let dt: XmlRpcDateTime = "1999-12-31T23:00:00-01:30".parse().unwrap();
+        assert_eq!(dt.to_utc().to_string(), "2000-01-01T00:30:00.000Z");
+
+        let dt = XmlRpcDateTime::new(2024, 6, 30, 23, 59, 60).unwrap();
+        assert_eq!(dt.to_utc(), dt);
+    }
+}
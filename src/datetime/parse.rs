@@ -0,0 +1,125 @@
+use crate::error::DecodingError;
+use crate::options::scalar_text;
+use crate::{Error, ParseOptions, Result, XmlRpcDateTime};
+
+/// Parses the text of a `<dateTime.iso8601>` tag. Strict mode only accepts the spec's
+/// `19980717T14:08:55`. Lenient mode also accepts dashed dates, times without colons or seconds,
+/// fractional seconds and a `Z`, `+01:00`, `+0100` or `+01` timezone suffix.
+pub(crate) fn parse_datetime(text: &str, options: &ParseOptions) -> Result<XmlRpcDateTime> {
+    let text = scalar_text(text, options);
+    let invalid = || Error::from(DecodingError::DateTimeDecodeError(text.into()));
+    if options.strict && !is_spec_datetime(text) {
+        return Err(invalid());
+    }
+
+    let mut input = DateTimeInput(text.as_bytes());
+    let (year, month, day) = if input.0.get(4) == Some(&b'-') {
+        let year = input.number(4);
+        input.eat(b'-');
+        let month = input.number(2);
+        input.eat(b'-');
+        (year, month, input.number(2))
+    } else {
+        (input.number(4), input.number(2), input.number(2))
+    };
+    if !(input.eat(b'T') || input.eat(b't') || input.eat(b' ')) {
+        return Err(invalid());
+    }
+
+    let hour = input.number(2);
+    let colons = input.eat(b':');
+    let minute = input.number(2);
+    let second = match input.0.first() {
+        Some(b':') if colons => {
+            input.eat(b':');
+            input.number(2)
+        }
+        Some(b'0'..=b'9') if !colons => input.number(2),
+        _ => Some(0),
+    };
+    let mut millisecond = 0;
+    if input.eat(b'.') || input.eat(b',') {
+        let digits = input.0.iter().take_while(|b| b.is_ascii_digit()).count();
+        if digits == 0 {
+            return Err(invalid());
+        }
+        for (i, b) in input.0[..digits].iter().take(3).enumerate() {
+            millisecond += u32::from(b - b'0') * 10u32.pow(2 - i as u32);
+        }
+        input.0 = &input.0[digits..];
+    }
+
+    let (mut tz_offset_hours, mut tz_offset_minutes) = (0, 0);
+    if !input.eat(b'Z') && !input.0.is_empty() {
+        let sign = if input.eat(b'+') {
+            1
+        } else if input.eat(b'-') {
+            -1
+        } else {
+            return Err(invalid());
+        };
+        tz_offset_hours = sign * input.number(2).ok_or_else(invalid)? as i32;
+        if !input.0.is_empty() {
+            input.eat(b':');
+            tz_offset_minutes = sign * input.number(2).ok_or_else(invalid)? as i32;
+        }
+    }
+
+    match (year, month, day, hour, minute, second) {
+        (
+            Some(year),
+            Some(month @ 1..=12),
+            Some(day @ 1..=31),
+            Some(hour @ 0..=23),
+            Some(minute @ 0..=59),
+            Some(second @ 0..=60),
+        ) if input.0.is_empty() && tz_offset_hours.abs() < 24 && tz_offset_minutes.abs() < 60 => {
+            Ok(XmlRpcDateTime {
+                year: year as i32,
+                month,
+                day,
+                hour,
+                minute,
+                second,
+                millisecond,
+                offset_minutes: tz_offset_hours * 60 + tz_offset_minutes,
+            })
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn is_spec_datetime(text: &str) -> bool {
+    let pattern = b"ddddddddTdd:dd:dd";
+    text.len() == pattern.len()
+        && text.bytes().zip(pattern).all(|(b, p)| match p {
+            b'd' => b.is_ascii_digit(),
+            p => b == *p,
+        })
+}
+
+/// The unparsed remainder of a dateTime value.
+struct DateTimeInput<'a>(&'a [u8]);
+
+impl DateTimeInput<'_> {
+    /// Consumes exactly `len` digits.
+    fn number(&mut self, len: usize) -> Option<u32> {
+        let digits = self.0.get(..len)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        self.0 = &self.0[len..];
+        Some(digits.iter().fold(0, |n, b| n * 10 + u32::from(b - b'0')))
+    }
+
+    /// Consumes `b` if it comes next.
+    fn eat(&mut self, b: u8) -> bool {
+        match self.0.split_first() {
+            Some((&first, rest)) if first == b => {
+                self.0 = rest;
+                true
+            }
+            _ => false,
+        }
+    }
+}
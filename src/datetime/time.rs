@@ -0,0 +1,85 @@
+//! Conversions between [`XmlRpcDateTime`] and `time`'s types.
+//!
+//! A primitive date/time converts with an offset of zero. Converting back fails for dates which
+//! don't exist, such as February 31st, and for leap seconds, which `time` doesn't support.
+
+use core::convert::TryFrom;
+
+use ::time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+use super::XmlRpcDateTime;
+use crate::Error;
+
+impl From<PrimitiveDateTime> for XmlRpcDateTime {
+    fn from(dt: PrimitiveDateTime) -> Self {
+        XmlRpcDateTime {
+            year: dt.year(),
+            month: u8::from(dt.month()).into(),
+            day: dt.day().into(),
+            hour: dt.hour().into(),
+            minute: dt.minute().into(),
+            second: dt.second().into(),
+            millisecond: dt.millisecond().into(),
+            offset_minutes: 0,
+        }
+    }
+}
+
+impl From<OffsetDateTime> for XmlRpcDateTime {
+    fn from(dt: OffsetDateTime) -> Self {
+        XmlRpcDateTime {
+            offset_minutes: dt.offset().whole_seconds() / 60,
+            ..XmlRpcDateTime::from(PrimitiveDateTime::new(dt.date(), dt.time()))
+        }
+    }
+}
+
+/// Takes the wall-clock time and drops the offset.
+impl TryFrom<XmlRpcDateTime> for PrimitiveDateTime {
+    type Error = Error;
+
+    fn try_from(dt: XmlRpcDateTime) -> Result<Self, Self::Error> {
+        let date = Month::try_from(dt.month as u8)
+            .ok()
+            .and_then(|month| Date::from_calendar_date(dt.year, month, dt.day as u8).ok());
+        let time = Time::from_hms_milli(
+            dt.hour as u8,
+            dt.minute as u8,
+            dt.second as u8,
+            dt.millisecond as u16,
+        )
+        .ok();
+        match (date, time) {
+            (Some(date), Some(time)) => Ok(PrimitiveDateTime::new(date, time)),
+            _ => Err(dt.out_of_range()),
+        }
+    }
+}
+
+impl TryFrom<XmlRpcDateTime> for OffsetDateTime {
+    type Error = Error;
+
+    fn try_from(dt: XmlRpcDateTime) -> Result<Self, Self::Error> {
+        let naive = PrimitiveDateTime::try_from(dt)?;
+        let offset =
+            UtcOffset::from_whole_seconds(dt.offset_minutes * 60).map_err(|_| dt.out_of_range())?;
+        Ok(naive.assume_offset(offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_roundtrip() {
+        let dt: XmlRpcDateTime = "2024-03-09T07:05:04.020-05:30".parse().unwrap();
+        let time = OffsetDateTime::try_from(dt).unwrap();
+        assert_eq!(time.offset().whole_minutes(), -330);
+        assert_eq!(time.millisecond(), 20);
+        assert_eq!(XmlRpcDateTime::from(time), dt);
+
+        let invalid: XmlRpcDateTime = "2023-02-29T00:00:00".parse().unwrap();
+        assert!(PrimitiveDateTime::try_from(invalid).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+//! Serialize [`rust_decimal::Decimal`] without losing precision.
+//!
+//! Use the module with `#[serde(with = "serde_xmlrpc::decimal")]`. Decimals are written as a
+//! `<string>` of their digits, or as Apache XML-RPC's `<ex:bigdecimal>` with
+//! [`WriteOptions::big_number_extension`](crate::WriteOptions::big_number_extension). Either is
+//! read back, as are `<int>`, `<i8>` and `<double>` values.
+//!
+//! ```
+//! use rust_decimal::Decimal;
+//! use serde::{Deserialize, Serialize};
+//! use serde_xmlrpc::WriteOptions;
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Invoice {
+//!     #[serde(with = "serde_xmlrpc::decimal")]
+//!     total: Decimal,
+//! }
+//!
+//! let invoice = Invoice {
+//!     total: "12345678901234.5678".parse().unwrap(),
+//! };
+//! let options = WriteOptions::apache();
+//! let body = options.value_to_string(options.to_value(&invoice).unwrap());
+//! assert!(body.unwrap().contains(">12345678901234.5678</ex:bigdecimal>"));
+//!
+//! let body = serde_xmlrpc::value_to_string(serde_xmlrpc::to_value(&invoice).unwrap()).unwrap();
+//! assert!(body.contains("<string>12345678901234.5678</string>"));
+//! let value = serde_xmlrpc::value_from_str(&body).unwrap();
+//! assert_eq!(serde_xmlrpc::from_value::<Invoice>(value).unwrap(), invoice);
+//! ```
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use serde::de::{self, Visitor};
+use serde::{Deserializer, Serializer};
+
+use crate::value::BIGDECIMAL_TOKEN;
+
+/// Serializes a [`Decimal`] as its digits.
+pub fn serialize<S>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_newtype_struct(BIGDECIMAL_TOKEN, &value.to_string())
+}
+
+/// Deserializes a [`Decimal`] from its digits, an integer or a double.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(DecimalVisitor)
+}
+
+struct DecimalVisitor;
+
+impl<'de> Visitor<'de> for DecimalVisitor {
+    type Value = Decimal;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a decimal number")
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Decimal::from(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Decimal::from(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Decimal::try_from(v).map_err(|_| E::invalid_value(de::Unexpected::Float(v), &self))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        // Java's BigDecimal.toString() uses exponents for very small and large values.
+        Decimal::from_str(v)
+            .or_else(|_| Decimal::from_scientific(v))
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+    use serde::{Deserialize, Serialize};
+
+    use crate::value::Deserializer;
+    use crate::{Value, WriteOptions};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Price {
+        #[serde(with = "super")]
+        n: Decimal,
+    }
+
+    #[test]
+    fn roundtrip() {
+        let price = Price {
+            n: "0.1000000000000000000000000001".parse().unwrap(),
+        };
+        for options in [WriteOptions::default(), WriteOptions::apache()].iter() {
+            let value = options.to_value(&price).unwrap();
+            let body = options.response_to_string(std::iter::once(value)).unwrap();
+            assert_eq!(crate::response_from_str::<Price>(&body).unwrap(), price);
+
+            // Values keep the extension type.
+            let value = crate::response_from_str::<Value>(&body).unwrap();
+            assert_eq!(crate::from_value::<Price>(value.clone()).unwrap(), price);
+            assert_eq!(
+                options.response_to_string(std::iter::once(value)).unwrap(),
+                body
+            );
+        }
+    }
+
+    #[test]
+    fn other_types() {
+        let read = |xml: &str| {
+            let value = crate::value_from_str(xml)?;
+            super::deserialize(Deserializer::from_value(value)).map(|n| n.to_string())
+        };
+        assert_eq!(read("<value><int>-7</int></value>").unwrap(), "-7");
+        assert_eq!(read("<value><double>2.5</double></value>").unwrap(), "2.5");
+        assert_eq!(read("<value>1.5E+3</value>").unwrap(), "1500");
+        assert!(read("<value>twelve</value>").is_err());
+    }
+}
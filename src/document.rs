@@ -0,0 +1,481 @@
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+
+use quick_xml::{events::Event, name::QName, Reader, Writer};
+use serde::Deserialize;
+
+use crate::error::{DecodingError, EncodingError};
+use crate::util::{ParseState, ReaderExt, ValueDeserializer, WriterExt};
+use crate::{Fault, ParseOptions, Result, Value, WriteOptions, XmlRpcType};
+
+/// A complete XML-RPC message: either a method call or one of the two kinds of response.
+///
+/// ```
+/// use serde_xmlrpc::{Document, Values};
+///
+/// let body = Document::Call {
+///     method: "add".into(),
+///     params: Values::from_params(&(1, 2)).unwrap(),
+/// }
+/// .to_xml()
+/// .unwrap();
+///
+/// match body.parse::<Document>().unwrap() {
+///     Document::Call { method, params } => {
+///         assert_eq!(method, "add");
+///         let (a, b): (i32, i32) = params.deserialize().unwrap();
+///         assert_eq!(a + b, 3);
+///     }
+///     _ => unreachable!(),
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Document {
+    /// A `<methodCall>` naming the method to call and its parameters.
+    Call { method: String, params: Values },
+    /// A successful `<methodResponse>` carrying `<params>`.
+    Response(Values),
+    /// A `<methodResponse>` carrying a `<fault>`.
+    Fault(Fault),
+}
+
+impl Document {
+    /// Writes the document with the default [`WriteOptions`].
+    pub fn to_xml(&self) -> Result<String> {
+        write_document(self, &WriteOptions::default())
+    }
+
+    /// Returns the params of a response, or the fault as an error. Calls are returned as an
+    /// error, since they can't be the result of anything.
+    pub fn into_result(self) -> Result<Values> {
+        match self {
+            Document::Response(params) => Ok(params),
+            Document::Fault(fault) => Err(fault.into()),
+            Document::Call { .. } => Err(DecodingError::UnexpectedTag(
+                "methodCall".into(),
+                "methodResponse".into(),
+            )
+            .into()),
+        }
+    }
+}
+
+/// Parses a document with the default [`ParseOptions`].
+impl FromStr for Document {
+    type Err = crate::Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        parse_document(input, &ParseOptions::default())
+    }
+}
+
+impl fmt::Display for Document {
+    /// Writes the document with the default [`WriteOptions`]. Use [`Document::to_xml`] to see
+    /// why writing failed.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_xml().map_err(|_| fmt::Error)?)
+    }
+}
+
+/// A `<methodCall>`, for code which only deals with calls such as a server.
+///
+/// ```
+/// use serde_xmlrpc::{MethodCall, MethodResponse, Values};
+///
+/// let call: MethodCall = r#"<?xml version="1.0"?>
+/// <methodCall><methodName>add</methodName><params>
+///   <param><value><int>1</int></value></param>
+///   <param><value><int>2</int></value></param>
+/// </params></methodCall>"#
+///     .parse()
+///     .unwrap();
+/// assert_eq!(call.method_name, "add");
+///
+/// let (a, b): (i32, i32) = call.params.deserialize().unwrap();
+/// let response = MethodResponse::Success(Values::from_params(&(a + b)).unwrap());
+/// assert!(response.to_string().contains("<int>3</int>"));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct MethodCall {
+    pub method_name: String,
+    pub params: Values,
+}
+
+/// A `<methodResponse>`, for code which only deals with responses such as a client.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MethodResponse {
+    /// A response carrying `<params>`.
+    Success(Values),
+    /// A response carrying a `<fault>`.
+    Fault(Fault),
+}
+
+impl MethodResponse {
+    /// Returns the params, or the fault as an error.
+    pub fn into_result(self) -> Result<Values> {
+        Document::from(self).into_result()
+    }
+}
+
+impl From<MethodCall> for Document {
+    fn from(call: MethodCall) -> Self {
+        Document::Call {
+            method: call.method_name,
+            params: call.params,
+        }
+    }
+}
+
+impl From<MethodResponse> for Document {
+    fn from(response: MethodResponse) -> Self {
+        match response {
+            MethodResponse::Success(params) => Document::Response(params),
+            MethodResponse::Fault(fault) => Document::Fault(fault),
+        }
+    }
+}
+
+/// Fails with [`DecodingError::UnexpectedTag`] for responses.
+impl TryFrom<Document> for MethodCall {
+    type Error = crate::Error;
+
+    fn try_from(document: Document) -> Result<Self> {
+        match document {
+            Document::Call { method, params } => Ok(MethodCall {
+                method_name: method,
+                params,
+            }),
+            _ => Err(
+                DecodingError::UnexpectedTag("methodResponse".into(), "methodCall".into()).into(),
+            ),
+        }
+    }
+}
+
+/// Fails with [`DecodingError::UnexpectedTag`] for calls.
+impl TryFrom<Document> for MethodResponse {
+    type Error = crate::Error;
+
+    fn try_from(document: Document) -> Result<Self> {
+        match document {
+            Document::Response(params) => Ok(MethodResponse::Success(params)),
+            Document::Fault(fault) => Ok(MethodResponse::Fault(fault)),
+            Document::Call { .. } => Err(DecodingError::UnexpectedTag(
+                "methodCall".into(),
+                "methodResponse".into(),
+            )
+            .into()),
+        }
+    }
+}
+
+/// Parses a call with the default [`ParseOptions`].
+impl FromStr for MethodCall {
+    type Err = crate::Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        input.parse::<Document>()?.try_into()
+    }
+}
+
+/// Parses a response with the default [`ParseOptions`].
+impl FromStr for MethodResponse {
+    type Err = crate::Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        input.parse::<Document>()?.try_into()
+    }
+}
+
+/// Writes the call with the default [`WriteOptions`].
+impl fmt::Display for MethodCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let call = Document::Call {
+            method: self.method_name.clone(),
+            params: self.params.clone(),
+        };
+        call.fmt(f)
+    }
+}
+
+/// Writes the response with the default [`WriteOptions`].
+impl fmt::Display for MethodResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Document::from(self.clone()).fmt(f)
+    }
+}
+
+/// The parameters of a call or response, in order.
+///
+/// `Values` dereferences to a `Vec<Value>`, so the usual methods for inspecting and modifying the
+/// list are available.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Values(Vec<Value>);
+
+impl Values {
+    pub fn new() -> Self {
+        Values(Vec::new())
+    }
+
+    /// Converts a `T` into a list of params. Tuples, sequences and tuple structs produce one
+    /// param per element while anything else produces a single param.
+    ///
+    /// ```
+    /// use serde_xmlrpc::{Value, Values};
+    ///
+    /// let params = Values::from_params(&("/rosout", 42)).unwrap();
+    /// assert_eq!(params[1], Value::Int(42));
+    ///
+    /// let params = Values::from_params(&"/rosout").unwrap();
+    /// assert_eq!(params.len(), 1);
+    /// ```
+    pub fn from_params<T>(params: &T) -> Result<Self>
+    where
+        T: serde::Serialize + ?Sized,
+    {
+        Values::from_params_with(params, &WriteOptions::default())
+    }
+
+    pub(crate) fn from_params_with<T>(params: &T, options: &WriteOptions) -> Result<Self>
+    where
+        T: serde::Serialize + ?Sized,
+    {
+        match options.to_value(params)? {
+            Value::Array(values) => Ok(Values(values)),
+            value => Ok(Values(vec![value])),
+        }
+    }
+
+    /// Converts the params into any type which can be deserialized from a sequence, such as a
+    /// tuple. See [`from_values`](crate::from_values).
+    pub fn deserialize<T>(self) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        crate::from_values(self.0)
+    }
+
+    /// Appends a param converted with [`XmlRpcType`], for chaining.
+    pub fn param<T: XmlRpcType>(mut self, param: &T) -> Self {
+        self.0.push(param.to_value());
+        self
+    }
+
+    /// Converts the param at `index` with [`XmlRpcType`]. A missing param is read as
+    /// [`Value::Nil`], so it converts successfully into an `Option`.
+    pub fn get<T: XmlRpcType>(&self, index: usize) -> Result<T> {
+        T::from_value(self.0.get(index).unwrap_or(&Value::Nil))
+    }
+
+    pub fn into_vec(self) -> Vec<Value> {
+        self.0
+    }
+}
+
+impl Deref for Values {
+    type Target = Vec<Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Values {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<Vec<Value>> for Values {
+    fn from(values: Vec<Value>) -> Self {
+        Values(values)
+    }
+}
+
+impl From<Values> for Vec<Value> {
+    fn from(values: Values) -> Self {
+        values.0
+    }
+}
+
+impl std::iter::FromIterator<Value> for Values {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        Values(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for Values {
+    type Item = Value;
+    type IntoIter = std::vec::IntoIter<Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Values {
+    type Item = &'a Value;
+    type IntoIter = std::slice::Iter<'a, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+pub(crate) fn parse_document(input: &str, options: &ParseOptions) -> Result<Document> {
+    let mut reader = Reader::from_str(input);
+    reader.expand_empty_elements(true);
+    reader.trim_text(true);
+    let state = ParseState::new(options);
+
+    let root = state.read_root(&mut reader)?;
+    if root.name() == QName(b"methodCall") {
+        // This code currently assumes that the <methodName> will always precede <params>
+        // in the xmlrpc request, I'm not certain that this is actually enforced by the
+        // specification, but could find not counter example where it wasn't true... -Carter
+        let method = match reader.read_event().map_err(DecodingError::from)? {
+            Event::Start(e) if e.name() == QName(b"methodName") => reader
+                .read_text(e.name())
+                .map_err(DecodingError::from)?
+                .into_owned(),
+            e => return Err(DecodingError::UnexpectedEvent(format!("{:?}", e)).into()),
+        };
+
+        reader.expect_tag(QName(b"params"))?;
+        let params = read_params(&mut reader, &state)?;
+
+        return Ok(Document::Call { method, params });
+    }
+
+    if root.name() != QName(b"methodResponse") {
+        return Err(DecodingError::UnexpectedTag(
+            String::from_utf8_lossy(root.name().into_inner()).into(),
+            "methodCall or methodResponse".into(),
+        )
+        .into());
+    }
+
+    match reader.read_event().map_err(DecodingError::from)? {
+        Event::Start(e) if e.name() == QName(b"params") => {
+            Ok(Document::Response(read_params(&mut reader, &state)?))
+        }
+        Event::Start(e) if e.name() == QName(b"fault") => {
+            // The inner portion of a fault is just a Value tag, so we
+            // deserialize it from a value.
+            reader.expect_tag(QName(b"value"))?;
+            let fault = Fault::deserialize(ValueDeserializer::new(&mut reader, &state)?)?;
+            reader.read_to_end(e.name()).map_err(DecodingError::from)?;
+
+            Ok(Document::Fault(fault))
+        }
+        e => Err(DecodingError::UnexpectedEvent(format!("{:?}", e)).into()),
+    }
+}
+
+/// Reads each `<param>` up to the closing `</params>`.
+fn read_params(reader: &mut Reader<&[u8]>, state: &ParseState) -> Result<Values> {
+    let mut params = Values::new();
+
+    loop {
+        match reader.read_event().map_err(DecodingError::from)? {
+            Event::Start(e) if e.name() == QName(b"param") => {
+                reader.expect_tag(QName(b"value"))?;
+                params.push(state.read_value(reader)?);
+
+                reader.read_to_end(e.name()).map_err(DecodingError::from)?;
+            }
+
+            // Once we see the relevant params end tag, we know we have all the params.
+            Event::End(e) if e.name() == QName(b"params") => return Ok(params),
+            e => return Err(DecodingError::UnexpectedEvent(format!("{:?}", e)).into()),
+        }
+    }
+}
+
+pub(crate) fn write_document(document: &Document, options: &WriteOptions) -> Result<String> {
+    match document {
+        Document::Call { method, params } => crate::write_request(method, params.to_vec(), options),
+        Document::Response(params) => crate::write_response(params.iter().cloned(), options),
+        Document::Fault(fault) => {
+            let mut writer = Writer::new(Vec::new());
+            writer.write_decl()?;
+
+            writer.write_start_tag("methodResponse")?;
+            writer.write_start_tag("fault")?;
+            crate::write_value_to(&mut writer, options.to_value(fault)?, options)?;
+            writer.write_end_tag("fault")?;
+            writer.write_end_tag("methodResponse")?;
+
+            Ok(String::from_utf8(writer.into_inner()).map_err(EncodingError::from)?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn document_roundtrip() {
+        let documents = vec![
+            Document::Call {
+                method: "system.listMethods".into(),
+                params: Values::new(),
+            },
+            Document::Call {
+                method: "add".into(),
+                params: vec![Value::Int(1), Value::from("two")].into(),
+            },
+            Document::Response(vec![Value::Int(42), Value::Bool(true)].into()),
+            Document::Fault(Fault {
+                fault_code: 4,
+                fault_string: "Too many parameters.".into(),
+            }),
+        ];
+
+        for document in documents {
+            let body = document.to_xml().unwrap();
+            assert_eq!(body.parse::<Document>().unwrap(), document);
+        }
+    }
+
+    #[test]
+    fn document_into_result() {
+        let body = crate::response_to_string(vec![Value::Int(1), Value::Int(2)].into_iter());
+        let params = body.unwrap().parse::<Document>().unwrap().into_result();
+        assert_eq!(params.unwrap().deserialize::<(i32, i32)>().unwrap(), (1, 2));
+
+        let fault = Fault {
+            fault_code: 1,
+            fault_string: "nope".into(),
+        };
+        let result = Document::Fault(fault.clone()).into_result();
+        assert!(matches!(result, Err(crate::Error::Fault(f)) if f == fault));
+    }
+
+    #[test]
+    fn method_call_and_response() {
+        let call = MethodCall {
+            method_name: "echo".into(),
+            params: vec![Value::from("hi")].into(),
+        };
+        assert_eq!(call.to_string().parse::<MethodCall>().unwrap(), call);
+        assert!(call.to_string().parse::<MethodResponse>().is_err());
+
+        let response = MethodResponse::Fault(Fault {
+            fault_code: 2,
+            fault_string: "bad".into(),
+        });
+        assert_eq!(
+            response.to_string().parse::<MethodResponse>().unwrap(),
+            response
+        );
+        assert!(response.to_string().parse::<MethodCall>().is_err());
+        assert!(matches!(
+            response.into_result(),
+            Err(crate::Error::Fault(_))
+        ));
+    }
+}
@@ -0,0 +1,280 @@
+//! Serialize [`std::time::Duration`] in the conventions XML-RPC APIs use for it.
+//!
+//! XML-RPC has no type for durations, so peers pick their own. Choose the submodule matching
+//! yours with `#[serde(with = "...")]`:
+//!
+//! * [`seconds`] writes a `<double>` of seconds, such as `1.5`.
+//! * [`millis`] writes an integer of milliseconds, such as `1500`.
+//! * [`parts`] writes a struct of whole seconds and nanoseconds, `{secs: 1, nanos: 500000000}`,
+//!   the same members as serde's own representation.
+//!
+//! Integers are written as an `<int>` where they fit and an `<i8>` otherwise. Each submodule
+//! reads back what it writes, and [`seconds`] also takes integers.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Timeouts {
+//!     #[serde(with = "serde_xmlrpc::duration::seconds")]
+//!     connect: Duration,
+//!     #[serde(with = "serde_xmlrpc::duration::millis")]
+//!     read: Duration,
+//! }
+//!
+//! let timeouts = Timeouts {
+//!     connect: Duration::from_millis(2500),
+//!     read: Duration::from_secs(30),
+//! };
+//! let value = serde_xmlrpc::to_value(&timeouts).unwrap();
+//! let body = serde_xmlrpc::value_to_string(value.clone()).unwrap();
+//! assert!(body.contains("<double>2.5</double>"));
+//! assert!(body.contains("<int>30000</int>"));
+//! assert_eq!(serde_xmlrpc::from_value::<Timeouts>(value).unwrap(), timeouts);
+//! ```
+
+use alloc::format;
+use core::convert::TryFrom;
+use core::fmt;
+use core::time::Duration;
+
+use serde::de::{self, Visitor};
+use serde::ser::Error as _;
+use serde::{Serialize, Serializer};
+
+/// An integer written as an `<int>` where it fits and an `<i8>` otherwise.
+struct Integer(u128);
+
+impl Serialize for Integer {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if let Ok(v) = i32::try_from(self.0) {
+            serializer.serialize_i32(v)
+        } else if let Ok(v) = i64::try_from(self.0) {
+            serializer.serialize_i64(v)
+        } else {
+            Err(S::Error::custom(format!(
+                "duration of {} doesn't fit in an i8",
+                self.0
+            )))
+        }
+    }
+}
+
+fn unsigned<E: de::Error>(v: i64, exp: &dyn de::Expected) -> Result<u64, E> {
+    u64::try_from(v).map_err(|_| E::invalid_value(de::Unexpected::Signed(v), exp))
+}
+
+/// A `<double>` of seconds.
+pub mod seconds {
+    use super::*;
+
+    /// Serializes a [`Duration`] as seconds.
+    pub fn serialize<S>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_f64(value.as_secs_f64())
+    }
+
+    /// Deserializes a [`Duration`] from seconds, as a double or an integer.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(SecondsVisitor)
+    }
+
+    struct SecondsVisitor;
+
+    impl<'de> Visitor<'de> for SecondsVisitor {
+        type Value = Duration;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a non-negative number of seconds")
+        }
+
+        fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            unsigned(v, &self).map(Duration::from_secs)
+        }
+
+        fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(Duration::from_secs(v))
+        }
+
+        fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Duration::try_from_secs_f64(v)
+                .map_err(|_| E::invalid_value(de::Unexpected::Float(v), &self))
+        }
+    }
+}
+
+/// An integer of milliseconds. Anything shorter than a millisecond is truncated.
+pub mod millis {
+    use super::*;
+
+    /// Serializes a [`Duration`] as whole milliseconds.
+    pub fn serialize<S>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Integer(value.as_millis()).serialize(serializer)
+    }
+
+    /// Deserializes a [`Duration`] from an integer of milliseconds.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(MillisVisitor)
+    }
+
+    struct MillisVisitor;
+
+    impl<'de> Visitor<'de> for MillisVisitor {
+        type Value = Duration;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a non-negative integer of milliseconds")
+        }
+
+        fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            unsigned(v, &self).map(Duration::from_millis)
+        }
+
+        fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(Duration::from_millis(v))
+        }
+    }
+}
+
+/// A struct of whole seconds and nanoseconds, `{secs, nanos}`.
+pub mod parts {
+    use serde::ser::SerializeStruct;
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Parts {
+        secs: i64,
+        nanos: i32,
+    }
+
+    /// Serializes a [`Duration`] as a struct of seconds and nanoseconds.
+    pub fn serialize<S>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut parts = serializer.serialize_struct("Duration", 2)?;
+        parts.serialize_field("secs", &Integer(value.as_secs().into()))?;
+        parts.serialize_field("nanos", &Integer(value.subsec_nanos().into()))?;
+        parts.end()
+    }
+
+    /// Deserializes a [`Duration`] from a struct of seconds and nanoseconds. Nanoseconds of a
+    /// second or more carry over into the seconds.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let parts = Parts::deserialize(deserializer)?;
+        match (u64::try_from(parts.secs), u32::try_from(parts.nanos)) {
+            (Ok(secs), Ok(nanos)) => Duration::from_secs(secs)
+                .checked_add(Duration::from_nanos(nanos.into()))
+                .ok_or_else(|| de::Error::custom("duration overflows")),
+            (Err(_), _) => Err(de::Error::invalid_value(
+                de::Unexpected::Signed(parts.secs),
+                &"non-negative seconds",
+            )),
+            (_, Err(_)) => Err(de::Error::invalid_value(
+                de::Unexpected::Signed(parts.nanos.into()),
+                &"non-negative nanoseconds",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::Value;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Durations {
+        #[serde(with = "seconds")]
+        seconds: Duration,
+        #[serde(with = "millis")]
+        millis: Duration,
+        #[serde(with = "parts")]
+        parts: Duration,
+    }
+
+    #[test]
+    fn roundtrip() {
+        let durations = Durations {
+            seconds: Duration::from_millis(250),
+            millis: Duration::from_secs(3_000_000),
+            parts: Duration::new(5, 7),
+        };
+        let value = crate::to_value(&durations).unwrap();
+        assert_eq!(value.pointer("/seconds"), Some(&Value::Double(0.25)));
+        assert_eq!(value.pointer("/millis"), Some(&Value::Int64(3_000_000_000)));
+        assert_eq!(value.pointer("/parts/secs"), Some(&Value::Int(5)));
+        assert_eq!(value.pointer("/parts/nanos"), Some(&Value::Int(7)));
+        assert_eq!(
+            crate::from_value::<Durations>(value.clone()).unwrap(),
+            durations
+        );
+
+        let response = crate::response_to_string(std::iter::once(value)).unwrap();
+        assert!(response.contains("<i8>3000000000</i8>"), "{}", response);
+        assert!(response.contains("<int>5</int>"), "{}", response);
+        assert_eq!(
+            crate::response_from_str::<Durations>(&response).unwrap(),
+            durations
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let read = |seconds: Value, millis: Value, secs: i32| {
+            let mut parts = crate::Map::new();
+            parts.insert("secs".to_string(), Value::Int(secs));
+            parts.insert("nanos".to_string(), Value::Int(0));
+            let mut members = crate::Map::new();
+            members.insert("seconds".to_string(), seconds);
+            members.insert("millis".to_string(), millis);
+            members.insert("parts".to_string(), Value::Struct(parts));
+            crate::from_value::<Durations>(Value::Struct(members))
+        };
+
+        assert!(read(Value::Int(2), Value::Int(1), 0).is_ok());
+        assert!(read(Value::Double(-1.0), Value::Int(1), 0).is_err());
+        assert!(read(Value::Double(f64::NAN), Value::Int(1), 0).is_err());
+        assert!(read(Value::Int(1), Value::Int(-1), 0).is_err());
+        assert!(read(Value::Int(1), Value::Double(1.5), 0).is_err());
+        assert!(read(Value::Int(1), Value::Int(1), -1).is_err());
+    }
+}
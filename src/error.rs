@@ -1,137 +1,595 @@
-use std::num::{ParseFloatError, ParseIntError};
-use std::string::FromUtf8Error;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{FromUtf8Error, String, ToString};
+use core::fmt;
+use core::num::{ParseFloatError, ParseIntError};
+use core::str::Utf8Error;
 
 use base64::DecodeError;
-use quick_xml::Error as XmlError;
+use serde::de::{Expected, Unexpected};
 use serde::{Deserialize, Serialize};
-use thiserror::Error as ThisError;
+
+use crate::{Value, ValueKind};
 
 /// Errors that can occur when trying to perform an XML-RPC request.
 ///
 /// This can be a lower-level error (for example, the HTTP request failed), a problem with the
 /// server (maybe it's not implementing XML-RPC correctly), or just a failure to execute the
 /// operation.
-#[derive(ThisError, Debug)]
+#[derive(Debug)]
 pub enum Error {
     /// The response could not be decoded. This can happen when the server doesn't correctly
     /// implement the XML-RPC spec or malformed XML is sent.
-    #[error("decoding error: {0}")]
-    DecodingError(#[from] DecodingError),
+    DecodingError(DecodingError),
 
     /// The response could not be encoded.
-    #[error("encoding error: {0}")]
-    EncodingError(#[from] EncodingError),
+    EncodingError(EncodingError),
 
     /// The server returned a `<fault>` response, indicating that the execution of the call
     /// encountered a problem (for example, an invalid (number of) arguments was passed).
-    #[error("server fault: {0}")]
-    Fault(#[from] Fault),
+    Fault(Fault),
+
+    /// The request couldn't be sent or the response couldn't be received, for example because
+    /// the HTTP request failed.
+    TransportError(Box<dyn core::error::Error + Send + Sync>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DecodingError(err) => write!(f, "decoding error: {}", err),
+            Error::EncodingError(err) => write!(f, "encoding error: {}", err),
+            Error::Fault(fault) => write!(f, "server fault: {}", fault),
+            Error::TransportError(err) => write!(f, "transport error: {}", err),
+        }
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Error::DecodingError(err) => Some(err),
+            Error::EncodingError(err) => Some(err),
+            Error::Fault(fault) => Some(fault),
+            Error::TransportError(err) => Some(&**err),
+        }
+    }
+}
+
+impl From<DecodingError> for Error {
+    fn from(err: DecodingError) -> Self {
+        Error::DecodingError(err)
+    }
+}
+
+impl From<EncodingError> for Error {
+    fn from(err: EncodingError) -> Self {
+        Error::EncodingError(err)
+    }
+}
+
+impl From<Fault> for Error {
+    fn from(fault: Fault) -> Self {
+        Error::Fault(fault)
+    }
 }
 
 impl serde::de::Error for Error {
     fn custom<T>(msg: T) -> Self
     where
-        T: std::fmt::Display,
+        T: fmt::Display,
     {
         DecodingError::SerdeError(msg.to_string()).into()
     }
+
+    fn invalid_type(unexp: Unexpected, exp: &dyn Expected) -> Self {
+        DecodingError::invalid_type(unexp, exp).into()
+    }
+
+    fn invalid_value(unexp: Unexpected, exp: &dyn Expected) -> Self {
+        DecodingError::invalid_value(unexp, exp).into()
+    }
 }
 
 impl serde::ser::Error for Error {
     fn custom<T>(msg: T) -> Self
     where
-        T: std::fmt::Display,
+        T: fmt::Display,
     {
         EncodingError::SerdeError(msg.to_string()).into()
     }
 }
 
+/// Malformed XML, or a failure to write XML, as reported by the XML parser or writer.
+///
+/// Which XML library is behind it is an implementation detail, so only its message is exposed,
+/// through `Display`. This keeps [`DecodingError`] and [`EncodingError`] the same if the library
+/// changes.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct XmlError(quick_xml::Error);
+
+#[cfg(feature = "std")]
+impl fmt::Display for XmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for XmlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<quick_xml::Error> for DecodingError {
+    fn from(err: quick_xml::Error) -> Self {
+        DecodingError::XmlError(XmlError(err))
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<quick_xml::Error> for EncodingError {
+    fn from(err: quick_xml::Error) -> Self {
+        EncodingError::XmlError(XmlError(err))
+    }
+}
+
 /// Error while parsing XML.
-#[derive(ThisError, Debug)]
+#[derive(Debug)]
 pub enum DecodingError {
-    #[error("malformed XML: {0}")]
-    XmlError(#[from] XmlError),
+    #[cfg(feature = "std")]
+    IoError(std::io::Error),
 
-    #[error("malformed XML: {0}")]
-    ParseIntError(#[from] ParseIntError),
+    #[cfg(feature = "std")]
+    XmlError(XmlError),
 
-    #[error("malformed XML: {0}")]
-    ParseFloatError(#[from] ParseFloatError),
+    ParseIntError(ParseIntError),
 
-    #[error("malformed XML: {0}")]
-    Base64DecodeError(#[from] DecodeError),
+    ParseFloatError(ParseFloatError),
+
+    Base64DecodeError(DecodeError),
 
-    #[error("malformed XML: invalid boolean value: {0}")]
     BooleanDecodeError(String),
 
-    #[error("malformed UTF-8: {0}")]
-    Utf8Error(#[from] FromUtf8Error),
+    IntDecodeError(String),
+
+    DoubleDecodeError(String),
+
+    DateTimeDecodeError(String),
+
+    Utf8Error(FromUtf8Error),
+
+    InvalidUtf8(Utf8Error),
+
+    /// The document declared an encoding which can't be decoded. Encodings other than UTF-8
+    /// need the `encodings` feature.
+    UnsupportedEncoding(String),
+
+    EncodingError(String),
 
-    #[error("unexpected tag: found {0}, expected {1}")]
     UnexpectedTag(String, String),
 
-    #[error("unexpected error: {0}, expected tag {1}")]
+    #[cfg(feature = "std")]
     UnexpectedError(anyhow::Error, String),
 
-    #[error("unexpected event: expected tag {0}")]
     UnexpectedEvent(String),
 
-    #[error("unexpected EOF: expected tag {0}")]
     UnexpectedEOF(String),
 
-    #[error("key must be convertable to a string")]
     KeyMustBeString,
 
-    #[error("serde: {0}")]
+    InvalidType {
+        found: String,
+        expected: String,
+    },
+
+    InvalidValue {
+        found: String,
+        expected: String,
+    },
+
+    /// Arrays and structs were nested deeper than [`ParseOptions::max_depth`] allows.
+    ///
+    /// [`ParseOptions::max_depth`]: crate::ParseOptions::max_depth
+    DepthLimitExceeded(usize),
+
+    /// A string, base64 value or member name was longer than [`ParseOptions::max_text_len`]
+    /// allows.
+    ///
+    /// [`ParseOptions::max_text_len`]: crate::ParseOptions::max_text_len
+    TextLimitExceeded(usize),
+
+    /// The document contained more values than [`ParseOptions::max_values`] allows.
+    ///
+    /// [`ParseOptions::max_values`]: crate::ParseOptions::max_values
+    ValueLimitExceeded(usize),
+
+    /// The document contained a `<!DOCTYPE>` declaration, which isn't allowed unless
+    /// [`ParseOptions::allow_doctype`] is set.
+    ///
+    /// [`ParseOptions::allow_doctype`]: crate::ParseOptions::allow_doctype
+    DoctypeNotAllowed,
+
+    /// A struct contained the same member name twice, which
+    /// [`DuplicateMembers::Error`](crate::DuplicateMembers::Error) doesn't allow.
+    DuplicateMember(String),
+
+    /// A value nested in a struct or array couldn't be converted by [`Value::extract`]. `path` is
+    /// a JSON Pointer to the value, which can be found again with [`Value::pointer`].
+    ///
+    /// [`Value::extract`]: crate::Value::extract
+    /// [`Value::pointer`]: crate::Value::pointer
+    AtPath {
+        path: String,
+        source: Box<DecodingError>,
+    },
+
+    /// Nothing in the document is at the path given to
+    /// [`from_str_at_path`](crate::from_str_at_path), or the path isn't a valid JSON Pointer.
+    PathNotFound(String),
+
     SerdeError(String),
 }
 
+impl fmt::Display for DecodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            DecodingError::IoError(err) => write!(f, "io error: {}", err),
+            #[cfg(feature = "std")]
+            DecodingError::XmlError(err) => write!(f, "malformed XML: {}", err),
+            DecodingError::ParseIntError(err) => write!(f, "malformed XML: {}", err),
+            DecodingError::ParseFloatError(err) => write!(f, "malformed XML: {}", err),
+            DecodingError::Base64DecodeError(err) => write!(f, "malformed XML: {}", err),
+            DecodingError::BooleanDecodeError(text) => {
+                write!(f, "malformed XML: invalid boolean value: {}", text)
+            }
+            DecodingError::IntDecodeError(text) => {
+                write!(f, "malformed XML: invalid int value: {}", text)
+            }
+            DecodingError::DoubleDecodeError(text) => {
+                write!(f, "malformed XML: invalid double value: {}", text)
+            }
+            DecodingError::DateTimeDecodeError(text) => {
+                write!(f, "malformed XML: invalid dateTime value: {}", text)
+            }
+            DecodingError::Utf8Error(err) => write!(f, "malformed UTF-8: {}", err),
+            DecodingError::InvalidUtf8(err) => write!(f, "malformed UTF-8: {}", err),
+            DecodingError::UnsupportedEncoding(label) => {
+                write!(f, "unsupported encoding: {}", label)
+            }
+            DecodingError::EncodingError(label) => write!(f, "malformed {} text", label),
+            DecodingError::UnexpectedTag(found, expected) => {
+                write!(f, "unexpected tag: found {}, expected {}", found, expected)
+            }
+            #[cfg(feature = "std")]
+            DecodingError::UnexpectedError(err, expected) => {
+                write!(f, "unexpected error: {}, expected tag {}", err, expected)
+            }
+            DecodingError::UnexpectedEvent(expected) => {
+                write!(f, "unexpected event: expected tag {}", expected)
+            }
+            DecodingError::UnexpectedEOF(expected) => {
+                write!(f, "unexpected EOF: expected tag {}", expected)
+            }
+            DecodingError::KeyMustBeString => f.write_str("key must be convertable to a string"),
+            DecodingError::InvalidType { found, expected } => {
+                write!(f, "invalid type: {}, expected {}", found, expected)
+            }
+            DecodingError::InvalidValue { found, expected } => {
+                write!(f, "invalid value: {}, expected {}", found, expected)
+            }
+            DecodingError::DepthLimitExceeded(limit) => {
+                write!(f, "nesting depth limit of {} exceeded", limit)
+            }
+            DecodingError::TextLimitExceeded(limit) => {
+                write!(f, "text length limit of {} bytes exceeded", limit)
+            }
+            DecodingError::ValueLimitExceeded(limit) => {
+                write!(f, "value count limit of {} exceeded", limit)
+            }
+            DecodingError::DoctypeNotAllowed => {
+                f.write_str("document type declarations are not allowed")
+            }
+            DecodingError::DuplicateMember(name) => write!(f, "duplicate struct member: {}", name),
+            DecodingError::AtPath { path, source } => write!(f, "{} at {}", source, path),
+            DecodingError::PathNotFound(path) => write!(f, "no value at {}", path),
+            DecodingError::SerdeError(msg) => write!(f, "serde: {}", msg),
+        }
+    }
+}
+
+impl core::error::Error for DecodingError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            DecodingError::IoError(err) => Some(err),
+            #[cfg(feature = "std")]
+            DecodingError::XmlError(err) => Some(err),
+            DecodingError::ParseIntError(err) => Some(err),
+            DecodingError::ParseFloatError(err) => Some(err),
+            // base64 only implements Error with its std feature.
+            #[cfg(feature = "std")]
+            DecodingError::Base64DecodeError(err) => Some(err),
+            DecodingError::Utf8Error(err) => Some(err),
+            DecodingError::InvalidUtf8(err) => Some(err),
+            DecodingError::AtPath { source, .. } => Some(&**source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for DecodingError {
+    fn from(err: std::io::Error) -> Self {
+        DecodingError::IoError(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<XmlError> for DecodingError {
+    fn from(err: XmlError) -> Self {
+        DecodingError::XmlError(err)
+    }
+}
+
+impl From<ParseIntError> for DecodingError {
+    fn from(err: ParseIntError) -> Self {
+        DecodingError::ParseIntError(err)
+    }
+}
+
+impl From<ParseFloatError> for DecodingError {
+    fn from(err: ParseFloatError) -> Self {
+        DecodingError::ParseFloatError(err)
+    }
+}
+
+impl From<DecodeError> for DecodingError {
+    fn from(err: DecodeError) -> Self {
+        DecodingError::Base64DecodeError(err)
+    }
+}
+
+impl From<FromUtf8Error> for DecodingError {
+    fn from(err: FromUtf8Error) -> Self {
+        DecodingError::Utf8Error(err)
+    }
+}
+
+impl From<Utf8Error> for DecodingError {
+    fn from(err: Utf8Error) -> Self {
+        DecodingError::InvalidUtf8(err)
+    }
+}
+
 impl serde::de::Error for DecodingError {
     fn custom<T>(msg: T) -> Self
     where
-        T: std::fmt::Display,
+        T: fmt::Display,
     {
         DecodingError::SerdeError(msg.to_string())
     }
+
+    fn invalid_type(unexp: Unexpected, exp: &dyn Expected) -> Self {
+        DecodingError::InvalidType {
+            found: unexp.to_string(),
+            expected: exp.to_string(),
+        }
+    }
+
+    fn invalid_value(unexp: Unexpected, exp: &dyn Expected) -> Self {
+        DecodingError::InvalidValue {
+            found: unexp.to_string(),
+            expected: exp.to_string(),
+        }
+    }
+}
+
+impl Error {
+    /// Replaces the description of the value found in an `InvalidType` error. The deserializers
+    /// use this when the type serde saw differs from the XML-RPC element, such as a
+    /// `<dateTime.iso8601>` being handed to the visitor as a string.
+    pub(crate) fn relabel_invalid_type(self, found: impl FnOnce() -> String) -> Self {
+        match self {
+            Error::DecodingError(DecodingError::InvalidType { expected, .. }) => {
+                DecodingError::InvalidType {
+                    found: found(),
+                    expected,
+                }
+                .into()
+            }
+            e => e,
+        }
+    }
+
+    /// Adds a step to the path of a decoding error as it passes out of a struct member or array
+    /// element. `segment` must already be escaped.
+    pub(crate) fn at_path(self, segment: &str) -> Self {
+        match self {
+            Error::DecodingError(DecodingError::AtPath { path, source }) => DecodingError::AtPath {
+                path: format!("/{}{}", segment, path),
+                source,
+            }
+            .into(),
+            Error::DecodingError(err) => DecodingError::AtPath {
+                path: format!("/{}", segment),
+                source: Box::new(err),
+            }
+            .into(),
+            e => e,
+        }
+    }
+}
+
+/// Error converting a [`Value`] into a Rust type with `TryFrom`.
+///
+/// Conversions are implemented both for `&Value`, borrowing from it where the type allows, and
+/// for `Value`, which moves strings, arrays and structs out without cloning them.
+///
+/// ```
+/// use std::convert::TryFrom;
+///
+/// use serde_xmlrpc::{Value, ValueError, ValueKind};
+///
+/// let value = Value::from(vec![Value::Int(1)]);
+/// assert_eq!(
+///     String::try_from(&value),
+///     Err(ValueError::TypeMismatch {
+///         expected: ValueKind::STRING,
+///         found: ValueKind::ARRAY,
+///     })
+/// );
+/// assert_eq!(Vec::<Value>::try_from(value).unwrap(), vec![Value::Int(1)]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValueError {
+    /// The value wasn't of the type the conversion takes.
+    TypeMismatch {
+        expected: ValueKind,
+        found: ValueKind,
+    },
+}
+
+impl fmt::Display for ValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueError::TypeMismatch { expected, found } => {
+                write!(f, "type mismatch: expected {}, found {}", expected, found)
+            }
+        }
+    }
+}
+
+impl core::error::Error for ValueError {}
+
+impl ValueError {
+    pub(crate) fn mismatch(expected: ValueKind, value: &Value) -> Self {
+        ValueError::TypeMismatch {
+            expected,
+            found: value.kind(),
+        }
+    }
+}
+
+impl From<ValueError> for DecodingError {
+    fn from(err: ValueError) -> Self {
+        match err {
+            ValueError::TypeMismatch { expected, found } => DecodingError::InvalidType {
+                expected: expected.to_string(),
+                found: found.to_string(),
+            },
+        }
+    }
+}
+
+impl From<ValueError> for Error {
+    fn from(err: ValueError) -> Self {
+        DecodingError::from(err).into()
+    }
 }
 
 /// Error while encoding XML.
 #[allow(clippy::enum_variant_names)]
-#[derive(ThisError, Debug)]
+#[derive(Debug)]
 pub enum EncodingError {
-    #[error("io error: {0}")]
-    IoError(#[from] std::io::Error),
+    #[cfg(feature = "std")]
+    IoError(std::io::Error),
 
-    #[error("malformed UTF-8: {0}")]
-    Utf8Error(#[from] FromUtf8Error),
+    Utf8Error(FromUtf8Error),
 
-    #[error("XML error: {0}")]
-    XmlError(#[from] XmlError),
+    #[cfg(feature = "std")]
+    XmlError(XmlError),
 
-    #[error("invalid key type: key must be an {0}")]
     InvalidKeyType(String),
 
-    #[error("serde: {0}")]
+    DuplicateMember(String),
+
+    Unrepresentable(String),
+
     SerdeError(String),
 }
 
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            EncodingError::IoError(err) => write!(f, "io error: {}", err),
+            EncodingError::Utf8Error(err) => write!(f, "malformed UTF-8: {}", err),
+            #[cfg(feature = "std")]
+            EncodingError::XmlError(err) => write!(f, "XML error: {}", err),
+            EncodingError::InvalidKeyType(kind) => {
+                write!(f, "invalid key type: key must be an {}", kind)
+            }
+            EncodingError::DuplicateMember(name) => write!(f, "duplicate struct member: {}", name),
+            EncodingError::Unrepresentable(what) => {
+                write!(f, "value can't be represented: {}", what)
+            }
+            EncodingError::SerdeError(msg) => write!(f, "serde: {}", msg),
+        }
+    }
+}
+
+impl core::error::Error for EncodingError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            EncodingError::IoError(err) => Some(err),
+            EncodingError::Utf8Error(err) => Some(err),
+            #[cfg(feature = "std")]
+            EncodingError::XmlError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for EncodingError {
+    fn from(err: std::io::Error) -> Self {
+        EncodingError::IoError(err)
+    }
+}
+
+impl From<FromUtf8Error> for EncodingError {
+    fn from(err: FromUtf8Error) -> Self {
+        EncodingError::Utf8Error(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<XmlError> for EncodingError {
+    fn from(err: XmlError) -> Self {
+        EncodingError::XmlError(err)
+    }
+}
+
 impl serde::ser::Error for EncodingError {
     fn custom<T>(msg: T) -> Self
     where
-        T: std::fmt::Display,
+        T: fmt::Display,
     {
         EncodingError::SerdeError(msg.to_string())
     }
 }
 
-pub type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// The outcome of a call as seen by the peers: either the value of `<params>` or a `<fault>`.
+///
+/// Use it with [`result_to_string`](crate::result_to_string) and
+/// [`result_from_str`](crate::result_from_str) to handle responses without treating a fault as a
+/// failure to communicate.
+pub type XmlRpcResult<T> = core::result::Result<T, Fault>;
 
 /// A `<fault>` response, indicating that a request failed.
 ///
 /// The XML-RPC specification requires that a `<faultCode>` and `<faultString>` is returned in the
 /// `<fault>` case, further describing the error.
-#[derive(ThisError, Deserialize, Serialize, Debug, PartialEq, Eq)]
-#[error("{fault_string} ({fault_code})")]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct Fault {
     /// An application-specific error code.
@@ -140,6 +598,23 @@ pub struct Fault {
     pub fault_string: String,
 }
 
+impl Fault {
+    pub fn new(fault_code: i32, fault_string: impl Into<String>) -> Self {
+        Fault {
+            fault_code,
+            fault_string: fault_string.into(),
+        }
+    }
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.fault_string, self.fault_code)
+    }
+}
+
+impl core::error::Error for Fault {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,6 +637,85 @@ mod tests {
         assert_eq!(new_input, input);
     }
 
+    #[test]
+    fn invalid_type_messages() {
+        fn response(value: &str) -> String {
+            format!(
+                "<methodResponse><params><param>{}</param></params></methodResponse>",
+                value
+            )
+        }
+
+        let err = crate::response_from_str::<i32>(response("<value><string>5</string></value>"))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::DecodingError(DecodingError::InvalidType { .. })
+        ));
+        assert_eq!(
+            err.to_string(),
+            "decoding error: invalid type: string \"5\", expected i32"
+        );
+
+        let err = crate::response_from_str::<i32>(response(
+            "<value><dateTime.iso8601>19980717T14:08:55</dateTime.iso8601></value>",
+        ))
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "decoding error: invalid type: dateTime.iso8601 \"19980717T14:08:55\", expected i32"
+        );
+
+        let err = crate::from_value::<i32>(Value::Nil).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "decoding error: invalid type: unit value, expected i32"
+        );
+
+        let err = crate::from_value::<bool>(Value::Int(2)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "decoding error: invalid type: integer `2`, expected a boolean"
+        );
+    }
+
+    #[test]
+    fn xml_errors() {
+        let err = crate::response_from_str::<String>(
+            "<methodResponse><params><param><value><string>a</value></param></params></methodResponse>",
+        )
+        .unwrap_err();
+        let xml = match err {
+            Error::DecodingError(DecodingError::XmlError(ref xml)) => xml,
+            ref err => panic!("{:?}", err),
+        };
+        assert!(err
+            .to_string()
+            .starts_with("decoding error: malformed XML: "));
+        assert!(err.to_string().ends_with(&xml.to_string()));
+    }
+
+    #[test]
+    fn error_sources() {
+        let err = Error::from(DecodingError::AtPath {
+            path: "/a".into(),
+            source: Box::new(DecodingError::IntDecodeError("x".into())),
+        });
+        let source = error::Error::source(&err).unwrap();
+        assert_eq!(
+            source.to_string(),
+            "malformed XML: invalid int value: x at /a"
+        );
+        let source = source.source().unwrap();
+        assert_eq!(source.to_string(), "malformed XML: invalid int value: x");
+        assert!(source.source().is_none());
+
+        let parse_err = "x".parse::<i32>().unwrap_err();
+        let err = Error::from(DecodingError::from(parse_err.clone()));
+        let source = error::Error::source(&err).unwrap().source().unwrap();
+        assert_eq!(source.to_string(), parse_err.to_string());
+    }
+
     #[test]
     fn error_impls_error() {
         fn assert_error<T: error::Error>() {}
@@ -0,0 +1,180 @@
+//! Standard faults from the [fault code interoperability spec].
+//!
+//! The spec reserves the codes from -32768 to -32000 for failures which happen outside of the
+//! called method, such as a request which can't be parsed. [`codes`] has a constant for each code
+//! it defines and [`Fault`] a constructor for each of them:
+//!
+//! ```
+//! use serde_xmlrpc::fault::codes;
+//! use serde_xmlrpc::Fault;
+//!
+//! let fault = Fault::method_not_found("frobnicate");
+//! assert_eq!(fault.fault_code, codes::METHOD_NOT_FOUND);
+//! assert_eq!(fault.fault_string, "method frobnicate not found");
+//! ```
+//!
+//! [fault code interoperability spec]: http://xmlrpc-epi.sourceforge.net/specs/rfc.fault_codes.php
+
+use crate::{DecodingError, Error, Fault};
+
+/// The fault codes defined by the spec.
+pub mod codes {
+    /// The request isn't well-formed XML.
+    pub const PARSE_ERROR: i32 = -32700;
+    /// The request declares an encoding which isn't supported.
+    pub const UNSUPPORTED_ENCODING: i32 = -32701;
+    /// The request contains a character which is invalid in its encoding.
+    pub const INVALID_CHARACTER: i32 = -32702;
+    /// The request is well-formed XML but not a valid XML-RPC call.
+    pub const INVALID_REQUEST: i32 = -32600;
+    /// The requested method doesn't exist.
+    pub const METHOD_NOT_FOUND: i32 = -32601;
+    /// The params don't match what the method takes.
+    pub const INVALID_PARAMS: i32 = -32602;
+    /// The server failed while handling the call.
+    pub const INTERNAL_ERROR: i32 = -32603;
+    /// The method failed.
+    pub const APPLICATION_ERROR: i32 = -32500;
+    /// The system the server runs on failed.
+    pub const SYSTEM_ERROR: i32 = -32400;
+    /// The transport the call was made over failed.
+    pub const TRANSPORT_ERROR: i32 = -32300;
+}
+
+impl Fault {
+    /// A fault with [`codes::PARSE_ERROR`].
+    pub fn parse_error(fault_string: impl Into<String>) -> Self {
+        Fault::new(codes::PARSE_ERROR, fault_string)
+    }
+
+    /// A fault with [`codes::UNSUPPORTED_ENCODING`].
+    pub fn unsupported_encoding(fault_string: impl Into<String>) -> Self {
+        Fault::new(codes::UNSUPPORTED_ENCODING, fault_string)
+    }
+
+    /// A fault with [`codes::INVALID_CHARACTER`].
+    pub fn invalid_character(fault_string: impl Into<String>) -> Self {
+        Fault::new(codes::INVALID_CHARACTER, fault_string)
+    }
+
+    /// A fault with [`codes::INVALID_REQUEST`].
+    pub fn invalid_request(fault_string: impl Into<String>) -> Self {
+        Fault::new(codes::INVALID_REQUEST, fault_string)
+    }
+
+    /// A fault with [`codes::METHOD_NOT_FOUND`] for the method called `method`.
+    pub fn method_not_found(method: &str) -> Self {
+        Fault::new(
+            codes::METHOD_NOT_FOUND,
+            format!("method {} not found", method),
+        )
+    }
+
+    /// A fault with [`codes::INVALID_PARAMS`].
+    pub fn invalid_params(fault_string: impl Into<String>) -> Self {
+        Fault::new(codes::INVALID_PARAMS, fault_string)
+    }
+
+    /// A fault with [`codes::INTERNAL_ERROR`].
+    pub fn internal_error(fault_string: impl Into<String>) -> Self {
+        Fault::new(codes::INTERNAL_ERROR, fault_string)
+    }
+
+    /// A fault with [`codes::APPLICATION_ERROR`].
+    pub fn application_error(fault_string: impl Into<String>) -> Self {
+        Fault::new(codes::APPLICATION_ERROR, fault_string)
+    }
+
+    /// A fault with [`codes::SYSTEM_ERROR`].
+    pub fn system_error(fault_string: impl Into<String>) -> Self {
+        Fault::new(codes::SYSTEM_ERROR, fault_string)
+    }
+
+    /// A fault with [`codes::TRANSPORT_ERROR`].
+    pub fn transport_error(fault_string: impl Into<String>) -> Self {
+        Fault::new(codes::TRANSPORT_ERROR, fault_string)
+    }
+
+    /// Picks the fault to answer a request which couldn't be parsed with, by what went wrong:
+    ///
+    /// * An encoding which isn't supported gets [`codes::UNSUPPORTED_ENCODING`].
+    /// * Text which isn't valid in its encoding gets [`codes::INVALID_CHARACTER`].
+    /// * Malformed or truncated XML gets [`codes::PARSE_ERROR`].
+    /// * Anything else, such as well-formed XML which isn't a `<methodCall>`, gets
+    ///   [`codes::INVALID_REQUEST`].
+    pub fn from_request_error(err: &Error) -> Self {
+        let message = err.to_string();
+        let err = match err {
+            Error::DecodingError(err) => err,
+            _ => return Fault::invalid_request(message),
+        };
+
+        match err {
+            DecodingError::UnsupportedEncoding(_) => Fault::unsupported_encoding(message),
+            DecodingError::EncodingError(_)
+            | DecodingError::Utf8Error(_)
+            | DecodingError::InvalidUtf8(_) => Fault::invalid_character(message),
+            DecodingError::IoError(_)
+            | DecodingError::XmlError(_)
+            | DecodingError::UnexpectedEOF(_) => Fault::parse_error(message),
+            DecodingError::UnexpectedEvent(event) if event == "Eof" => Fault::parse_error(message),
+            _ => Fault::invalid_request(message),
+        }
+    }
+}
+
+/// Turns an error into the fault a server would answer with, so a type whose methods return
+/// `Fault` can be implemented on top of a client. A fault is returned as is, a transport error
+/// gets [`codes::TRANSPORT_ERROR`] and anything else [`codes::INTERNAL_ERROR`].
+impl From<Error> for Fault {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Fault(fault) => fault,
+            Error::TransportError(_) => Fault::transport_error(err.to_string()),
+            err => Fault::internal_error(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+
+    fn request_fault(input: &str) -> i32 {
+        let err = crate::ParseOptions::default()
+            .document_from_slice(input.as_bytes())
+            .and_then(crate::MethodCall::try_from)
+            .unwrap_err();
+        Fault::from_request_error(&err).fault_code
+    }
+
+    #[test]
+    fn request_faults() {
+        assert_eq!(request_fault("<methodCall>"), codes::PARSE_ERROR);
+        assert_eq!(
+            request_fault("<methodCall><methodName>a</methodName><params></methodCall>"),
+            codes::PARSE_ERROR
+        );
+        assert_eq!(
+            request_fault("<methodResponse><params></params></methodResponse>"),
+            codes::INVALID_REQUEST
+        );
+        assert_eq!(
+            request_fault(
+                "<?xml version=\"1.0\" encoding=\"x-unknown\"?>\
+                 <methodCall><methodName>a</methodName></methodCall>"
+            ),
+            codes::UNSUPPORTED_ENCODING
+        );
+    }
+
+    #[test]
+    fn from_error() {
+        let fault = Fault::new(4, "too many parameters");
+        assert_eq!(Fault::from(Error::Fault(fault.clone())), fault);
+        let err = crate::value_from_str("<value>").unwrap_err();
+        assert_eq!(Fault::from(err).fault_code, codes::INTERNAL_ERROR);
+    }
+}
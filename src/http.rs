@@ -0,0 +1,213 @@
+//! Checks of the HTTP messages XML-RPC is carried in.
+//!
+//! The built-in clients check each response with these, and they can be used with any other HTTP
+//! stack, such as in a custom client `Transport`. Failed checks are returned as
+//! [`Error::TransportError`] holding an [`HttpError`].
+//!
+//! ```
+//! use serde_xmlrpc::http;
+//!
+//! assert!(http::check_status(200).is_ok());
+//! assert!(http::check_content_type(Some("text/xml; charset=utf-8")).is_ok());
+//! assert!(http::check_length(1024, Some(512)).is_err());
+//!
+//! let err = http::check_status(404).unwrap_err();
+//! assert_eq!(err.to_string(), "transport error: HTTP request failed with status 404");
+//! ```
+
+use crate::{Error, Result};
+
+/// The content type of XML-RPC requests and responses.
+pub const CONTENT_TYPE: &str = "text/xml";
+
+/// An HTTP message which can't carry an XML-RPC call or response.
+#[derive(Debug, thiserror::Error)]
+pub enum HttpError {
+    /// The status wasn't `200 OK`, which the spec requires for every response, faults included.
+    #[error("HTTP request failed with status {0}")]
+    Status(u16),
+    /// The content type wasn't XML.
+    #[error("unexpected Content-Type {0}, expected text/xml")]
+    ContentType(String),
+    /// The body was compressed in a way which isn't supported.
+    #[error("unsupported Content-Encoding {0}")]
+    ContentEncoding(String),
+    /// The body was longer than allowed.
+    #[error("HTTP body of {length} bytes exceeds the limit of {limit} bytes")]
+    TooLarge { length: u64, limit: u64 },
+}
+
+impl From<HttpError> for Error {
+    fn from(err: HttpError) -> Self {
+        Error::TransportError(Box::new(err))
+    }
+}
+
+/// Checks that a response has status `200 OK`.
+pub fn check_status(status: u16) -> Result<()> {
+    if status == 200 {
+        Ok(())
+    } else {
+        Err(HttpError::Status(status).into())
+    }
+}
+
+/// Checks that a message is XML, if it has a `Content-Type` header. Many servers leave it out, so
+/// a missing header is accepted. See [`is_xml_content_type`].
+pub fn check_content_type(content_type: Option<&str>) -> Result<()> {
+    match content_type {
+        Some(content_type) if !is_xml_content_type(content_type) => {
+            Err(HttpError::ContentType(content_type.to_string()).into())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks that a body of `length` bytes, such as from a `Content-Length` header, is within
+/// `limit`. There is no limit with `None`.
+pub fn check_length(length: u64, limit: Option<u64>) -> Result<()> {
+    match limit {
+        Some(limit) if length > limit => Err(HttpError::TooLarge { length, limit }.into()),
+        _ => Ok(()),
+    }
+}
+
+/// The `Accept-Encoding` the clients send, for the encodings [`decode_body`] understands.
+#[cfg(feature = "gzip")]
+pub const ACCEPT_ENCODING: &str = "gzip, deflate";
+
+/// Decompresses a body sent with `Content-Encoding: encoding`, which may be `gzip`, `deflate` or
+/// `identity`. Decompression stops with [`HttpError::TooLarge`] once the body exceeds `limit`.
+///
+/// ```
+/// use std::io::Write;
+///
+/// use flate2::{write::GzEncoder, Compression};
+///
+/// let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+/// encoder.write_all(b"<methodResponse/>").unwrap();
+/// let body = encoder.finish().unwrap();
+///
+/// let body = serde_xmlrpc::http::decode_body("gzip", &body, None).unwrap();
+/// assert_eq!(body, b"<methodResponse/>");
+/// ```
+#[cfg(feature = "gzip")]
+pub fn decode_body(encoding: &str, body: &[u8], limit: Option<u64>) -> Result<Vec<u8>> {
+    use std::io::Read;
+
+    use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+
+    fn read_limited(reader: impl Read, limit: Option<u64>) -> Result<Vec<u8>> {
+        let mut ret = Vec::new();
+        reader
+            .take(limit.map_or(u64::MAX, |limit| limit.saturating_add(1)))
+            .read_to_end(&mut ret)
+            .map_err(|err| Error::TransportError(Box::new(err)))?;
+        check_length(ret.len() as u64, limit)?;
+        Ok(ret)
+    }
+
+    let encoding = encoding.trim();
+    if encoding.eq_ignore_ascii_case("gzip") || encoding.eq_ignore_ascii_case("x-gzip") {
+        read_limited(GzDecoder::new(body), limit)
+    } else if encoding.eq_ignore_ascii_case("deflate") {
+        // `deflate` is meant to be zlib wrapped, but some servers send raw deflate data.
+        let zlib = body.len() >= 2
+            && body[0] & 0x0f == 8
+            && u16::from_be_bytes([body[0], body[1]]).is_multiple_of(31);
+        if zlib {
+            read_limited(ZlibDecoder::new(body), limit)
+        } else {
+            read_limited(DeflateDecoder::new(body), limit)
+        }
+    } else if encoding.is_empty() || encoding.eq_ignore_ascii_case("identity") {
+        check_length(body.len() as u64, limit)?;
+        Ok(body.to_vec())
+    } else {
+        Err(HttpError::ContentEncoding(encoding.to_string()).into())
+    }
+}
+
+/// Whether `content_type` is `text/xml`, which the spec requires, or one of the other XML types
+/// implementations send, `application/xml` and `+xml` types. Parameters such as `charset` are
+/// ignored.
+pub fn is_xml_content_type(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+
+    mime.eq_ignore_ascii_case("text/xml")
+        || mime.eq_ignore_ascii_case("application/xml")
+        || (mime.len() > 4 && mime[mime.len() - 4..].eq_ignore_ascii_case("+xml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks() {
+        assert!(check_status(204).is_err());
+        assert!(check_content_type(None).is_ok());
+        assert!(check_content_type(Some("application/rss+xml")).is_ok());
+        assert!(check_length(512, None).is_ok());
+        assert!(check_length(512, Some(512)).is_ok());
+
+        let err = check_content_type(Some("text/html")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "transport error: unexpected Content-Type text/html, expected text/xml"
+        );
+
+        match check_length(513, Some(512)).unwrap_err() {
+            Error::TransportError(err) => assert!(matches!(
+                err.downcast_ref::<HttpError>(),
+                Some(HttpError::TooLarge {
+                    length: 513,
+                    limit: 512
+                })
+            )),
+            err => panic!("unexpected error: {}", err),
+        }
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn decode_bodies() {
+        use std::io::Write;
+
+        use flate2::write::{DeflateEncoder, GzEncoder, ZlibEncoder};
+        use flate2::Compression;
+
+        let text = "<value>".repeat(100);
+
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(text.as_bytes()).unwrap();
+        let gzip = gzip.finish().unwrap();
+        assert_eq!(decode_body("gzip", &gzip, None).unwrap(), text.as_bytes());
+        assert_eq!(
+            decode_body("x-gzip", &gzip, Some(700)).unwrap(),
+            text.as_bytes()
+        );
+        let err = decode_body("gzip", &gzip, Some(699)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "transport error: HTTP body of 700 bytes exceeds the limit of 699 bytes"
+        );
+
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(text.as_bytes()).unwrap();
+        let zlib = zlib.finish().unwrap();
+        assert_eq!(
+            decode_body("deflate", &zlib, None).unwrap(),
+            text.as_bytes()
+        );
+
+        let mut raw = DeflateEncoder::new(Vec::new(), Compression::default());
+        raw.write_all(text.as_bytes()).unwrap();
+        let raw = raw.finish().unwrap();
+        assert_eq!(decode_body("deflate", &raw, None).unwrap(), text.as_bytes());
+
+        assert_eq!(decode_body("identity", b"abc", None).unwrap(), b"abc");
+        assert!(decode_body("br", b"abc", None).is_err());
+        assert!(decode_body("gzip", b"not gzip", None).is_err());
+    }
+}
@@ -1,19 +1,158 @@
 //! This library provides a basic API for serializing / deserializng xmlrpc.
 //! Combine with your transport or server of choice for an easy and quick xmlrpc experience.
-
+//!
+//! The free functions such as [`response_from_str`] and [`request_to_string`] cover the common
+//! cases with default settings. Each of them is also available as a method on [`ParseOptions`] or
+//! [`WriteOptions`], which take the place of the free functions when the defaults don't fit. Whole
+//! messages can be handled as data with [`Document`], and the params of a call or response with
+//! [`Values`]:
+//!
+//! ```
+//! use serde_xmlrpc::{Document, ParseOptions, Values, WriteOptions};
+//!
+//! let call = Document::Call {
+//!     method: "echo".into(),
+//!     params: Values::from_params(&("hello", 42)).unwrap(),
+//! };
+//! let body = WriteOptions::strict().document_to_string(&call).unwrap();
+//! assert_eq!(ParseOptions::strict().document_from_str(&body).unwrap(), call);
+//! ```
+//!
+//! Parsing never expands entities defined by the document, so entity expansion attacks such as
+//! "billion laughs" and external entity references can't be used against it. See
+//! [`ParseOptions`] for this and for the limits to use with untrusted input.
+//!
+//! Byte buffers are written as `<base64>`, but serde treats a plain `Vec<u8>` as a sequence and
+//! writes it as an `<array>` of ints. Use [`serde_bytes`](https://docs.rs/serde_bytes) on those
+//! fields to get `<base64>` instead:
+//!
+//! ```
+//! # use serde::{Deserialize, Serialize};
+//! #[derive(Serialize, Deserialize)]
+//! struct Upload {
+//!     #[serde(with = "serde_bytes")]
+//!     data: Vec<u8>,
+//! }
+//!
+//! let upload = Upload { data: b"hello".to_vec() };
+//! let body = serde_xmlrpc::value_to_string(serde_xmlrpc::to_value(&upload).unwrap()).unwrap();
+//! assert!(body.contains("<base64>aGVsbG8=</base64>"));
+//! ```
+//!
+//! Fields marked `#[serde(flatten)]` are written as members of the surrounding `<struct>` and read
+//! back from it, and a flattened `HashMap<String, Value>` keeps the members a struct doesn't
+//! model. Since serde reads flattened structs as maps, they can't be filled positionally from a
+//! params array the way plain structs can, and errors in their fields don't carry the member's
+//! path.
+//!
+//! The crate builds without the standard library, on `core` and `alloc` alone, when the default
+//! `std` feature is turned off. That leaves the [`Value`] model with [`to_value`] and
+//! [`from_value`], [`XmlRpcDateTime`] and the error types, for devices which build their values
+//! in memory and hand them to an XML layer of their own. Reading and writing XML, and everything
+//! built on it, needs `std`.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
 use quick_xml::{events::Event, name::QName, Reader, Writer};
+#[cfg(feature = "std")]
 use serde::Deserialize;
-use serde_transcode::transcode;
 
+#[cfg(feature = "axum")]
+pub mod axum;
+#[cfg(feature = "bigint")]
+pub mod bigint;
+#[cfg(feature = "std")]
+mod call;
+#[cfg(feature = "std")]
+mod capabilities;
+#[cfg(any(feature = "client", feature = "blocking"))]
+pub mod client;
+#[cfg(feature = "test-util")]
+pub mod conformance;
+mod datetime;
+#[cfg(feature = "decimal")]
+pub mod decimal;
+#[cfg(feature = "std")]
+mod document;
+pub mod duration;
 mod error;
+#[cfg(feature = "std")]
+pub mod fault;
+#[cfg(feature = "std")]
+pub mod http;
+#[cfg(feature = "metaweblog")]
+pub mod metaweblog;
+#[cfg(feature = "test-util")]
+pub mod mock;
+mod options;
+#[cfg(feature = "std")]
+mod path;
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod private;
+#[cfg(feature = "std")]
+mod recover;
+#[cfg(feature = "ros")]
+pub mod ros;
+#[cfg(feature = "std")]
+pub mod schema;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "supervisor")]
+pub mod supervisor;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "tower")]
+pub mod tower;
+#[cfg(any(feature = "client", feature = "blocking", feature = "server"))]
+mod trace;
+#[cfg(feature = "std")]
 mod util;
+#[cfg(feature = "std")]
+mod validate;
 mod value;
-
-use util::{ReaderExt, ValueDeserializer, ValueSerializer, WriterExt};
-
-pub use error::{Error, Fault, Result};
-pub use value::{to_value, Value};
-
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "std")]
+use util::{ParseState, ReaderExt, ValueDeserializer, WriterExt};
+
+#[cfg(feature = "std")]
+pub use call::{AsyncCaller, CallFuture, Caller};
+#[cfg(feature = "std")]
+pub use capabilities::{Capabilities, Capability};
+pub use datetime::XmlRpcDateTime;
+#[cfg(feature = "std")]
+pub use document::{Document, MethodCall, MethodResponse, Values};
+#[cfg(feature = "std")]
+pub use error::XmlError;
+pub use error::{DecodingError, EncodingError, Error, Fault, Result, ValueError, XmlRpcResult};
+pub use options::{
+    DateTimeFormat, DuplicateMembers, EscapePolicy, ParseOptions, WriteOptions,
+    DEFAULT_MAX_TEXT_LEN, DEFAULT_MAX_VALUES, MAX_SAFE_DEPTH,
+};
+#[cfg(feature = "derive")]
+pub use serde_xmlrpc_derive::{xmlrpc_interface, XmlRpcStruct};
+#[cfg(feature = "std")]
+pub use stats::DocumentStats;
+#[cfg(feature = "std")]
+pub use stream::ResponseStream;
+#[cfg(feature = "std")]
+pub use validate::{ValidateOptions, Violation};
+#[cfg(feature = "std")]
+pub use value::Redactor;
+pub use value::{to_value, DiffEntry, Entry, Map, RawValue, Value, ValueKind, Walk, XmlRpcType};
+
+#[cfg(feature = "std")]
 /// Parses the body of an xmlrpc http request and attempts to convert it to the desired type.
 /// ```
 /// let val: String = serde_xmlrpc::response_from_str(
@@ -26,31 +165,33 @@ pub use value::{to_value, Value};
 ///
 /// assert_eq!(val, "hello world".to_string());
 /// ```
-pub fn response_from_str<'a, T>(input: String) -> Result<T>
+pub fn response_from_str<'a, T>(input: impl AsRef<str>) -> Result<T>
+where
+    T: serde::de::Deserialize<'a>,
+{
+    parse_response(input.as_ref(), &ParseOptions::default())
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn parse_response<'a, T>(input: &str, options: &ParseOptions) -> Result<T>
 where
     T: serde::de::Deserialize<'a>,
 {
-    let mut reader = Reader::from_str(input.as_str());
+    let mut reader = Reader::from_str(input);
     reader.expand_empty_elements(true);
     reader.trim_text(true);
+    let state = ParseState::new(options);
+
+    state.expect_root(&mut reader, QName(b"methodResponse"))?;
 
     // Check the first event. This will determine if we're loading a Fault or a
     // Value.
-    loop {
-        match reader.read_event().map_err(error::DecodingError::from)? {
-            Event::Decl(_) => continue,
-            Event::Start(e) if e.name() == QName(b"methodResponse") => {
-                break;
-            }
-            e => return Err(error::DecodingError::UnexpectedEvent(format!("{:?}", e)).into()),
-        };
-    }
 
     match reader.read_event().map_err(error::DecodingError::from)? {
         Event::Start(e) if e.name() == QName(b"params") => {
             reader.expect_tag(QName(b"param"))?;
             reader.expect_tag(QName(b"value"))?;
-            let deserializer = ValueDeserializer::new(&mut reader)?;
+            let deserializer = ValueDeserializer::new(&mut reader, &state)?;
             let ret = T::deserialize(deserializer)?;
             reader
                 .read_to_end(QName(b"param"))
@@ -64,7 +205,7 @@ where
             // The inner portion of a fault is just a Value tag, so we
             // deserialize it from a value.
             reader.expect_tag(QName(b"value"))?;
-            let deserializer = ValueDeserializer::new(&mut reader)?;
+            let deserializer = ValueDeserializer::new(&mut reader, &state)?;
             let fault: Fault = Fault::deserialize(deserializer)?;
 
             // Pull the reader back out so we can verify the end tag.
@@ -80,16 +221,150 @@ where
     }
 }
 
+#[cfg(feature = "std")]
+/// Iterates over the elements of a response whose param is an `<array>`, converting them to `T`
+/// one at a time instead of collecting them in a `Vec` like [`response_from_str`] does. A fault
+/// is returned as [`Error::Fault`] right away. See [`ResponseStream`].
+///
+/// ```
+/// use serde_xmlrpc::Value;
+///
+/// let body = serde_xmlrpc::response_to_string(
+///     vec![Value::Array(vec![Value::Int(1), Value::Int(2)])].into_iter(),
+/// )
+/// .unwrap();
+///
+/// let mut sum = 0;
+/// for n in serde_xmlrpc::response_stream::<i32>(&body).unwrap() {
+///     sum += n.unwrap();
+/// }
+/// assert_eq!(sum, 3);
+/// ```
+pub fn response_stream<T>(input: &str) -> Result<ResponseStream<'_, T>>
+where
+    T: serde::de::DeserializeOwned,
+{
+    static OPTIONS: ParseOptions = ParseOptions::lenient();
+    ResponseStream::new(input, &OPTIONS)
+}
+
+#[cfg(feature = "std")]
+/// Reads only the value at `path` of a call or response and converts it to `T`, skipping the
+/// rest of the document without decoding it. This is much cheaper than [`response_from_str`]
+/// when only one field of a large response matters.
+///
+/// `path` is a [JSON Pointer](Value::pointer) whose first steps are `/params/<index>`, or
+/// `/fault` for the value of a fault. Further steps name struct members and array indices, and
+/// the first member with a name is the one selected. Asking for a param of a fault returns the
+/// fault as [`Error::Fault`], and a path which leads nowhere returns
+/// [`DecodingError::PathNotFound`].
+///
+/// Nothing after the selected value is read, so errors later in the document aren't reported.
+///
+/// ```
+/// use serde_xmlrpc::Value;
+///
+/// let body = r#"<?xml version="1.0"?>
+/// <methodResponse><params><param><value><struct>
+///   <member><name>total</name><value><int>2</int></value></member>
+///   <member><name>items</name><value><array><data>
+///     <value><string>first</string></value>
+///     <value><string>second</string></value>
+///   </data></array></value></member>
+/// </struct></value></param></params></methodResponse>"#;
+///
+/// let items: Vec<String> = serde_xmlrpc::from_str_at_path(body, "/params/0/items").unwrap();
+/// assert_eq!(items, ["first", "second"]);
+/// let second: Value = serde_xmlrpc::from_str_at_path(body, "/params/0/items/1").unwrap();
+/// assert_eq!(second, Value::String("second".into()));
+/// ```
+pub fn from_str_at_path<T>(input: &str, path: &str) -> Result<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    ParseOptions::default().from_str_at_path(input, path)
+}
+
+#[cfg(feature = "std")]
+/// Parses a response body into its outcome: the param converted to `T`, or the fault. Unlike
+/// [`response_from_str`], the outer error is only used when the response couldn't be read.
+///
+/// ```
+/// use serde_xmlrpc::{Fault, XmlRpcResult};
+///
+/// let body = serde_xmlrpc::result_to_string(&XmlRpcResult::<i32>::Err(Fault::new(4, "busy")));
+/// let result: XmlRpcResult<i32> = serde_xmlrpc::result_from_str(&body.unwrap()).unwrap();
+/// assert_eq!(result, Err(Fault::new(4, "busy")));
+///
+/// let body = serde_xmlrpc::result_to_string(&XmlRpcResult::<i32>::Ok(42));
+/// assert_eq!(serde_xmlrpc::result_from_str(&body.unwrap()).unwrap(), Ok(42));
+/// ```
+pub fn result_from_str<T>(input: &str) -> Result<XmlRpcResult<T>>
+where
+    T: serde::de::DeserializeOwned,
+{
+    parse_result(input, &ParseOptions::default())
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn parse_result<T>(input: &str, options: &ParseOptions) -> Result<XmlRpcResult<T>>
+where
+    T: serde::de::DeserializeOwned,
+{
+    match parse_response(input, options) {
+        Ok(value) => Ok(Ok(value)),
+        Err(Error::Fault(fault)) => Ok(Err(fault)),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(feature = "std")]
+/// Writes the outcome of a call as a response body: a success as its single param, a fault as a
+/// `<fault>`. See [`result_from_str`].
+pub fn result_to_string<T>(result: &XmlRpcResult<T>) -> Result<String>
+where
+    T: serde::Serialize,
+{
+    write_result(result, &WriteOptions::default())
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn write_result<T>(result: &XmlRpcResult<T>, options: &WriteOptions) -> Result<String>
+where
+    T: serde::Serialize,
+{
+    let document = match result {
+        Ok(value) => Document::Response(vec![options.to_value(value)?].into()),
+        Err(fault) => Document::Fault(fault.clone()),
+    };
+
+    document::write_document(&document, options)
+}
+
+#[cfg(feature = "std")]
 /// Attempt to serialize a xmlrpc response from a list of values.
 /// Each item in the list will be represented as a separate "param" in xmlrpc parlance.
 /// ```
 /// use serde_xmlrpc::{response_to_string,Value};
-/// let body = response_to_string(vec![Value::Int(42), Value::String("data".to_string())].into_iter()).unwrap();
+/// let params = vec![Value::Int(42), Value::String("data".to_string())];
+/// let body = response_to_string(params.into_iter()).unwrap();
 /// assert_eq!(body,
-/// r#"<?xml version="1.0" encoding="utf-8"?><methodResponse><params><param><value><int>42</int></value></param><param><value><string>data</string></value></param></params></methodResponse>"#
-/// );
+/// concat!(
+///     r#"<?xml version="1.0" encoding="utf-8"?><methodResponse><params>"#,
+///     r#"<param><value><int>42</int></value></param>"#,
+///     r#"<param><value><string>data</string></value></param>"#,
+///     r#"</params></methodResponse>"#,
+/// ));
 /// ```
 pub fn response_to_string(params: impl Iterator<Item = Value>) -> Result<String> {
+    write_response(params, &WriteOptions::default())
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn write_response(
+    params: impl Iterator<Item = Value>,
+    options: &WriteOptions,
+) -> Result<String> {
     let mut writer = Writer::new(Vec::new());
     writer.write_decl()?;
 
@@ -97,11 +372,7 @@ pub fn response_to_string(params: impl Iterator<Item = Value>) -> Result<String>
     writer.write_start_tag("params")?;
     for value in params {
         writer.write_start_tag("param")?;
-
-        let deserializer = value::Deserializer::from_value(value);
-        let serializer = ValueSerializer::new(&mut writer);
-        transcode(deserializer, serializer)?;
-
+        write_value_to(&mut writer, value, options)?;
         writer.write_end_tag("param")?;
     }
     writer.write_end_tag("params")?;
@@ -109,98 +380,61 @@ pub fn response_to_string(params: impl Iterator<Item = Value>) -> Result<String>
     Ok(String::from_utf8(writer.into_inner()).map_err(error::EncodingError::from)?)
 }
 
-/// Expects an input string which is a valid xmlrpc request body, and parses out the method name and parameters from it.
-/// This function would typically be used by a server to parse incoming requests.
+#[cfg(feature = "std")]
+/// Expects an input string which is a valid xmlrpc request body, and parses out the method name
+/// and parameters from it. This function would typically be used by a server to parse incoming
+/// requests.
 ///   * Returns a tuple of (method name, Arguments) if successful
+///
 /// This does not parse the types of the arguments, as typically the server needs to resolve
 /// the method name before it can know the expected types.
 pub fn request_from_str(request: &str) -> Result<(String, Vec<Value>)> {
-    let mut reader = Reader::from_str(request);
-    reader.expand_empty_elements(true);
-    reader.trim_text(true);
-
-    // Search for methodCall start
-    loop {
-        match reader.read_event().map_err(error::DecodingError::from)? {
-            Event::Decl(_) => continue,
-            Event::Start(e) if e.name() == QName(b"methodCall") => {
-                break;
-            }
-            e => return Err(error::DecodingError::UnexpectedEvent(format!("{:?}", e)).into()),
-        };
-    }
-
-    // This code currently assumes that the <methodName> will always precede <params>
-    // in the xmlrpc request, I'm not certain that this is actually enforced by the
-    // specification, but could find not counter example where it wasn't true... -Carter
-
-    let method_name = match reader.read_event().map_err(error::DecodingError::from)? {
-        Event::Start(e) if e.name() == QName(b"methodName") => reader
-            .read_text(e.name())
-            .map_err(error::DecodingError::from)?,
-        e => return Err(error::DecodingError::UnexpectedEvent(format!("{:?}", e)).into()),
-    };
+    parse_request(request, &ParseOptions::default())
+}
 
-    match reader.read_event().map_err(error::DecodingError::from)? {
-        Event::Start(e) if e.name() == QName(b"params") => {
-            let mut params = Vec::new();
-
-            let params = loop {
-                break match reader.read_event().map_err(error::DecodingError::from)? {
-                    // Read each parameter into a Value
-                    Event::Start(e) if e.name() == QName(b"param") => {
-                        reader.expect_tag(QName(b"value"))?;
-                        let deserializer = ValueDeserializer::new(&mut reader)?;
-                        let serializer = value::Serializer::new();
-                        let x = transcode(deserializer, serializer)?;
-                        params.push(x);
-
-                        reader
-                            .read_to_end(e.name())
-                            .map_err(error::DecodingError::from)?;
-
-                        continue;
-                    }
-
-                    // Once we see the relevant params end tag, we know we have all the params.
-                    Event::End(e) if e.name() == QName(b"params") => params,
-                    e => {
-                        return Err(error::DecodingError::UnexpectedEvent(format!("{:?}", e)).into())
-                    }
-                };
-            };
-
-            // We can skip reading to the end of the params tag because if we're
-            // here, we've already hit the end tag.
-
-            Ok((method_name.into_owned(), params))
-        }
-        e => Err(error::DecodingError::UnexpectedEvent(format!("{:?}", e)).into()),
+#[cfg(feature = "std")]
+pub(crate) fn parse_request(request: &str, options: &ParseOptions) -> Result<(String, Vec<Value>)> {
+    match document::parse_document(request, options)? {
+        Document::Call { method, params } => Ok((method, params.into_vec())),
+        _ => Err(
+            error::DecodingError::UnexpectedTag("methodResponse".into(), "methodCall".into())
+                .into(),
+        ),
     }
 }
 
-/// Takes in the name of a method call and a list of parameters and attempts to convert them to a String
-/// which would be a valid body for an xmlrpc request.
+#[cfg(feature = "std")]
+/// Takes in the name of a method call and a list of parameters and attempts to convert them to a
+/// String which would be a valid body for an xmlrpc request.
 ///
 /// ```
 /// let body = serde_xmlrpc::request_to_string("myMethod", vec![1.into(), "param2".into()]);
 /// ```
 pub fn request_to_string(name: &str, args: Vec<Value>) -> Result<String> {
-    let mut writer = Writer::new(Vec::new());
+    write_request(name, args, &WriteOptions::default())
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn write_request(
+    name: &str,
+    args: Vec<Value>,
+    options: &WriteOptions,
+) -> Result<String> {
+    let len: usize = args
+        .iter()
+        .map(|arg| util::value_xml_len(arg, options))
+        .sum();
+    let mut writer = Writer::new(Vec::with_capacity(len + name.len() + 15 * args.len() + 128));
 
     writer.write_decl()?;
 
     writer.write_start_tag("methodCall")?;
-    writer.write_tag("methodName", name)?;
+    writer.write_tag("methodName", name, options.escape)?;
 
     writer.write_start_tag("params")?;
     for value in args {
         writer.write_start_tag("param")?;
-
-        let deserializer = value::Deserializer::from_value(value);
-        let serializer = ValueSerializer::new(&mut writer);
-        transcode(deserializer, serializer)?;
-
+        write_value_to(&mut writer, value, options)?;
         writer.write_end_tag("param")?;
     }
     writer.write_end_tag("params")?;
@@ -209,22 +443,165 @@ pub fn request_to_string(name: &str, args: Vec<Value>) -> Result<String> {
     Ok(String::from_utf8(writer.into_inner()).map_err(error::EncodingError::from)?)
 }
 
+#[cfg(feature = "std")]
+/// Makes a call over any pair of streams, such as a pipe, serial port or SSH channel: writes the
+/// request to `writer`, then reads the response from `reader` until its closing
+/// `</methodResponse>` tag and converts its param to `T`. A `<fault>` response is returned as
+/// [`Error::Fault`].
+///
+/// The stream doesn't have to be closed after the response, so it can stay open for more calls.
+/// `reader` is read in chunks though, so anything the peer sends right after the response may
+/// be read along with it and dropped.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// let response = serde_xmlrpc::response_to_string(vec![3.into()].into_iter()).unwrap();
+/// let mut request = Vec::new();
+///
+/// let sum: i32 = serde_xmlrpc::call_reader_writer(
+///     "add",
+///     vec![1.into(), 2.into()],
+///     Cursor::new(response),
+///     &mut request,
+/// )
+/// .unwrap();
+/// assert_eq!(sum, 3);
+/// assert!(String::from_utf8(request).unwrap().contains("<methodName>add</methodName>"));
+/// ```
+pub fn call_reader_writer<T, R, W>(
+    method: &str,
+    args: Vec<Value>,
+    reader: R,
+    writer: W,
+) -> Result<T>
+where
+    T: serde::de::DeserializeOwned,
+    R: std::io::Read,
+    W: std::io::Write,
+{
+    call(
+        method,
+        args,
+        reader,
+        writer,
+        &ParseOptions::default(),
+        &WriteOptions::default(),
+    )
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn call<T, R, W>(
+    method: &str,
+    args: Vec<Value>,
+    mut reader: R,
+    mut writer: W,
+    parse_options: &ParseOptions,
+    write_options: &WriteOptions,
+) -> Result<T>
+where
+    T: serde::de::DeserializeOwned,
+    R: std::io::Read,
+    W: std::io::Write,
+{
+    let request = write_request(method, args, write_options)?;
+    writer
+        .write_all(request.as_bytes())
+        .and_then(|_| writer.flush())
+        .map_err(error::EncodingError::from)?;
+
+    // Streams such as pipes stay open after the response, so stop at its end rather than EOF.
+    let mut response = Vec::new();
+    let mut chunk = [0; 8192];
+    let mut scanned = 0;
+    loop {
+        let len = match reader.read(&mut chunk) {
+            Ok(len) => len,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(error::DecodingError::from(err).into()),
+        };
+        if len == 0 {
+            break;
+        }
+        response.extend_from_slice(&chunk[..len]);
+        if let Some(end) = response_end(&response, &mut scanned) {
+            response.truncate(end);
+            break;
+        }
+    }
+    parse_options.response_from_slice(&response)
+}
+
+#[cfg(feature = "std")]
+/// Finds the end of the closing `</methodResponse>` tag in the part of a response read so far.
+/// Tags inside CDATA sections and comments don't count. `scanned` is where the last call left
+/// off, so each byte is only looked at once as more of the response comes in.
+fn response_end(response: &[u8], scanned: &mut usize) -> Option<usize> {
+    const END_TAG: &[u8] = b"</methodResponse";
+
+    while let Some(start) = memchr::memchr(b'<', &response[*scanned..]) {
+        let start = *scanned + start;
+        let rest = &response[start..];
+        let skip = |open: &[u8], close: &[u8]| {
+            let after = memchr::memmem::find(&rest[open.len()..], close)?;
+            Some(start + open.len() + after + close.len())
+        };
+
+        let next = if rest.starts_with(b"<![CDATA[") {
+            skip(b"<![CDATA[", b"]]>")
+        } else if rest.starts_with(b"<!--") {
+            skip(b"<!--", b"-->")
+        } else if rest.starts_with(END_TAG) {
+            let close = memchr::memchr(b'>', &rest[END_TAG.len()..]);
+            if let Some(close) = close {
+                let between = &rest[END_TAG.len()..END_TAG.len() + close];
+                if between.iter().all(u8::is_ascii_whitespace) {
+                    return Some(start + END_TAG.len() + close + 1);
+                }
+            }
+            close.map(|_| start + 1)
+        } else if rest.len() < END_TAG.len() {
+            // Too little to tell which markup this is yet.
+            None
+        } else {
+            Some(start + 1)
+        };
+
+        match next {
+            Some(next) => *scanned = next,
+            // Wait for the rest of the markup.
+            None => {
+                *scanned = start;
+                return None;
+            }
+        }
+    }
+    *scanned = response.len();
+    None
+}
+
+#[cfg(feature = "std")]
 /// Attempts to parse an individual value out of a str.
 /// ```
 /// let x = serde_xmlrpc::value_from_str("<value><int>42</int></value>").unwrap().as_i32();
 /// assert_eq!(x, Some(42));
 /// ```
 pub fn value_from_str(input: &str) -> Result<Value> {
+    parse_value(input, &ParseOptions::default())
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn parse_value(input: &str, options: &ParseOptions) -> Result<Value> {
     let mut reader = Reader::from_str(input);
     reader.expand_empty_elements(true);
     reader.trim_text(true);
+    let state = ParseState::new(options);
 
-    reader.expect_tag(QName(b"value"))?;
-    let deserializer = ValueDeserializer::new(&mut reader)?;
-    let serializer = value::Serializer::new();
-    transcode(deserializer, serializer)
+    state.expect_root(&mut reader, QName(b"value"))?;
+    state.read_value(&mut reader)
 }
 
+#[cfg(feature = "std")]
 /// Attempts to convert any data type which can be represented as an xmlrpc value into a String.
 /// ```
 /// let a = serde_xmlrpc::value_to_string(42);
@@ -235,13 +612,99 @@ pub fn value_to_string<I>(val: I) -> Result<String>
 where
     I: Into<Value>,
 {
-    let d = value::Deserializer::from_value(val.into());
-    let mut writer = Writer::new(Vec::new());
-    let s = ValueSerializer::new(&mut writer);
-    transcode(d, s)?;
+    write_value(val.into(), &WriteOptions::default())
+}
+
+#[cfg(feature = "std")]
+/// Checks the body of a request against the XML-RPC spec, returning every violation found. An
+/// empty list means the request is valid. Extensions such as `<nil/>` are violations unless
+/// allowed with [`ValidateOptions`].
+/// ```
+/// let violations = serde_xmlrpc::validate_request(
+///     "<methodCall><methodName>add</methodName>\
+///     <params><param><value><int>1e3</int></value></param></params></methodCall>",
+/// );
+/// assert_eq!(violations[0].to_string(), "invalid <int> value \"1e3\" at byte 62");
+/// ```
+pub fn validate_request(input: &str) -> Vec<Violation> {
+    ValidateOptions::default().validate_request(input)
+}
+
+#[cfg(feature = "std")]
+/// Checks the body of a response against the XML-RPC spec, returning every violation found. Among
+/// other things a successful response must have exactly one param. See [`validate_request`].
+pub fn validate_response(input: &str) -> Vec<Violation> {
+    ValidateOptions::default().validate_response(input)
+}
+
+#[cfg(feature = "std")]
+/// Reads a call or response without stopping at the first error, returning it with every value
+/// which can't be read replaced by [`Value::Nil`], along with the errors and their positions.
+/// Unlike [`validate_request`], only what the parser itself rejects is reported, with the default
+/// [`ParseOptions`]. See [`ParseOptions::recover_document`].
+///
+/// ```
+/// use serde_xmlrpc::{Document, Value};
+///
+/// let (document, errors) = serde_xmlrpc::recover_document(
+///     "<methodCall><methodName>add</methodName><params>\
+///      <param><value><int>1</int></value></param><param><value><int>x</int></value></param>\
+///      </params></methodCall>",
+/// );
+/// match document {
+///     Some(Document::Call { params, .. }) => assert_eq!(params[1], Value::Nil),
+///     _ => unreachable!(),
+/// }
+/// assert_eq!(errors.len(), 1);
+/// ```
+pub fn recover_document(input: &str) -> (Option<Document>, Vec<Violation>) {
+    ParseOptions::default().recover_document(input)
+}
+
+#[cfg(feature = "std")]
+/// Counts the values of a call or response by type and measures how deeply they are nested and
+/// how much text they hold, without converting them. See [`DocumentStats`].
+///
+/// ```
+/// use serde_xmlrpc::ParseOptions;
+///
+/// let body = "<methodResponse><params><param><value><array><data>\
+///     <value><i4>1</i4></value><value><i4>2</i4></value>\
+///     </data></array></value></param></params></methodResponse>";
+///
+/// let stats = serde_xmlrpc::inspect(body).unwrap();
+/// assert_eq!((stats.values, stats.max_depth), (3, 1));
+///
+/// let options = ParseOptions::default().max_values(Some(2));
+/// assert!(stats.values > 2 && options.response_from_str::<Vec<i32>>(body).is_err());
+/// ```
+pub fn inspect(input: &str) -> Result<DocumentStats> {
+    stats::inspect(input)
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn write_value(val: Value, options: &WriteOptions) -> Result<String> {
+    let mut writer = Writer::new(Vec::with_capacity(util::value_xml_len(&val, options)));
+    write_value_to(&mut writer, val, options)?;
     Ok(String::from_utf8(writer.into_inner()).map_err(error::EncodingError::from)?)
 }
 
+#[cfg(feature = "std")]
+pub(crate) fn write_value_to<W>(
+    writer: &mut Writer<W>,
+    val: Value,
+    options: &WriteOptions,
+) -> Result<()>
+where
+    W: std::io::Write,
+{
+    let mut val = val;
+    if options.canonicalize {
+        val.canonicalize();
+    }
+    util::write_value(writer, &val, options)
+}
+
 /// Attempts to convert a Vec of values to any data type which can be deserialized.
 /// This is typically used with [request_from_str] to implement server behavior:
 /// ```
@@ -258,6 +721,27 @@ where
 /// // Now that we know what method is being called we can typecast our args
 /// let (a, b): (String, i32) = serde_xmlrpc::from_values(vals).unwrap();
 /// ```
+///
+/// Tuples need exactly one element per param. Structs are filled positionally, with the first
+/// param going to the first field and so on. This matches how methods are usually documented and
+/// avoids long tuple signatures. More params than fields is an error, and trailing fields marked
+/// `#[serde(default)]` may be left out:
+/// ```
+/// # use serde::Deserialize;
+/// # use serde_xmlrpc::Value;
+/// #[derive(Deserialize)]
+/// struct RequestTopic {
+///     caller_id: String,
+///     topic: String,
+///     #[serde(default)]
+///     protocols: Vec<Vec<String>>,
+/// }
+///
+/// let vals = vec![Value::from("/node"), Value::from("/rosout")];
+/// let params: RequestTopic = serde_xmlrpc::from_values(vals).unwrap();
+/// assert_eq!(params.topic, "/rosout");
+/// assert!(params.protocols.is_empty());
+/// ```
 pub fn from_values<T: serde::de::DeserializeOwned>(values: Vec<Value>) -> Result<T> {
     // Wrap input vec into our value type so it is compatible with our deserializer
     // Kinda a cheap hack, but I like returning Vec<Value> for the args to a function
@@ -266,6 +750,52 @@ pub fn from_values<T: serde::de::DeserializeOwned>(values: Vec<Value>) -> Result
     from_value(val)
 }
 
+/// Like [`from_values`], but borrows the params, and an error converting one of them says which
+/// one with a [`DecodingError::AtPath`] such as `/1` for the second param.
+///
+/// ```
+/// use serde_xmlrpc::Value;
+///
+/// let params = vec![Value::from("/node"), Value::Int(3)];
+/// let (caller_id, flags): (String, i32) = serde_xmlrpc::params_from_slice(&params).unwrap();
+/// assert_eq!(flags, 3);
+///
+/// let err = serde_xmlrpc::params_from_slice::<(String, String)>(&params).unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "decoding error: invalid type: integer `3`, expected a string at /1"
+/// );
+///
+/// let err = serde_xmlrpc::params_from_slice::<(String,)>(&params).unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "decoding error: serde: invalid length 2, expected a tuple of size 1"
+/// );
+/// ```
+pub fn params_from_slice<T: serde::de::DeserializeOwned>(params: &[Value]) -> Result<T> {
+    T::deserialize(value::Deserializer::tracking_path(Value::Array(
+        params.to_vec(),
+    )))
+}
+
+#[cfg(feature = "std")]
+/// Converts a tuple, slice or other sequence into the params of a call, one param per element.
+/// Anything else becomes a single param. See [`Values::from_params`].
+///
+/// ```
+/// use serde_xmlrpc::Value;
+///
+/// let params = serde_xmlrpc::params_to_vec(("/node", 3)).unwrap();
+/// assert_eq!(params, [Value::from("/node"), Value::Int(3)]);
+///
+/// let body = serde_xmlrpc::request_to_string("add", params).unwrap();
+/// assert!(body.contains("<param><value><int>3</int></value></param>"));
+/// assert!(!body.contains("<array>"));
+/// ```
+pub fn params_to_vec<T: serde::Serialize>(params: T) -> Result<Vec<Value>> {
+    Values::from_params(&params).map(Values::into_vec)
+}
+
 /// Attempts to deserialize the Value into the given type, equivalent API of
 /// [serde_json::from_value](https://docs.rs/serde_json/latest/serde_json/fn.from_value.html).
 /// ```
@@ -278,6 +808,29 @@ pub fn from_value<T: serde::de::DeserializeOwned>(value: Value) -> Result<T> {
     T::deserialize(d)
 }
 
+/// Compares two values member by member and element by element, returning every member or
+/// element which was added, removed or changed from `old` to `new`. Equal values give an empty
+/// `Vec`. Arrays are compared by index, so an element inserted at the front changes every element
+/// after it.
+///
+/// ```
+/// use serde_xmlrpc::{DiffEntry, Value};
+///
+/// let mut old = Value::Nil;
+/// old.entry("port").or_insert(80);
+/// let mut new = Value::Nil;
+/// new.entry("port").or_insert(8080);
+/// new.entry("debug").or_insert(true);
+///
+/// let changes = serde_xmlrpc::diff(&old, &new);
+/// assert_eq!(changes.len(), 2);
+/// assert_eq!(changes[0].to_string(), "changed /port: Int(80) -> Int(8080)");
+/// assert!(matches!(&changes[1], DiffEntry::Added { path, .. } if path == "/debug"));
+/// ```
+pub fn diff(old: &Value, new: &Value) -> Vec<DiffEntry> {
+    value::diff(old, new)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -290,6 +843,50 @@ mod tests {
         )
     }
 
+    #[test]
+    fn escape_policies() {
+        let text = r#"a>b'c"d&e<f"#;
+        let written = |policy| {
+            WriteOptions::default()
+                .escape(policy)
+                .value_to_string(text)
+                .unwrap()
+        };
+
+        assert_eq!(
+            written(EscapePolicy::Full),
+            "<value><string>a&gt;b&apos;c&quot;d&amp;e&lt;f</string></value>"
+        );
+        assert_eq!(written(EscapePolicy::Full), value_to_string(text).unwrap());
+        assert_eq!(
+            written(EscapePolicy::Text),
+            r#"<value><string>a&gt;b'c"d&amp;e&lt;f</string></value>"#
+        );
+        assert_eq!(
+            written(EscapePolicy::Minimal),
+            r#"<value><string>a>b'c"d&amp;e&lt;f</string></value>"#
+        );
+
+        for policy in [
+            EscapePolicy::Full,
+            EscapePolicy::Text,
+            EscapePolicy::Minimal,
+        ] {
+            let val = value_from_str(&written(policy)).unwrap();
+            assert_eq!(val.as_str(), Some(text));
+        }
+    }
+
+    #[test]
+    fn escape_minimal_cdata_end() {
+        let options = WriteOptions::default().escape(EscapePolicy::Minimal);
+        let body = options
+            .request_to_string("a]]>b", vec![Value::from("x]]>y >")])
+            .unwrap();
+        assert!(body.contains("<methodName>a]]&gt;b</methodName>"));
+        assert!(body.contains("<string>x]]&gt;y ></string>"));
+    }
+
     /// A 32-bit signed integer (`<i4>` or `<int>`).
     #[test]
     fn parse_int_values() {
@@ -333,6 +930,93 @@ mod tests {
         );
     }
 
+    /// Signs and leading zeros are allowed by the spec, whitespace only by lenient parsing.
+    #[test]
+    fn parse_padded_numbers() {
+        for options in [ParseOptions::lenient(), ParseOptions::strict()] {
+            let val = options.value_from_str("<value><int>+0042</int></value>");
+            assert_eq!(val.unwrap().as_i32(), Some(42));
+            let val = options.value_from_str("<value><double>+007.5</double></value>");
+            assert_eq!(val.unwrap().as_f64(), Some(7.5));
+        }
+
+        let lenient = ParseOptions::lenient();
+        let val = lenient.value_from_str("<value><i8>\n  -42 \n</i8></value>");
+        assert_eq!(val.unwrap().as_i32(), Some(-42));
+        let val = lenient.value_from_str("<value><double> 1.5\t</double></value>");
+        assert_eq!(val.unwrap().as_f64(), Some(1.5));
+
+        let strict = ParseOptions::strict();
+        assert!(strict
+            .response_from_str::<i32>(
+                "<methodResponse><params><param><value><int> 42</int></value></param></params></methodResponse>"
+            )
+            .is_err());
+        assert!(strict
+            .response_from_str::<f64>(
+                "<methodResponse><params><param><value><double>1.5 </double></value></param></params></methodResponse>"
+            )
+            .is_err());
+        let err = strict
+            .response_from_str::<i64>(
+                "<methodResponse><params><param><value><i4>2147483648</i4></value></param></params></methodResponse>"
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::DecodingError(error::DecodingError::IntDecodeError(_))
+        ));
+    }
+
+    /// Pretty-printed documents put whitespace inside every element.
+    #[test]
+    fn parse_pretty_printed_scalars() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Entry {
+            done: bool,
+            at: String,
+            note: String,
+        }
+
+        let body = "<methodResponse><params><param><value><struct>
+            <member><name>done</name><value><boolean>
+                1
+            </boolean></value></member>
+            <member><name>at</name><value><dateTime.iso8601>
+                19980717T14:08:55
+            </dateTime.iso8601></value></member>
+            <member><name>note</name><value><string>
+                hello
+            </string></value></member>
+            </struct></value></param></params></methodResponse>";
+
+        let entry: Entry = response_from_str(body).unwrap();
+        assert!(entry.done);
+        assert_eq!(entry.note.trim(), "hello");
+        assert_ne!(entry.note, "hello");
+
+        let trimmed = ParseOptions::default().trim_strings(true);
+        let entry: Entry = trimmed.response_from_str(body).unwrap();
+        assert_eq!(entry.note, "hello");
+        let val = trimmed
+            .value_from_str("<value>\n  untyped\n</value>")
+            .unwrap();
+        assert_eq!(val.as_str(), Some("untyped"));
+
+        for options in [
+            ParseOptions::strict(),
+            ParseOptions::lenient().trim_scalars(false),
+        ]
+        .iter()
+        {
+            assert!(options.response_from_str::<Entry>(body).is_err());
+        }
+        let entry = ParseOptions::strict()
+            .trim_scalars(true)
+            .response_from_str::<Entry>(body);
+        assert!(entry.unwrap().done);
+    }
+
     /// A boolean value (`<boolean>`, 0 == `false`, 1 == `true`).
     #[test]
     fn parse_boolean_values() {
@@ -350,6 +1034,25 @@ mod tests {
         );
     }
 
+    /// Python and PHP servers sometimes write the words instead.
+    #[test]
+    fn parse_boolean_words() {
+        let lenient = ParseOptions::lenient();
+        for (text, expected) in [("true", true), ("FALSE", false), ("True", true)].iter() {
+            let input = format!("<value><boolean>{}</boolean></value>", text);
+            assert_eq!(
+                lenient.value_from_str(&input).unwrap().as_bool(),
+                Some(*expected)
+            );
+            assert!(ParseOptions::strict().value_from_str(&input).is_err());
+        }
+
+        for text in ["yes", "2", "truth", ""].iter() {
+            let input = format!("<value><boolean>{}</boolean></value>", text);
+            assert!(lenient.value_from_str(&input).is_err(), "{}", text);
+        }
+    }
+
     /// A string (`<string>`). Note that these can also appear as a raw
     /// value tag as well.
     #[test]
@@ -369,8 +1072,258 @@ mod tests {
         assert_eq!(value_from_str("<value />").unwrap().as_str(), Some(""));
     }
 
+    /// Entities and character references in strings are unescaped, and whitespace is kept.
+    #[test]
+    fn parse_string_escapes() {
+        assert_eq!(
+            value_from_str("<value><string> a &amp; b&#33; </string></value>")
+                .unwrap()
+                .as_str(),
+            Some(" a & b! ")
+        );
+    }
+
+    #[test]
+    fn parse_datetime_values() {
+        let format = DateTimeFormat::EXTENDED.timezone(true).fraction(true);
+        let parse = |options: &ParseOptions, text: &str| {
+            options
+                .value_from_str(&format!(
+                    "<value><dateTime.iso8601>{}</dateTime.iso8601></value>",
+                    text
+                ))
+                .map(|v| v.as_datetime().map(|dt| format.format(&dt)))
+        };
+        let lenient = ParseOptions::lenient();
+        let strict = ParseOptions::strict();
+
+        assert_eq!(
+            parse(&strict, "19980717T14:08:55").unwrap().unwrap(),
+            "1998-07-17T14:08:55.000Z"
+        );
+        for (text, expected) in [
+            ("19980717T14:08:55", "1998-07-17T14:08:55.000Z"),
+            ("1998-07-17T14:08:55", "1998-07-17T14:08:55.000Z"),
+            (" 19980717T140855 ", "1998-07-17T14:08:55.000Z"),
+            ("19980717T14:08", "1998-07-17T14:08:00.000Z"),
+            ("1998-07-17 14:08:55.25Z", "1998-07-17T14:08:55.250Z"),
+            ("19980717T14:08:55+01:00", "1998-07-17T14:08:55.000+01:00"),
+            ("19980717T14:08:55-0130", "1998-07-17T14:08:55.000-01:30"),
+            ("19980717T14:08:55+01", "1998-07-17T14:08:55.000+01:00"),
+        ] {
+            assert_eq!(
+                parse(&lenient, text).unwrap().unwrap(),
+                expected,
+                "{}",
+                text
+            );
+        }
+
+        for text in [
+            "1998-07-17T14:08:55",
+            "19980717T14:08",
+            "19980717T14:08:55Z",
+        ] {
+            assert!(parse(&strict, text).is_err(), "{}", text);
+        }
+        for text in [
+            "",
+            "yesterday",
+            "19981317T14:08:55",
+            "19980717T14:08:55+",
+            "19980717",
+        ] {
+            assert!(parse(&lenient, text).is_err(), "{}", text);
+        }
+
+        // Typed targets still receive the text as it was written.
+        let body = r#"<?xml version="1.0" encoding="utf-8"?><methodResponse><params><param><value><dateTime.iso8601>1998-07-17T14:08:55Z</dateTime.iso8601></value></param></params></methodResponse>"#;
+        assert_eq!(
+            response_from_str::<String>(body).unwrap(),
+            "1998-07-17T14:08:55Z"
+        );
+
+        let dt = value_from_str(&value_to_string(parse_dt("2024-03-09T07:05:04")).unwrap());
+        assert_eq!(
+            dt.unwrap(),
+            Value::DateTime(parse_dt("2024-03-09T07:05:04"))
+        );
+    }
+
+    fn parse_dt(text: &str) -> XmlRpcDateTime {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn float_extension() {
+        let body = r#"<value><ex:float xmlns:ex="http://ws.apache.org/xmlrpc/namespaces/extensions">1.5</ex:float></value>"#;
+        assert_eq!(value_from_str(body).unwrap(), Value::Float(1.5));
+        assert_eq!(value_to_string(Value::Float(1.5)).unwrap(), body);
+
+        let response = response_to_string(vec![Value::Float(0.25)].into_iter()).unwrap();
+        assert_eq!(response_from_str::<f32>(&response).unwrap(), 0.25);
+        assert_eq!(response_from_str::<f64>(&response).unwrap(), 0.25);
+
+        let options = WriteOptions::default().float_extension(true);
+        let params = options.request_to_string("scale", vec![options.to_value(2.5f32).unwrap()]);
+        let (_, params) = request_from_str(&params.unwrap()).unwrap();
+        assert_eq!(params, vec![Value::Float(2.5)]);
+    }
+
+    #[test]
+    fn big_number_extension() {
+        let body = r#"<value><ex:bigdecimal xmlns:ex="http://ws.apache.org/xmlrpc/namespaces/extensions">1.10000000000000000000000000001</ex:bigdecimal></value>"#;
+        let value = value_from_str(body).unwrap();
+        assert!(matches!(value, Value::Raw(_)));
+        assert_eq!(value_to_string(value.clone()).unwrap(), body);
+        assert_eq!(
+            from_value::<String>(value).unwrap(),
+            "1.10000000000000000000000000001"
+        );
+
+        let response = response_to_string(vec![value_from_str(body).unwrap()].into_iter());
+        let response = response.unwrap();
+        assert_eq!(
+            response_from_str::<String>(&response).unwrap(),
+            "1.10000000000000000000000000001"
+        );
+        assert_eq!(
+            value_to_string(response_from_str::<Value>(&response).unwrap()).unwrap(),
+            body
+        );
+
+        let body = "<value><ex:biginteger> 12345678901234567890 </ex:biginteger></value>";
+        assert_eq!(
+            response_from_str::<String>(&format!(
+                "<methodResponse><params><param>{}</param></params></methodResponse>",
+                body
+            ))
+            .unwrap(),
+            "12345678901234567890"
+        );
+    }
+
+    #[test]
+    fn parse_cdata_values() {
+        for (input, expected) in [
+            (
+                "<value><string><![CDATA[<b>&amp;</b>]]></string></value>",
+                "<b>&amp;</b>",
+            ),
+            ("<value><![CDATA[ <b> ]]></value>", " <b> "),
+            (
+                "<value><string>a &lt; <![CDATA[<b>]]> &gt; c</string></value>",
+                "a < <b> > c",
+            ),
+            ("<value><string><![CDATA[]]></string></value>", ""),
+        ] {
+            assert_eq!(value_from_str(input).unwrap().as_str(), Some(expected));
+        }
+
+        assert!(value_from_str("<value><string>a<b/></string></value>").is_err());
+    }
+
+    #[test]
+    fn parse_member_names() {
+        let member = |name: &str| {
+            format!(
+                "<value><struct><member><name>{}</name><value><int>1</int></value></member></struct></value>",
+                name
+            )
+        };
+        let names = |options: &ParseOptions, name: &str| {
+            options
+                .value_from_str(&member(name))
+                .map(|val| val.as_struct().unwrap().keys().cloned().collect::<Vec<_>>())
+        };
+
+        let lenient = ParseOptions::lenient();
+        let strict = ParseOptions::strict();
+        for options in [&lenient, &strict] {
+            assert_eq!(names(options, "a &amp; b").unwrap(), ["a & b"]);
+            assert_eq!(names(options, "<![CDATA[a<b>]]>").unwrap(), ["a<b>"]);
+        }
+
+        assert_eq!(names(&lenient, "\n  padded\n").unwrap(), ["padded"]);
+        assert_eq!(names(&strict, " padded ").unwrap(), [" padded "]);
+
+        assert_eq!(
+            names(&lenient, "<string>nested</string>").unwrap(),
+            ["nested"]
+        );
+        assert!(names(&strict, "<string>nested</string>").is_err());
+    }
+
+    #[test]
+    fn write_datetime_values() {
+        let dt: XmlRpcDateTime = "2024-03-09T07:05:04.020-05:30".parse().unwrap();
+        let written = |format| {
+            WriteOptions::default()
+                .datetime_format(format)
+                .value_to_string(dt)
+                .unwrap()
+        };
+
+        assert_eq!(
+            value_to_string(dt).unwrap(),
+            "<value><dateTime.iso8601>20240309T07:05:04</dateTime.iso8601></value>"
+        );
+        for (format, expected) in [
+            (DateTimeFormat::CLASSIC, "20240309T07:05:04"),
+            (DateTimeFormat::EXTENDED, "2024-03-09T07:05:04"),
+            (
+                DateTimeFormat::CLASSIC.timezone(true),
+                "20240309T07:05:04-05:30",
+            ),
+            (
+                DateTimeFormat::EXTENDED.fraction(true).timezone(true),
+                "2024-03-09T07:05:04.020-05:30",
+            ),
+        ] {
+            assert_eq!(
+                written(format),
+                format!(
+                    "<value><dateTime.iso8601>{}</dateTime.iso8601></value>",
+                    expected
+                )
+            );
+        }
+
+        let utc: XmlRpcDateTime = "2024-03-09T07:05:04Z".parse().unwrap();
+        assert_eq!(
+            DateTimeFormat::EXTENDED.timezone(true).format(&utc),
+            "2024-03-09T07:05:04Z"
+        );
+
+        // The default writes the naive form Python expects, whatever the timezone.
+        assert_eq!(
+            WriteOptions::default().value_to_string(utc).unwrap(),
+            "<value><dateTime.iso8601>20240309T07:05:04</dateTime.iso8601></value>"
+        );
+        assert_eq!(
+            WriteOptions::apache().to_value(1.5f32).unwrap(),
+            Value::Float(1.5)
+        );
+    }
+
+    #[test]
+    fn write_cdata_values() {
+        let options = WriteOptions::default().cdata_threshold(Some(2));
+        for text in ["<p>]]></p>", "]]>]]>", "a < b", "plain"] {
+            let body = options.value_to_string(text).unwrap();
+            assert_eq!(value_from_str(&body).unwrap().as_str(), Some(text));
+        }
+
+        let body = options.value_to_string("<p>]]></p>").unwrap();
+        assert_eq!(
+            body,
+            "<value><string><![CDATA[<p>]]]]><![CDATA[></p>]]></string></value>"
+        );
+    }
+
     /// A double-precision IEEE 754 floating point number (`<double>`).
     #[test]
+    #[allow(clippy::approx_constant)]
     fn parse_double_values() {
         assert_eq!(
             value_from_str("<value><double>1</double></value>")
@@ -404,10 +1357,97 @@ mod tests {
         );
     }
 
+    /// Exponent notation is only accepted by lenient parsing.
+    #[test]
+    fn parse_double_exponent() {
+        assert_eq!(
+            value_from_str("<value><double>1e10</double></value>")
+                .unwrap()
+                .as_f64(),
+            Some(1e10)
+        );
+        assert_eq!(
+            ParseOptions::strict()
+                .value_from_str("<value><double>-3.25</double></value>")
+                .unwrap()
+                .as_f64(),
+            Some(-3.25)
+        );
+        assert_eq!(
+            ParseOptions::strict()
+                .value_from_str("<value><double>+.5</double></value>")
+                .unwrap()
+                .as_f64(),
+            Some(0.5)
+        );
+
+        for input in ["1e10", "1.5E-3", "inf", "NaN", ".", ""] {
+            let err = ParseOptions::strict()
+                .response_from_str::<f64>(&format!(
+                    "<methodResponse><params><param><value><double>{}</double></value></param></params></methodResponse>",
+                    input
+                ))
+                .unwrap_err();
+            assert!(
+                matches!(
+                    err,
+                    Error::DecodingError(error::DecodingError::DoubleDecodeError(_))
+                ),
+                "{:?}",
+                err
+            );
+        }
+    }
+
+    /// Python writes NaN and infinity as words, which the spec has no place for.
+    #[test]
+    fn non_finite_doubles() {
+        let lenient = WriteOptions::lenient();
+        for (val, text) in [
+            (f64::NAN, "nan"),
+            (f64::INFINITY, "inf"),
+            (f64::NEG_INFINITY, "-inf"),
+        ]
+        .iter()
+        {
+            let body = lenient.value_to_string(*val).unwrap();
+            assert_eq!(body, format!("<value><double>{}</double></value>", text));
+            let parsed = value_from_str(&body).unwrap().as_f64().unwrap();
+            assert!(parsed == *val || parsed.is_nan() && val.is_nan());
+
+            for options in [WriteOptions::strict(), WriteOptions::apache()].iter() {
+                let err = options.value_to_string(*val).unwrap_err();
+                assert!(matches!(
+                    err,
+                    Error::EncodingError(EncodingError::Unrepresentable(_))
+                ));
+            }
+        }
+        let body = lenient.value_to_string(Value::Float(f32::INFINITY));
+        assert!(body.unwrap().contains(">inf</ex:float>"));
+        assert!(WriteOptions::apache().value_to_string(1.5f32).is_ok());
+
+        for input in ["NaN", "-Infinity", "+inf"].iter() {
+            let input = format!("<value><double>{}</double></value>", input);
+            assert!(value_from_str(&input).is_ok());
+            let strict = ParseOptions::strict().allow_non_finite(true);
+            assert!(strict.value_from_str(&input).is_ok());
+            let lenient = ParseOptions::lenient().allow_non_finite(false);
+            assert!(lenient.value_from_str(&input).is_err());
+        }
+
+        // Numbers out of range overflow to infinity.
+        let input = "<value><double>1e999</double></value>";
+        assert_eq!(value_from_str(input).unwrap().as_f64(), Some(f64::INFINITY));
+        let lenient = ParseOptions::lenient().allow_non_finite(false);
+        assert!(lenient.value_from_str(input).is_err());
+    }
+
     /// An ISO 8601 formatted date/time value (`<dateTime.iso8601>`).
 
     /// Base64-encoded binary data (`<base64>`).
     #[test]
+    #[allow(clippy::empty_line_after_doc_comments)]
     fn parse_base64_values() {
         assert_eq!(
             value_from_str("<value><base64>aGVsbG8gd29ybGQ=</base64></value>")
@@ -417,10 +1457,247 @@ mod tests {
         );
     }
 
+    /// Flattened fields end up as members of the one struct, and are picked out of it again when
+    /// reading, whether from a document or a `Value`.
+    #[test]
+    fn serde_flatten_roundtrip() {
+        #[derive(Debug, PartialEq, serde::Serialize, Deserialize)]
+        struct Limits {
+            timeout: Option<i32>,
+            retries: u32,
+        }
+
+        #[derive(Debug, PartialEq, serde::Serialize, Deserialize)]
+        #[serde(tag = "kind")]
+        enum Target {
+            Disk { size: i64 },
+            Host { name: String },
+        }
+
+        #[derive(Debug, PartialEq, serde::Serialize, Deserialize)]
+        struct Config {
+            id: i32,
+            #[serde(flatten)]
+            limits: Limits,
+            #[serde(flatten)]
+            target: Target,
+        }
+
+        let config = Config {
+            id: 1,
+            limits: Limits {
+                timeout: None,
+                retries: 3,
+            },
+            target: Target::Host {
+                name: "example.com".into(),
+            },
+        };
+
+        let val = to_value(&config).unwrap();
+        let mut names: Vec<_> = val.as_struct().unwrap().keys().cloned().collect();
+        names.sort();
+        assert_eq!(names, vec!["id", "kind", "name", "retries", "timeout"]);
+        assert_eq!(val.pointer("/timeout"), Some(&Value::Nil));
+
+        let body = response_to_string(vec![val.clone()].into_iter()).unwrap();
+        assert_eq!(response_from_str::<Config>(&body).unwrap(), config);
+        assert_eq!(from_value::<Config>(val).unwrap(), config);
+
+        // Peers leave out members and use whichever integer and string forms they like.
+        let body = "<methodResponse><params><param><value><struct>\
+            <member><name>kind</name><value>Disk</value></member>\
+            <member><name>retries</name><value><i4>5</i4></value></member>\
+            <member><name>size</name><value><i8>5000000000</i8></value></member>\
+            <member><name>id</name><value><int>2</int></value></member>\
+            </struct></value></param></params></methodResponse>";
+        let config = Config {
+            id: 2,
+            limits: Limits {
+                timeout: None,
+                retries: 5,
+            },
+            target: Target::Disk {
+                size: 5_000_000_000,
+            },
+        };
+        assert_eq!(response_from_str::<Config>(body).unwrap(), config);
+        let val = body.parse::<Document>().unwrap().into_result().unwrap();
+        assert_eq!(
+            from_value::<Config>(val.into_vec().remove(0)).unwrap(),
+            config
+        );
+    }
+
+    /// Members a struct doesn't model can be kept in a flattened map of values, and are written
+    /// back out along with the rest.
+    #[test]
+    fn serde_flatten_unknown_members() {
+        use std::collections::HashMap;
+
+        #[derive(Debug, PartialEq, serde::Serialize, Deserialize)]
+        struct Status {
+            state: String,
+            #[serde(flatten)]
+            other: HashMap<String, Value>,
+        }
+
+        let body = "<methodResponse><params><param><value><struct>\
+            <member><name>state</name><value>up</value></member>\
+            <member><name>load</name><value><array><data><value><double>0.5</double></value>\
+            </data></array></value></member>\
+            <member><name>since</name><value><base64>AQI=</base64></value></member>\
+            </struct></value></param></params></methodResponse>";
+        let status: Status = response_from_str(body).unwrap();
+        assert_eq!(status.state, "up");
+        assert_eq!(status.other["load"], Value::Array(vec![Value::Double(0.5)]));
+        assert_eq!(status.other["since"], Value::Base64(vec![1, 2]));
+
+        let val = to_value(&status).unwrap();
+        assert_eq!(val.pointer("/load/0"), Some(&Value::Double(0.5)));
+        assert_eq!(from_value::<Status>(val).unwrap(), status);
+    }
+
+    #[test]
+    fn punctuated_member_names() {
+        #[derive(Debug, PartialEq, serde::Serialize, Deserialize)]
+        struct Bug {
+            #[serde(rename = "bug.id")]
+            id: i32,
+            #[serde(rename = "last-change")]
+            last_change: String,
+            #[serde(rename = "assigned to")]
+            assigned_to: String,
+        }
+
+        let bug = Bug {
+            id: 42,
+            last_change: "today".to_string(),
+            assigned_to: "nobody".to_string(),
+        };
+        let body = response_to_string(vec![to_value(&bug).unwrap()].into_iter()).unwrap();
+        assert!(body.contains("<name>bug.id</name>"));
+        assert!(body.contains("<name>last-change</name>"));
+        assert!(body.contains("<name>assigned to</name>"));
+        assert_eq!(response_from_str::<Bug>(&body).unwrap(), bug);
+        let strict: Bug = ParseOptions::strict().response_from_str(&body).unwrap();
+        assert_eq!(strict, bug);
+
+        let val = response_from_str::<Value>(&body).unwrap();
+        assert_eq!(val.pointer("/assigned to"), Some(&Value::from("nobody")));
+        assert_eq!(from_value::<Bug>(val).unwrap(), bug);
+    }
+
+    #[test]
+    fn rename_member_table() {
+        #[derive(Debug, PartialEq, serde::Serialize, Deserialize)]
+        struct Bug {
+            bug_id: i32,
+            last_change: String,
+            status: String,
+        }
+
+        let bug = Bug {
+            bug_id: 42,
+            last_change: "today".to_string(),
+            status: "NEW".to_string(),
+        };
+        let write = WriteOptions::default()
+            .rename_member("bug_id", "id")
+            .rename_member("bug_id", "bug.id")
+            .rename_member("last_change", "last-change time");
+        let val = write.to_value(&bug).unwrap();
+        let names: Vec<_> = val.as_struct().unwrap().keys().cloned().collect();
+        assert!(names.contains(&"bug.id".to_string()));
+        assert!(names.contains(&"last-change time".to_string()));
+        assert!(names.contains(&"status".to_string()));
+        assert_eq!(names.len(), 3);
+
+        let body = write.response_to_string(vec![val].into_iter()).unwrap();
+        assert!(response_from_str::<Bug>(&body).is_err());
+        let read = ParseOptions::default()
+            .rename_member("bug_id", "bug.id")
+            .rename_member("last_change", "last-change time");
+        assert_eq!(read.response_from_str::<Bug>(&body).unwrap(), bug);
+    }
+
+    #[test]
+    fn duplicate_members() {
+        use std::collections::HashMap;
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Ticket {
+            id: i32,
+            tag: String,
+        }
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Tags {
+            id: i32,
+            tag: Vec<String>,
+            owner: Option<Vec<Value>>,
+        }
+
+        let body = "<methodResponse><params><param><value><struct>\
+            <member><name>tag</name><value>a</value></member>\
+            <member><name>id</name><value><int>1</int></value></member>\
+            <member><name>tag</name><value>b</value></member>\
+            <member><name>tag</name><value>c</value></member>\
+            </struct></value></param></params></methodResponse>";
+        let read = |policy| ParseOptions::default().duplicate_members(policy);
+        let ticket = |tag: &str| Ticket {
+            id: 1,
+            tag: tag.to_string(),
+        };
+
+        assert!(response_from_str::<Ticket>(body).is_err());
+        let map: HashMap<String, Value> = response_from_str(body).unwrap();
+        assert_eq!(map["tag"], Value::from("c"));
+        let first = read(DuplicateMembers::KeepFirst).response_from_str::<Ticket>(body);
+        assert_eq!(first.unwrap(), ticket("a"));
+        let last = read(DuplicateMembers::KeepLast).response_from_str::<Ticket>(body);
+        assert_eq!(last.unwrap(), ticket("c"));
+        let map: HashMap<String, Value> = read(DuplicateMembers::KeepFirst)
+            .response_from_str(body)
+            .unwrap();
+        assert_eq!(map["tag"], Value::from("a"));
+
+        let err = read(DuplicateMembers::Error)
+            .response_from_str::<Ticket>(body)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::DecodingError(DecodingError::DuplicateMember(ref name)) if name == "tag"
+        ));
+
+        let tags: Tags = read(DuplicateMembers::Collect)
+            .response_from_str(body)
+            .unwrap();
+        assert_eq!(tags.id, 1);
+        assert_eq!(tags.tag, vec!["a", "b", "c"]);
+        assert_eq!(tags.owner, None);
+
+        // Collected values are read in full, and keep their types when read into a Value.
+        let body = "<value><struct>\
+            <member><name>at</name><value><dateTime.iso8601>19980717T14:08:55</dateTime.iso8601></value></member>\
+            <member><name>at</name><value><struct><member><name>n</name><value><int>2</int></value></member>\
+            <member><name>n</name><value><int>3</int></value></member></struct></value></member>\
+            </struct></value>";
+        let val = read(DuplicateMembers::Collect)
+            .value_from_str(body)
+            .unwrap();
+        assert!(matches!(val.pointer("/at/0"), Some(Value::DateTime(_))));
+        assert_eq!(
+            val.pointer("/at/1/n"),
+            Some(&Value::Array(vec![Value::Int(2), Value::Int(3)]))
+        );
+    }
+
     /// A mapping of named values (`<struct>`).
 
     /// A list of arbitrary (heterogeneous) values (`<array>`).
     #[test]
+    #[allow(clippy::empty_line_after_doc_comments)]
     fn parse_array_values() {
         assert_eq!(
             value_from_str(
@@ -432,6 +1709,117 @@ mod tests {
         );
     }
 
+    #[test]
+    fn serde_bytes_roundtrip() {
+        #[derive(Debug, PartialEq, serde::Serialize, Deserialize)]
+        struct Upload {
+            #[serde(with = "serde_bytes")]
+            data: Vec<u8>,
+            plain: Vec<u8>,
+        }
+
+        let upload = Upload {
+            data: b"hello world".to_vec(),
+            plain: vec![1, 2],
+        };
+
+        let val = to_value(&upload).unwrap();
+        let members = val.as_struct().unwrap();
+        assert_eq!(members["data"], Value::Base64(b"hello world".to_vec()));
+        assert_eq!(
+            members["plain"],
+            Value::Array(vec![Value::Int(1), Value::Int(2)])
+        );
+
+        let body = response_to_string(vec![val.clone()].into_iter()).unwrap();
+        assert!(body.contains("<base64>aGVsbG8gd29ybGQ=</base64>"));
+        assert_eq!(response_from_str::<Upload>(&body).unwrap(), upload);
+        assert_eq!(from_value::<Upload>(val).unwrap(), upload);
+    }
+
+    #[cfg(feature = "preserve_order")]
+    #[test]
+    fn struct_member_order_roundtrip() {
+        let input = "<value><struct><member><name>zeta</name><value><int>1</int></value></member><member><name>alpha</name><value><int>2</int></value></member></struct></value>";
+
+        let value = value_from_str(input).unwrap();
+        let names: Vec<_> = value.as_struct().unwrap().keys().cloned().collect();
+        assert_eq!(names, vec!["zeta", "alpha"]);
+        assert_eq!(value_to_string(value).unwrap(), input);
+    }
+
+    #[test]
+    fn parse_rejects_entity_expansion() {
+        let billion_laughs = r#"<?xml version="1.0"?>
+<!DOCTYPE lolz [
+ <!ENTITY lol "lol">
+ <!ENTITY lol1 "&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;">
+ <!ENTITY lol2 "&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;">
+ <!ENTITY lol3 "&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;">
+ <!ENTITY lol4 "&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;">
+ <!ENTITY lol5 "&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;">
+ <!ENTITY lol6 "&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;">
+ <!ENTITY lol7 "&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;">
+ <!ENTITY lol8 "&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;">
+ <!ENTITY lol9 "&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;">
+]>"#;
+        let external = r#"<?xml version="1.0"?>
+<!DOCTYPE foo [<!ENTITY xxe SYSTEM "file:///etc/passwd">]>"#;
+        let quadratic = format!(r#"<!DOCTYPE foo [<!ENTITY a "{}">]>"#, "a".repeat(50_000));
+
+        for (prolog, entity) in [
+            (billion_laughs, "&lol9;"),
+            (external, "&xxe;"),
+            (quadratic.as_str(), &"&a;".repeat(1000)),
+        ] {
+            let value = format!("<value>{}</value>", entity);
+            let bodies = [
+                format!("{}{}", prolog, value),
+                format!(
+                    "{}<methodCall><methodName>x</methodName><params><param>{}</param></params></methodCall>",
+                    prolog, value
+                ),
+                format!(
+                    "{}<methodResponse><params><param>{}</param></params></methodResponse>",
+                    prolog, value
+                ),
+            ];
+
+            let options = ParseOptions::default();
+            let results = [
+                options.value_from_str(&bodies[0]),
+                options
+                    .request_from_str(&bodies[1])
+                    .map(|(_, mut p)| p.remove(0)),
+                options
+                    .response_from_str::<String>(&bodies[2])
+                    .map(Value::from),
+                options.document_from_str(&bodies[2]).map(|_| Value::Nil),
+            ];
+            for result in results {
+                assert!(matches!(
+                    result,
+                    Err(Error::DecodingError(DecodingError::DoctypeNotAllowed))
+                ));
+            }
+
+            // Even when the declaration is allowed its entities are never expanded.
+            let options = ParseOptions::default().allow_doctype(true);
+            let results = [
+                options.value_from_str(&bodies[0]),
+                options
+                    .request_from_str(&bodies[1])
+                    .map(|(_, mut p)| p.remove(0)),
+                options
+                    .response_from_str::<String>(&bodies[2])
+                    .map(Value::from),
+            ];
+            for val in results.iter().flatten() {
+                assert!(val.as_str().unwrap().len() <= entity.len());
+            }
+        }
+    }
+
     /// The empty (Unit) value (`<nil/>`).
     #[test]
     fn parse_nil_values() {
@@ -442,6 +1830,67 @@ mod tests {
     }
 
     #[test]
+    fn parse_limits() {
+        let nested = |depth| {
+            let mut input = "<value><array><data>".repeat(depth);
+            input.push_str(&"</data></array></value>".repeat(depth));
+            input
+        };
+
+        assert!(value_from_str(&nested(MAX_SAFE_DEPTH)).is_ok());
+        assert!(matches!(
+            value_from_str(&nested(MAX_SAFE_DEPTH + 1)),
+            Err(Error::DecodingError(DecodingError::DepthLimitExceeded(
+                MAX_SAFE_DEPTH
+            )))
+        ));
+        #[cfg(not(feature = "small-limits"))]
+        assert_eq!(MAX_SAFE_DEPTH, 64);
+        assert!(ParseOptions::default()
+            .max_depth(Some(100))
+            .value_from_str(&nested(100))
+            .is_ok());
+
+        let options = ParseOptions::default().max_text_len(Some(4));
+        assert!(options
+            .value_from_str("<value><string>abcd</string></value>")
+            .is_ok());
+        for input in [
+            "<value><string>abcde</string></value>",
+            "<value>abcde</value>",
+            "<value><base64>aGVsbG8=</base64></value>",
+            "<value><struct><member><name>abcde</name><value><int>1</int></value></member></struct></value>",
+        ] {
+            assert!(matches!(
+                options.value_from_str(input),
+                Err(Error::DecodingError(DecodingError::TextLimitExceeded(4)))
+            ));
+        }
+
+        // The value count covers the whole document, not each param on its own.
+        let options = ParseOptions::default().max_values(Some(3));
+        let request = request_to_string(
+            "test",
+            vec![Value::Int(1), Value::Array(vec![Value::Int(2)])],
+        )
+        .unwrap();
+        assert!(options.request_from_str(&request).is_ok());
+        let request = request_to_string(
+            "test",
+            vec![
+                Value::Int(1),
+                Value::Array(vec![Value::Int(2), Value::Int(3)]),
+            ],
+        )
+        .unwrap();
+        assert!(matches!(
+            options.request_from_str(&request),
+            Err(Error::DecodingError(DecodingError::ValueLimitExceeded(3)))
+        ));
+    }
+
+    #[test]
+    #[allow(clippy::unnecessary_to_owned, clippy::assertions_on_constants)]
     fn parse_fault() {
         let err = response_from_str::<String>(
             r#"<?xml version="1.0" encoding="utf-8"?>
@@ -460,7 +1909,8 @@ mod tests {
                  </struct>
                 </value>
               </fault>
-            </methodResponse>"#.to_string(),
+            </methodResponse>"#
+                .to_string(),
         )
         .unwrap_err();
 
@@ -480,6 +1930,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_to_owned)]
     fn parse_value() {
         let val: String = response_from_str(
             r#"<?xml version="1.0" encoding="utf-8"?>
@@ -487,7 +1938,8 @@ mod tests {
               <params>
                 <param><value><string>hello world</string></value></param>
               </params>
-            </methodResponse>"#.to_string(),
+            </methodResponse>"#
+                .to_string(),
         )
         .unwrap();
 
@@ -531,4 +1983,96 @@ mod tests {
         assert_eq!(b, 1.0);
         assert_eq!(c, "hello");
     }
+
+    #[test]
+    fn test_params_from_slice() {
+        let vals: Vec<Value> = (0..12).map(Value::Int).collect();
+        let params: (i32, i32, i32, i32, i32, i32, i32, i32, i32, i32, i32, i64) =
+            params_from_slice(&vals).unwrap();
+        assert_eq!(params.11, 11);
+
+        let err = params_from_slice::<(i32, i32, i32)>(&vals[..2]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "decoding error: serde: invalid length 2, expected a tuple of size 3"
+        );
+        assert!(params_from_slice::<(i32,)>(&vals[..2]).is_err());
+        assert!(from_values::<(i32,)>(vals).is_err());
+    }
+
+    #[test]
+    fn test_params_to_vec() {
+        let params = params_to_vec(&[1, 2][..]).unwrap();
+        assert_eq!(params, [Value::Int(1), Value::Int(2)]);
+        let params = params_to_vec((vec![1, 2],)).unwrap();
+        assert_eq!(params, [Value::Array(vec![Value::Int(1), Value::Int(2)])]);
+        assert_eq!(params_to_vec("x").unwrap(), [Value::from("x")]);
+    }
+
+    #[test]
+    fn call_reader_writer_open_stream() {
+        /// Hands out `chunks` one read at a time, then fails the test instead of blocking like a
+        /// pipe that stays open would.
+        struct Pipe(std::collections::VecDeque<Vec<u8>>);
+
+        impl std::io::Read for Pipe {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let chunk = self
+                    .0
+                    .pop_front()
+                    .expect("read past the end of the response");
+                buf[..chunk.len()].copy_from_slice(&chunk);
+                Ok(chunk.len())
+            }
+        }
+
+        let pipe = |response: &str, at: &[usize]| {
+            let mut chunks = std::collections::VecDeque::new();
+            let mut last = 0;
+            for &at in at.iter().chain(std::iter::once(&response.len())) {
+                chunks.push_back(response.as_bytes()[last..at].to_vec());
+                last = at;
+            }
+            Pipe(chunks)
+        };
+
+        let response = "<?xml version=\"1.0\"?><methodResponse><params><param>\
+            <value><string><![CDATA[</methodResponse>]]></string></value>\
+            </param></params><!-- </methodResponse> --></methodResponse \n>";
+        let end = response.find("</methodResponse ").unwrap();
+        for at in [vec![], vec![50], vec![end + 3, end + 17], vec![end + 16]] {
+            let reader = pipe(response, &at);
+            let text: String = call_reader_writer("echo", vec![], reader, Vec::new()).unwrap();
+            assert_eq!(text, "</methodResponse>", "split at {:?}", at);
+        }
+
+        // A stream which closes without the end tag is read up to EOF, as before.
+        let reader = std::io::Cursor::new(&response[..end]);
+        let text: String = call_reader_writer("echo", vec![], reader, Vec::new()).unwrap();
+        assert_eq!(text, "</methodResponse>");
+    }
+
+    #[test]
+    fn call_reader_writer_fault() {
+        let response = Document::Fault(Fault {
+            fault_code: 3,
+            fault_string: "no such method".into(),
+        })
+        .to_xml()
+        .unwrap();
+
+        let mut request = Vec::new();
+        let err = call_reader_writer::<i32, _, _>(
+            "missing",
+            vec![],
+            std::io::Cursor::new(response),
+            &mut request,
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::Fault(Fault { fault_code: 3, .. })));
+
+        let (method, args) = request_from_str(std::str::from_utf8(&request).unwrap()).unwrap();
+        assert_eq!(method, "missing");
+        assert!(args.is_empty());
+    }
 }
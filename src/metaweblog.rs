@@ -0,0 +1,542 @@
+//! Typed calls for the Blogger, [MetaWeblog] and WordPress blogging APIs.
+//!
+//! Every method is a struct of its params which implements [`BlogMethod`], so a call is written
+//! with [`request_to_string`] and its response read with [`response_from_str`]:
+//!
+//! ```
+//! use serde_xmlrpc::metaweblog::{self, NewPost, Post};
+//!
+//! let call = NewPost {
+//!     blog_id: "1".into(),
+//!     username: "admin".into(),
+//!     password: "secret".into(),
+//!     post: Post {
+//!         title: "Hello".into(),
+//!         description: "<p>First post</p>".into(),
+//!         categories: vec!["News".into()],
+//!         ..Post::default()
+//!     },
+//!     publish: true,
+//! };
+//! let body = metaweblog::request_to_string(&call).unwrap();
+//! assert!(body.contains("<methodName>metaWeblog.newPost</methodName>"));
+//! assert!(body.contains("<name>title</name><value><string>Hello</string>"));
+//!
+//! // WordPress returns the ID of the new post as a string, others as an int.
+//! let response = "<methodResponse><params><param><value><int>42</int></value></param></params>\
+//!     </methodResponse>";
+//! let id = metaweblog::response_from_str::<NewPost>(response).unwrap();
+//! assert_eq!(id, "42");
+//! ```
+//!
+//! Blog servers are loose with types, so the structs here read what they are known to send:
+//! IDs as strings or integers, booleans as `<boolean>`, integers or strings, dates as
+//! `<dateTime.iso8601>` or strings, and missing members as empty. They double as examples of
+//! handling such peers with serde attributes.
+//!
+//! [MetaWeblog]: http://xmlrpc.com/metaWeblogApi.html
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::error::ValueError;
+use crate::{Result, Value, ValueKind, Values, XmlRpcDateTime};
+
+/// A method of the blogging APIs.
+pub trait BlogMethod {
+    /// The name the method is called by, such as `metaWeblog.newPost`.
+    const NAME: &'static str;
+
+    /// The value of the response.
+    type Output;
+
+    /// Converts the call into its params, in order.
+    fn to_params(&self) -> Result<Values>;
+
+    /// Reads the value of the response.
+    fn read_output(value: Value) -> Result<Self::Output>;
+}
+
+/// Writes the request body of a call.
+pub fn request_to_string<M: BlogMethod>(method: &M) -> Result<String> {
+    crate::request_to_string(M::NAME, method.to_params()?.into_vec())
+}
+
+/// Reads the response to a call of `M`. Faults are returned as
+/// [`Error::Fault`](crate::Error::Fault).
+pub fn response_from_str<M: BlogMethod>(input: &str) -> Result<M::Output> {
+    M::read_output(crate::response_from_str(input)?)
+}
+
+/// A post, as written with `metaWeblog.newPost` and read with `metaWeblog.getPost`.
+///
+/// Members which are empty aren't written, so a post for `metaWeblog.editPost` only changes what
+/// it sets.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Post {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub title: String,
+    /// The body of the post, usually HTML.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// When the post was published. Servers without a timezone mean their own.
+    #[serde(
+        rename = "dateCreated",
+        default,
+        with = "optional_date",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub date_created: Option<XmlRpcDateTime>,
+    /// The names of the post's categories.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+    #[serde(
+        default,
+        deserialize_with = "string_or_number",
+        skip_serializing_if = "String::is_empty"
+    )]
+    pub postid: String,
+    #[serde(
+        default,
+        deserialize_with = "string_or_number",
+        skip_serializing_if = "String::is_empty"
+    )]
+    pub userid: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub link: String,
+    #[serde(
+        rename = "permaLink",
+        default,
+        skip_serializing_if = "String::is_empty"
+    )]
+    pub perma_link: String,
+    /// A summary of the post, a Movable Type extension.
+    #[serde(
+        rename = "mt_excerpt",
+        default,
+        skip_serializing_if = "String::is_empty"
+    )]
+    pub excerpt: String,
+    /// Comma-separated tags, a Movable Type extension.
+    #[serde(
+        rename = "mt_keywords",
+        default,
+        skip_serializing_if = "String::is_empty"
+    )]
+    pub keywords: String,
+    /// Such as `publish` or `draft`, a WordPress extension.
+    #[serde(
+        rename = "post_status",
+        default,
+        skip_serializing_if = "String::is_empty"
+    )]
+    pub status: String,
+}
+
+/// A blog the user can post to, as returned by `blogger.getUsersBlogs`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Blog {
+    #[serde(rename = "blogid", deserialize_with = "string_or_number")]
+    pub blog_id: String,
+    #[serde(rename = "blogName", default)]
+    pub blog_name: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(rename = "isAdmin", default, deserialize_with = "lenient_bool")]
+    pub is_admin: bool,
+    /// The blog's XML-RPC endpoint, a WordPress extension.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub xmlrpc: String,
+}
+
+/// A category, as returned by `metaWeblog.getCategories`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Category {
+    /// The ID of the category, a WordPress extension.
+    #[serde(
+        rename = "categoryId",
+        default,
+        deserialize_with = "string_or_number",
+        skip_serializing_if = "String::is_empty"
+    )]
+    pub category_id: String,
+    /// The name of the category. Servers which return the categories as a struct keyed by name
+    /// have it filled in from the key.
+    #[serde(rename = "categoryName", default)]
+    pub category_name: String,
+    /// Usually the name of the category again.
+    #[serde(default)]
+    pub description: String,
+    #[serde(rename = "htmlUrl", default)]
+    pub html_url: String,
+    #[serde(rename = "rssUrl", default)]
+    pub rss_url: String,
+}
+
+/// Creates a post, returning its ID.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NewPost {
+    pub blog_id: String,
+    pub username: String,
+    pub password: String,
+    pub post: Post,
+    /// Whether to publish the post rather than keep it as a draft.
+    pub publish: bool,
+}
+
+impl BlogMethod for NewPost {
+    const NAME: &'static str = "metaWeblog.newPost";
+    type Output = String;
+
+    fn to_params(&self) -> Result<Values> {
+        Ok(Values::new()
+            .param(&self.blog_id)
+            .param(&self.username)
+            .param(&self.password)
+            .param(&crate::to_value(&self.post)?)
+            .param(&self.publish))
+    }
+
+    fn read_output(value: Value) -> Result<Self::Output> {
+        id_from_value(value)
+    }
+}
+
+/// Replaces the members of a post which are set, returning whether it succeeded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EditPost {
+    pub post_id: String,
+    pub username: String,
+    pub password: String,
+    pub post: Post,
+    pub publish: bool,
+}
+
+impl BlogMethod for EditPost {
+    const NAME: &'static str = "metaWeblog.editPost";
+    type Output = bool;
+
+    fn to_params(&self) -> Result<Values> {
+        Ok(Values::new()
+            .param(&self.post_id)
+            .param(&self.username)
+            .param(&self.password)
+            .param(&crate::to_value(&self.post)?)
+            .param(&self.publish))
+    }
+
+    fn read_output(value: Value) -> Result<Self::Output> {
+        bool_from_value(&value).ok_or_else(|| ValueError::mismatch(ValueKind::BOOL, &value).into())
+    }
+}
+
+/// Reads a post.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GetPost {
+    pub post_id: String,
+    pub username: String,
+    pub password: String,
+}
+
+impl BlogMethod for GetPost {
+    const NAME: &'static str = "metaWeblog.getPost";
+    type Output = Post;
+
+    fn to_params(&self) -> Result<Values> {
+        Ok(Values::new()
+            .param(&self.post_id)
+            .param(&self.username)
+            .param(&self.password))
+    }
+
+    fn read_output(value: Value) -> Result<Self::Output> {
+        crate::from_value(value)
+    }
+}
+
+/// Reads the most recent posts, newest first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GetRecentPosts {
+    pub blog_id: String,
+    pub username: String,
+    pub password: String,
+    pub number_of_posts: i32,
+}
+
+impl BlogMethod for GetRecentPosts {
+    const NAME: &'static str = "metaWeblog.getRecentPosts";
+    type Output = Vec<Post>;
+
+    fn to_params(&self) -> Result<Values> {
+        Ok(Values::new()
+            .param(&self.blog_id)
+            .param(&self.username)
+            .param(&self.password)
+            .param(&self.number_of_posts))
+    }
+
+    fn read_output(value: Value) -> Result<Self::Output> {
+        crate::from_value(value)
+    }
+}
+
+/// Lists the categories of a blog.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GetCategories {
+    pub blog_id: String,
+    pub username: String,
+    pub password: String,
+}
+
+impl BlogMethod for GetCategories {
+    const NAME: &'static str = "metaWeblog.getCategories";
+    type Output = Vec<Category>;
+
+    fn to_params(&self) -> Result<Values> {
+        Ok(Values::new()
+            .param(&self.blog_id)
+            .param(&self.username)
+            .param(&self.password))
+    }
+
+    /// Reads either an array of categories or, as Movable Type returns them, a struct keyed by
+    /// name.
+    fn read_output(value: Value) -> Result<Self::Output> {
+        match value {
+            Value::Struct(members) => members
+                .into_iter()
+                .map(|(name, category)| {
+                    let mut category: Category = crate::from_value(category)?;
+                    if category.category_name.is_empty() {
+                        category.category_name = name;
+                    }
+                    Ok(category)
+                })
+                .collect(),
+            value => crate::from_value(value),
+        }
+    }
+}
+
+/// Deletes a post, returning whether it succeeded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeletePost {
+    /// A key identifying the client to Blogger, which other servers ignore.
+    pub app_key: String,
+    pub post_id: String,
+    pub username: String,
+    pub password: String,
+    pub publish: bool,
+}
+
+impl BlogMethod for DeletePost {
+    const NAME: &'static str = "blogger.deletePost";
+    type Output = bool;
+
+    fn to_params(&self) -> Result<Values> {
+        Ok(Values::new()
+            .param(&self.app_key)
+            .param(&self.post_id)
+            .param(&self.username)
+            .param(&self.password)
+            .param(&self.publish))
+    }
+
+    fn read_output(value: Value) -> Result<Self::Output> {
+        bool_from_value(&value).ok_or_else(|| ValueError::mismatch(ValueKind::BOOL, &value).into())
+    }
+}
+
+/// Lists the blogs the user can post to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GetUsersBlogs {
+    /// A key identifying the client to Blogger, which other servers ignore.
+    pub app_key: String,
+    pub username: String,
+    pub password: String,
+}
+
+impl BlogMethod for GetUsersBlogs {
+    const NAME: &'static str = "blogger.getUsersBlogs";
+    type Output = Vec<Blog>;
+
+    fn to_params(&self) -> Result<Values> {
+        Ok(Values::new()
+            .param(&self.app_key)
+            .param(&self.username)
+            .param(&self.password))
+    }
+
+    fn read_output(value: Value) -> Result<Self::Output> {
+        crate::from_value(value)
+    }
+}
+
+/// Reads an ID sent as either a string or an integer.
+fn id_from_value(value: Value) -> Result<String> {
+    match value {
+        Value::String(id) => Ok(id),
+        Value::Int(id) => Ok(id.to_string()),
+        Value::Int64(id) => Ok(id.to_string()),
+        value => Err(ValueError::mismatch(ValueKind::STRING | ValueKind::INTEGER, &value).into()),
+    }
+}
+
+/// Reads a boolean sent as a `<boolean>`, an integer or a string.
+fn bool_from_value(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(b) => Some(*b),
+        Value::Int(i) => Some(*i != 0),
+        Value::String(s) => match s.trim() {
+            "1" | "true" => Some(true),
+            "0" | "false" | "" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn string_or_number<'de, D>(deserializer: D) -> std::result::Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    id_from_value(Value::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+fn lenient_bool<'de, D>(deserializer: D) -> std::result::Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+    bool_from_value(&value)
+        .ok_or_else(|| serde::de::Error::custom(ValueError::mismatch(ValueKind::BOOL, &value)))
+}
+
+/// A `<dateTime.iso8601>`, or a string of one, which is empty if the post has no date.
+mod optional_date {
+    use super::*;
+
+    pub fn serialize<S>(
+        date: &Option<XmlRpcDateTime>,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        date.map(Value::DateTime).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(
+        deserializer: D,
+    ) -> std::result::Result<Option<XmlRpcDateTime>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Value::deserialize(deserializer)? {
+            Value::DateTime(date) => Ok(Some(date)),
+            Value::Nil => Ok(None),
+            Value::String(text) if text.trim().is_empty() => Ok(None),
+            Value::String(text) => text
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(serde::de::Error::custom),
+            value => Err(serde::de::Error::custom(ValueError::mismatch(
+                ValueKind::DATETIME,
+                &value,
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn respond(body: &str) -> String {
+        format!(
+            "<methodResponse><params><param><value>{}</value></param></params></methodResponse>",
+            body
+        )
+    }
+
+    #[test]
+    fn new_post() {
+        let call = NewPost {
+            blog_id: "1".into(),
+            username: "admin".into(),
+            password: "secret".into(),
+            post: Post {
+                title: "Hello".into(),
+                date_created: Some("20240102T03:04:05".parse().unwrap()),
+                ..Post::default()
+            },
+            publish: false,
+        };
+        let (name, params) = crate::request_from_str(&request_to_string(&call).unwrap()).unwrap();
+        assert_eq!(name, "metaWeblog.newPost");
+        assert_eq!(params.len(), 5);
+        let post = params[3].as_struct().unwrap();
+        assert_eq!(post.keys().collect::<Vec<_>>().len(), 2);
+        assert!(matches!(post["dateCreated"], Value::DateTime(_)));
+
+        let id = response_from_str::<NewPost>(&respond("<string>abc</string>"));
+        assert_eq!(id.unwrap(), "abc");
+        assert!(response_from_str::<NewPost>(&respond("<boolean>1</boolean>")).is_err());
+    }
+
+    #[test]
+    fn sloppy_posts() {
+        let body = respond(
+            "<array><data><value><struct>\
+               <member><name>postid</name><value><int>7</int></value></member>\
+               <member><name>title</name><value>Untyped</value></member>\
+               <member><name>dateCreated</name><value><string>2024-01-02T03:04:05Z</string></value></member>\
+               <member><name>categories</name><value><array><data/></array></value></member>\
+               <member><name>unknown</name><value><int>1</int></value></member>\
+             </struct></value><value><struct>\
+               <member><name>postid</name><value><string>8</string></value></member>\
+               <member><name>dateCreated</name><value><string></string></value></member>\
+             </struct></value></data></array>",
+        );
+        let posts = response_from_str::<GetRecentPosts>(&body).unwrap();
+        assert_eq!(posts[0].postid, "7");
+        assert_eq!(posts[0].title, "Untyped");
+        assert_eq!(
+            posts[0].date_created.unwrap().to_string(),
+            "2024-01-02T03:04:05.000Z"
+        );
+        assert_eq!(posts[1].postid, "8");
+        assert_eq!(posts[1].date_created, None);
+
+        let ok = |body: &str| response_from_str::<EditPost>(&respond(body));
+        assert!(ok("<boolean>1</boolean>").unwrap());
+        assert!(ok("<int>1</int>").unwrap());
+        assert!(!ok("<string>0</string>").unwrap());
+        assert!(ok("<double>1</double>").is_err());
+    }
+
+    #[test]
+    fn blogs_and_categories() {
+        let body = respond(
+            "<array><data><value><struct>\
+               <member><name>blogid</name><value><int>1</int></value></member>\
+               <member><name>blogName</name><value><string>Notes</string></value></member>\
+               <member><name>url</name><value><string>http://example.com/</string></value></member>\
+               <member><name>isAdmin</name><value><string>1</string></value></member>\
+             </struct></value></data></array>",
+        );
+        let blogs = response_from_str::<GetUsersBlogs>(&body).unwrap();
+        assert_eq!(blogs[0].blog_id, "1");
+        assert!(blogs[0].is_admin);
+
+        let body = respond(
+            "<struct><member><name>News</name><value><struct>\
+               <member><name>description</name><value><string>News</string></value></member>\
+               <member><name>htmlUrl</name><value><string>http://example.com/news</string></value></member>\
+             </struct></value></member></struct>",
+        );
+        let categories = response_from_str::<GetCategories>(&body).unwrap();
+        assert_eq!(categories[0].category_name, "News");
+        assert_eq!(categories[0].html_url, "http://example.com/news");
+    }
+}
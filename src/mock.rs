@@ -0,0 +1,601 @@
+//! An in-process XML-RPC server for testing clients against.
+//!
+//! A [`MockServer`] listens on a local port and answers each call with the [`MockResponse`]
+//! programmed for its method, which can be params, a fault, a malformed body or an HTTP error. It
+//! records every request it receives, so tests can check what was sent. It speaks plain HTTP/1.1
+//! with keep-alive on its own threads, so it works with any client, blocking or async.
+//!
+//! ```
+//! use serde_xmlrpc::mock::{MockResponse, MockServer};
+//! use serde_xmlrpc::{Fault, Value};
+//!
+//! let server = MockServer::start();
+//! server
+//!     .on("add", MockResponse::value(3))
+//!     .on("divide", MockResponse::fault(Fault::new(4, "division by zero")));
+//!
+//! // Point the client under test at `server.url()`, then:
+//! # let params = vec![Value::Int(1), Value::Int(2)];
+//! # let body = serde_xmlrpc::request_to_string("add", params).unwrap();
+//! # let _ = reqwest::blocking::Client::new().post(server.url()).body(body).send().unwrap();
+//! assert_eq!(server.calls("add"), vec![vec![Value::Int(1), Value::Int(2)]]);
+//! ```
+//!
+//! Methods without a response are answered with a `-32601` fault, and requests which can't be
+//! parsed with the fault [`Fault::from_request_error`] gives.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use crate::{Document, Fault, Value, Values, WriteOptions};
+
+/// The most bytes read for the head of a request.
+const MAX_HEAD_LEN: usize = 64 * 1024;
+
+/// What a [`MockServer`] answers a call with.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use serde_xmlrpc::mock::MockResponse;
+///
+/// let slow = MockResponse::value("done").delay(Duration::from_millis(50));
+/// let unavailable = MockResponse::status(503).header("Retry-After", "1");
+/// let truncated = MockResponse::body("<methodResponse><params>");
+/// ```
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    body: Body,
+    status: u16,
+    headers: Vec<(String, String)>,
+    delay: Option<Duration>,
+}
+
+#[derive(Clone, Debug)]
+enum Body {
+    Document(Document),
+    Raw(Vec<u8>),
+}
+
+impl MockResponse {
+    /// A response with the single param `value`.
+    pub fn value(value: impl Into<Value>) -> Self {
+        MockResponse::params(vec![value.into()])
+    }
+
+    /// A response with `params`.
+    pub fn params(params: Vec<Value>) -> Self {
+        MockResponse::document(Document::Response(Values::from(params)))
+    }
+
+    /// A `<fault>` response.
+    pub fn fault(fault: Fault) -> Self {
+        MockResponse::document(Document::Fault(fault))
+    }
+
+    /// A response with `body` as it is, such as malformed XML.
+    pub fn body(body: impl Into<Vec<u8>>) -> Self {
+        MockResponse::from_body(Body::Raw(body.into()))
+    }
+
+    /// An empty response with HTTP status `status`, such as `503`.
+    pub fn status(status: u16) -> Self {
+        MockResponse {
+            status,
+            ..MockResponse::body(Vec::new())
+        }
+    }
+
+    /// Adds the header `name` to the response.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Waits for `delay` before answering, to test timeouts.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    fn document(document: Document) -> Self {
+        MockResponse::from_body(Body::Document(document))
+    }
+
+    fn from_body(body: Body) -> Self {
+        MockResponse {
+            body,
+            status: 200,
+            headers: Vec::new(),
+            delay: None,
+        }
+    }
+
+    /// The whole HTTP response, which asks to keep the connection open if `keep_alive` is set.
+    fn to_http(&self, keep_alive: bool) -> Vec<u8> {
+        let (status, body) = match &self.body {
+            Body::Document(document) => {
+                match WriteOptions::default().document_to_string(document) {
+                    Ok(body) => (self.status, body.into_bytes()),
+                    Err(err) => (500, err.to_string().into_bytes()),
+                }
+            }
+            Body::Raw(body) => (self.status, body.clone()),
+        };
+
+        let mut response = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: text/xml\r\nContent-Length: {}\r\nConnection: {}\r\n",
+            status,
+            reason(status),
+            body.len(),
+            if keep_alive { "keep-alive" } else { "close" }
+        );
+        for (name, value) in &self.headers {
+            response.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        response.push_str("\r\n");
+
+        let mut response = response.into_bytes();
+        response.extend_from_slice(&body);
+        response
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        411 => "Length Required",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "Unknown",
+    }
+}
+
+/// A request received by a [`MockServer`].
+#[derive(Clone, Debug)]
+pub struct MockRequest {
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    call: Option<(String, Vec<Value>)>,
+}
+
+impl MockRequest {
+    /// The path the request was posted to, such as `/RPC2`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The value of the header `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The body of the request.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// The method called, or `None` if the body isn't a valid call.
+    pub fn method(&self) -> Option<&str> {
+        self.call.as_ref().map(|(method, _)| method.as_str())
+    }
+
+    /// The params of the call, which are empty if the body isn't a valid call.
+    pub fn params(&self) -> &[Value] {
+        self.call.as_ref().map_or(&[], |(_, params)| params)
+    }
+}
+
+/// The responses programmed for a method.
+#[derive(Debug, Default)]
+struct Route {
+    once: VecDeque<MockResponse>,
+    always: Option<MockResponse>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    routes: BTreeMap<String, Route>,
+    fallback: Option<MockResponse>,
+    requests: Vec<MockRequest>,
+    connections: usize,
+}
+
+impl State {
+    fn respond(&mut self, method: Option<&str>) -> Option<MockResponse> {
+        let route = self.routes.get_mut(method?)?;
+        route.once.pop_front().or_else(|| route.always.clone())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<State>,
+    streams: Mutex<Vec<TcpStream>>,
+    shutdown: AtomicBool,
+}
+
+impl Shared {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// An XML-RPC server on a local port which answers calls as programmed and records the
+/// requests. See the [module docs](self).
+///
+/// The server stops when it's dropped, closing any open connections.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+    accept: Option<thread::JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Starts a server on a free port of `127.0.0.1`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no port can be bound.
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind a mock server");
+        let addr = listener.local_addr().expect("failed to bind a mock server");
+        let shared = Arc::new(Shared::default());
+
+        let accept = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || accept(listener, shared))
+        };
+        MockServer {
+            addr,
+            shared,
+            accept: Some(accept),
+        }
+    }
+
+    /// The address the server listens on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The URL to point clients at. The server answers calls posted to any path.
+    pub fn url(&self) -> String {
+        format!("http://{}/RPC2", self.addr)
+    }
+
+    /// Answers every call of `method` with `response`, after the responses queued with
+    /// [`once`](MockServer::once).
+    pub fn on(&self, method: impl Into<String>, response: MockResponse) -> &Self {
+        let mut state = self.shared.state();
+        state.routes.entry(method.into()).or_default().always = Some(response);
+        self
+    }
+
+    /// Answers the next call of `method` with `response`. Responses queued for the same method
+    /// are used in order, for testing retries.
+    pub fn once(&self, method: impl Into<String>, response: MockResponse) -> &Self {
+        let mut state = self.shared.state();
+        let route = state.routes.entry(method.into()).or_default();
+        route.once.push_back(response);
+        self
+    }
+
+    /// Answers calls of methods without a response with `response`, instead of a `-32601`
+    /// fault.
+    pub fn fallback(&self, response: MockResponse) -> &Self {
+        self.shared.state().fallback = Some(response);
+        self
+    }
+
+    /// The requests received so far, in order.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.shared.state().requests.clone()
+    }
+
+    /// The params of each call of `method` received so far, in order.
+    pub fn calls(&self, method: &str) -> Vec<Vec<Value>> {
+        self.shared
+            .state()
+            .requests
+            .iter()
+            .filter(|request| request.method() == Some(method))
+            .map(|request| request.params().to_vec())
+            .collect()
+    }
+
+    /// The number of connections accepted so far, for testing connection reuse.
+    pub fn connections(&self) -> usize {
+        self.shared.state().connections
+    }
+
+    /// Forgets the requests received so far, keeping the programmed responses.
+    pub fn clear_requests(&self) {
+        self.shared.state().requests.clear();
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::SeqCst);
+        // Wakes the accept loop so it sees the flag.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(accept) = self.accept.take() {
+            let _ = accept.join();
+        }
+
+        let streams = self
+            .shared
+            .streams
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        for stream in streams.iter() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+fn accept(listener: TcpListener, shared: Arc<Shared>) {
+    for stream in listener.incoming() {
+        if shared.shutdown.load(Ordering::SeqCst) {
+            break;
+        }
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+
+        shared.state().connections += 1;
+        if let Ok(clone) = stream.try_clone() {
+            let mut streams = shared.streams.lock().unwrap_or_else(|err| err.into_inner());
+            streams.push(clone);
+        }
+        let shared = Arc::clone(&shared);
+        thread::spawn(move || {
+            let mut stream = stream;
+            let _ = serve(&mut stream, &shared);
+            // The clone kept for dropping the server holds the socket open otherwise.
+            let _ = stream.shutdown(Shutdown::Both);
+        });
+    }
+}
+
+/// Answers the requests sent over `stream` until the client closes it or asks to.
+fn serve(stream: &mut TcpStream, shared: &Shared) -> io::Result<()> {
+    let mut buf = Vec::new();
+    while let Some(request) = read_request(stream, &mut buf)? {
+        let (request, keep_alive) = match request {
+            Ok(request) => request,
+            Err(response) => {
+                stream.write_all(&response.to_http(false))?;
+                return Ok(());
+            }
+        };
+
+        let call = std::str::from_utf8(&request.body)
+            .map_err(|err| crate::Error::from(crate::DecodingError::from(err)))
+            .and_then(crate::request_from_str);
+        let (call, parse_error) = match call {
+            Ok(call) => (Some(call), None),
+            Err(err) => (None, Some(Fault::from_request_error(&err))),
+        };
+        let request = MockRequest { call, ..request };
+
+        let response = {
+            let mut state = shared.state();
+            let method = request.method().map(str::to_string);
+            let response = match (&parse_error, state.respond(method.as_deref())) {
+                (Some(fault), _) => MockResponse::fault(fault.clone()),
+                (None, Some(response)) => response,
+                (None, None) => state.fallback.clone().unwrap_or_else(|| {
+                    MockResponse::fault(Fault::method_not_found(method.as_deref().unwrap_or("")))
+                }),
+            };
+            state.requests.push(request);
+            response
+        };
+
+        if let Some(delay) = response.delay {
+            thread::sleep(delay);
+        }
+        stream.write_all(&response.to_http(keep_alive))?;
+        if !keep_alive {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// A request read by [`read_request`], and whether the connection is kept open after it, or the
+/// response to a request which can't be read.
+type ReadRequest = std::result::Result<(MockRequest, bool), MockResponse>;
+
+/// Reads the next request from `stream`, keeping bytes read past its end in `buf`. Returns
+/// `None` once the client closes the connection.
+fn read_request(stream: &mut TcpStream, buf: &mut Vec<u8>) -> io::Result<Option<ReadRequest>> {
+    let head_len = loop {
+        if let Some(end) = buf.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+        if buf.len() > MAX_HEAD_LEN {
+            return Ok(Some(Err(MockResponse::status(400))));
+        }
+        if !fill(stream, buf)? {
+            return Ok(None);
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..head_len]).into_owned();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or("").split(' ');
+    let path = request_line.nth(1).unwrap_or("/").to_string();
+    let version = request_line.next().unwrap_or("");
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    };
+    let len = match header("Content-Length").map(str::parse::<usize>) {
+        Some(Ok(len)) => len,
+        Some(Err(_)) => return Ok(Some(Err(MockResponse::status(400)))),
+        None => return Ok(Some(Err(MockResponse::status(411)))),
+    };
+    let keep_alive = match header("Connection") {
+        Some(connection) if connection.eq_ignore_ascii_case("close") => false,
+        Some(connection) if connection.eq_ignore_ascii_case("keep-alive") => true,
+        _ => version == "HTTP/1.1",
+    };
+
+    while buf.len() < head_len + len {
+        if !fill(stream, buf)? {
+            return Ok(None);
+        }
+    }
+    let body = buf[head_len..head_len + len].to_vec();
+    buf.drain(..head_len + len);
+
+    let request = MockRequest {
+        path,
+        headers,
+        body,
+        call: None,
+    };
+    Ok(Some(Ok((request, keep_alive))))
+}
+
+/// Reads more of `stream` into `buf`, returning `false` at the end of the stream.
+fn fill(stream: &mut TcpStream, buf: &mut Vec<u8>) -> io::Result<bool> {
+    let mut chunk = [0; 4096];
+    let len = stream.read(&mut chunk)?;
+    buf.extend_from_slice(&chunk[..len]);
+    Ok(len > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Posts `body` in its own connection and returns the status line and body of the response.
+    fn post(server: &MockServer, body: &str) -> (String, String) {
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        write!(
+            stream,
+            "POST /RPC2 HTTP/1.1\r\nHost: localhost\r\nUser-Agent: test\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        (head.lines().next().unwrap().to_string(), body.to_string())
+    }
+
+    fn call(server: &MockServer, method: &str, params: Vec<Value>) -> (String, String) {
+        post(server, &crate::request_to_string(method, params).unwrap())
+    }
+
+    #[test]
+    fn responses() {
+        let server = MockServer::start();
+        server
+            .on("add", MockResponse::value(3))
+            .once("add", MockResponse::status(503))
+            .on("fail", MockResponse::fault(Fault::new(4, "no")))
+            .on("bad", MockResponse::body("<methodResponse>"));
+
+        let (status, _) = call(&server, "add", vec![Value::Int(1)]);
+        assert_eq!(status, "HTTP/1.1 503 Service Unavailable");
+        let (status, body) = call(&server, "add", vec![Value::Int(2)]);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(crate::response_from_str::<i32>(&body).unwrap(), 3);
+
+        let (_, body) = call(&server, "fail", vec![]);
+        match crate::response_from_str::<i32>(&body) {
+            Err(crate::Error::Fault(fault)) => assert_eq!(fault, Fault::new(4, "no")),
+            result => panic!("unexpected result {:?}", result),
+        }
+        let (_, body) = call(&server, "bad", vec![]);
+        assert_eq!(body, "<methodResponse>");
+
+        let (_, body) = call(&server, "missing", vec![]);
+        match crate::response_from_str::<i32>(&body) {
+            Err(crate::Error::Fault(fault)) => assert_eq!(fault.fault_code, -32601),
+            result => panic!("unexpected result {:?}", result),
+        }
+        server.fallback(MockResponse::value("fallback"));
+        let (_, body) = call(&server, "missing", vec![]);
+        assert_eq!(
+            crate::response_from_str::<String>(&body).unwrap(),
+            "fallback"
+        );
+        let (_, body) = post(&server, "<garbage");
+        assert!(crate::response_from_str::<i32>(&body).is_err());
+    }
+
+    #[test]
+    fn records_requests() {
+        let server = MockServer::start();
+        server.on("add", MockResponse::value(3));
+        call(&server, "add", vec![Value::Int(1), Value::Int(2)]);
+        call(&server, "sub", vec![]);
+        post(&server, "<garbage");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].path(), "/RPC2");
+        assert_eq!(requests[0].header("user-agent"), Some("test"));
+        assert_eq!(requests[1].method(), Some("sub"));
+        assert_eq!(requests[2].method(), None);
+        assert_eq!(requests[2].body(), b"<garbage");
+        assert_eq!(
+            server.calls("add"),
+            vec![vec![Value::Int(1), Value::Int(2)]]
+        );
+        assert_eq!(server.connections(), 3);
+
+        server.clear_requests();
+        assert!(server.requests().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn blocking_client() {
+        use crate::client::{BlockingClient, RetryPolicy};
+
+        let server = MockServer::start();
+        server
+            .on("add", MockResponse::value(3))
+            .once("add", MockResponse::status(503));
+        let client = BlockingClient::new(server.url()).retry(
+            RetryPolicy::new(1)
+                .backoff(Duration::ZERO, Duration::ZERO)
+                .idempotent("add"),
+        );
+
+        assert_eq!(client.call::<_, i32>("add", (1, 2)).unwrap(), 3);
+        assert_eq!(client.call::<_, i32>("add", (1, 2)).unwrap(), 3);
+        assert_eq!(server.calls("add").len(), 3);
+        assert_eq!(server.connections(), 1);
+    }
+}
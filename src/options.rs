@@ -0,0 +1,923 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use crate::{Document, ResponseStream, Violation, XmlRpcResult};
+use crate::{Result, Value, XmlRpcDateTime};
+
+/// Options controlling how XML-RPC documents are parsed.
+///
+/// The default options are lenient: they accept the common deviations from the spec that real
+/// servers emit. Use [`ParseOptions::strict`] to only accept documents which follow the XML-RPC
+/// specification to the letter.
+///
+/// ```
+/// use serde_xmlrpc::ParseOptions;
+///
+/// let val = ParseOptions::lenient().value_from_str("<value><double>1e3</double></value>");
+/// assert_eq!(val.unwrap().as_f64(), Some(1000.0));
+///
+/// let val = ParseOptions::strict().value_from_str("<value><double>1e3</double></value>");
+/// assert!(val.is_err());
+/// ```
+///
+/// Documents are never allowed to define or expand their own entities: only the five predefined
+/// XML entities and character references are understood, and a `<!DOCTYPE>` is rejected unless
+/// [`ParseOptions::allow_doctype`] is set. This makes entity expansion attacks such as "billion
+/// laughs" and external entity references harmless.
+///
+/// Documents from untrusted sources should also be parsed with limits on their size, see
+/// [`ParseOptions::max_depth`], [`ParseOptions::max_text_len`] and [`ParseOptions::max_values`].
+///
+/// A `<dateTime.iso8601>` without a timezone, which is the form the spec shows and the only one
+/// Python's `xmlrpc.client` writes, is a naive time and is read with an offset of zero. This
+/// doesn't mean it is UTC: its zone is whatever the peers agreed on, and a naive time which
+/// is known to be local can be given its offset with [`XmlRpcDateTime::with_offset_minutes`].
+///
+/// ```
+/// use serde_xmlrpc::ParseOptions;
+///
+/// let input = "<value><dateTime.iso8601>19980717T14:08:55</dateTime.iso8601></value>";
+/// let val = ParseOptions::default().value_from_str(input).unwrap();
+/// let dt = val.as_datetime().unwrap();
+/// assert_eq!(dt.offset_minutes(), 0);
+/// let local = dt.with_offset_minutes(120).unwrap();
+/// assert_eq!(local.to_utc().to_string(), "1998-07-17T12:08:55.000Z");
+/// ```
+#[derive(Clone, Debug)]
+pub struct ParseOptions {
+    pub(crate) strict: bool,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) max_text_len: Option<usize>,
+    pub(crate) max_values: Option<usize>,
+    pub(crate) allow_doctype: bool,
+    pub(crate) trim_scalars: bool,
+    pub(crate) trim_strings: bool,
+    pub(crate) allow_non_finite: bool,
+    pub(crate) renames: Vec<(String, String)>,
+    pub(crate) duplicate_members: DuplicateMembers,
+    pub(crate) keep_unknown: bool,
+}
+
+/// How deeply arrays and structs may be nested by default, see [`ParseOptions::max_depth`].
+///
+/// This is 64, far deeper than any real document needs while keeping recursion well clear of
+/// the stack limit even in debug builds. The `small-limits` feature lowers it to 16 for targets
+/// with tiny stacks.
+#[cfg(not(feature = "small-limits"))]
+pub const MAX_SAFE_DEPTH: usize = 64;
+#[cfg(feature = "small-limits")]
+pub const MAX_SAFE_DEPTH: usize = 16;
+
+/// The default limit on the length of a single text value, see [`ParseOptions::max_text_len`].
+///
+/// There is no limit unless the `small-limits` feature is enabled, which sets it to 64 KiB.
+#[cfg(not(feature = "small-limits"))]
+pub const DEFAULT_MAX_TEXT_LEN: Option<usize> = None;
+#[cfg(feature = "small-limits")]
+pub const DEFAULT_MAX_TEXT_LEN: Option<usize> = Some(64 * 1024);
+
+/// The default limit on the number of values in a document, see [`ParseOptions::max_values`].
+///
+/// There is no limit unless the `small-limits` feature is enabled, which sets it to 4096.
+#[cfg(not(feature = "small-limits"))]
+pub const DEFAULT_MAX_VALUES: Option<usize> = None;
+#[cfg(feature = "small-limits")]
+pub const DEFAULT_MAX_VALUES: Option<usize> = Some(4096);
+
+/// What to do when a `<struct>` contains the same member name more than once, see
+/// [`ParseOptions::duplicate_members`].
+///
+/// The spec doesn't say, and servers disagree on which of the members counts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateMembers {
+    /// Hand every member to the type being read, so maps and [`Value`]s keep the last one and
+    /// structs deriving `Deserialize` reject the duplicate. This is the default, and the cheapest
+    /// since nothing is tracked.
+    #[default]
+    PassThrough,
+    /// Fail with [`DecodingError::DuplicateMember`](crate::DecodingError::DuplicateMember).
+    Error,
+    /// Use the first member and ignore the later ones.
+    KeepFirst,
+    /// Use the last member and ignore the earlier ones. Structs are read twice to find out which
+    /// members come again later, which makes reading them slower.
+    KeepLast,
+    /// Read all the members of that name as an `<array>` of their values, in document order.
+    /// Members which only appear once are read as they are, so a field which may repeat should
+    /// be a `Vec` or a [`Value`].
+    Collect,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions::lenient()
+    }
+}
+
+impl ParseOptions {
+    /// Options which only accept documents following the XML-RPC specification.
+    ///
+    /// In particular, `<double>` values must be written in plain decimal notation (an optional
+    /// sign, digits and an optional fractional part) so exponents, `inf` and `nan` are rejected.
+    /// Numbers may not be padded with whitespace and `<int>`/`<i4>` values must fit in 32 bits.
+    /// `<dateTime.iso8601>` values must have the spec's exact `19980717T14:08:55` form.
+    /// Struct member names are taken exactly as written, and a `<name>` containing elements
+    /// rather than just text is an error.
+    pub fn strict() -> Self {
+        ParseOptions {
+            strict: true,
+            trim_scalars: false,
+            allow_non_finite: false,
+            ..ParseOptions::lenient()
+        }
+    }
+
+    /// Options which accept common deviations from the XML-RPC specification.
+    ///
+    /// `<double>` values are parsed with the same rules as Rust's [`f64::from_str`], which means
+    /// exponent notation such as `1e10` is accepted. Whitespace around numbers, booleans, dateTimes
+    /// and struct member names is ignored, and elements inside a `<name>` are ignored in favor of
+    /// their text. `<boolean>` values may also be `true` or `false` in any case.
+    /// `<dateTime.iso8601>` values may also use dashed dates, leave out the colons or seconds,
+    /// and carry fractional seconds and a timezone such as `Z`, `+01:00` or `+0100`.
+    ///
+    /// [`f64::from_str`]: https://doc.rust-lang.org/std/primitive.f64.html#method.from_str
+    pub const fn lenient() -> Self {
+        ParseOptions {
+            strict: false,
+            max_depth: Some(MAX_SAFE_DEPTH),
+            max_text_len: DEFAULT_MAX_TEXT_LEN,
+            max_values: DEFAULT_MAX_VALUES,
+            allow_doctype: false,
+            trim_scalars: true,
+            trim_strings: false,
+            allow_non_finite: true,
+            renames: Vec::new(),
+            duplicate_members: DuplicateMembers::PassThrough,
+            keep_unknown: false,
+        }
+    }
+
+    /// Limits how deeply arrays and structs may be nested, or removes the limit with `None`.
+    /// Deeper documents fail with [`DecodingError::DepthLimitExceeded`]. The default is
+    /// [`MAX_SAFE_DEPTH`].
+    ///
+    /// ```
+    /// use serde_xmlrpc::ParseOptions;
+    ///
+    /// let input = "<value><array><data>\
+    ///     <value><array><data></data></array></value>\
+    ///     </data></array></value>";
+    /// assert!(ParseOptions::default().max_depth(Some(2)).value_from_str(input).is_ok());
+    /// assert!(ParseOptions::default().max_depth(Some(1)).value_from_str(input).is_err());
+    /// ```
+    ///
+    /// [`DecodingError::DepthLimitExceeded`]: crate::DecodingError::DepthLimitExceeded
+    pub fn max_depth(mut self, limit: Option<usize>) -> Self {
+        self.max_depth = limit;
+        self
+    }
+
+    /// Limits the length in bytes of the text of a single string, base64 value or struct member
+    /// name, or removes the limit with `None`. Base64 values are measured before decoding. Longer
+    /// text fails with [`DecodingError::TextLimitExceeded`]. The default is
+    /// [`DEFAULT_MAX_TEXT_LEN`].
+    ///
+    /// [`DecodingError::TextLimitExceeded`]: crate::DecodingError::TextLimitExceeded
+    pub fn max_text_len(mut self, limit: Option<usize>) -> Self {
+        self.max_text_len = limit;
+        self
+    }
+
+    /// Limits the total number of `<value>` elements in a document, or removes the limit with
+    /// `None`. Larger documents fail with [`DecodingError::ValueLimitExceeded`]. The default is
+    /// [`DEFAULT_MAX_VALUES`].
+    ///
+    /// [`DecodingError::ValueLimitExceeded`]: crate::DecodingError::ValueLimitExceeded
+    pub fn max_values(mut self, limit: Option<usize>) -> Self {
+        self.max_values = limit;
+        self
+    }
+
+    /// Skips a `<!DOCTYPE>` declaration instead of failing with
+    /// [`DecodingError::DoctypeNotAllowed`]. The declaration is ignored entirely: entities it
+    /// defines are still never expanded, so this only helps with peers which send a harmless
+    /// declaration. It is not allowed by default.
+    ///
+    /// ```
+    /// use serde_xmlrpc::ParseOptions;
+    ///
+    /// let input = r#"<!DOCTYPE value SYSTEM "xmlrpc.dtd"><value><int>1</int></value>"#;
+    /// assert!(ParseOptions::default().value_from_str(input).is_err());
+    /// assert!(ParseOptions::default().allow_doctype(true).value_from_str(input).is_ok());
+    /// ```
+    ///
+    /// [`DecodingError::DoctypeNotAllowed`]: crate::DecodingError::DoctypeNotAllowed
+    pub fn allow_doctype(mut self, allow: bool) -> Self {
+        self.allow_doctype = allow;
+        self
+    }
+
+    /// Trims whitespace around the text of `<int>`, `<i4>`, `<i8>`, `<double>`, `<ex:float>`,
+    /// `<boolean>` and `<dateTime.iso8601>` values before parsing it, as found in pretty-printed
+    /// documents. Without it such values are an error. This is on for [`ParseOptions::lenient`]
+    /// and off for [`ParseOptions::strict`].
+    ///
+    /// ```
+    /// use serde_xmlrpc::ParseOptions;
+    ///
+    /// let input = "<value><int>\n  5\n</int></value>";
+    /// assert_eq!(ParseOptions::lenient().value_from_str(input).unwrap().as_i32(), Some(5));
+    /// assert!(ParseOptions::lenient().trim_scalars(false).value_from_str(input).is_err());
+    /// assert!(ParseOptions::strict().trim_scalars(true).value_from_str(input).is_ok());
+    /// ```
+    pub fn trim_scalars(mut self, trim: bool) -> Self {
+        self.trim_scalars = trim;
+        self
+    }
+
+    /// Trims whitespace around the text of `<string>` values and values without a type. Strings
+    /// are taken exactly as written by default, since their whitespace may be significant.
+    /// Whitespace in CDATA sections is trimmed the same way, so a string reads the same whether
+    /// or not it was sent as CDATA.
+    ///
+    /// ```
+    /// use serde_xmlrpc::ParseOptions;
+    ///
+    /// let input = "<value><string>\n  hello\n</string></value>";
+    /// let val = ParseOptions::default().value_from_str(input).unwrap();
+    /// assert_eq!(val.as_str(), Some("\n  hello\n"));
+    /// let val = ParseOptions::default().trim_strings(true).value_from_str(input).unwrap();
+    /// assert_eq!(val.as_str(), Some("hello"));
+    ///
+    /// let input = "<value><![CDATA[ <b>hello</b> ]]></value>";
+    /// let val = ParseOptions::default().trim_strings(true).value_from_str(input).unwrap();
+    /// assert_eq!(val.as_str(), Some("<b>hello</b>"));
+    /// ```
+    pub fn trim_strings(mut self, trim: bool) -> Self {
+        self.trim_strings = trim;
+        self
+    }
+
+    /// Accepts doubles which aren't finite: `nan`, `inf` and `infinity` in any case and with an
+    /// optional sign, as Python writes them, and numbers too large for an `f64`. The spec has no
+    /// way to express these, so this is on for [`ParseOptions::lenient`] and off for
+    /// [`ParseOptions::strict`].
+    ///
+    /// ```
+    /// use serde_xmlrpc::ParseOptions;
+    ///
+    /// let input = "<value><double>-inf</double></value>";
+    /// let val = ParseOptions::lenient().value_from_str(input).unwrap();
+    /// assert_eq!(val.as_f64(), Some(f64::NEG_INFINITY));
+    /// assert!(ParseOptions::lenient().allow_non_finite(false).value_from_str(input).is_err());
+    /// assert!(ParseOptions::strict().allow_non_finite(true).value_from_str(input).is_ok());
+    /// ```
+    pub fn allow_non_finite(mut self, allow: bool) -> Self {
+        self.allow_non_finite = allow;
+        self
+    }
+
+    /// Sets what happens when a struct contains the same member name more than once. The
+    /// default is [`DuplicateMembers::PassThrough`].
+    ///
+    /// ```
+    /// use serde_xmlrpc::{DuplicateMembers, ParseOptions, Value};
+    ///
+    /// let input = "<value><struct>\
+    ///     <member><name>tag</name><value>a</value></member>\
+    ///     <member><name>tag</name><value>b</value></member>\
+    ///     </struct></value>";
+    /// let read = |policy| ParseOptions::default().duplicate_members(policy).value_from_str(input);
+    /// let tag = |policy| read(policy).unwrap().pointer("/tag").cloned();
+    ///
+    /// assert_eq!(tag(DuplicateMembers::KeepFirst), Some(Value::from("a")));
+    /// assert_eq!(tag(DuplicateMembers::KeepLast), Some(Value::from("b")));
+    /// assert_eq!(
+    ///     tag(DuplicateMembers::Collect),
+    ///     Some(Value::Array(vec!["a".into(), "b".into()]))
+    /// );
+    /// assert!(read(DuplicateMembers::Error).is_err());
+    /// ```
+    pub fn duplicate_members(mut self, policy: DuplicateMembers) -> Self {
+        self.duplicate_members = policy;
+        self
+    }
+
+    /// Keeps `<value>`s of a type this crate doesn't know as [`Value::Raw`] when reading a
+    /// [`Value`], instead of failing with [`DecodingError::UnexpectedTag`]. The raw value holds
+    /// the element exactly as it was sent and is written back the same way, so a proxy can pass
+    /// on extensions it doesn't understand unchanged. This is off by default, and values read
+    /// into other types still reject unknown types.
+    ///
+    /// ```
+    /// use serde_xmlrpc::{ParseOptions, Value};
+    ///
+    /// let input = "<value><ex:point x=\"1\"> <y>2</y> </ex:point></value>";
+    /// assert!(ParseOptions::default().value_from_str(input).is_err());
+    ///
+    /// let val = ParseOptions::default().keep_unknown(true).value_from_str(input).unwrap();
+    /// assert!(matches!(&val, Value::Raw(raw) if raw.as_str() == input));
+    /// assert_eq!(serde_xmlrpc::value_to_string(val).unwrap(), input);
+    /// ```
+    ///
+    /// [`DecodingError::UnexpectedTag`]: crate::DecodingError::UnexpectedTag
+    pub fn keep_unknown(mut self, keep: bool) -> Self {
+        self.keep_unknown = keep;
+        self
+    }
+
+    /// Reads struct members called `name` into the field or map key `field`, the reverse of
+    /// [`WriteOptions::rename_member`]. Renaming a field more than once keeps the last name.
+    ///
+    /// This only applies when deserializing a document straight into a `T`, not to a [`Value`]
+    /// or [`from_value`](crate::from_value), which keep member names as written.
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_xmlrpc::ParseOptions;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Bug {
+    ///     bug_id: i32,
+    /// }
+    ///
+    /// let input = "<?xml version=\"1.0\"?><methodResponse><params><param><value><struct>\
+    ///     <member><name>bug.id</name><value><int>42</int></value></member>\
+    ///     </struct></value></param></params></methodResponse>";
+    /// let options = ParseOptions::default().rename_member("bug_id", "bug.id");
+    /// let bug: Bug = options.response_from_str(input).unwrap();
+    /// assert_eq!(bug.bug_id, 42);
+    /// ```
+    pub fn rename_member(mut self, field: impl Into<String>, name: impl Into<String>) -> Self {
+        let field = field.into();
+        self.renames.retain(|(f, _)| *f != field);
+        self.renames.push((field, name.into()));
+        self
+    }
+
+    /// The field or map key a member called `name` is read into, if it is renamed.
+    #[cfg(feature = "std")]
+    pub(crate) fn renamed_field(&self, name: &str) -> Option<&str> {
+        let (field, _) = self.renames.iter().find(|(_, n)| n == name)?;
+        Some(field)
+    }
+}
+
+/// Trims the text of a scalar value if the options call for it, see
+/// [`ParseOptions::trim_scalars`].
+pub(crate) fn scalar_text<'t>(text: &'t str, options: &ParseOptions) -> &'t str {
+    if options.trim_scalars {
+        text.trim()
+    } else {
+        text
+    }
+}
+
+#[cfg(feature = "std")]
+impl ParseOptions {
+    /// Parses the body of an xmlrpc http response using these options. See
+    /// [`response_from_str`](crate::response_from_str).
+    pub fn response_from_str<'a, T>(&self, input: &str) -> Result<T>
+    where
+        T: serde::de::Deserialize<'a>,
+    {
+        crate::parse_response(input, self)
+    }
+
+    /// Parses a response body into its outcome using these options. See
+    /// [`result_from_str`](crate::result_from_str).
+    pub fn result_from_str<T>(&self, input: &str) -> Result<XmlRpcResult<T>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        crate::parse_result(input, self)
+    }
+
+    /// Iterates over the elements of a response whose param is an array using these options. See
+    /// [`response_stream`](crate::response_stream).
+    pub fn response_stream<'a, T>(&'a self, input: &'a str) -> Result<ResponseStream<'a, T>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        ResponseStream::new(input, self)
+    }
+
+    /// Reads only the value at `path` of a call or response using these options. See
+    /// [`from_str_at_path`](crate::from_str_at_path).
+    ///
+    /// The limits of the options apply to the selected value and to the arrays and structs on
+    /// the way to it, not to what is skipped.
+    pub fn from_str_at_path<T>(&self, input: &str, path: &str) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        crate::path::from_str_at_path(input, path, self)
+    }
+
+    /// Parses an xmlrpc request body using these options. See
+    /// [`request_from_str`](crate::request_from_str).
+    pub fn request_from_str(&self, request: &str) -> Result<(String, Vec<Value>)> {
+        crate::parse_request(request, self)
+    }
+
+    /// Parses an individual value using these options. See
+    /// [`value_from_str`](crate::value_from_str).
+    pub fn value_from_str(&self, input: &str) -> Result<Value> {
+        crate::parse_value(input, self)
+    }
+
+    /// Parses a whole call or response using these options. See [`Document`].
+    pub fn document_from_str(&self, input: &str) -> Result<Document> {
+        crate::document::parse_document(input, self)
+    }
+
+    /// Reads an individual value without stopping at the first error, for tools which show
+    /// everything wrong with a document at once. Each value which can't be read is replaced by
+    /// [`Value::Nil`], and the errors are returned with their positions. A value can only be
+    /// recovered from well-formed XML, and is [`Value::Nil`] otherwise.
+    ///
+    /// ```
+    /// use serde_xmlrpc::{ParseOptions, Value};
+    ///
+    /// let input = "<value><array><data>\
+    ///     <value><int>1</int></value>\
+    ///     <value><int>two</int></value>\
+    ///     <value><boolean>3</boolean></value>\
+    ///     </data></array></value>";
+    /// let (value, errors) = ParseOptions::default().recover_value(input);
+    /// assert_eq!(value, Value::Array(vec![Value::Int(1), Value::Nil, Value::Nil]));
+    /// assert_eq!(errors.len(), 2);
+    /// assert_eq!(errors[0].position, 54);
+    /// ```
+    pub fn recover_value(&self, input: &str) -> (Value, Vec<Violation>) {
+        crate::recover::recover_value(input, self)
+    }
+
+    /// Reads a whole call or response without stopping at the first error, like
+    /// [`ParseOptions::recover_value`]. The document is `None` if it isn't well-formed XML or
+    /// isn't a `<methodCall>` or `<methodResponse>`. See
+    /// [`recover_document`](crate::recover_document).
+    pub fn recover_document(&self, input: &str) -> (Option<Document>, Vec<Violation>) {
+        crate::recover::recover_document(input, self)
+    }
+
+    /// Parses the body of an xmlrpc http response from raw bytes using these options. See
+    /// [`response_from_str`](crate::response_from_str).
+    ///
+    /// The `_from_slice` methods decode the document according to the `encoding` in its XML
+    /// declaration. Without the `encodings` feature only UTF-8 is supported.
+    ///
+    /// ```
+    /// use serde_xmlrpc::ParseOptions;
+    ///
+    /// let body = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>\
+    ///     <methodResponse><params><param><value>caf\xE9</value></param></params>\
+    ///     </methodResponse>";
+    /// let val = ParseOptions::default().response_from_slice::<String>(body);
+    /// # #[cfg(feature = "encodings")]
+    /// assert_eq!(val.unwrap(), "caf\u{e9}");
+    /// ```
+    pub fn response_from_slice<T>(&self, input: &[u8]) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        crate::parse_response(&crate::util::decode(input)?, self)
+    }
+
+    /// Parses an xmlrpc request body from raw bytes using these options. See
+    /// [`request_from_str`](crate::request_from_str).
+    pub fn request_from_slice(&self, input: &[u8]) -> Result<(String, Vec<Value>)> {
+        crate::parse_request(&crate::util::decode(input)?, self)
+    }
+
+    /// Parses an individual value from raw bytes using these options. See
+    /// [`value_from_str`](crate::value_from_str).
+    pub fn value_from_slice(&self, input: &[u8]) -> Result<Value> {
+        crate::parse_value(&crate::util::decode(input)?, self)
+    }
+
+    /// Parses a whole call or response from raw bytes using these options. See [`Document`].
+    pub fn document_from_slice(&self, input: &[u8]) -> Result<Document> {
+        crate::document::parse_document(&crate::util::decode(input)?, self)
+    }
+}
+
+/// Options controlling how values are written.
+///
+/// The default options are lenient and match the historical behavior of this crate. Use
+/// [`WriteOptions::strict`] to turn ambiguous input into errors rather than silently picking an
+/// interpretation.
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use serde_xmlrpc::WriteOptions;
+///
+/// let mut map = HashMap::new();
+/// map.insert(1, "one");
+/// map.insert(2, "two");
+///
+/// let val = WriteOptions::strict().to_value(&map).unwrap();
+/// assert_eq!(val.as_struct().unwrap()["1"].as_str(), Some("one"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct WriteOptions {
+    pub(crate) strict: bool,
+    pub(crate) escape: EscapePolicy,
+    pub(crate) cdata_threshold: Option<usize>,
+    pub(crate) datetime: DateTimeFormat,
+    pub(crate) float_extension: bool,
+    pub(crate) big_number_extension: bool,
+    pub(crate) allow_non_finite: bool,
+    pub(crate) renames: Vec<(String, String)>,
+    pub(crate) canonicalize: bool,
+}
+
+/// Which characters are escaped in text written to the document.
+///
+/// The XML-RPC spec only requires `<` and `&` to be escaped, but peers disagree on what they
+/// accept: some choke on entities they don't expect while others require everything to be
+/// escaped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EscapePolicy {
+    /// Escape `<`, `>`, `&`, `'` and `"`, making the text safe to use in attributes as well. This
+    /// is the default.
+    #[default]
+    Full,
+    /// Escape `<`, `>` and `&`, following the usual rules for text nodes.
+    Text,
+    /// Only escape `<` and `&`, as required by the spec. `>` is only escaped when it ends a `]]>`
+    /// sequence, since that isn't valid in XML text.
+    Minimal,
+}
+
+/// How `<dateTime.iso8601>` values are written.
+///
+/// The XML-RPC spec only shows the compact `19980717T14:08:55` form without a timezone, and
+/// servers differ wildly in what else they accept. Start from [`DateTimeFormat::CLASSIC`] or
+/// [`DateTimeFormat::EXTENDED`] and add the parts a peer needs:
+///
+/// ```
+/// use serde_xmlrpc::{DateTimeFormat, XmlRpcDateTime};
+///
+/// let dt: XmlRpcDateTime = "1998-07-17T14:08:55.250+02:00".parse().unwrap();
+/// assert_eq!(DateTimeFormat::CLASSIC.format(&dt), "19980717T14:08:55");
+/// assert_eq!(
+///     DateTimeFormat::EXTENDED.timezone(true).fraction(true).format(&dt),
+///     "1998-07-17T14:08:55.250+02:00"
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DateTimeFormat {
+    extended: bool,
+    timezone: bool,
+    fraction: bool,
+}
+
+impl DateTimeFormat {
+    /// The format used by the XML-RPC spec, `19980717T14:08:55`. This is the default.
+    pub const CLASSIC: DateTimeFormat = DateTimeFormat {
+        extended: false,
+        timezone: false,
+        fraction: false,
+    };
+
+    /// Extended ISO 8601 with dashes between the date parts, `1998-07-17T14:08:55`.
+    pub const EXTENDED: DateTimeFormat = DateTimeFormat {
+        extended: true,
+        timezone: false,
+        fraction: false,
+    };
+
+    /// Sets whether the timezone offset is written, as `Z` for UTC or `+02:00` otherwise.
+    ///
+    /// Without it the time is written naively, as the wall-clock time in its own offset, which
+    /// is what the spec shows and the only form some peers accept: Python's `xmlrpc.client`, for
+    /// one, fails on a trailing `Z` or offset. See [`WriteOptions::lenient`].
+    pub const fn timezone(mut self, include: bool) -> Self {
+        self.timezone = include;
+        self
+    }
+
+    /// Sets whether milliseconds are written, as in `14:08:55.250`.
+    pub const fn fraction(mut self, include: bool) -> Self {
+        self.fraction = include;
+        self
+    }
+
+    /// Formats a date/time according to this format.
+    pub fn format(&self, dt: &XmlRpcDateTime) -> String {
+        use core::fmt::Write;
+
+        let mut ret = String::new();
+        let sep = if self.extended { "-" } else { "" };
+
+        // Writing to a String can't fail.
+        if dt.year < 0 {
+            ret.push('-');
+        }
+        let _ = write!(
+            ret,
+            "{:04}{}{:02}{}{:02}",
+            dt.year.abs(),
+            sep,
+            dt.month,
+            sep,
+            dt.day
+        );
+
+        let _ = write!(ret, "T{:02}:{:02}:{:02}", dt.hour, dt.minute, dt.second);
+        if self.fraction {
+            let _ = write!(ret, ".{:03}", dt.millisecond);
+        }
+
+        if self.timezone {
+            let offset = dt.offset_minutes;
+            if offset == 0 {
+                ret.push('Z');
+            } else {
+                let sign = if offset < 0 { '-' } else { '+' };
+                let _ = write!(
+                    ret,
+                    "{}{:02}:{:02}",
+                    sign,
+                    offset.abs() / 60,
+                    offset.abs() % 60
+                );
+            }
+        }
+
+        ret
+    }
+}
+
+impl Default for DateTimeFormat {
+    fn default() -> Self {
+        DateTimeFormat::CLASSIC
+    }
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions::lenient()
+    }
+}
+
+impl WriteOptions {
+    /// Options which reject input that can't be written unambiguously.
+    ///
+    /// In particular, serializing a map or struct which produces the same member name twice is an
+    /// error rather than keeping the last value, and so is a NaN or infinite double.
+    pub const fn strict() -> Self {
+        let mut options = WriteOptions::lenient();
+        options.strict = true;
+        options.allow_non_finite = false;
+        options
+    }
+
+    /// Options which write whatever they are given, resolving ambiguities the same way previous
+    /// versions of this crate did.
+    ///
+    /// These are also the options for talking to Python's `xmlrpc.client` and
+    /// `SimpleXMLRPCServer`: dates are written in the [`DateTimeFormat::CLASSIC`] form without a
+    /// timezone, which is the only one Python parses, `f32` values are widened to `<double>`, and
+    /// NaN and infinite doubles are written the way Python spells them.
+    pub const fn lenient() -> Self {
+        WriteOptions {
+            strict: false,
+            escape: EscapePolicy::Full,
+            cdata_threshold: None,
+            datetime: DateTimeFormat::CLASSIC,
+            float_extension: false,
+            big_number_extension: false,
+            allow_non_finite: true,
+            renames: Vec::new(),
+            canonicalize: false,
+        }
+    }
+
+    /// Options for talking to Java peers built on Apache XML-RPC, which understand its
+    /// extensions. `f32` values are written as `<ex:float>` and big numbers as `<ex:bigdecimal>`
+    /// and `<ex:biginteger>`; see [`WriteOptions::float_extension`] and
+    /// [`WriteOptions::big_number_extension`]. NaN and infinite doubles are an error, since Java
+    /// can't parse the way Python spells them.
+    pub const fn apache() -> Self {
+        WriteOptions::lenient()
+            .float_extension(true)
+            .big_number_extension(true)
+            .allow_non_finite(false)
+    }
+
+    /// Options which write the same bytes for values which mean the same, so documents can be
+    /// hashed or signed, or compared in tests.
+    ///
+    /// Values are written in the form [`Value::canonicalize`] gives them, with `<`, `>` and `&`
+    /// escaped, never as CDATA, and dates in UTC with milliseconds, such as
+    /// `1998-07-17T14:08:55.000Z`. No whitespace is written between elements, which is true of
+    /// every option.
+    ///
+    /// ```
+    /// use serde_xmlrpc::{Value, WriteOptions};
+    ///
+    /// let options = WriteOptions::canonical();
+    /// let body = options.value_to_string(Value::Int64(7)).unwrap();
+    /// assert_eq!(body, options.value_to_string(Value::Int(7)).unwrap());
+    ///
+    /// let dt: serde_xmlrpc::XmlRpcDateTime = "1998-07-17T16:08:55+02:00".parse().unwrap();
+    /// let body = options.value_to_string(dt).unwrap();
+    /// assert_eq!(
+    ///     body,
+    ///     "<value><dateTime.iso8601>1998-07-17T14:08:55.000Z</dateTime.iso8601></value>"
+    /// );
+    /// ```
+    pub const fn canonical() -> Self {
+        let mut options = WriteOptions::lenient();
+        options.escape = EscapePolicy::Text;
+        options.datetime = DateTimeFormat::EXTENDED.timezone(true).fraction(true);
+        options.canonicalize = true;
+        options
+    }
+
+    /// Sets which characters are escaped in text content.
+    ///
+    /// ```
+    /// use serde_xmlrpc::{EscapePolicy, WriteOptions};
+    ///
+    /// let options = WriteOptions::default().escape(EscapePolicy::Minimal);
+    /// let body = options.value_to_string("'a' > \"b\" & c").unwrap();
+    /// assert_eq!(body, "<value><string>'a' > \"b\" &amp; c</string></value>");
+    /// ```
+    pub fn escape(mut self, policy: EscapePolicy) -> Self {
+        self.escape = policy;
+        self
+    }
+
+    /// Writes strings as a CDATA section when they contain at least `threshold` characters which
+    /// would otherwise be escaped, or never with `None`. Strings full of markup, such as embedded
+    /// HTML, are much shorter and easier to read this way. The default is `None`.
+    ///
+    /// ```
+    /// use serde_xmlrpc::WriteOptions;
+    ///
+    /// let options = WriteOptions::default().cdata_threshold(Some(4));
+    /// let body = options.value_to_string("<b>hi</b>").unwrap();
+    /// assert_eq!(body, "<value><string><![CDATA[<b>hi</b>]]></string></value>");
+    /// let body = options.value_to_string("a < b").unwrap();
+    /// assert_eq!(body, "<value><string>a &lt; b</string></value>");
+    /// ```
+    pub fn cdata_threshold(mut self, threshold: Option<usize>) -> Self {
+        self.cdata_threshold = threshold;
+        self
+    }
+
+    /// Sets how `<dateTime.iso8601>` values are written. The default is
+    /// [`DateTimeFormat::CLASSIC`].
+    pub fn datetime_format(mut self, format: DateTimeFormat) -> Self {
+        self.datetime = format;
+        self
+    }
+
+    /// Writes `f32` values as [`Value::Float`], which is written as Apache XML-RPC's
+    /// `<ex:float>` extension, rather than widening them to a `<double>`. Only enable this for
+    /// peers which understand the extension, such as Java servers built on Apache XML-RPC. The
+    /// default is `false`.
+    ///
+    /// ```
+    /// use serde_xmlrpc::{Value, WriteOptions};
+    ///
+    /// let options = WriteOptions::default().float_extension(true);
+    /// assert_eq!(options.to_value(1.5f32).unwrap(), Value::Float(1.5));
+    /// assert_eq!(WriteOptions::default().to_value(1.5f32).unwrap(), Value::Double(1.5));
+    /// ```
+    pub const fn float_extension(mut self, enable: bool) -> Self {
+        self.float_extension = enable;
+        self
+    }
+
+    /// Writes the struct field or map key `field` as a member called `name`. Servers such as
+    /// Bugzilla and Trac use member names with dots, dashes or spaces, which `#[serde(rename)]`
+    /// handles as well, but a table built at runtime is easier when many types share the same
+    /// names or the names depend on the server. Renaming a field more than once keeps the last
+    /// name.
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_xmlrpc::WriteOptions;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Search {
+    ///     bug_id: i32,
+    ///     last_change_time: String,
+    /// }
+    ///
+    /// let options = WriteOptions::default()
+    ///     .rename_member("bug_id", "bug.id")
+    ///     .rename_member("last_change_time", "last-change time");
+    /// let search = Search { bug_id: 42, last_change_time: "today".into() };
+    /// let val = options.to_value(&search).unwrap();
+    /// assert_eq!(val.pointer("/bug.id").and_then(|v| v.as_i32()), Some(42));
+    /// assert_eq!(val.pointer("/last-change time").and_then(|v| v.as_str()), Some("today"));
+    /// ```
+    ///
+    /// [`ParseOptions::rename_member`] reads the members back into the same fields.
+    pub fn rename_member(mut self, field: impl Into<String>, name: impl Into<String>) -> Self {
+        let field = field.into();
+        self.renames.retain(|(f, _)| *f != field);
+        self.renames.push((field, name.into()));
+        self
+    }
+
+    /// The member name the field or map key `field` is written as.
+    pub(crate) fn member_name<'a>(&'a self, field: &'a str) -> &'a str {
+        match self.renames.iter().find(|(f, _)| f == field) {
+            Some((_, name)) => name,
+            None => field,
+        }
+    }
+
+    /// Writes numbers serialized with the `decimal` and `bigint` modules as Apache XML-RPC's
+    /// `<ex:bigdecimal>` and `<ex:biginteger>` extensions rather than as a `<string>` of their
+    /// digits. Only enable this for peers which understand the extensions. The default is
+    /// `false`.
+    ///
+    /// Both forms are read back without losing precision, and the extension types are kept
+    /// as [`Value::Raw`] when read into a [`Value`].
+    pub const fn big_number_extension(mut self, enable: bool) -> Self {
+        self.big_number_extension = enable;
+        self
+    }
+
+    /// Writes NaN and infinite doubles as `nan`, `inf` and `-inf`, the way Python does, rather than
+    /// failing with [`EncodingError::Unrepresentable`]. The spec has no way to express them, but
+    /// Python and lenient parsers of this crate read them back. This is on for
+    /// [`WriteOptions::lenient`].
+    ///
+    /// ```
+    /// use serde_xmlrpc::WriteOptions;
+    ///
+    /// let body = WriteOptions::lenient().value_to_string(f64::NAN).unwrap();
+    /// assert_eq!(body, "<value><double>nan</double></value>");
+    /// assert!(WriteOptions::strict().value_to_string(f64::INFINITY).is_err());
+    /// ```
+    ///
+    /// [`EncodingError::Unrepresentable`]: crate::EncodingError::Unrepresentable
+    pub const fn allow_non_finite(mut self, allow: bool) -> Self {
+        self.allow_non_finite = allow;
+        self
+    }
+
+    /// Rewrites values with [`Value::canonicalize`] before writing them. This is on for
+    /// [`WriteOptions::canonical`]. The default is `false`.
+    pub const fn canonicalize(mut self, enable: bool) -> Self {
+        self.canonicalize = enable;
+        self
+    }
+
+    /// Converts a `T` into a [`Value`] using these options. See [`to_value`](crate::to_value).
+    pub fn to_value<T>(&self, value: T) -> Result<Value>
+    where
+        T: serde::Serialize,
+    {
+        value.serialize(crate::value::Serializer::with_options(self))
+    }
+}
+
+#[cfg(feature = "std")]
+impl WriteOptions {
+    /// Writes an xmlrpc request body using these options. See
+    /// [`request_to_string`](crate::request_to_string).
+    pub fn request_to_string(&self, name: &str, args: Vec<Value>) -> Result<String> {
+        crate::write_request(name, args, self)
+    }
+
+    /// Writes an xmlrpc response body using these options. See
+    /// [`response_to_string`](crate::response_to_string).
+    pub fn response_to_string(&self, params: impl Iterator<Item = Value>) -> Result<String> {
+        crate::write_response(params, self)
+    }
+
+    /// Writes the outcome of a call as a response body using these options. See
+    /// [`result_to_string`](crate::result_to_string).
+    pub fn result_to_string<T>(&self, result: &XmlRpcResult<T>) -> Result<String>
+    where
+        T: serde::Serialize,
+    {
+        crate::write_result(result, self)
+    }
+
+    /// Writes an individual value using these options. See
+    /// [`value_to_string`](crate::value_to_string).
+    pub fn value_to_string<I>(&self, val: I) -> Result<String>
+    where
+        I: Into<Value>,
+    {
+        crate::write_value(val.into(), self)
+    }
+
+    /// Writes a whole call or response using these options. See [`Document`].
+    pub fn document_to_string(&self, document: &Document) -> Result<String> {
+        crate::document::write_document(document, self)
+    }
+}
@@ -0,0 +1,254 @@
+//! Reading a single value out of a call or response, see [`ParseOptions::from_str_at_path`].
+
+use std::borrow::Cow;
+
+use quick_xml::events::Event;
+use quick_xml::name::QName;
+use quick_xml::Reader;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::error::DecodingError;
+use crate::util::{DepthGuard, ParseState, ReaderExt, ValueDeserializer};
+use crate::value::{pointer_index, pointer_tokens};
+use crate::{Error, Fault, ParseOptions, Result};
+
+pub(crate) fn from_str_at_path<T>(input: &str, path: &str, options: &ParseOptions) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let not_found = || Error::from(DecodingError::PathNotFound(path.into()));
+    let mut tokens = pointer_tokens(path).ok_or_else(not_found)?;
+    let mut reader = Reader::from_str(input);
+    reader.expand_empty_elements(true);
+    reader.trim_text(true);
+    let state = ParseState::new(options);
+
+    let root = state.read_root(&mut reader)?;
+    match root.name().into_inner() {
+        b"methodCall" => {
+            reader.expect_tag(QName(b"methodName"))?;
+            reader
+                .read_to_end(QName(b"methodName"))
+                .map_err(DecodingError::from)?;
+        }
+        b"methodResponse" => {}
+        _ => {
+            return Err(DecodingError::UnexpectedTag(
+                String::from_utf8_lossy(root.name().into_inner()).into(),
+                "methodCall or methodResponse".into(),
+            )
+            .into())
+        }
+    }
+
+    let first = tokens.next().ok_or_else(not_found)?;
+    match reader.read_event().map_err(DecodingError::from)? {
+        Event::Start(e) if e.name() == QName(b"params") && first == "params" => {
+            let index = tokens
+                .next()
+                .as_deref()
+                .and_then(pointer_index)
+                .ok_or_else(not_found)?;
+            if !skip_to_param(&mut reader, index)? {
+                return Err(not_found());
+            }
+        }
+        // A fault is returned as is when a param was asked for, like `response_from_str` does.
+        Event::Start(e) if e.name() == QName(b"fault") && first == "params" => {
+            reader.expect_tag(QName(b"value"))?;
+            let fault = Fault::deserialize(ValueDeserializer::new(&mut reader, &state)?)?;
+            return Err(fault.into());
+        }
+        Event::Start(e) if e.name() == QName(b"fault") && first == "fault" => {
+            reader.expect_tag(QName(b"value"))?;
+        }
+        Event::Start(e) if e.name() == QName(b"params") || e.name() == QName(b"fault") => {
+            return Err(not_found())
+        }
+        Event::End(_) => return Err(not_found()),
+        e => return Err(DecodingError::UnexpectedEvent(format!("{:?}", e)).into()),
+    }
+
+    // The guards keep the depth of the arrays and structs skipped into until the value is read.
+    let mut guards: Vec<DepthGuard<'_>> = Vec::new();
+    for token in tokens {
+        let found = match reader.read_event().map_err(DecodingError::from)? {
+            Event::Start(e) if e.name() == QName(b"struct") => {
+                guards.push(state.enter()?);
+                skip_to_member(&mut reader, &state, &token)?
+            }
+            Event::Start(e) if e.name() == QName(b"array") => {
+                guards.push(state.enter()?);
+                reader.expect_tag(QName(b"data"))?;
+                match pointer_index(&token) {
+                    Some(index) => skip_to_value(&mut reader, index)?,
+                    None => false,
+                }
+            }
+            // Scalars have nothing to look into.
+            _ => false,
+        };
+        if !found {
+            return Err(not_found());
+        }
+    }
+
+    T::deserialize(ValueDeserializer::new(&mut reader, &state)?)
+}
+
+/// Skips the params before the one at `index` and reads up to its `<value>`. Returns whether
+/// there is such a param.
+fn skip_to_param(reader: &mut Reader<&[u8]>, index: usize) -> Result<bool> {
+    for _ in 0..index {
+        match reader.read_event().map_err(DecodingError::from)? {
+            Event::Start(e) if e.name() == QName(b"param") => {
+                reader
+                    .read_to_end(QName(b"param"))
+                    .map_err(DecodingError::from)?;
+            }
+            Event::End(e) if e.name() == QName(b"params") => return Ok(false),
+            e => return Err(DecodingError::UnexpectedEvent(format!("{:?}", e)).into()),
+        }
+    }
+
+    match reader.read_event().map_err(DecodingError::from)? {
+        Event::Start(e) if e.name() == QName(b"param") => {
+            reader.expect_tag(QName(b"value"))?;
+            Ok(true)
+        }
+        Event::End(e) if e.name() == QName(b"params") => Ok(false),
+        e => Err(DecodingError::UnexpectedEvent(format!("{:?}", e)).into()),
+    }
+}
+
+/// Skips the values of an array before the one at `index` and reads up to its start. Returns
+/// whether there is such a value.
+fn skip_to_value(reader: &mut Reader<&[u8]>, index: usize) -> Result<bool> {
+    let mut skipped = 0;
+    loop {
+        match reader.read_event().map_err(DecodingError::from)? {
+            Event::Start(e) if e.name() == QName(b"value") && skipped == index => return Ok(true),
+            Event::Start(e) if e.name() == QName(b"value") => {
+                reader
+                    .read_to_end(QName(b"value"))
+                    .map_err(DecodingError::from)?;
+                skipped += 1;
+            }
+            Event::End(e) if e.name() == QName(b"data") => return Ok(false),
+            e => return Err(DecodingError::UnexpectedEvent(format!("{:?}", e)).into()),
+        }
+    }
+}
+
+/// Skips the members of a struct up to the first one called `name` and reads up to the start of
+/// its value. Returns whether there is such a member.
+fn skip_to_member(reader: &mut Reader<&[u8]>, state: &ParseState, name: &str) -> Result<bool> {
+    loop {
+        match reader.read_event().map_err(DecodingError::from)? {
+            Event::Start(e) if e.name() == QName(b"member") => {
+                reader.expect_tag(QName(b"name"))?;
+                let member = reader.read_string(Cow::Borrowed(""), QName(b"name"))?;
+                state.check_text(&member)?;
+                if member == name {
+                    reader.expect_tag(QName(b"value"))?;
+                    return Ok(true);
+                }
+                reader
+                    .read_to_end(QName(b"member"))
+                    .map_err(DecodingError::from)?;
+            }
+            Event::End(e) if e.name() == QName(b"struct") => return Ok(false),
+            e => return Err(DecodingError::UnexpectedEvent(format!("{:?}", e)).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use crate::{DecodingError, Error, Fault, ParseOptions, Value};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Item {
+        id: i32,
+    }
+
+    fn response() -> String {
+        let mut result = Value::Nil;
+        result.entry("total").or_insert(2);
+        let items = (1..=2)
+            .map(|id| {
+                let mut item = Value::Nil;
+                item.entry("id").or_insert(id);
+                item
+            })
+            .collect::<Vec<_>>();
+        result.entry("items").or_insert(items);
+        result.entry("a/b").or_insert("slash");
+        crate::response_to_string(vec![Value::Bool(true), result].into_iter()).unwrap()
+    }
+
+    #[test]
+    fn selects_values() {
+        let body = response();
+        let items: Vec<Item> = crate::from_str_at_path(&body, "/params/1/items").unwrap();
+        assert_eq!(items, [Item { id: 1 }, Item { id: 2 }]);
+        let item: Item = crate::from_str_at_path(&body, "/params/1/items/1").unwrap();
+        assert_eq!(item, Item { id: 2 });
+        let id: i32 = crate::from_str_at_path(&body, "/params/1/items/0/id").unwrap();
+        assert_eq!(id, 1);
+        let ok: bool = crate::from_str_at_path(&body, "/params/0").unwrap();
+        assert!(ok);
+        let escaped: String = crate::from_str_at_path(&body, "/params/1/a~1b").unwrap();
+        assert_eq!(escaped, "slash");
+        let value: Value = crate::from_str_at_path(&body, "/params/1/total").unwrap();
+        assert_eq!(value, Value::Int(2));
+
+        let call = crate::request_to_string("add", vec![Value::Int(1), Value::Int(2)]).unwrap();
+        let second: i32 = crate::from_str_at_path(&call, "/params/1").unwrap();
+        assert_eq!(second, 2);
+
+        let fault = crate::result_to_string::<()>(&Err(Fault::new(3, "gone"))).unwrap();
+        let string: String = crate::from_str_at_path(&fault, "/fault/faultString").unwrap();
+        assert_eq!(string, "gone");
+        let err = crate::from_str_at_path::<i32>(&fault, "/params/0").unwrap_err();
+        assert!(matches!(err, Error::Fault(Fault { fault_code: 3, .. })));
+    }
+
+    #[test]
+    fn missing_paths() {
+        let body = response();
+        for path in [
+            "",
+            "params/0",
+            "/params",
+            "/params/2",
+            "/params/01",
+            "/fault",
+            "/params/0/a",
+            "/params/1/missing",
+            "/params/1/items/2",
+            "/params/1/items/x",
+            "/params/1/total/0",
+        ] {
+            let err = crate::from_str_at_path::<Value>(&body, path).unwrap_err();
+            assert!(
+                matches!(&err, Error::DecodingError(DecodingError::PathNotFound(p)) if p == path),
+                "{}: {:?}",
+                path,
+                err
+            );
+        }
+
+        // Limits apply to the arrays and structs on the way to the value.
+        let options = ParseOptions::default().max_depth(Some(1));
+        assert!(options
+            .from_str_at_path::<i32>(&body, "/params/1/items/0/id")
+            .is_err());
+        assert!(options
+            .from_str_at_path::<i32>(&body, "/params/1/total")
+            .is_ok());
+    }
+}
@@ -0,0 +1,160 @@
+//! Support for the code generated by `#[derive(XmlRpcStruct)]`. Not part of the public API.
+
+use std::convert::TryFrom;
+
+use serde::de::Error as _;
+
+use crate::error::DecodingError;
+use crate::value::escape_pointer;
+use crate::{DateTimeFormat, Error, Map, RawValue, Result, Value, ValueKind, XmlRpcDateTime};
+
+fn invalid_type(value: &Value, expected: ValueKind) -> Error {
+    DecodingError::InvalidType {
+        found: value.kind().to_string(),
+        expected: expected.to_string(),
+    }
+    .into()
+}
+
+/// Returns the members of a struct value.
+pub fn members(value: &Value) -> Result<&Map<String, Value>> {
+    match value {
+        Value::Struct(members) => Ok(members),
+        _ => Err(invalid_type(value, ValueKind::STRUCT)),
+    }
+}
+
+/// Converts the member `name` with `read`. A missing member is read as `<nil/>`, so `Option`
+/// fields may be left out, and is reported as missing if that fails.
+pub fn member<T>(
+    members: &Map<String, Value>,
+    name: &str,
+    read: fn(&Value) -> Result<T>,
+) -> Result<T> {
+    match members.get(name) {
+        Some(value) => read(value).map_err(|err| err.at_path(&escape_pointer(name))),
+        None => {
+            read(&Value::Nil).map_err(|_| Error::custom(format_args!("missing field `{}`", name)))
+        }
+    }
+}
+
+/// Converts the member `name` with `read`, or returns the default if it's missing.
+pub fn member_or_default<T: Default>(
+    members: &Map<String, Value>,
+    name: &str,
+    read: fn(&Value) -> Result<T>,
+) -> Result<T> {
+    match members.get(name) {
+        Some(value) => read(value).map_err(|err| err.at_path(&escape_pointer(name))),
+        None => Ok(T::default()),
+    }
+}
+
+/// Fails on the first member which isn't one of `names`.
+pub fn deny_unknown(members: &Map<String, Value>, names: &'static [&'static str]) -> Result<()> {
+    match members.keys().find(|key| !names.contains(&key.as_str())) {
+        Some(key) => Err(Error::unknown_field(key, names)),
+        None => Ok(()),
+    }
+}
+
+/// A field written with `#[xmlrpc(datetime_format = ...)]`.
+pub trait DateTimeField: Sized {
+    fn to_value(&self, format: DateTimeFormat) -> Value;
+
+    fn from_value(value: &Value) -> Result<Self>;
+}
+
+impl DateTimeField for XmlRpcDateTime {
+    /// Writes the dateTime as raw XML, since a [`Value::DateTime`] is written in the format of
+    /// the whole document.
+    fn to_value(&self, format: DateTimeFormat) -> Value {
+        let xml = format!(
+            "<value><dateTime.iso8601>{}</dateTime.iso8601></value>",
+            format.format(self)
+        );
+        Value::Raw(RawValue(xml))
+    }
+
+    fn from_value(value: &Value) -> Result<Self> {
+        match value {
+            Value::DateTime(dt) => Ok(*dt),
+            Value::Raw(raw) => match crate::value_from_str(raw.as_str())? {
+                Value::DateTime(dt) => Ok(dt),
+                value => Err(invalid_type(&value, ValueKind::DATETIME)),
+            },
+            _ => Err(invalid_type(value, ValueKind::DATETIME)),
+        }
+    }
+}
+
+impl<T: DateTimeField> DateTimeField for Option<T> {
+    fn to_value(&self, format: DateTimeFormat) -> Value {
+        match self {
+            Some(v) => v.to_value(format),
+            None => Value::Nil,
+        }
+    }
+
+    fn from_value(value: &Value) -> Result<Self> {
+        match value {
+            Value::Nil => Ok(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+/// A field written with `#[xmlrpc(bytes = ...)]`.
+pub trait BytesField: Sized {
+    fn to_value(&self, base64: bool) -> Value;
+
+    fn from_value(value: &Value) -> Result<Self>;
+}
+
+/// Reads either encoding, whichever was asked for when writing.
+impl BytesField for Vec<u8> {
+    fn to_value(&self, base64: bool) -> Value {
+        if base64 {
+            Value::Base64(self.clone())
+        } else {
+            Value::Array(self.iter().map(|&b| Value::Int(b.into())).collect())
+        }
+    }
+
+    fn from_value(value: &Value) -> Result<Self> {
+        match value {
+            Value::Base64(bytes) => Ok(bytes.clone()),
+            Value::Array(values) => values
+                .iter()
+                .map(|value| match value {
+                    Value::Int(i) => u8::try_from(*i).map_err(|_| {
+                        DecodingError::InvalidValue {
+                            found: i.to_string(),
+                            expected: "a byte".into(),
+                        }
+                        .into()
+                    }),
+                    _ => Err(invalid_type(value, ValueKind::INT)),
+                })
+                .collect(),
+            _ => Err(invalid_type(value, ValueKind::BASE64 | ValueKind::ARRAY)),
+        }
+    }
+}
+
+impl<T: BytesField> BytesField for Option<T> {
+    fn to_value(&self, base64: bool) -> Value {
+        match self {
+            Some(v) => v.to_value(base64),
+            None => Value::Nil,
+        }
+    }
+
+    fn from_value(value: &Value) -> Result<Self> {
+        match value {
+            Value::Nil => Ok(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
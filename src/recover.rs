@@ -0,0 +1,408 @@
+//! Reading documents without stopping at the first error, see [`ParseOptions::recover_value`].
+
+use std::convert::TryFrom;
+
+use quick_xml::name::QName;
+
+use crate::error::DecodingError;
+use crate::util::{
+    decode_base64, parse_bool, parse_datetime, parse_double, parse_int, scalar_text, string_text,
+    ParseState,
+};
+use crate::validate::{read_tree, Element};
+use crate::value::RawValue;
+use crate::{
+    Document, DuplicateMembers, Error, Fault, Map, ParseOptions, Value, Values, Violation,
+};
+
+/// Converts a tree of elements into values, putting a [`Value::Nil`] in place of each value
+/// which can't be read and recording why.
+struct Recovery<'a> {
+    state: ParseState<'a>,
+    depth: usize,
+    violations: Vec<Violation>,
+}
+
+impl<'a> Recovery<'a> {
+    fn new(options: &'a ParseOptions) -> Self {
+        Recovery {
+            state: ParseState::new(options),
+            depth: 0,
+            violations: Vec::new(),
+        }
+    }
+
+    fn violation(&mut self, position: usize, message: String) {
+        self.violations.push(Violation { position, message });
+    }
+
+    /// Records `err` for the element at `position` and returns the value put in its place.
+    fn failed(&mut self, position: usize, err: Error) -> Value {
+        self.violation(position, err.to_string());
+        Value::Nil
+    }
+
+    fn unexpected(&mut self, child: &Element, parent: &str) {
+        let message = format!("unexpected <{}> in <{}>", child.name, parent);
+        self.violation(child.position, message);
+    }
+
+    fn value(&mut self, value: &Element) -> Value {
+        if value.name != "value" {
+            let message = format!("expected <value>, found <{}>", value.name);
+            self.violation(value.position, message);
+            return Value::Nil;
+        }
+
+        let (typed, rest) = match value.children.split_first() {
+            Some(split) => split,
+            // A value without a type is a string.
+            None => {
+                let text = string_text(value.text.trim(), self.state.options);
+                return match self.state.check_text(text) {
+                    Ok(()) => Value::String(text.into()),
+                    Err(err) => self.failed(value.position, err),
+                };
+            }
+        };
+        for other in rest {
+            self.unexpected(other, "value");
+        }
+        if let Some(position) = value.text_position {
+            self.violation(position, "unexpected text in <value>".into());
+        }
+        self.typed(typed)
+    }
+
+    /// Reads the element giving a value its type, such as `<int>`.
+    fn typed(&mut self, typed: &Element) -> Value {
+        let options = self.state.options;
+        let text = typed.text.as_str();
+        if !matches!(typed.name.as_str(), "struct" | "array") {
+            for child in &typed.children {
+                self.unexpected(child, &typed.name);
+            }
+        }
+
+        let value = match typed.name.as_str() {
+            name @ ("int" | "i4" | "i8") => parse_int(QName(name.as_bytes()), text, options)
+                .map(|int| i32::try_from(int).map_or(Value::Int64(int), Value::Int)),
+            "boolean" => parse_bool(text, options).map(Value::Bool),
+            "string" => {
+                let text = string_text(text, options);
+                self.state
+                    .check_text(text)
+                    .map(|_| Value::String(text.into()))
+            }
+            "double" => parse_double(text, options).map(Value::Double),
+            "ex:float" => parse_double(text, options).map(Value::Float),
+            "ex:bigdecimal" | "ex:biginteger" => {
+                let text = scalar_text(text, options);
+                let tag = &typed.name["ex:".len()..];
+                self.state
+                    .check_text(text)
+                    .map(|_| Value::Raw(RawValue::extension(tag, text)))
+            }
+            "dateTime.iso8601" => parse_datetime(text, options).map(Value::DateTime),
+            "base64" => self
+                .state
+                .check_text(text)
+                .and_then(|_| decode_base64(text))
+                .map(Value::Base64),
+            "nil" => Ok(Value::Nil),
+            "struct" => return self.nested(typed, Recovery::members),
+            "array" => return self.nested(typed, Recovery::array),
+            _ => {
+                let message = format!("unknown type <{}>", typed.name);
+                self.violation(typed.position, message);
+                return Value::Nil;
+            }
+        };
+
+        value.unwrap_or_else(|err| self.failed(typed.position, err))
+    }
+
+    /// Reads a struct or array with `read`, unless it's nested too deeply.
+    fn nested(&mut self, element: &Element, read: fn(&mut Self, &Element) -> Value) -> Value {
+        match self.state.options.max_depth {
+            Some(max) if self.depth == max => {
+                let err = DecodingError::DepthLimitExceeded(max).into();
+                return self.failed(element.position, err);
+            }
+            _ => {}
+        }
+
+        self.depth += 1;
+        let value = read(self, element);
+        self.depth -= 1;
+        value
+    }
+
+    fn members(&mut self, s: &Element) -> Value {
+        let mut members = Map::new();
+        // The members read as an array of their values, see `DuplicateMembers::Collect`.
+        let mut collected: Vec<String> = Vec::new();
+        for member in &s.children {
+            let (name, value) = match member.children.as_slice() {
+                [name, value] if member.name == "member" && name.name == "name" => (name, value),
+                _ if member.name != "member" => {
+                    self.unexpected(member, "struct");
+                    continue;
+                }
+                _ => {
+                    let message = "<member> must contain a <name> followed by a <value>".into();
+                    self.violation(member.position, message);
+                    continue;
+                }
+            };
+
+            let key = scalar_text(&name.text, self.state.options).to_string();
+            let value = self.value(value);
+            match (members.get_mut(&key), self.state.options.duplicate_members) {
+                (None, _) => {
+                    members.insert(key, value);
+                }
+                (Some(_), DuplicateMembers::Error) => {
+                    let message = format!("duplicate member {:?}", key);
+                    self.violation(member.position, message);
+                }
+                (Some(_), DuplicateMembers::KeepFirst) => {}
+                (Some(values), DuplicateMembers::Collect) => {
+                    if let (true, Value::Array(values)) = (collected.contains(&key), &mut *values) {
+                        values.push(value);
+                    } else {
+                        let first = std::mem::replace(values, Value::Nil);
+                        *values = Value::Array(vec![first, value]);
+                        collected.push(key);
+                    }
+                }
+                (Some(last), _) => *last = value,
+            }
+        }
+        Value::Struct(members)
+    }
+
+    fn array(&mut self, array: &Element) -> Value {
+        let mut values = Vec::new();
+        for data in &array.children {
+            if data.name != "data" {
+                self.unexpected(data, "array");
+                continue;
+            }
+            values.extend(data.children.iter().map(|value| self.value(value)));
+        }
+        Value::Array(values)
+    }
+
+    fn params(&mut self, params: &Element) -> Values {
+        let mut values = Vec::new();
+        for param in &params.children {
+            match param.children.as_slice() {
+                [value] if param.name == "param" => values.push(self.value(value)),
+                _ if param.name != "param" => self.unexpected(param, "params"),
+                _ => {
+                    let message = "<param> must contain a single <value>".into();
+                    self.violation(param.position, message);
+                }
+            }
+        }
+        values.into()
+    }
+
+    fn document(&mut self, root: &Element) -> Document {
+        let child = |name: &str| root.children.iter().find(|child| child.name == name);
+        for other in &root.children {
+            let expected: &[&str] = match root.name.as_str() {
+                "methodCall" => &["methodName", "params"],
+                _ => &["params", "fault"],
+            };
+            if !expected.contains(&other.name.as_str()) {
+                self.unexpected(other, &root.name);
+            }
+        }
+
+        if root.name == "methodCall" {
+            let method = match child("methodName") {
+                Some(name) => scalar_text(&name.text, self.state.options).to_string(),
+                None => {
+                    let message = "<methodCall> is missing <methodName>".into();
+                    self.violation(root.position, message);
+                    String::new()
+                }
+            };
+            let params = child("params").map_or_else(Values::new, |params| self.params(params));
+            return Document::Call { method, params };
+        }
+
+        match (child("params"), child("fault")) {
+            (Some(params), _) => Document::Response(self.params(params)),
+            (None, Some(fault)) => Document::Fault(self.fault(fault)),
+            (None, None) => {
+                let message = "<methodResponse> must contain either <params> or <fault>".into();
+                self.violation(root.position, message);
+                Document::Response(Values::new())
+            }
+        }
+    }
+
+    /// Reads a `<fault>`, filling in a code of 0 or an empty string for what's missing.
+    fn fault(&mut self, fault: &Element) -> Fault {
+        let value = match fault.children.first() {
+            Some(value) => self.value(value),
+            None => Value::Nil,
+        };
+        let members = value.as_struct();
+        let member = |name: &str| members.and_then(|members| members.get(name));
+        let code = member("faultCode").and_then(Value::as_i32);
+        let string = member("faultString").and_then(Value::as_str);
+        if code.is_none() || string.is_none() {
+            let message =
+                "a fault must be a struct with an int faultCode and a string faultString".into();
+            self.violation(fault.position, message);
+        }
+        Fault::new(code.unwrap_or_default(), string.unwrap_or_default())
+    }
+}
+
+pub(crate) fn recover_value(input: &str, options: &ParseOptions) -> (Value, Vec<Violation>) {
+    let mut recovery = Recovery::new(options);
+    let root = read_tree(
+        input,
+        &["value"],
+        options.allow_doctype,
+        &mut recovery.violations,
+    );
+    let value = match root {
+        Some(root) => recovery.value(&root),
+        None => Value::Nil,
+    };
+    (value, recovery.violations)
+}
+
+pub(crate) fn recover_document(
+    input: &str,
+    options: &ParseOptions,
+) -> (Option<Document>, Vec<Violation>) {
+    let mut recovery = Recovery::new(options);
+    let root = read_tree(
+        input,
+        &["methodCall", "methodResponse"],
+        options.allow_doctype,
+        &mut recovery.violations,
+    );
+    let document = root.map(|root| recovery.document(&root));
+    (document, recovery.violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(violations: &[Violation]) -> Vec<&str> {
+        violations.iter().map(|v| v.message.as_str()).collect()
+    }
+
+    #[test]
+    fn valid_documents() {
+        let documents = [
+            "<methodCall><methodName>a</methodName><params><param><value><array><data>\
+             <value><boolean>1</boolean></value><value><double>-12.5</double></value>\
+             <value><dateTime.iso8601>19980717T14:08:55</dateTime.iso8601></value>\
+             <value><base64>eW91IGNhbid0IHJlYWQgdGhpcyE=</base64></value><value/>\
+             <value> untyped </value><value><i8>4294967296</i8></value><value><nil/></value>\
+             </data></array></value></param></params></methodCall>",
+            "<methodResponse><params><param><value><struct>\
+             <member><name>a</name><value><string> b </string></value></member>\
+             <member><name>c</name><value><ex:float>1.5</ex:float></value></member>\
+             </struct></value></param></params></methodResponse>",
+            "<methodResponse><fault><value><struct>\
+             <member><name>faultCode</name><value><int>4</int></value></member>\
+             <member><name>faultString</name><value>Too many</value></member>\
+             </struct></value></fault></methodResponse>",
+        ];
+
+        let options = ParseOptions::default();
+        for input in documents.iter() {
+            let (document, violations) = recover_document(input, &options);
+            assert_eq!(violations, vec![]);
+            assert_eq!(document.unwrap(), options.document_from_str(input).unwrap());
+        }
+    }
+
+    #[test]
+    fn recovers() {
+        let input = "<methodResponse><params><param><value><struct>\
+            <member><name>count</name><value><int>many</int></value></member>\
+            <member><name>ok</name><value><boolean>yes</boolean></value></member>\
+            <member><name>when</name><value><dateTime.iso8601>noon</dateTime.iso8601></value></member>\
+            <member><name>kept</name><value><array><data>\
+            <value><int>1</int></value><value><wat>2</wat></value>\
+            </data></array></value></member>\
+            </struct></value></param></params></methodResponse>";
+        let (document, violations) = ParseOptions::default().recover_document(input);
+
+        let mut expected = Map::new();
+        expected.insert("count".into(), Value::Nil);
+        expected.insert("ok".into(), Value::Nil);
+        expected.insert("when".into(), Value::Nil);
+        expected.insert("kept".into(), Value::Array(vec![Value::Int(1), Value::Nil]));
+        assert_eq!(
+            document,
+            Some(Document::Response(vec![Value::Struct(expected)].into()))
+        );
+        assert_eq!(violations.len(), 4);
+        assert_eq!(violations[0].position, 79);
+        assert!(violations[1].message.contains("boolean"));
+        assert_eq!(violations[3].message, "unknown type <wat>");
+        assert!(violations.windows(2).all(|w| w[0].position < w[1].position));
+    }
+
+    #[test]
+    fn structure() {
+        let options = ParseOptions::default();
+        let (value, violations) =
+            options.recover_value("<value><array><data><value><int>1</int></value>");
+        assert_eq!(value, Value::Nil);
+        assert_eq!(
+            messages(&violations),
+            ["unexpected end of document in <value>"]
+        );
+
+        let (document, violations) = options.recover_document("<value/>");
+        assert_eq!(document, None);
+        assert_eq!(
+            messages(&violations),
+            ["expected <methodCall> or <methodResponse>, found <value>"]
+        );
+
+        let (document, violations) = options.recover_document(
+            "<methodResponse><fault><value>oops</value></fault></methodResponse>",
+        );
+        assert_eq!(document, Some(Document::Fault(Fault::new(0, ""))));
+        assert_eq!(violations.len(), 1);
+
+        let duplicate = "<value><struct>\
+            <member><name>a</name><value><int>1</int></value></member>\
+            <member><name>a</name><value><int>2</int></value></member>\
+            </struct></value>";
+        let (value, _) = options.recover_value(duplicate);
+        assert_eq!(value.as_struct().unwrap().get("a"), Some(&Value::Int(2)));
+        let collect = options.clone().duplicate_members(DuplicateMembers::Collect);
+        let (value, _) = collect.recover_value(duplicate);
+        assert_eq!(
+            value.as_struct().unwrap().get("a"),
+            Some(&Value::Array(vec![Value::Int(1), Value::Int(2)]))
+        );
+        let error = options.clone().duplicate_members(DuplicateMembers::Error);
+        let (value, violations) = error.recover_value(duplicate);
+        assert_eq!(value.as_struct().unwrap().get("a"), Some(&Value::Int(1)));
+        assert_eq!(messages(&violations), ["duplicate member \"a\""]);
+
+        let deep = options.clone().max_depth(Some(1));
+        let (value, violations) = deep.recover_value(
+            "<value><array><data><value><array><data/></array></value></data></array></value>",
+        );
+        assert_eq!(value, Value::Array(vec![Value::Nil]));
+        assert_eq!(violations.len(), 1);
+    }
+}
@@ -0,0 +1,605 @@
+//! Typed calls for the [ROS 1 master and slave APIs].
+//!
+//! Every method is a struct of its params which implements [`RosMethod`], so a call is written
+//! with [`request_to_string`] and its response read with [`response_from_str`]. ROS responses are
+//! a `[code, statusMessage, value]` triple. Reading one checks the code and returns the value, or
+//! an [`Error::Fault`] with the code and status message when the call didn't succeed.
+//!
+//! ```
+//! use serde_xmlrpc::ros::{self, LookupNode, RegisterPublisher};
+//!
+//! let call = RegisterPublisher {
+//!     caller_id: "/talker".into(),
+//!     topic: "/chatter".into(),
+//!     topic_type: "std_msgs/String".into(),
+//!     caller_api: "http://localhost:40001/".into(),
+//! };
+//! let body = ros::request_to_string(&call).unwrap();
+//! assert!(body.contains("<methodName>registerPublisher</methodName>"));
+//!
+//! let response = r#"<methodResponse><params><param><value><array><data>
+//!     <value><int>1</int></value>
+//!     <value><string>Registered [/talker] as publisher of [/chatter]</string></value>
+//!     <value><array><data>
+//!         <value><string>http://localhost:40002/</string></value>
+//!     </data></array></value>
+//! </data></array></value></param></params></methodResponse>"#;
+//! let subscribers = ros::response_from_str::<RegisterPublisher>(response).unwrap();
+//! assert_eq!(subscribers, ["http://localhost:40002/"]);
+//!
+//! let response = r#"<methodResponse><params><param><value><array><data>
+//!     <value><int>-1</int></value>
+//!     <value><string>unknown node [/nobody]</string></value>
+//!     <value><string></string></value>
+//! </data></array></value></param></params></methodResponse>"#;
+//! let err = ros::response_from_str::<LookupNode>(response).unwrap_err();
+//! assert!(matches!(err, serde_xmlrpc::Error::Fault(f) if f.fault_code == ros::codes::ERROR));
+//! ```
+//!
+//! Nodes serving the slave API read calls with [`RosMethod::from_params`] and answer with
+//! [`response_to_string`].
+//!
+//! [ROS 1 master and slave APIs]: http://wiki.ros.org/ROS/Master_Slave_APIs
+
+use serde::de::DeserializeOwned;
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Error, Fault, Map, Result, Value, Values};
+
+/// The status codes of ROS responses.
+pub mod codes {
+    /// The call failed because of an error in the request, such as a bad param.
+    pub const ERROR: i32 = -1;
+    /// The call was valid but didn't succeed.
+    pub const FAILURE: i32 = 0;
+    /// The call succeeded.
+    pub const SUCCESS: i32 = 1;
+}
+
+/// A method of the master or slave API.
+pub trait RosMethod: Sized {
+    /// The name the method is called by, such as `registerPublisher`.
+    const NAME: &'static str;
+
+    /// The value of a successful response.
+    type Output: Serialize + DeserializeOwned;
+
+    /// Converts the call into its params, in order.
+    fn to_params(&self) -> Values;
+
+    /// Reads a call from its params.
+    fn from_params(params: &Values) -> Result<Self>;
+}
+
+/// The `[code, statusMessage, value]` triple every ROS method returns.
+///
+/// The value of a response which didn't succeed is often of a different type than a successful
+/// one, which is why `T` defaults to [`Value`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RosResponse<T = Value> {
+    /// One of [`codes`].
+    pub code: i32,
+    /// A human-readable description of what happened.
+    pub status_message: String,
+    /// The result of the call.
+    pub value: T,
+}
+
+impl<T> RosResponse<T> {
+    pub fn new(code: i32, status_message: impl Into<String>, value: T) -> Self {
+        RosResponse {
+            code,
+            status_message: status_message.into(),
+            value,
+        }
+    }
+
+    /// A successful response.
+    pub fn success(status_message: impl Into<String>, value: T) -> Self {
+        RosResponse::new(codes::SUCCESS, status_message, value)
+    }
+
+    /// Returns the value of a successful response, or the code and status message as a fault.
+    pub fn into_result(self) -> Result<T> {
+        if self.code == codes::SUCCESS {
+            Ok(self.value)
+        } else {
+            Err(Error::Fault(Fault::new(self.code, self.status_message)))
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for RosResponse<T> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut triple = serializer.serialize_tuple(3)?;
+        triple.serialize_element(&self.code)?;
+        triple.serialize_element(&self.status_message)?;
+        triple.serialize_element(&self.value)?;
+        triple.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for RosResponse<T> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (code, status_message, value) = Deserialize::deserialize(deserializer)?;
+        Ok(RosResponse {
+            code,
+            status_message,
+            value,
+        })
+    }
+}
+
+/// Writes the request body of a call.
+pub fn request_to_string<M: RosMethod>(method: &M) -> Result<String> {
+    crate::request_to_string(M::NAME, method.to_params().into_vec())
+}
+
+/// Reads the response to a call of `M`, returning its value if the call succeeded.
+pub fn response_from_str<M: RosMethod>(input: &str) -> Result<M::Output> {
+    let response: RosResponse = crate::response_from_str(input)?;
+    crate::from_value(response.into_result()?)
+}
+
+/// Writes the body of a response.
+pub fn response_to_string<T: Serialize>(response: &RosResponse<T>) -> Result<String> {
+    crate::response_to_string(std::iter::once(crate::to_value(response)?))
+}
+
+/// Flattens a parameter tree, such as `getParam` returns for a namespace, into the full name of
+/// each parameter in it. Names are joined with `/` as in ROS, so `{"b": {"c": 1}}` under `/a`
+/// becomes `/a/b/c`. Empty structs are kept as parameters of their own, and a `value` which
+/// isn't a struct becomes the single parameter `namespace`.
+///
+/// ```
+/// use serde_xmlrpc::ros::{flatten_params, unflatten_params};
+/// use serde_xmlrpc::Value;
+///
+/// let tree = serde_xmlrpc::value_from_str(
+///     "<value><struct>\
+///        <member><name>rate</name><value><int>10</int></value></member>\
+///        <member><name>camera</name><value><struct>\
+///          <member><name>fps</name><value><double>29.97</double></value></member>\
+///        </struct></value></member>\
+///      </struct></value>",
+/// )
+/// .unwrap();
+///
+/// let params = flatten_params("/robot", &tree);
+/// assert_eq!(params["/robot/rate"], Value::Int(10));
+/// assert_eq!(params["/robot/camera/fps"], Value::Double(29.97));
+///
+/// let rebuilt = unflatten_params(params);
+/// assert_eq!(rebuilt.pointer("/robot/camera/fps"), Some(&Value::Double(29.97)));
+/// ```
+pub fn flatten_params(namespace: &str, value: &Value) -> Map<String, Value> {
+    fn flatten(name: String, value: &Value, params: &mut Map<String, Value>) {
+        match value {
+            Value::Struct(members) if !members.is_empty() => {
+                for (key, member) in members {
+                    flatten(
+                        format!("{}/{}", name.trim_end_matches('/'), key),
+                        member,
+                        params,
+                    );
+                }
+            }
+            value => {
+                params.insert(name, value.clone());
+            }
+        }
+    }
+
+    let mut params = Map::new();
+    flatten(namespace.to_string(), value, &mut params);
+    params
+}
+
+/// Builds a parameter tree out of full parameter names and their values, the reverse of
+/// [`flatten_params`]. Each `/`-separated part of a name is a struct, so the result can be passed
+/// to `setParam` for the root namespace. Where names overlap, such as `/a` and `/a/b`, later
+/// parameters replace earlier ones the way `setParam` does.
+pub fn unflatten_params<I, K>(params: I) -> Value
+where
+    I: IntoIterator<Item = (K, Value)>,
+    K: AsRef<str>,
+{
+    let mut root = Value::Struct(Map::new());
+    for (name, value) in params {
+        let mut node = &mut root;
+        for part in name.as_ref().split('/').filter(|part| !part.is_empty()) {
+            if !matches!(node, Value::Struct(_)) {
+                *node = Value::Struct(Map::new());
+            }
+            node = match node {
+                Value::Struct(members) => members
+                    .entry(part.to_string())
+                    .or_insert_with(|| Value::Struct(Map::new())),
+                _ => unreachable!(),
+            };
+        }
+        *node = value;
+    }
+    root
+}
+
+/// Topics or services and the nodes using them, such as `[["/chatter", ["/talker"]]]`.
+pub type Registrations = Vec<(String, Vec<String>)>;
+
+/// Topics and their types, such as `[["/chatter", "std_msgs/String"]]`.
+pub type TopicTypes = Vec<(String, String)>;
+
+/// The result of `getSystemState`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    from = "(Registrations, Registrations, Registrations)",
+    into = "(Registrations, Registrations, Registrations)"
+)]
+pub struct SystemState {
+    /// Each topic and the nodes publishing it.
+    pub publishers: Registrations,
+    /// Each topic and the nodes subscribed to it.
+    pub subscribers: Registrations,
+    /// Each service and the nodes providing it.
+    pub services: Registrations,
+}
+
+impl From<(Registrations, Registrations, Registrations)> for SystemState {
+    fn from(
+        (publishers, subscribers, services): (Registrations, Registrations, Registrations),
+    ) -> Self {
+        SystemState {
+            publishers,
+            subscribers,
+            services,
+        }
+    }
+}
+
+impl From<SystemState> for (Registrations, Registrations, Registrations) {
+    fn from(state: SystemState) -> Self {
+        (state.publishers, state.subscribers, state.services)
+    }
+}
+
+macro_rules! ros_methods {
+    ($(
+        $(#[$doc:meta])*
+        $name:ident => $method:literal -> $output:ty {
+            $($(#[$field_doc:meta])* $field:ident: $ty:ty,)*
+        }
+    )*) => {
+        $(
+            $(#[$doc])*
+            #[derive(Clone, Debug, PartialEq)]
+            pub struct $name {
+                $($(#[$field_doc])* pub $field: $ty,)*
+            }
+
+            impl RosMethod for $name {
+                const NAME: &'static str = $method;
+                type Output = $output;
+
+                fn to_params(&self) -> Values {
+                    Values::new()$(.param(&self.$field))*
+                }
+
+                fn from_params(params: &Values) -> Result<Self> {
+                    let mut index = 0..;
+                    Ok($name {
+                        $($field: params.get(index.next().unwrap_or_default())?,)*
+                    })
+                }
+            }
+        )*
+    };
+}
+
+ros_methods! {
+    /// Registers `caller_api` as a provider of `service`. The value is ignored.
+    RegisterService => "registerService" -> i32 {
+        caller_id: String,
+        service: String,
+        /// The `rosrpc://` URI of the service.
+        service_api: String,
+        /// The XML-RPC URI of the node.
+        caller_api: String,
+    }
+
+    /// Unregisters `caller_api` as a provider of `service`. The value is how many registrations
+    /// were removed.
+    UnregisterService => "unregisterService" -> i32 {
+        caller_id: String,
+        service: String,
+        service_api: String,
+    }
+
+    /// Subscribes the node to `topic`. The value is the XML-RPC URIs of its publishers.
+    RegisterSubscriber => "registerSubscriber" -> Vec<String> {
+        caller_id: String,
+        topic: String,
+        topic_type: String,
+        caller_api: String,
+    }
+
+    /// Unsubscribes the node from `topic`. The value is how many registrations were removed.
+    UnregisterSubscriber => "unregisterSubscriber" -> i32 {
+        caller_id: String,
+        topic: String,
+        caller_api: String,
+    }
+
+    /// Registers the node as a publisher of `topic`. The value is the XML-RPC URIs of its
+    /// subscribers.
+    RegisterPublisher => "registerPublisher" -> Vec<String> {
+        caller_id: String,
+        topic: String,
+        topic_type: String,
+        caller_api: String,
+    }
+
+    /// Unregisters the node as a publisher of `topic`. The value is how many registrations were
+    /// removed.
+    UnregisterPublisher => "unregisterPublisher" -> i32 {
+        caller_id: String,
+        topic: String,
+        caller_api: String,
+    }
+
+    /// Looks up the XML-RPC URI of the node called `node_name`.
+    LookupNode => "lookupNode" -> String {
+        caller_id: String,
+        node_name: String,
+    }
+
+    /// Lists the topics which have publishers, within `subgraph` or everywhere if it's empty.
+    GetPublishedTopics => "getPublishedTopics" -> TopicTypes {
+        caller_id: String,
+        subgraph: String,
+    }
+
+    /// Lists every known topic and its type.
+    GetTopicTypes => "getTopicTypes" -> TopicTypes {
+        caller_id: String,
+    }
+
+    /// Lists every publisher, subscriber and service.
+    GetSystemState => "getSystemState" -> SystemState {
+        caller_id: String,
+    }
+
+    /// Asks for the URI of the master.
+    GetUri => "getUri" -> String {
+        caller_id: String,
+    }
+
+    /// Looks up the `rosrpc://` URI of `service`.
+    LookupService => "lookupService" -> String {
+        caller_id: String,
+        service: String,
+    }
+
+    /// Deletes the parameter `key`. The value is ignored.
+    DeleteParam => "deleteParam" -> i32 {
+        caller_id: String,
+        key: String,
+    }
+
+    /// Sets the parameter `key`. A struct sets each of its members as a parameter under `key`.
+    /// The value is ignored.
+    SetParam => "setParam" -> i32 {
+        caller_id: String,
+        key: String,
+        value: Value,
+    }
+
+    /// Reads the parameter `key`. A namespace is read as a struct of the parameters in it.
+    GetParam => "getParam" -> Value {
+        caller_id: String,
+        key: String,
+    }
+
+    /// Searches for `key` from the caller's namespace upwards. The value is the full name of the
+    /// closest parameter found.
+    SearchParam => "searchParam" -> String {
+        caller_id: String,
+        key: String,
+    }
+
+    /// Subscribes `caller_api` to updates of the parameter `key`. The value is the parameter's
+    /// current value, or an empty struct if it isn't set.
+    SubscribeParam => "subscribeParam" -> Value {
+        caller_id: String,
+        caller_api: String,
+        key: String,
+    }
+
+    /// Unsubscribes `caller_api` from updates of the parameter `key`. The value is how many
+    /// subscriptions were removed.
+    UnsubscribeParam => "unsubscribeParam" -> i32 {
+        caller_id: String,
+        caller_api: String,
+        key: String,
+    }
+
+    /// Checks whether the parameter `key` is set.
+    HasParam => "hasParam" -> bool {
+        caller_id: String,
+        key: String,
+    }
+
+    /// Lists the names of every parameter.
+    GetParamNames => "getParamNames" -> Vec<String> {
+        caller_id: String,
+    }
+
+    /// Asks a node for statistics about its connections, in a layout which varies by client
+    /// library.
+    GetBusStats => "getBusStats" -> Value {
+        caller_id: String,
+    }
+
+    /// Asks a node for information about its connections, in a layout which varies by client
+    /// library.
+    GetBusInfo => "getBusInfo" -> Value {
+        caller_id: String,
+    }
+
+    /// Asks a node for the URI of the master it uses.
+    GetMasterUri => "getMasterUri" -> String {
+        caller_id: String,
+    }
+
+    /// Asks a node to shut down. The value is ignored.
+    Shutdown => "shutdown" -> i32 {
+        caller_id: String,
+        msg: String,
+    }
+
+    /// Asks a node for its process ID.
+    GetPid => "getPid" -> i32 {
+        caller_id: String,
+    }
+
+    /// Asks a node which topics it subscribes to.
+    GetSubscriptions => "getSubscriptions" -> TopicTypes {
+        caller_id: String,
+    }
+
+    /// Asks a node which topics it publishes.
+    GetPublications => "getPublications" -> TopicTypes {
+        caller_id: String,
+    }
+
+    /// Tells a node that a parameter it subscribed to changed. The value is ignored.
+    ParamUpdate => "paramUpdate" -> i32 {
+        caller_id: String,
+        parameter_key: String,
+        parameter_value: Value,
+    }
+
+    /// Tells a node the current publishers of a topic it subscribes to. The value is ignored.
+    PublisherUpdate => "publisherUpdate" -> i32 {
+        caller_id: String,
+        topic: String,
+        /// The XML-RPC URIs of the publishers.
+        publishers: Vec<String>,
+    }
+
+    /// Asks a publisher for a connection to `topic`. The value is the chosen protocol's name
+    /// followed by its params, such as `["TCPROS", "localhost", 40003]`.
+    RequestTopic => "requestTopic" -> Vec<Value> {
+        caller_id: String,
+        topic: String,
+        /// The protocols the caller supports in order of preference, each its name followed by
+        /// its params, such as `[["TCPROS"]]`.
+        protocols: Vec<Vec<Value>>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ros_calls() {
+        let call = RequestTopic {
+            caller_id: "/listener".into(),
+            topic: "/chatter".into(),
+            protocols: vec![vec![Value::from("TCPROS")]],
+        };
+        let body = request_to_string(&call).unwrap();
+        let (name, params) = crate::request_from_str(&body).unwrap();
+        assert_eq!(name, "requestTopic");
+        assert_eq!(RequestTopic::from_params(&params.into()).unwrap(), call);
+
+        let short = Values::new().param(&"/listener".to_string());
+        assert!(RequestTopic::from_params(&short).is_err());
+    }
+
+    #[test]
+    fn ros_responses() {
+        let response = RosResponse::success(
+            "ready on localhost:40003",
+            vec![
+                Value::from("TCPROS"),
+                Value::from("localhost"),
+                Value::Int(40003),
+            ],
+        );
+        let body = response_to_string(&response).unwrap();
+        assert_eq!(
+            response_from_str::<RequestTopic>(&body).unwrap(),
+            response.value
+        );
+        assert_eq!(
+            crate::response_from_str::<RosResponse<Vec<Value>>>(&body).unwrap(),
+            response
+        );
+
+        let failure = RosResponse::new(codes::FAILURE, "no such topic", 0);
+        let body = response_to_string(&failure).unwrap();
+        match response_from_str::<RequestTopic>(&body) {
+            Err(Error::Fault(fault)) => {
+                assert_eq!(fault, Fault::new(codes::FAILURE, "no such topic"))
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn param_trees() {
+        let mut camera = Map::new();
+        camera.insert("fps".to_string(), Value::Int(30));
+        camera.insert("extra".to_string(), Value::Struct(Map::new()));
+        let mut tree = Map::new();
+        tree.insert("camera".to_string(), Value::Struct(camera));
+        tree.insert("name".to_string(), Value::from("rover"));
+        let tree = Value::Struct(tree);
+
+        let params = flatten_params("/", &tree);
+        let mut names = params.keys().map(String::as_str).collect::<Vec<_>>();
+        names.sort_unstable();
+        assert_eq!(names, ["/camera/extra", "/camera/fps", "/name"]);
+        assert_eq!(unflatten_params(params), tree);
+
+        assert_eq!(
+            flatten_params("/rate", &Value::Int(5))["/rate"],
+            Value::Int(5)
+        );
+
+        let overlapping = vec![
+            ("/a", Value::Int(1)),
+            ("/a/b", Value::Int(2)),
+            ("c//d/", Value::Int(3)),
+        ];
+        let rebuilt = unflatten_params(overlapping);
+        assert_eq!(rebuilt.pointer("/a/b"), Some(&Value::Int(2)));
+        assert_eq!(rebuilt.pointer("/c/d"), Some(&Value::Int(3)));
+        assert_eq!(
+            unflatten_params(vec![("/a/b", Value::Int(2)), ("/a", Value::Int(1))]).pointer("/a"),
+            Some(&Value::Int(1))
+        );
+    }
+
+    #[test]
+    fn system_state() {
+        let state = SystemState {
+            publishers: vec![("/rosout".into(), vec!["/talker".into(), "/listener".into()])],
+            subscribers: vec![("/rosout".into(), vec!["/rosout".into()])],
+            services: Vec::new(),
+        };
+        let body = response_to_string(&RosResponse::success("", state.clone())).unwrap();
+        assert!(body.contains(
+            "<array><data><value><array><data><value><array><data><value><string>/rosout</string>"
+        ));
+        assert_eq!(response_from_str::<GetSystemState>(&body).unwrap(), state);
+    }
+}
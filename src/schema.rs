@@ -0,0 +1,923 @@
+//! Descriptions of the values a method expects, for checking params before they are dispatched.
+//!
+//! A [`Schema`] can be built by hand or derived from a Rust type with [`Schema::of`]. Checking a
+//! value reports every place it doesn't match, each with its path, rather than stopping at the
+//! first problem the way converting it does.
+//!
+//! ```
+//! use serde::Deserialize;
+//! use serde_xmlrpc::schema::{Schema, StructSchema};
+//! use serde_xmlrpc::{Value, ValueKind};
+//!
+//! #[derive(Deserialize)]
+//! struct Login {
+//!     user: String,
+//!     token: Option<String>,
+//! }
+//!
+//! let schema = Schema::of::<Login>().unwrap();
+//! assert_eq!(
+//!     schema,
+//!     StructSchema::new()
+//!         .required("user", ValueKind::STRING)
+//!         .optional("token", Schema::optional(ValueKind::STRING))
+//!         .into()
+//! );
+//!
+//! let mut login = Value::Nil;
+//! login.entry("user").or_insert(42);
+//! let violations = schema.validate(&login).unwrap_err();
+//! assert_eq!(violations[0].to_string(), "invalid type: int, expected string at /user");
+//! ```
+
+use std::fmt;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+
+use crate::{Error, Result, Value, ValueKind};
+
+/// The values a param or value may have.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Schema {
+    /// Any value at all.
+    Any,
+    /// A value of one of these types. Arrays and structs may have any contents.
+    Kind(ValueKind),
+    /// An array whose elements all match the schema.
+    Array(Box<Schema>),
+    /// An array with one element per schema, matched in order, such as the params of a call.
+    Tuple(Vec<Schema>),
+    /// A struct whose members all match the schema, with any names.
+    Map(Box<Schema>),
+    /// A struct with the given members.
+    Struct(StructSchema),
+    /// A value matching at least one of the schemas.
+    Union(Vec<Schema>),
+}
+
+impl Schema {
+    /// Derives the schema of values which can be converted to `T`, by watching what `T` asks for
+    /// while deserializing a made-up value.
+    ///
+    /// Struct members are required unless they are an `Option`. Members with
+    /// `#[serde(default)]` are still required, since serde doesn't say which have a default, and
+    /// unknown members are allowed since `#[serde(deny_unknown_fields)]` isn't visible either.
+    /// Enums and types which deserialize themselves from any value, such as [`Value`], accept
+    /// any value. A type which refers to itself is described one level deep.
+    ///
+    /// This fails for types which reject the made-up values, such as a string which has to parse
+    /// as a URL, for `#[serde(flatten)]` fields and for types which refer to themselves through
+    /// an `Option`. Build the schema by hand for those.
+    pub fn of<T>() -> Result<Schema>
+    where
+        T: DeserializeOwned,
+    {
+        let mut schema = Schema::Any;
+        let mut trace = Trace::default();
+        T::deserialize(Tracer {
+            schema: &mut schema,
+            trace: &mut trace,
+        })?;
+        Ok(schema)
+    }
+
+    /// An array whose elements all match `element`.
+    pub fn array(element: impl Into<Schema>) -> Schema {
+        Schema::Array(Box::new(element.into()))
+    }
+
+    /// A struct whose members all match `member`, such as a `HashMap` keyed by name.
+    pub fn map(member: impl Into<Schema>) -> Schema {
+        Schema::Map(Box::new(member.into()))
+    }
+
+    /// Either a value matching `schema` or `<nil/>`, such as an `Option`.
+    pub fn optional(schema: impl Into<Schema>) -> Schema {
+        Schema::Union(vec![schema.into(), Schema::Kind(ValueKind::NIL)])
+    }
+
+    /// Checks `value` against the schema, returning every violation if it doesn't match.
+    pub fn validate(&self, value: &Value) -> std::result::Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+        self.check(&mut String::new(), value, &mut violations);
+        into_result(violations)
+    }
+
+    /// Checks the params of a call, as if they were an array. Paths start with the index of the
+    /// param, such as `/0/user`. A [`Schema::Tuple`] describes a fixed list of params, and
+    /// [`Schema::of`] a tuple of handler arguments gives one.
+    pub fn validate_params(&self, params: &[Value]) -> std::result::Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+        if !self.kinds().contains(ValueKind::ARRAY) {
+            violations.push(invalid_type(String::new(), ValueKind::ARRAY, self.kinds()));
+        } else if let Schema::Union(schemas) = self {
+            // Only the arrays among the union can match.
+            let mut first = None;
+            for schema in schemas
+                .iter()
+                .filter(|s| s.kinds().contains(ValueKind::ARRAY))
+            {
+                match schema.validate_params(params) {
+                    Ok(()) => return Ok(()),
+                    Err(found) => {
+                        first.get_or_insert(found);
+                    }
+                }
+            }
+            violations = first.unwrap_or_default();
+        } else {
+            self.check_elements(&mut String::new(), params, &mut violations);
+        }
+        into_result(violations)
+    }
+
+    /// The XML-RPC type of the values which can match, as used in introspection signatures, such
+    /// as `"int"` or `"struct"`. `<nil/>` is left out unless it's the only type, so an optional
+    /// string is `"string"`. Returns `None` if values of several types can match, except that a
+    /// schema for any number is `"double"`.
+    ///
+    /// ```
+    /// use serde_xmlrpc::schema::Schema;
+    ///
+    /// assert_eq!(Schema::of::<Option<u16>>().unwrap().type_name(), Some("int"));
+    /// assert_eq!(Schema::of::<Vec<bool>>().unwrap().type_name(), Some("array"));
+    /// assert_eq!(Schema::Any.type_name(), None);
+    /// ```
+    pub fn type_name(&self) -> Option<&'static str> {
+        let kinds = match self.kinds() {
+            ValueKind::NIL => return Some("nil"),
+            kinds => kinds.difference(ValueKind::NIL),
+        };
+
+        let name = match kinds {
+            ValueKind::INT | ValueKind::INTEGER => "int",
+            ValueKind::INT64 => "i8",
+            kinds if ValueKind::NUMBER.contains(kinds) && !kinds.is_empty() => "double",
+            ValueKind::BOOL => "boolean",
+            ValueKind::STRING => "string",
+            ValueKind::DATETIME => "dateTime.iso8601",
+            ValueKind::BASE64 => "base64",
+            ValueKind::STRUCT => "struct",
+            ValueKind::ARRAY => "array",
+            _ => return None,
+        };
+        Some(name)
+    }
+
+    /// The types of the values which can match.
+    fn kinds(&self) -> ValueKind {
+        match self {
+            Schema::Any => ValueKind::ANY,
+            Schema::Kind(kinds) => *kinds,
+            Schema::Array(_) | Schema::Tuple(_) => ValueKind::ARRAY,
+            Schema::Map(_) | Schema::Struct(_) => ValueKind::STRUCT,
+            Schema::Union(schemas) => schemas
+                .iter()
+                .fold(ValueKind::NONE, |kinds, schema| kinds | schema.kinds()),
+        }
+    }
+
+    /// Whether the schema accepts `<nil/>`, which makes a struct member derived from it optional.
+    fn is_optional(&self) -> bool {
+        matches!(self, Schema::Union(schemas) if schemas.contains(&Schema::Kind(ValueKind::NIL)))
+    }
+
+    fn check(&self, path: &mut String, value: &Value, violations: &mut Vec<Violation>) {
+        match (self, value) {
+            (Schema::Any, _) => {}
+            (Schema::Kind(kinds), value) if value.matches(*kinds) => {}
+            (Schema::Array(_), Value::Array(values)) | (Schema::Tuple(_), Value::Array(values)) => {
+                self.check_elements(path, values, violations)
+            }
+            (Schema::Map(member), Value::Struct(members)) => {
+                let len = path.len();
+                for (name, value) in members {
+                    push_member(path, name);
+                    member.check(path, value, violations);
+                    path.truncate(len);
+                }
+            }
+            (Schema::Struct(schema), Value::Struct(members)) => {
+                let len = path.len();
+                for member in &schema.members {
+                    match members.get(&member.name) {
+                        Some(value) => {
+                            push_member(path, &member.name);
+                            member.schema.check(path, value, violations);
+                            path.truncate(len);
+                        }
+                        None if member.required => violations.push(Violation {
+                            path: path.clone(),
+                            message: format!("missing member `{}`", member.name),
+                        }),
+                        None => {}
+                    }
+                }
+
+                if schema.deny_unknown {
+                    for name in members.keys() {
+                        if !schema.members.iter().any(|member| member.name == *name) {
+                            violations.push(Violation {
+                                path: path.clone(),
+                                message: format!("unknown member `{}`", name),
+                            });
+                        }
+                    }
+                }
+            }
+            (Schema::Union(schemas), value) => {
+                // Report why the first schema of the right type didn't match, since that's most
+                // likely the one meant.
+                let mut first = None;
+                for schema in schemas.iter().filter(|s| value.matches(s.kinds())) {
+                    let mut found = Vec::new();
+                    schema.check(path, value, &mut found);
+                    if found.is_empty() {
+                        return;
+                    }
+                    first.get_or_insert(found);
+                }
+
+                match first {
+                    Some(found) => violations.extend(found),
+                    None => violations.push(invalid_type(path.clone(), value.kind(), self.kinds())),
+                }
+            }
+            (schema, value) => {
+                violations.push(invalid_type(path.clone(), value.kind(), schema.kinds()))
+            }
+        }
+    }
+
+    /// Checks the elements of an array against an array or tuple schema.
+    fn check_elements(&self, path: &mut String, values: &[Value], violations: &mut Vec<Violation>) {
+        let len = path.len();
+        match self {
+            Schema::Array(element) => {
+                for (idx, value) in values.iter().enumerate() {
+                    path.push_str(&format!("/{}", idx));
+                    element.check(path, value, violations);
+                    path.truncate(len);
+                }
+            }
+            Schema::Tuple(schemas) => {
+                if schemas.len() != values.len() {
+                    violations.push(Violation {
+                        path: path.clone(),
+                        message: format!(
+                            "invalid length {}, expected {} elements",
+                            values.len(),
+                            schemas.len()
+                        ),
+                    });
+                    return;
+                }
+                for (idx, (schema, value)) in schemas.iter().zip(values).enumerate() {
+                    path.push_str(&format!("/{}", idx));
+                    schema.check(path, value, violations);
+                    path.truncate(len);
+                }
+            }
+            // Any and kinds which include arrays accept any elements.
+            _ => {}
+        }
+    }
+}
+
+impl From<ValueKind> for Schema {
+    fn from(kinds: ValueKind) -> Self {
+        Schema::Kind(kinds)
+    }
+}
+
+impl From<StructSchema> for Schema {
+    fn from(schema: StructSchema) -> Self {
+        Schema::Struct(schema)
+    }
+}
+
+/// The members of a struct, see [`Schema::Struct`].
+///
+/// Members not listed are allowed unless [`deny_unknown`](StructSchema::deny_unknown) is set.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StructSchema {
+    members: Vec<Member>,
+    deny_unknown: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Member {
+    name: String,
+    schema: Schema,
+    required: bool,
+}
+
+impl StructSchema {
+    pub fn new() -> Self {
+        StructSchema::default()
+    }
+
+    /// Adds a member which must be present and match `schema`, replacing any previous member of
+    /// the same name.
+    pub fn required(self, name: impl Into<String>, schema: impl Into<Schema>) -> Self {
+        self.member(name.into(), schema.into(), true)
+    }
+
+    /// Adds a member which must match `schema` if it's present, replacing any previous member of
+    /// the same name.
+    pub fn optional(self, name: impl Into<String>, schema: impl Into<Schema>) -> Self {
+        self.member(name.into(), schema.into(), false)
+    }
+
+    /// Rejects members which weren't added to the schema. The default is `false`.
+    pub fn deny_unknown(mut self, deny: bool) -> Self {
+        self.deny_unknown = deny;
+        self
+    }
+
+    fn member(mut self, name: String, schema: Schema, required: bool) -> Self {
+        self.members.retain(|member| member.name != name);
+        self.members.push(Member {
+            name,
+            schema,
+            required,
+        });
+        self
+    }
+}
+
+/// A place where a value doesn't match a [`Schema`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// The path of the value at fault, a [JSON Pointer] as for [`Value::pointer`]. It's empty
+    /// for the value itself.
+    ///
+    /// [JSON Pointer]: https://www.rfc-editor.org/rfc/rfc6901
+    pub path: String,
+    /// What is wrong, such as ``missing member `user` ``.
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{} at {}", self.message, self.path)
+        }
+    }
+}
+
+impl std::error::Error for Violation {}
+
+fn into_result(violations: Vec<Violation>) -> std::result::Result<(), Vec<Violation>> {
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+fn invalid_type(path: String, found: ValueKind, expected: ValueKind) -> Violation {
+    Violation {
+        path,
+        message: format!("invalid type: {}, expected {}", found, expected),
+    }
+}
+
+fn push_member(path: &mut String, name: &str) {
+    path.push('/');
+    path.push_str(&crate::value::escape_pointer(name));
+}
+
+/// What [`Schema::of`] has seen so far.
+#[derive(Default)]
+struct Trace {
+    /// The structs being described, innermost last.
+    structs: Vec<&'static str>,
+    /// Set when a struct turned up inside itself, until the error this causes is caught by a
+    /// sequence or map, which then ends up empty.
+    recursive: bool,
+}
+
+/// A deserializer which records what is asked of it in `schema` and hands out placeholder
+/// values.
+struct Tracer<'a> {
+    schema: &'a mut Schema,
+    trace: &'a mut Trace,
+}
+
+macro_rules! trace_integers {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                *self.schema = Schema::Kind(ValueKind::INTEGER);
+                visitor.visit_u8(0)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for Tracer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        *self.schema = Schema::Any;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        *self.schema = Schema::Kind(ValueKind::BOOL);
+        visitor.visit_bool(false)
+    }
+
+    trace_integers!(
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+    );
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        // Integers convert to floating point numbers as well.
+        *self.schema = Schema::Kind(ValueKind::NUMBER);
+        visitor.visit_f64(0.0)
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        *self.schema = Schema::Kind(ValueKind::STRING);
+        visitor.visit_char(' ')
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        *self.schema = Schema::Kind(ValueKind::STRING);
+        visitor.visit_str("")
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        *self.schema = Schema::Kind(ValueKind::BASE64);
+        visitor.visit_bytes(&[])
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let mut inner = Schema::Any;
+        let ret = visitor.visit_some(Tracer {
+            schema: &mut inner,
+            trace: self.trace,
+        })?;
+        *self.schema = Schema::optional(inner);
+        Ok(ret)
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        *self.schema = Schema::Kind(ValueKind::NIL);
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let mut element = [Schema::Any];
+        let ret = visitor.visit_seq(Elements {
+            schemas: element.iter_mut(),
+            trace: self.trace,
+            catch: true,
+        })?;
+        let [element] = element;
+        *self.schema = Schema::array(element);
+        Ok(ret)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let mut schemas = vec![Schema::Any; len];
+        let ret = visitor.visit_seq(Elements {
+            schemas: schemas.iter_mut(),
+            trace: self.trace,
+            catch: false,
+        })?;
+        *self.schema = Schema::Tuple(schemas);
+        Ok(ret)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let mut member = Schema::Any;
+        let ret = visitor.visit_map(Entry {
+            schema: Some(&mut member),
+            trace: self.trace,
+        })?;
+        *self.schema = Schema::map(member);
+        Ok(ret)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.trace.structs.contains(&name) {
+            self.trace.recursive = true;
+            return Err(de::Error::custom(format!(
+                "can't describe recursive type {}",
+                name
+            )));
+        }
+
+        self.trace.structs.push(name);
+        let mut schemas = vec![Schema::Any; fields.len()];
+        let ret = visitor.visit_map(Fields {
+            fields: fields.iter().zip(schemas.iter_mut()),
+            pending: None,
+            trace: &mut *self.trace,
+        });
+        self.trace.structs.pop();
+        let ret = ret?;
+
+        let members = fields
+            .iter()
+            .zip(schemas)
+            .map(|(name, schema)| Member {
+                name: name.to_string(),
+                required: !schema.is_optional(),
+                schema,
+            })
+            .collect();
+        *self.schema = Schema::Struct(StructSchema {
+            members,
+            deny_unknown: false,
+        });
+        Ok(ret)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        *self.schema = Schema::Any;
+        let variant: &str = variants.first().copied().unwrap_or_default();
+        visitor.visit_enum(variant.into_deserializer())
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_str("")
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+}
+
+/// Hands out one element per schema. A sequence of any length gets a single element, which is
+/// left out if it refers to a struct being described.
+struct Elements<'a> {
+    schemas: std::slice::IterMut<'a, Schema>,
+    trace: &'a mut Trace,
+    catch: bool,
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for Elements<'a> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let schema = match self.schemas.next() {
+            Some(schema) => schema,
+            None => return Ok(None),
+        };
+
+        let ret = seed.deserialize(Tracer {
+            schema: &mut *schema,
+            trace: &mut *self.trace,
+        });
+        match ret {
+            Ok(value) => Ok(Some(value)),
+            Err(_) if self.catch && self.trace.recursive => {
+                self.trace.recursive = false;
+                *schema = Schema::Any;
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Hands out a single entry for a map, which is left out if it refers to a struct being
+/// described.
+struct Entry<'a> {
+    schema: Option<&'a mut Schema>,
+    trace: &'a mut Trace,
+}
+
+impl<'de, 'a> de::MapAccess<'de> for Entry<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.schema.is_none() {
+            return Ok(None);
+        }
+        seed.deserialize(Tracer {
+            schema: &mut Schema::Any,
+            trace: &mut *self.trace,
+        })
+        .map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let mut unused = Schema::Any;
+        let schema = self.schema.take().unwrap_or(&mut unused);
+        seed.deserialize(Tracer {
+            schema,
+            trace: &mut *self.trace,
+        })
+    }
+
+    fn next_entry_seed<K, V>(&mut self, kseed: K, vseed: V) -> Result<Option<(K::Value, V::Value)>>
+    where
+        K: DeserializeSeed<'de>,
+        V: DeserializeSeed<'de>,
+    {
+        let schema = match self.schema.take() {
+            Some(schema) => schema,
+            None => return Ok(None),
+        };
+
+        // The value goes first, so a recursive one can be left out before the key is handed out.
+        let value = vseed.deserialize(Tracer {
+            schema: &mut *schema,
+            trace: &mut *self.trace,
+        });
+        let value = match value {
+            Ok(value) => value,
+            Err(_) if self.trace.recursive => {
+                self.trace.recursive = false;
+                *schema = Schema::Any;
+                return Ok(None);
+            }
+            Err(err) => return Err(err),
+        };
+        let key = kseed.deserialize(Tracer {
+            schema: &mut Schema::Any,
+            trace: &mut *self.trace,
+        })?;
+        Ok(Some((key, value)))
+    }
+}
+
+/// Hands out the fields of a struct.
+struct Fields<'a, I> {
+    fields: I,
+    pending: Option<&'a mut Schema>,
+    trace: &'a mut Trace,
+}
+
+impl<'de, 'a, I> de::MapAccess<'de> for Fields<'a, I>
+where
+    I: Iterator<Item = (&'static &'static str, &'a mut Schema)>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let (name, schema) = match self.fields.next() {
+            Some(field) => field,
+            None => return Ok(None),
+        };
+        self.pending = Some(schema);
+        let name: &str = name;
+        seed.deserialize(name.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let mut unused = Schema::Any;
+        let schema = self.pending.take().unwrap_or(&mut unused);
+        seed.deserialize(Tracer {
+            schema,
+            trace: &mut *self.trace,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Node {
+        id: u32,
+        weight: f64,
+        label: Option<String>,
+        data: serde_bytes::ByteBuf,
+        children: Vec<Node>,
+        tags: HashMap<String, Value>,
+    }
+
+    #[test]
+    fn derive() {
+        let schema = Schema::of::<(i32, Vec<Node>)>().unwrap();
+        let node = StructSchema::new()
+            .required("id", ValueKind::INTEGER)
+            .required("weight", ValueKind::NUMBER)
+            .optional("label", Schema::optional(ValueKind::STRING))
+            .required("data", ValueKind::BASE64)
+            .required("children", Schema::array(Schema::Any))
+            .required("tags", Schema::map(Schema::Any));
+        assert_eq!(
+            schema,
+            Schema::Tuple(vec![Schema::Kind(ValueKind::INTEGER), Schema::array(node)])
+        );
+
+        assert_eq!(Schema::of::<()>().unwrap(), Schema::Kind(ValueKind::NIL));
+        assert_eq!(
+            Schema::of::<HashMap<u32, Vec<bool>>>().unwrap(),
+            Schema::map(Schema::array(ValueKind::BOOL))
+        );
+    }
+
+    #[test]
+    fn validate() {
+        let schema = Schema::array(
+            StructSchema::new()
+                .required("id", ValueKind::INT)
+                .optional("name", ValueKind::STRING)
+                .deny_unknown(true),
+        );
+
+        let mut good = Value::Nil;
+        good.entry("id").or_insert(1);
+        let mut bad = Value::Nil;
+        bad.entry("name").or_insert(2);
+        bad.entry("a/b").or_insert(true);
+        assert!(schema
+            .validate(&Value::Array(vec![good.clone(), good.clone()]))
+            .is_ok());
+
+        let violations = schema.validate(&Value::Array(vec![good, bad])).unwrap_err();
+        let messages: Vec<String> = violations.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "missing member `id` at /1",
+                "invalid type: int, expected string at /1/name",
+                "unknown member `a/b` at /1",
+            ]
+        );
+
+        let violations = schema.validate(&Value::Int(1)).unwrap_err();
+        assert_eq!(
+            violations[0].to_string(),
+            "invalid type: int, expected array"
+        );
+
+        let union = Schema::Union(vec![
+            Schema::array(ValueKind::INT),
+            Schema::Kind(ValueKind::STRING),
+        ]);
+        assert!(union.validate(&Value::from("a")).is_ok());
+        let violations = union
+            .validate(&Value::Array(vec![Value::from("a")]))
+            .unwrap_err();
+        assert_eq!(violations[0].path, "/0");
+        let violations = union.validate(&Value::Nil).unwrap_err();
+        assert_eq!(
+            violations[0].message,
+            "invalid type: nil, expected string|array"
+        );
+    }
+
+    #[test]
+    fn validate_params() {
+        let schema = Schema::of::<(String, Option<i32>)>().unwrap();
+        assert!(schema
+            .validate_params(&[Value::from("a"), Value::Nil])
+            .is_ok());
+
+        let violations = schema.validate_params(&[Value::from("a")]).unwrap_err();
+        assert_eq!(
+            violations[0].to_string(),
+            "invalid length 1, expected 2 elements"
+        );
+        let violations = schema
+            .validate_params(&[Value::from("a"), Value::from("b")])
+            .unwrap_err();
+        assert_eq!(violations[0].path, "/1");
+
+        assert!(Schema::Any.validate_params(&[Value::Nil]).is_ok());
+        assert!(Schema::array(ValueKind::INT)
+            .validate_params(&[Value::Nil])
+            .is_err());
+        assert!(Schema::Kind(ValueKind::STRUCT)
+            .validate_params(&[])
+            .is_err());
+    }
+}
@@ -0,0 +1,1344 @@
+//! A method router for building XML-RPC servers on top of any transport.
+//!
+//! ```
+//! use serde_xmlrpc::server::Dispatcher;
+//! use serde_xmlrpc::{Fault, MethodResponse};
+//!
+//! let mut dispatcher = Dispatcher::new();
+//! dispatcher
+//!     .register("add", |a: i32, b: i32| Ok::<_, Fault>(a + b))
+//!     .register("div", |a: i32, b: i32| {
+//!         a.checked_div(b).ok_or_else(|| Fault::new(1, "division by zero"))
+//!     });
+//!
+//! let request = serde_xmlrpc::request_to_string("add", vec![1.into(), 2.into()]).unwrap();
+//! let response = dispatcher.handle(&request).unwrap();
+//! assert_eq!(serde_xmlrpc::response_from_str::<i32>(&response).unwrap(), 3);
+//!
+//! let request = serde_xmlrpc::request_to_string("div", vec![1.into(), 0.into()]).unwrap();
+//! let response = dispatcher.handle(&request).unwrap();
+//! assert!(matches!(
+//!     response.parse::<MethodResponse>().unwrap(),
+//!     MethodResponse::Fault(Fault { fault_code: 1, .. })
+//! ));
+//! ```
+//!
+//! With the `tracing` feature, the dispatchers record each call in a `call` span under the
+//! `serde_xmlrpc::server` target, with the method name, param count, body sizes, duration and
+//! outcome. Calls refused by the [`Quota`] of their method also get a `quota exceeded` event.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+#[cfg(feature = "async")]
+use std::future::Future;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+#[cfg(feature = "async")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "async")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "async")]
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use quick_xml::name::QName;
+use quick_xml::Reader;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::schema::Schema;
+use crate::trace::{self, CallSpan};
+use crate::util::{ParseState, ReaderExt};
+use crate::{
+    Capabilities, Capability, Error, Fault, MethodCall, MethodResponse, ParseOptions, Result,
+    Value, Values,
+};
+
+type BoxedHandler = Box<dyn Fn(Values) -> Result<Value> + Send + Sync>;
+
+/// Routes calls to handlers registered by method name.
+///
+/// Failures are turned into faults rather than errors, so every call gets a response. The codes
+/// are the standard ones from [`fault::codes`](crate::fault::codes):
+///
+/// * A body which isn't a valid `<methodCall>` gets the fault picked by
+///   [`Fault::from_request_error`], usually `-32700`.
+/// * An unknown method gets a `-32601` fault.
+/// * Params which don't match the handler's arguments, or the method's [`Schema`], get a
+///   `-32602` fault.
+/// * A handler returning an error gets that error as its fault.
+/// * A result which can't be written gets a `-32603` fault.
+/// * A call which breaks the [`Quota`] of its method gets a `-32600` or `-32400` fault, see
+///   [`quota`](Dispatcher::quota).
+///
+/// The `system.*` introspection methods are answered once an [`Introspection`] is attached, and
+/// `system.getCapabilities` once [`Capabilities`] are. Handlers registered with
+/// [`register_typed`](Dispatcher::register_typed) don't need their signatures listed in it.
+#[derive(Default)]
+pub struct Dispatcher {
+    methods: BTreeMap<String, BoxedHandler>,
+    schemas: BTreeMap<String, Schema>,
+    quotas: BTreeMap<String, Quota>,
+    system: SystemMethods,
+}
+
+impl Dispatcher {
+    pub fn new() -> Self {
+        Dispatcher::default()
+    }
+
+    /// Registers a handler for `name`, replacing any previous handler. See [`Handler`] for what
+    /// can be used as one.
+    pub fn register<Args, H>(&mut self, name: impl Into<String>, handler: H) -> &mut Self
+    where
+        H: Handler<Args>,
+    {
+        let name = name.into();
+        self.system.signatures.remove(&name);
+        self.methods
+            .insert(name, Box::new(move |params| handler.call(params)));
+        self
+    }
+
+    /// Registers a handler like [`register`](Dispatcher::register), and derives its signature
+    /// for `system.methodSignature` from its argument and return types, see [`Signature`].
+    /// Signatures added to the [`Introspection`] take precedence.
+    ///
+    /// ```
+    /// use serde_xmlrpc::server::{Dispatcher, Introspection};
+    /// use serde_xmlrpc::{Fault, MethodCall, Value};
+    ///
+    /// let mut dispatcher = Dispatcher::new();
+    /// dispatcher
+    ///     .register_typed("repeat", |text: String, times: u32| {
+    ///         Ok::<_, Fault>(vec![text; times as usize])
+    ///     })
+    ///     .introspection(Introspection::new());
+    ///
+    /// let call = MethodCall {
+    ///     method_name: "system.methodSignature".into(),
+    ///     params: vec!["repeat".into()].into(),
+    /// };
+    /// let (signatures,): (Vec<Vec<String>>,) =
+    ///     dispatcher.call(call).into_result().unwrap().deserialize().unwrap();
+    /// assert_eq!(signatures, [["array", "string", "int"]]);
+    /// ```
+    pub fn register_typed<Args, H>(&mut self, name: impl Into<String>, handler: H) -> &mut Self
+    where
+        H: Handler<Args> + Signature<Args>,
+    {
+        let name = name.into();
+        self.register(name.clone(), handler);
+        if let Some(signature) = H::signature() {
+            self.system.signatures.insert(name, signature);
+        }
+        self
+    }
+
+    /// Checks the params of calls to `name` against `schema` before they reach the handler, as
+    /// with [`Schema::validate_params`], replacing any previous schema. Calls which don't match
+    /// get a `-32602` fault listing every violation, rather than the first error converting them
+    /// runs into. [`Schema::of`] the handler's argument tuple describes what it accepts.
+    pub fn schema(&mut self, name: impl Into<String>, schema: Schema) -> &mut Self {
+        self.schemas.insert(name.into(), schema);
+        self
+    }
+
+    /// Limits the size and parse time of requests calling `name` and how long its handler may
+    /// run, replacing any previous quota. See [`Quota`] for the faults calls breaking it get.
+    ///
+    /// The size and parse time are only checked by [`handle`](Dispatcher::handle), since
+    /// [`call`](Dispatcher::call) is given a call which was already parsed. A handler can't be
+    /// interrupted, so one which runs past the deadline has its response replaced by the fault.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use serde_xmlrpc::server::{Dispatcher, Quota};
+    /// use serde_xmlrpc::{Fault, MethodResponse};
+    ///
+    /// let mut dispatcher = Dispatcher::new();
+    /// dispatcher
+    ///     .register("upload", |data: String| Ok::<_, Fault>(data.len()))
+    ///     .quota(
+    ///         "upload",
+    ///         Quota::new()
+    ///             .max_request_len(Some(1024))
+    ///             .deadline(Some(Duration::from_secs(5))),
+    ///     );
+    ///
+    /// let request = serde_xmlrpc::request_to_string("upload", vec!["x".repeat(2048).into()]);
+    /// let response = dispatcher.handle(&request.unwrap()).unwrap();
+    /// assert!(matches!(
+    ///     response.parse::<MethodResponse>().unwrap(),
+    ///     MethodResponse::Fault(Fault { fault_code: -32600, .. })
+    /// ));
+    /// ```
+    pub fn quota(&mut self, name: impl Into<String>, quota: Quota) -> &mut Self {
+        self.quotas.insert(name.into(), quota);
+        self
+    }
+
+    /// Answers the `system.*` introspection methods with the signatures and help in
+    /// `introspection`. Handlers registered under the same names take precedence.
+    pub fn introspection(&mut self, introspection: Introspection) -> &mut Self {
+        self.system.introspection = Some(introspection);
+        self
+    }
+
+    /// Answers `system.getCapabilities` with `capabilities`, usually [`Capabilities::new`]. The
+    /// `introspection` capability is added if an [`Introspection`] is attached.
+    pub fn capabilities(&mut self, capabilities: Capabilities) -> &mut Self {
+        self.system.capabilities = Some(capabilities);
+        self
+    }
+
+    /// Returns the names of the registered methods, in order.
+    pub fn methods(&self) -> impl Iterator<Item = &str> {
+        self.methods.keys().map(String::as_str)
+    }
+
+    /// Calls the handler for `call` and returns its response.
+    pub fn call(&self, call: MethodCall) -> MethodResponse {
+        let span = CallSpan::server(&call.method_name, call.params.len());
+        self.call_in(call, &span)
+    }
+
+    fn call_in(&self, call: MethodCall, span: &CallSpan) -> MethodResponse {
+        let response = match self.methods.get(&call.method_name) {
+            Some(handler) => match check_params(&self.schemas, &call) {
+                Ok(()) => {
+                    let start = Instant::now();
+                    let result = handler(call.params);
+                    let deadline = self
+                        .quotas
+                        .get(&call.method_name)
+                        .and_then(|quota| quota.deadline);
+                    match deadline {
+                        Some(deadline) if start.elapsed() > deadline => {
+                            into_response(Err(deadline_exceeded(&call.method_name, deadline)))
+                        }
+                        _ => into_response(result),
+                    }
+                }
+                Err(response) => response,
+            },
+            None => self
+                .system
+                .call(self.methods(), &call)
+                .unwrap_or_else(|| method_not_found(&call.method_name)),
+        };
+
+        span.finish_response(&response);
+        response
+    }
+
+    /// Handles a request body and returns the response body. This only fails if the response
+    /// can't be written.
+    pub fn handle(&self, body: &str) -> Result<String> {
+        let call = match parse_call(body, &self.quotas) {
+            Ok(call) => call,
+            Err(response) => return crate::Document::from(response).to_xml(),
+        };
+
+        let span = CallSpan::server(&call.method_name, call.params.len());
+        span.request(body.len());
+        let response = crate::Document::from(self.call_in(call, &span)).to_xml()?;
+        span.response(response.len());
+        Ok(response)
+    }
+}
+
+/// Limits on the calls of one method, set with [`Dispatcher::quota`] or
+/// [`AsyncDispatcher::quota`]. There are no limits by default.
+///
+/// Calls breaking a limit get a fault with one of the standard codes from
+/// [`fault::codes`](crate::fault::codes), and a `quota exceeded` event with the `tracing`
+/// feature:
+///
+/// * A request longer than [`max_request_len`](Quota::max_request_len) gets a `-32600` fault
+///   without being parsed, as its method name is read from the start of the body.
+/// * A request which took longer than [`max_parse_time`](Quota::max_parse_time) to parse gets a
+///   `-32400` fault instead of reaching the handler.
+/// * A handler which runs past the [`deadline`](Quota::deadline) gets a `-32400` fault.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Quota {
+    pub(crate) max_request_len: Option<usize>,
+    pub(crate) max_parse_time: Option<Duration>,
+    pub(crate) deadline: Option<Duration>,
+}
+
+impl Quota {
+    /// A quota without limits.
+    pub const fn new() -> Self {
+        Quota {
+            max_request_len: None,
+            max_parse_time: None,
+            deadline: None,
+        }
+    }
+
+    /// Sets the most bytes a request body may have.
+    pub const fn max_request_len(mut self, limit: Option<usize>) -> Self {
+        self.max_request_len = limit;
+        self
+    }
+
+    /// Sets the longest parsing a request body may take. Parsing isn't interrupted, so this is
+    /// checked once it's done.
+    pub const fn max_parse_time(mut self, limit: Option<Duration>) -> Self {
+        self.max_parse_time = limit;
+        self
+    }
+
+    /// Sets how long the handler may run.
+    pub const fn deadline(mut self, deadline: Option<Duration>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+}
+
+/// Parses a request body, enforcing the request size and parse time limits of the quota of its
+/// method. Requests which fail get the fault to answer them with.
+fn parse_call(
+    body: &str,
+    quotas: &BTreeMap<String, Quota>,
+) -> std::result::Result<MethodCall, MethodResponse> {
+    // Only requests for methods with a quota need their method name read ahead.
+    let name = if quotas.is_empty() {
+        None
+    } else {
+        method_name(body)
+    };
+    let (name, quota) = match name.and_then(|name| Some((quotas.get(name.as_ref())?, name))) {
+        Some((quota, name)) => (name, *quota),
+        None => (Cow::Borrowed(""), Quota::new()),
+    };
+
+    if let Some(max) = quota.max_request_len {
+        if body.len() > max {
+            trace::quota_exceeded(&name, "max_request_len");
+            return Err(MethodResponse::Fault(Fault::invalid_request(format!(
+                "the request is longer than the {} bytes allowed for {}",
+                max, name
+            ))));
+        }
+    }
+
+    let start = Instant::now();
+    let call = body.parse::<MethodCall>().map_err(parse_error)?;
+    if let Some(max) = quota.max_parse_time {
+        if start.elapsed() > max {
+            trace::quota_exceeded(&name, "max_parse_time");
+            return Err(MethodResponse::Fault(Fault::system_error(format!(
+                "the request took longer than the {:?} allowed for {} to parse",
+                max, name
+            ))));
+        }
+    }
+    Ok(call)
+}
+
+/// Reads the method name from the start of a request body, without parsing the rest.
+fn method_name(body: &str) -> Option<Cow<'_, str>> {
+    let mut reader = Reader::from_str(body);
+    reader.expand_empty_elements(true);
+    reader.trim_text(true);
+    let options = ParseOptions::default();
+    let state = ParseState::new(&options);
+
+    state.expect_root(&mut reader, QName(b"methodCall")).ok()?;
+    reader.expect_tag(QName(b"methodName")).ok()?;
+    reader
+        .read_string(Cow::Borrowed(""), QName(b"methodName"))
+        .ok()
+}
+
+/// The error of a handler for `method` which ran past its `deadline`.
+fn deadline_exceeded(method: &str, deadline: Duration) -> Error {
+    trace::quota_exceeded(method, "deadline");
+    Error::Fault(Fault::system_error(format!(
+        "{} didn't finish within its deadline of {:?}",
+        method, deadline
+    )))
+}
+
+/// The future of a handler whose method has a deadline, which fails once it passes. Without a
+/// runtime to ask for a timer, a thread wakes the call at the deadline, and is stopped when the
+/// future is dropped.
+#[cfg(feature = "async")]
+struct Timeout {
+    future: HandlerFuture,
+    method: String,
+    deadline: Duration,
+    expires: Instant,
+    timer: Option<(std::thread::Thread, Arc<Timer>)>,
+}
+
+#[cfg(feature = "async")]
+struct Timer {
+    waker: Mutex<Waker>,
+    done: AtomicBool,
+}
+
+#[cfg(feature = "async")]
+impl Timeout {
+    fn wrap(future: HandlerFuture, method: &str, deadline: Duration) -> HandlerFuture {
+        Box::pin(Timeout {
+            future,
+            method: method.into(),
+            deadline,
+            expires: Instant::now() + deadline,
+            timer: None,
+        })
+    }
+
+    /// Makes sure the waker of `cx` is woken at the deadline.
+    fn wake_at_deadline(&mut self, cx: &Context<'_>) {
+        if let Some((_, timer)) = &self.timer {
+            let mut waker = timer.waker.lock().unwrap_or_else(|err| err.into_inner());
+            waker.clone_from(cx.waker());
+            return;
+        }
+
+        let timer = Arc::new(Timer {
+            waker: Mutex::new(cx.waker().clone()),
+            done: AtomicBool::new(false),
+        });
+        let expires = self.expires;
+        let shared = timer.clone();
+        let thread = std::thread::spawn(move || loop {
+            if shared.done.load(Ordering::Acquire) {
+                return;
+            }
+            match expires.checked_duration_since(Instant::now()) {
+                Some(remaining) if !remaining.is_zero() => std::thread::park_timeout(remaining),
+                _ => {
+                    let waker = shared.waker.lock().unwrap_or_else(|err| err.into_inner());
+                    waker.wake_by_ref();
+                    return;
+                }
+            }
+        });
+        self.timer = Some((thread.thread().clone(), timer));
+    }
+}
+
+#[cfg(feature = "async")]
+impl Future for Timeout {
+    type Output = Result<Value>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(result) = self.future.as_mut().poll(cx) {
+            return Poll::Ready(result);
+        }
+        if Instant::now() >= self.expires {
+            return Poll::Ready(Err(deadline_exceeded(&self.method, self.deadline)));
+        }
+
+        self.wake_at_deadline(cx);
+        Poll::Pending
+    }
+}
+
+#[cfg(feature = "async")]
+impl Drop for Timeout {
+    fn drop(&mut self) {
+        if let Some((thread, timer)) = &self.timer {
+            timer.done.store(true, Ordering::Release);
+            thread.unpark();
+        }
+    }
+}
+
+fn method_not_found(name: &str) -> MethodResponse {
+    MethodResponse::Fault(Fault::method_not_found(name))
+}
+
+/// Checks the params of `call` against the schema of its method, if it has one.
+fn check_params(
+    schemas: &BTreeMap<String, Schema>,
+    call: &MethodCall,
+) -> std::result::Result<(), MethodResponse> {
+    let schema = match schemas.get(&call.method_name) {
+        Some(schema) => schema,
+        None => return Ok(()),
+    };
+
+    schema.validate_params(&call.params).map_err(|violations| {
+        let violations: Vec<String> = violations.iter().map(ToString::to_string).collect();
+        MethodResponse::Fault(Fault::invalid_params(violations.join("; ")))
+    })
+}
+
+pub(crate) fn parse_error(err: Error) -> MethodResponse {
+    MethodResponse::Fault(Fault::from_request_error(&err))
+}
+
+/// Turns the result of a handler into a response, picking a fault code for errors which didn't
+/// come from the handler itself.
+fn into_response(result: Result<Value>) -> MethodResponse {
+    match result {
+        Ok(value) => MethodResponse::Success(vec![value].into()),
+        Err(Error::Fault(fault)) => MethodResponse::Fault(fault),
+        Err(err @ Error::DecodingError(_)) => {
+            MethodResponse::Fault(Fault::invalid_params(err.to_string()))
+        }
+        Err(err @ Error::EncodingError(_)) | Err(err @ Error::TransportError(_)) => {
+            MethodResponse::Fault(Fault::internal_error(err.to_string()))
+        }
+    }
+}
+
+// The names of the built-in `system.*` methods.
+const LIST_METHODS: &str = "system.listMethods";
+const METHOD_SIGNATURE: &str = "system.methodSignature";
+const METHOD_HELP: &str = "system.methodHelp";
+const GET_CAPABILITIES: &str = "system.getCapabilities";
+
+/// Signatures and help for the methods of a dispatcher, used to answer the standard
+/// introspection methods once attached with [`Dispatcher::introspection`]:
+///
+/// * `system.listMethods` returns the names of all methods, including these ones.
+/// * `system.methodSignature` returns the signatures of a method, or `"undef"` if it has none.
+/// * `system.methodHelp` returns the help of a method, or an empty string if it has none.
+///
+/// Asking about a method which isn't registered gets a `-32602` fault.
+///
+/// ```
+/// use serde_xmlrpc::server::{Dispatcher, Introspection};
+/// use serde_xmlrpc::{Fault, MethodCall, MethodResponse, Value};
+///
+/// let mut dispatcher = Dispatcher::new();
+/// dispatcher
+///     .register("add", |a: i32, b: i32| Ok::<_, Fault>(a + b))
+///     .introspection(
+///         Introspection::new()
+///             .signature("add", &["int", "int", "int"])
+///             .help("add", "Adds two numbers."),
+///     );
+///
+/// let call = MethodCall {
+///     method_name: "system.methodHelp".into(),
+///     params: vec!["add".into()].into(),
+/// };
+/// assert_eq!(
+///     dispatcher.call(call),
+///     MethodResponse::Success(vec![Value::from("Adds two numbers.")].into())
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct Introspection {
+    methods: BTreeMap<String, MethodInfo>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct MethodInfo {
+    signatures: Vec<Vec<String>>,
+    help: String,
+}
+
+impl Introspection {
+    /// Creates an `Introspection` which only describes the introspection methods themselves.
+    pub fn new() -> Self {
+        Introspection {
+            methods: BTreeMap::new(),
+        }
+        .signature(LIST_METHODS, &["array"])
+        .help(
+            LIST_METHODS,
+            "Returns the names of the methods this server offers.",
+        )
+        .signature(METHOD_SIGNATURE, &["array", "string"])
+        .help(
+            METHOD_SIGNATURE,
+            "Returns the signatures of a method, each one its return type followed by the types \
+             of its params, or \"undef\" if they aren't known.",
+        )
+        .signature(METHOD_HELP, &["string", "string"])
+        .help(METHOD_HELP, "Returns the help of a method.")
+        .signature(GET_CAPABILITIES, &["struct"])
+        .help(
+            GET_CAPABILITIES,
+            "Returns the extensions this server supports.",
+        )
+    }
+
+    /// Adds a signature for `method`: its return type followed by the types of its params, such
+    /// as `&["int", "int", "int"]`. Methods taking different params can have several.
+    pub fn signature(mut self, method: impl Into<String>, signature: &[&str]) -> Self {
+        self.methods
+            .entry(method.into())
+            .or_default()
+            .signatures
+            .push(signature.iter().map(|ty| ty.to_string()).collect());
+        self
+    }
+
+    /// Sets the help for `method`.
+    pub fn help(mut self, method: impl Into<String>, help: impl Into<String>) -> Self {
+        self.methods.entry(method.into()).or_default().help = help.into();
+        self
+    }
+}
+
+impl Default for Introspection {
+    fn default() -> Self {
+        Introspection::new()
+    }
+}
+
+/// The built-in `system.*` methods which have been enabled on a dispatcher.
+#[derive(Default)]
+struct SystemMethods {
+    introspection: Option<Introspection>,
+    capabilities: Option<Capabilities>,
+    /// The signatures derived from the types of handlers.
+    signatures: BTreeMap<String, Vec<String>>,
+}
+
+impl SystemMethods {
+    /// Answers `call` if it's one of the enabled methods, given the names of the registered
+    /// `methods`.
+    fn call<'a>(
+        &self,
+        methods: impl Iterator<Item = &'a str>,
+        call: &MethodCall,
+    ) -> Option<MethodResponse> {
+        let mut names: Vec<&str> = methods.collect();
+        if self.introspection.is_some() {
+            names.extend([LIST_METHODS, METHOD_SIGNATURE, METHOD_HELP].iter());
+        }
+        if self.capabilities.is_some() {
+            names.push(GET_CAPABILITIES);
+        }
+        names.sort_unstable();
+        names.dedup();
+
+        let result = match (call.method_name.as_str(), &self.introspection) {
+            (LIST_METHODS, Some(_)) => {
+                check_arity(&call.params, "no params", 0).and_then(|_| crate::to_value(&names))
+            }
+            (METHOD_SIGNATURE, Some(introspection)) => {
+                describe(introspection, &self.signatures, &names, call).and_then(|info| {
+                    if info.signatures.is_empty() {
+                        Ok(Value::from("undef"))
+                    } else {
+                        crate::to_value(&info.signatures)
+                    }
+                })
+            }
+            (METHOD_HELP, Some(introspection)) => {
+                describe(introspection, &self.signatures, &names, call)
+                    .map(|info| Value::String(info.help))
+            }
+            (GET_CAPABILITIES, _) => {
+                let mut capabilities = self.capabilities.clone()?;
+                if self.introspection.is_some() && capabilities.introspection.is_none() {
+                    capabilities.introspection = Some(Capability::introspection());
+                }
+                check_arity(&call.params, "no params", 0)
+                    .and_then(|_| crate::to_value(&capabilities))
+            }
+            _ => return None,
+        };
+
+        Some(into_response(result))
+    }
+}
+
+/// Looks up the method named by the only param of `call`, which must be one of `names`. Derived
+/// `signatures` are used if the introspection has none for it.
+fn describe(
+    introspection: &Introspection,
+    signatures: &BTreeMap<String, Vec<String>>,
+    names: &[&str],
+    call: &MethodCall,
+) -> Result<MethodInfo> {
+    check_arity(&call.params, "1 param", 1)?;
+    let (name,) = call.params.clone().deserialize::<(String,)>()?;
+    if names.binary_search(&name.as_str()).is_err() {
+        return Err(Fault::invalid_params(format!("method {} not found", name)).into());
+    }
+
+    let mut info = introspection
+        .methods
+        .get(&name)
+        .cloned()
+        .unwrap_or_default();
+    if info.signatures.is_empty() {
+        info.signatures.extend(signatures.get(&name).cloned());
+    }
+    Ok(info)
+}
+
+impl fmt::Debug for Dispatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dispatcher")
+            .field("methods", &self.methods.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// A function which can handle calls.
+///
+/// This is implemented for functions and closures taking up to 8 arguments which can be
+/// deserialized, one per param, and returning a `Result` whose value can be serialized and whose
+/// error converts into a [`Fault`], such as an [`XmlRpcResult`](crate::XmlRpcResult). `Args` is
+/// the tuple of argument types, which only exists to tell the implementations apart.
+pub trait Handler<Args>: Send + Sync + 'static {
+    /// Calls the handler with the params of a call, returning its result.
+    fn call(&self, params: Values) -> Result<Value>;
+}
+
+fn check_arity(params: &Values, expected: &'static str, len: usize) -> Result<()> {
+    if params.len() != len {
+        return Err(serde::de::Error::invalid_length(params.len(), &expected));
+    }
+
+    Ok(())
+}
+
+impl<F, R, E> Handler<()> for F
+where
+    F: Fn() -> std::result::Result<R, E> + Send + Sync + 'static,
+    R: Serialize,
+    E: Into<Fault>,
+{
+    fn call(&self, params: Values) -> Result<Value> {
+        check_arity(&params, "no params", 0)?;
+        let ret = self().map_err(|err| Error::Fault(err.into()))?;
+        crate::to_value(ret)
+    }
+}
+
+macro_rules! impl_handler {
+    ($len:expr, $expected:expr, $($arg:ident),+) => {
+        impl<F, R, E, $($arg),+> Handler<($($arg,)+)> for F
+        where
+            F: Fn($($arg),+) -> std::result::Result<R, E> + Send + Sync + 'static,
+            R: Serialize,
+            E: Into<Fault>,
+            $($arg: DeserializeOwned,)+
+        {
+            #[allow(non_snake_case)]
+            fn call(&self, params: Values) -> Result<Value> {
+                check_arity(&params, $expected, $len)?;
+                let ($($arg,)+) = params.deserialize::<($($arg,)+)>()?;
+                let ret = self($($arg),+).map_err(|err| Error::Fault(err.into()))?;
+                crate::to_value(ret)
+            }
+        }
+    };
+}
+
+impl_handler!(1, "1 param", A);
+impl_handler!(2, "2 params", A, B);
+impl_handler!(3, "3 params", A, B, C);
+impl_handler!(4, "4 params", A, B, C, D);
+impl_handler!(5, "5 params", A, B, C, D, G);
+impl_handler!(6, "6 params", A, B, C, D, G, H);
+impl_handler!(7, "7 params", A, B, C, D, G, H, I);
+impl_handler!(8, "8 params", A, B, C, D, G, H, I, J);
+
+/// A [`Handler`] whose signature can be derived from its types, for
+/// [`Dispatcher::register_typed`].
+///
+/// This is implemented for the same functions and closures as [`Handler`], as long as the value
+/// they return can be deserialized as well, which is how its type is found. The types are
+/// derived with [`Schema::of`] and named with [`Schema::type_name`], so an `Option` argument is
+/// described by its inner type.
+pub trait Signature<Args> {
+    /// Returns the return type followed by the argument types, such as `["int", "string"]`, or
+    /// `None` if one of them can have several XML-RPC types, such as an enum or a [`Value`].
+    fn signature() -> Option<Vec<String>>;
+}
+
+/// Names the types of a signature, or returns `None` if one of them can't be named.
+fn signature_of(schemas: &[Result<Schema>]) -> Option<Vec<String>> {
+    schemas
+        .iter()
+        .map(|schema| {
+            let name = schema.as_ref().ok()?.type_name()?;
+            Some(name.to_string())
+        })
+        .collect()
+}
+
+/// An [`AsyncHandler`] whose signature can be derived from its types, for
+/// [`AsyncDispatcher::register_typed`]. See [`Signature`].
+#[cfg(feature = "async")]
+pub trait AsyncSignature<Args> {
+    /// Returns the type of the handler's output followed by the argument types, or `None` if
+    /// one of them can have several XML-RPC types.
+    fn signature() -> Option<Vec<String>>;
+}
+
+macro_rules! impl_signature {
+    ($($arg:ident),*) => {
+        impl<F, R, E, $($arg),*> Signature<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> std::result::Result<R, E>,
+            R: DeserializeOwned,
+            $($arg: DeserializeOwned,)*
+        {
+            fn signature() -> Option<Vec<String>> {
+                signature_of(&[Schema::of::<R>(), $(Schema::of::<$arg>()),*])
+            }
+        }
+
+        #[cfg(feature = "async")]
+        impl<F, Fut, R, E, $($arg),*> AsyncSignature<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> Fut,
+            Fut: Future<Output = std::result::Result<R, E>>,
+            R: DeserializeOwned,
+            $($arg: DeserializeOwned,)*
+        {
+            fn signature() -> Option<Vec<String>> {
+                signature_of(&[Schema::of::<R>(), $(Schema::of::<$arg>()),*])
+            }
+        }
+    };
+}
+
+impl_signature!();
+impl_signature!(A);
+impl_signature!(A, B);
+impl_signature!(A, B, C);
+impl_signature!(A, B, C, D);
+impl_signature!(A, B, C, D, G);
+impl_signature!(A, B, C, D, G, H);
+impl_signature!(A, B, C, D, G, H, I);
+impl_signature!(A, B, C, D, G, H, I, J);
+
+/// The future returned by an [`AsyncHandler`].
+#[cfg(feature = "async")]
+pub type HandlerFuture = Pin<Box<dyn Future<Output = Result<Value>> + Send>>;
+
+#[cfg(feature = "async")]
+type BoxedAsyncHandler = Box<dyn Fn(Values) -> HandlerFuture + Send + Sync>;
+
+/// Routes calls to handlers which return futures, for servers running on an async runtime.
+/// Otherwise it works like [`Dispatcher`], including how failures are turned into faults.
+///
+/// ```
+/// use serde_xmlrpc::server::AsyncDispatcher;
+/// use serde_xmlrpc::Fault;
+///
+/// let mut dispatcher = AsyncDispatcher::new();
+/// dispatcher.register("add", |a: i32, b: i32| async move { Ok::<_, Fault>(a + b) });
+///
+/// // Called from an HTTP handler with the request body.
+/// async fn serve(dispatcher: &AsyncDispatcher, body: &str) -> String {
+///     dispatcher.handle(body).await.unwrap()
+/// }
+/// ```
+#[cfg(feature = "async")]
+#[derive(Default)]
+pub struct AsyncDispatcher {
+    methods: BTreeMap<String, BoxedAsyncHandler>,
+    schemas: BTreeMap<String, Schema>,
+    quotas: BTreeMap<String, Quota>,
+    system: SystemMethods,
+}
+
+#[cfg(feature = "async")]
+impl AsyncDispatcher {
+    pub fn new() -> Self {
+        AsyncDispatcher::default()
+    }
+
+    /// Registers a handler for `name`, replacing any previous handler. See [`AsyncHandler`] for
+    /// what can be used as one.
+    pub fn register<Args, H>(&mut self, name: impl Into<String>, handler: H) -> &mut Self
+    where
+        H: AsyncHandler<Args>,
+    {
+        let name = name.into();
+        self.system.signatures.remove(&name);
+        self.methods
+            .insert(name, Box::new(move |params| handler.call(params)));
+        self
+    }
+
+    /// Registers a handler like [`register`](AsyncDispatcher::register), and derives its
+    /// signature for `system.methodSignature` from its argument and output types, see
+    /// [`AsyncSignature`]. Signatures added to the [`Introspection`] take precedence.
+    pub fn register_typed<Args, H>(&mut self, name: impl Into<String>, handler: H) -> &mut Self
+    where
+        H: AsyncHandler<Args> + AsyncSignature<Args>,
+    {
+        let name = name.into();
+        self.register(name.clone(), handler);
+        if let Some(signature) = H::signature() {
+            self.system.signatures.insert(name, signature);
+        }
+        self
+    }
+
+    /// Checks the params of calls to `name` against `schema` before they reach the handler, as
+    /// with [`Schema::validate_params`], replacing any previous schema. Calls which don't match
+    /// get a `-32602` fault listing every violation, rather than the first error converting them
+    /// runs into. [`Schema::of`] the handler's argument tuple describes what it accepts.
+    pub fn schema(&mut self, name: impl Into<String>, schema: Schema) -> &mut Self {
+        self.schemas.insert(name.into(), schema);
+        self
+    }
+
+    /// Limits the size and parse time of requests calling `name` and how long its handler may
+    /// run, replacing any previous quota, like [`Dispatcher::quota`]. The handler's future is
+    /// dropped once the deadline passes, and a thread is kept to wake the call at the deadline
+    /// while it's pending.
+    pub fn quota(&mut self, name: impl Into<String>, quota: Quota) -> &mut Self {
+        self.quotas.insert(name.into(), quota);
+        self
+    }
+
+    /// Answers the `system.*` introspection methods with the signatures and help in
+    /// `introspection`. Handlers registered under the same names take precedence.
+    pub fn introspection(&mut self, introspection: Introspection) -> &mut Self {
+        self.system.introspection = Some(introspection);
+        self
+    }
+
+    /// Answers `system.getCapabilities` with `capabilities`, usually [`Capabilities::new`]. The
+    /// `introspection` capability is added if an [`Introspection`] is attached.
+    pub fn capabilities(&mut self, capabilities: Capabilities) -> &mut Self {
+        self.system.capabilities = Some(capabilities);
+        self
+    }
+
+    /// Returns the names of the registered methods, in order.
+    pub fn methods(&self) -> impl Iterator<Item = &str> {
+        self.methods.keys().map(String::as_str)
+    }
+
+    /// Calls the handler for `call` and returns its response. The params are converted before
+    /// this returns, so the future doesn't borrow the dispatcher.
+    pub fn call(&self, call: MethodCall) -> impl Future<Output = MethodResponse> + Send {
+        let span = CallSpan::server(&call.method_name, call.params.len());
+        self.call_in(call, span)
+    }
+
+    fn call_in(
+        &self,
+        call: MethodCall,
+        span: CallSpan,
+    ) -> impl Future<Output = MethodResponse> + Send {
+        let handler = match self.methods.get(&call.method_name) {
+            Some(handler) => check_params(&self.schemas, &call).map(|()| {
+                let future = handler(call.params);
+                match self.quotas.get(&call.method_name).and_then(|q| q.deadline) {
+                    Some(deadline) => Timeout::wrap(future, &call.method_name, deadline),
+                    None => future,
+                }
+            }),
+            None => Err(self
+                .system
+                .call(self.methods(), &call)
+                .unwrap_or_else(|| method_not_found(&call.method_name))),
+        };
+
+        async move {
+            let response = match handler {
+                Ok(handler) => into_response(handler.await),
+                Err(response) => response,
+            };
+            span.finish_response(&response);
+            response
+        }
+    }
+
+    /// Handles a request body and returns the response body. This only fails if the response
+    /// can't be written.
+    pub fn handle(&self, body: &str) -> impl Future<Output = Result<String>> + Send {
+        let call = parse_call(body, &self.quotas).map(|call| {
+            let span = CallSpan::server(&call.method_name, call.params.len());
+            span.request(body.len());
+            (self.call_in(call, span.clone()), span)
+        });
+
+        async move {
+            let (call, span) = match call {
+                Ok(call) => call,
+                Err(response) => return crate::Document::from(response).to_xml(),
+            };
+
+            let response = crate::Document::from(call.await).to_xml()?;
+            span.response(response.len());
+            Ok(response)
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl fmt::Debug for AsyncDispatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncDispatcher")
+            .field("methods", &self.methods.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// A function which can handle calls asynchronously.
+///
+/// This is implemented for functions and closures taking the same arguments as a [`Handler`]
+/// and returning a future which resolves to the same kind of `Result`.
+#[cfg(feature = "async")]
+pub trait AsyncHandler<Args>: Send + Sync + 'static {
+    /// Calls the handler with the params of a call. The params are converted before the future
+    /// is created, so conversion errors are returned from it straight away.
+    fn call(&self, params: Values) -> HandlerFuture;
+}
+
+#[cfg(feature = "async")]
+impl<F, Fut, R, E> AsyncHandler<()> for F
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = std::result::Result<R, E>> + Send + 'static,
+    R: Serialize,
+    E: Into<Fault>,
+{
+    fn call(&self, params: Values) -> HandlerFuture {
+        if let Err(err) = check_arity(&params, "no params", 0) {
+            return Box::pin(async move { Err(err) });
+        }
+
+        let ret = self();
+        Box::pin(async move { crate::to_value(ret.await.map_err(|err| Error::Fault(err.into()))?) })
+    }
+}
+
+#[cfg(feature = "async")]
+macro_rules! impl_async_handler {
+    ($len:expr, $expected:expr, $($arg:ident),+) => {
+        impl<F, Fut, R, E, $($arg),+> AsyncHandler<($($arg,)+)> for F
+        where
+            F: Fn($($arg),+) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = std::result::Result<R, E>> + Send + 'static,
+            R: Serialize,
+            E: Into<Fault>,
+            $($arg: DeserializeOwned,)+
+        {
+            #[allow(non_snake_case)]
+            fn call(&self, params: Values) -> HandlerFuture {
+                let args = check_arity(&params, $expected, $len)
+                    .and_then(|_| params.deserialize::<($($arg,)+)>());
+                let ($($arg,)+) = match args {
+                    Ok(args) => args,
+                    Err(err) => return Box::pin(async move { Err(err) }),
+                };
+
+                let ret = self($($arg),+);
+                Box::pin(async move {
+                    crate::to_value(ret.await.map_err(|err| Error::Fault(err.into()))?)
+                })
+            }
+        }
+    };
+}
+
+#[cfg(feature = "async")]
+impl_async_handler!(1, "1 param", A);
+#[cfg(feature = "async")]
+impl_async_handler!(2, "2 params", A, B);
+#[cfg(feature = "async")]
+impl_async_handler!(3, "3 params", A, B, C);
+#[cfg(feature = "async")]
+impl_async_handler!(4, "4 params", A, B, C, D);
+#[cfg(feature = "async")]
+impl_async_handler!(5, "5 params", A, B, C, D, G);
+#[cfg(feature = "async")]
+impl_async_handler!(6, "6 params", A, B, C, D, G, H);
+#[cfg(feature = "async")]
+impl_async_handler!(7, "7 params", A, B, C, D, G, H, I);
+#[cfg(feature = "async")]
+impl_async_handler!(8, "8 params", A, B, C, D, G, H, I, J);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fault::codes::*;
+    #[cfg(feature = "async")]
+    use crate::util::block_on;
+
+    fn dispatcher() -> Dispatcher {
+        let mut dispatcher = Dispatcher::new();
+        dispatcher
+            .register("ping", || Ok::<_, Fault>("pong"))
+            .register("concat", |a: String, b: String| Ok::<_, Fault>(a + &b));
+        dispatcher
+    }
+
+    fn fault_code(response: MethodResponse) -> i32 {
+        match response {
+            MethodResponse::Fault(fault) => fault.fault_code,
+            response => panic!("expected a fault, got {:?}", response),
+        }
+    }
+
+    fn call(method: &str, params: Vec<Value>) -> MethodCall {
+        MethodCall {
+            method_name: method.into(),
+            params: params.into(),
+        }
+    }
+
+    #[test]
+    fn dispatch() {
+        let dispatcher = dispatcher();
+        assert_eq!(dispatcher.methods().collect::<Vec<_>>(), ["concat", "ping"]);
+
+        let response = dispatcher.call(call("concat", vec!["a".into(), "b".into()]));
+        assert_eq!(response, MethodResponse::Success(vec!["ab".into()].into()));
+        let response = dispatcher.call(call("ping", vec![]));
+        assert_eq!(
+            response,
+            MethodResponse::Success(vec!["pong".into()].into())
+        );
+    }
+
+    #[test]
+    fn dispatch_faults() {
+        let dispatcher = dispatcher();
+
+        let response = dispatcher.call(call("missing", vec![]));
+        assert_eq!(fault_code(response), METHOD_NOT_FOUND);
+        let response = dispatcher.call(call("concat", vec!["a".into()]));
+        assert_eq!(fault_code(response), INVALID_PARAMS);
+        let response = dispatcher.call(call("concat", vec!["a".into(), 1.into()]));
+        assert_eq!(fault_code(response), INVALID_PARAMS);
+        let response = dispatcher.call(call("ping", vec![1.into()]));
+        assert_eq!(fault_code(response), INVALID_PARAMS);
+
+        let response = dispatcher.handle("<methodCall>").unwrap();
+        assert_eq!(fault_code(response.parse().unwrap()), PARSE_ERROR);
+    }
+
+    #[test]
+    fn dispatch_schemas() {
+        let mut dispatcher = dispatcher();
+        dispatcher.schema("concat", Schema::of::<(String, String)>().unwrap());
+
+        let response = dispatcher.call(call("concat", vec!["a".into(), "b".into()]));
+        assert_eq!(response, MethodResponse::Success(vec!["ab".into()].into()));
+
+        let response = dispatcher.call(call("concat", vec![1.into(), true.into()]));
+        match response {
+            MethodResponse::Fault(fault) => {
+                assert_eq!(fault.fault_code, INVALID_PARAMS);
+                assert_eq!(
+                    fault.fault_string,
+                    "invalid type: int, expected string at /0; \
+                     invalid type: boolean, expected string at /1"
+                );
+            }
+            response => panic!("expected a fault, got {:?}", response),
+        }
+    }
+
+    #[test]
+    fn introspection() {
+        let mut dispatcher = dispatcher();
+        let response = dispatcher.call(call("system.listMethods", vec![]));
+        assert_eq!(fault_code(response), METHOD_NOT_FOUND);
+
+        dispatcher.introspection(
+            Introspection::new()
+                .signature("concat", &["string", "string", "string"])
+                .help("concat", "Joins two strings."),
+        );
+
+        let response = dispatcher.call(call("system.listMethods", vec![]));
+        let (names,): (Vec<String>,) = response.into_result().unwrap().deserialize().unwrap();
+        assert_eq!(
+            names,
+            [
+                "concat",
+                "ping",
+                "system.listMethods",
+                "system.methodHelp",
+                "system.methodSignature"
+            ]
+        );
+
+        let response = dispatcher.call(call("system.methodSignature", vec!["concat".into()]));
+        let (signatures,): (Vec<Vec<String>>,) =
+            response.into_result().unwrap().deserialize().unwrap();
+        assert_eq!(signatures, [["string", "string", "string"]]);
+        let response = dispatcher.call(call("system.methodSignature", vec!["ping".into()]));
+        assert_eq!(
+            response,
+            MethodResponse::Success(vec!["undef".into()].into())
+        );
+
+        let response = dispatcher.call(call("system.methodHelp", vec!["concat".into()]));
+        assert_eq!(
+            response,
+            MethodResponse::Success(vec!["Joins two strings.".into()].into())
+        );
+        let response = dispatcher.call(call("system.methodHelp", vec!["ping".into()]));
+        assert_eq!(response, MethodResponse::Success(vec!["".into()].into()));
+
+        let response = dispatcher.call(call("system.methodHelp", vec!["missing".into()]));
+        assert_eq!(fault_code(response), INVALID_PARAMS);
+        let response = dispatcher.call(call("system.methodHelp", vec![]));
+        assert_eq!(fault_code(response), INVALID_PARAMS);
+    }
+
+    #[test]
+    fn typed_signatures() {
+        let mut dispatcher = dispatcher();
+        dispatcher
+            .register_typed("scale", |v: Vec<f64>, by: Option<f64>| {
+                Ok::<_, Fault>(v.iter().map(|x| x * by.unwrap_or(1.0)).collect::<Vec<_>>())
+            })
+            .register_typed("echo", |v: Value| Ok::<_, Fault>(v))
+            .register_typed("concat", |a: String, b: String| Ok::<_, Fault>(a + &b))
+            .introspection(Introspection::new().signature("concat", &["string", "string"]));
+
+        let signatures = |dispatcher: &Dispatcher, name: &str| {
+            let response = dispatcher.call(call("system.methodSignature", vec![name.into()]));
+            response.into_result().unwrap().into_iter().next().unwrap()
+        };
+        assert_eq!(
+            signatures(&dispatcher, "scale"),
+            Value::from(vec![Value::from(vec![
+                Value::from("array"),
+                Value::from("array"),
+                Value::from("double"),
+            ])])
+        );
+        assert_eq!(signatures(&dispatcher, "echo"), Value::from("undef"));
+        assert_eq!(signatures(&dispatcher, "concat").pointer("/0/2"), None);
+
+        dispatcher.register("scale", || Ok::<_, Fault>(1));
+        assert_eq!(signatures(&dispatcher, "scale"), Value::from("undef"));
+    }
+
+    #[test]
+    fn capabilities() {
+        let mut dispatcher = dispatcher();
+        let response = dispatcher.call(call("system.getCapabilities", vec![]));
+        assert_eq!(fault_code(response), METHOD_NOT_FOUND);
+
+        dispatcher.capabilities(Capabilities::new());
+        let response = dispatcher.call(call("system.getCapabilities", vec![]));
+        let (capabilities,): (Capabilities,) =
+            response.into_result().unwrap().deserialize().unwrap();
+        assert_eq!(capabilities, Capabilities::new());
+
+        dispatcher.introspection(Introspection::new());
+        let response = dispatcher.call(call("system.getCapabilities", vec![]));
+        let (capabilities,): (Capabilities,) =
+            response.into_result().unwrap().deserialize().unwrap();
+        assert_eq!(
+            capabilities.introspection,
+            Some(Capability::introspection())
+        );
+
+        let response = dispatcher.call(call("system.listMethods", vec![]));
+        let (names,): (Vec<String>,) = response.into_result().unwrap().deserialize().unwrap();
+        assert!(names.iter().any(|name| name == "system.getCapabilities"));
+    }
+
+    #[test]
+    fn quotas() {
+        let mut dispatcher = dispatcher();
+        dispatcher
+            .register("sleep", |ms: i32| {
+                std::thread::sleep(Duration::from_millis(ms as u64));
+                Ok::<_, Fault>(ms)
+            })
+            .quota("concat", Quota::new().max_request_len(Some(256)))
+            .quota("ping", Quota::new().max_parse_time(Some(Duration::ZERO)))
+            .quota(
+                "sleep",
+                Quota::new().deadline(Some(Duration::from_millis(20))),
+            );
+
+        let handle = |method: &str, params: Vec<Value>| {
+            let request = crate::request_to_string(method, params).unwrap();
+            dispatcher.handle(&request).unwrap().parse().unwrap()
+        };
+        let response: MethodResponse = handle("concat", vec!["a".into(), "b".into()]);
+        assert_eq!(response, MethodResponse::Success(vec!["ab".into()].into()));
+        let response = handle("concat", vec!["a".repeat(256).into(), "b".into()]);
+        assert_eq!(fault_code(response), INVALID_REQUEST);
+        let response = handle("ping", vec![]);
+        assert_eq!(fault_code(response), SYSTEM_ERROR);
+
+        let response = dispatcher.call(call("sleep", vec![1.into()]));
+        assert_eq!(response, MethodResponse::Success(vec![1.into()].into()));
+        let response = dispatcher.call(call("sleep", vec![50.into()]));
+        assert_eq!(fault_code(response), SYSTEM_ERROR);
+
+        // A body too broken to read the method name from is left to the parser.
+        let response = dispatcher.handle("<methodCall>").unwrap();
+        assert_eq!(fault_code(response.parse().unwrap()), PARSE_ERROR);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_dispatch() {
+        let mut dispatcher = AsyncDispatcher::new();
+        dispatcher
+            .register("ping", || async { Ok::<_, Fault>("pong") })
+            .register("concat", |a: String, b: String| async move {
+                Ok::<_, Fault>(a + &b)
+            })
+            .schema("concat", Schema::array(crate::ValueKind::STRING));
+
+        let response = block_on(dispatcher.call(call("concat", vec!["a".into(), "b".into()])));
+        assert_eq!(response, MethodResponse::Success(vec!["ab".into()].into()));
+        let response = block_on(dispatcher.call(call("ping", vec![])));
+        assert_eq!(
+            response,
+            MethodResponse::Success(vec!["pong".into()].into())
+        );
+
+        let response = block_on(dispatcher.call(call("missing", vec![])));
+        assert_eq!(fault_code(response), METHOD_NOT_FOUND);
+        let response = block_on(dispatcher.call(call("concat", vec!["a".into()])));
+        assert_eq!(fault_code(response), INVALID_PARAMS);
+        let response = block_on(dispatcher.call(call("concat", vec!["a".into(), 1.into()])));
+        assert_eq!(fault_code(response), INVALID_PARAMS);
+
+        let response = block_on(dispatcher.handle("<methodCall>")).unwrap();
+        assert_eq!(fault_code(response.parse().unwrap()), PARSE_ERROR);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_quotas() {
+        let mut dispatcher = AsyncDispatcher::new();
+        dispatcher
+            .register(
+                "hang",
+                std::future::pending::<std::result::Result<(), Fault>>,
+            )
+            .register("ping", || async { Ok::<_, Fault>("pong") })
+            .quota(
+                "hang",
+                Quota::new().deadline(Some(Duration::from_millis(20))),
+            )
+            .quota("ping", Quota::new().deadline(Some(Duration::from_secs(60))))
+            .quota("big", Quota::new().max_request_len(Some(0)));
+
+        let start = Instant::now();
+        let response = block_on(dispatcher.call(call("hang", vec![])));
+        assert_eq!(fault_code(response), SYSTEM_ERROR);
+        assert!(start.elapsed() < Duration::from_secs(60));
+        let response = block_on(dispatcher.call(call("ping", vec![])));
+        assert_eq!(
+            response,
+            MethodResponse::Success(vec!["pong".into()].into())
+        );
+
+        let request = crate::request_to_string("big", vec![]).unwrap();
+        let response = block_on(dispatcher.handle(&request)).unwrap();
+        assert_eq!(fault_code(response.parse().unwrap()), INVALID_REQUEST);
+    }
+}
@@ -0,0 +1,261 @@
+use quick_xml::{events::Event, name::QName, Reader};
+
+use crate::error::DecodingError;
+use crate::{Result, ValueKind};
+
+/// The size and shape of a document, returned by [`inspect`](crate::inspect).
+///
+/// Gathering these only scans the XML, without converting any values, so it is cheap enough to
+/// run on untrusted payloads before deciding whether, and with which [`ParseOptions`] limits, to
+/// parse them. The fields line up with those limits.
+///
+/// Only the values are looked at, so any document with `<value>` elements can be inspected, but
+/// it isn't checked against the spec. Use [`validate_request`](crate::validate_request) for that.
+///
+/// ```
+/// let body = serde_xmlrpc::request_to_string(
+///     "upload",
+///     vec!["notes.txt".into(), b"hello".to_vec().into()],
+/// )
+/// .unwrap();
+///
+/// let stats = serde_xmlrpc::inspect(&body).unwrap();
+/// assert_eq!(stats.values, 2);
+/// assert_eq!(stats.string_bytes, 9);
+/// assert_eq!(stats.base64_bytes, 5);
+/// assert_eq!(stats.count(serde_xmlrpc::ValueKind::BASE64), 1);
+/// ```
+///
+/// [`ParseOptions`]: crate::ParseOptions
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DocumentStats {
+    /// The number of `<value>` elements, compare [`ParseOptions::max_values`].
+    ///
+    /// [`ParseOptions::max_values`]: crate::ParseOptions::max_values
+    pub values: usize,
+    /// The number of struct members.
+    pub members: usize,
+    /// How deeply arrays and structs are nested, compare [`ParseOptions::max_depth`]. A document
+    /// of scalars has depth 0.
+    ///
+    /// [`ParseOptions::max_depth`]: crate::ParseOptions::max_depth
+    pub max_depth: usize,
+    /// The length of the longest text of a value in bytes, compare
+    /// [`ParseOptions::max_text_len`].
+    ///
+    /// [`ParseOptions::max_text_len`]: crate::ParseOptions::max_text_len
+    pub max_text_len: usize,
+    /// The length of all strings together in bytes, after unescaping.
+    pub string_bytes: usize,
+    /// The length of all base64 values together in bytes, once decoded. This is worked out from
+    /// the length of the text, without decoding it.
+    pub base64_bytes: usize,
+    counts: Vec<(ValueKind, usize)>,
+}
+
+impl DocumentStats {
+    /// The number of values of the types in `kind`. Values of extension types other than
+    /// `<nil/>`, `<i8>` and `<ex:float>` aren't counted as any type.
+    pub fn count(&self, kind: ValueKind) -> usize {
+        self.counts
+            .iter()
+            .filter(|(counted, _)| kind.contains(*counted))
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    fn add(&mut self, kind: ValueKind) {
+        match self.counts.iter_mut().find(|(counted, _)| *counted == kind) {
+            Some((_, count)) => *count += 1,
+            None => self.counts.push((kind, 1)),
+        }
+    }
+}
+
+/// Where the scan is in relation to the values.
+enum Position {
+    /// Outside of any scalar value.
+    Outside,
+    /// Inside a `<value>` whose type element hasn't been seen, whose text is a string.
+    Value { len: usize },
+    /// Inside the type element of a scalar value.
+    Scalar {
+        kind: Option<ValueKind>,
+        len: usize,
+        base64_chars: usize,
+    },
+}
+
+fn kind_of(name: QName) -> Option<ValueKind> {
+    match name {
+        QName(b"int") | QName(b"i4") => Some(ValueKind::INT),
+        QName(b"i8") => Some(ValueKind::INT64),
+        QName(b"boolean") => Some(ValueKind::BOOL),
+        QName(b"string") => Some(ValueKind::STRING),
+        QName(b"double") => Some(ValueKind::DOUBLE),
+        QName(b"ex:float") => Some(ValueKind::FLOAT),
+        QName(b"dateTime.iso8601") => Some(ValueKind::DATETIME),
+        QName(b"base64") => Some(ValueKind::BASE64),
+        QName(b"nil") => Some(ValueKind::NIL),
+        QName(b"struct") => Some(ValueKind::STRUCT),
+        QName(b"array") => Some(ValueKind::ARRAY),
+        _ => None,
+    }
+}
+
+pub(crate) fn inspect(input: &str) -> Result<DocumentStats> {
+    let mut reader = Reader::from_str(input);
+    reader.expand_empty_elements(true);
+    reader.trim_text(true);
+
+    let mut stats = DocumentStats::default();
+    let mut position = Position::Outside;
+    let mut open = 0;
+    let mut depth = 0;
+    let mut root = false;
+
+    loop {
+        let text = match reader.read_event().map_err(DecodingError::from)? {
+            Event::Start(e) => {
+                open += 1;
+                root = true;
+
+                if let Position::Value { .. } = position {
+                    let kind = kind_of(e.name());
+                    if let Some(kind) = kind {
+                        stats.add(kind);
+                    }
+                    position = if kind == Some(ValueKind::STRUCT) || kind == Some(ValueKind::ARRAY)
+                    {
+                        depth += 1;
+                        stats.max_depth = stats.max_depth.max(depth);
+                        Position::Outside
+                    } else {
+                        Position::Scalar {
+                            kind,
+                            len: 0,
+                            base64_chars: 0,
+                        }
+                    };
+                } else if e.name() == QName(b"value") {
+                    stats.values += 1;
+                    position = Position::Value { len: 0 };
+                } else if e.name() == QName(b"member") {
+                    stats.members += 1;
+                }
+                continue;
+            }
+            Event::End(e) => {
+                open -= 1;
+
+                match position {
+                    Position::Value { len } => {
+                        stats.add(ValueKind::STRING);
+                        stats.string_bytes += len;
+                        stats.max_text_len = stats.max_text_len.max(len);
+                    }
+                    Position::Scalar {
+                        kind,
+                        len,
+                        base64_chars,
+                    } => {
+                        if kind == Some(ValueKind::STRING) {
+                            stats.string_bytes += len;
+                        } else if kind == Some(ValueKind::BASE64) {
+                            stats.base64_bytes += base64_chars * 3 / 4;
+                        }
+                        stats.max_text_len = stats.max_text_len.max(len);
+                    }
+                    Position::Outside => {
+                        if e.name() == QName(b"struct") || e.name() == QName(b"array") {
+                            depth -= 1;
+                        }
+                    }
+                }
+                position = Position::Outside;
+                continue;
+            }
+            Event::Text(e) => e.unescape().map_err(DecodingError::from)?,
+            Event::CData(e) => String::from_utf8(e.into_inner().into_owned())
+                .map_err(DecodingError::from)?
+                .into(),
+            Event::Eof if open == 0 && root => return Ok(stats),
+            Event::Eof => {
+                return Err(DecodingError::UnexpectedEOF("end of element".into()).into());
+            }
+            _ => continue,
+        };
+
+        match &mut position {
+            Position::Value { len } => *len += text.len(),
+            Position::Scalar {
+                kind,
+                len,
+                base64_chars,
+            } => {
+                *len += text.len();
+                if *kind == Some(ValueKind::BASE64) {
+                    *base64_chars += text
+                        .bytes()
+                        .filter(|&b| b != b'=' && !b.is_ascii_whitespace())
+                        .count();
+                }
+            }
+            Position::Outside => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Fault, Value};
+
+    #[test]
+    fn inspect_values() {
+        let mut record = Value::Nil;
+        record.entry("name").or_insert("a & b");
+        record
+            .entry("tags")
+            .or_insert(vec![Value::from("x"), Value::Nil, Value::Int64(1 << 40)]);
+        record.entry("data").or_insert(vec![1u8, 2, 3, 4]);
+        let body = crate::response_to_string(vec![record].into_iter()).unwrap();
+
+        let stats = inspect(&body).unwrap();
+        assert_eq!(stats.values, 7);
+        assert_eq!(stats.members, 3);
+        assert_eq!(stats.max_depth, 2);
+        assert_eq!(stats.max_text_len, 13);
+        assert_eq!(stats.string_bytes, 6);
+        assert_eq!(stats.base64_bytes, 4);
+        assert_eq!(stats.count(ValueKind::STRING), 2);
+        assert_eq!(stats.count(ValueKind::INTEGER | ValueKind::NIL), 2);
+        assert_eq!(stats.count(ValueKind::ANY), 7);
+        assert_eq!(stats.count(ValueKind::DOUBLE), 0);
+
+        let stats = inspect(
+            "<methodCall><methodName>m</methodName><params>\
+             <param><value>bare</value></param>\
+             <param><value><![CDATA[<x>]]></value></param>\
+             <param><value><ex:i1>1</ex:i1></value></param>\
+             </params></methodCall>",
+        )
+        .unwrap();
+        assert_eq!(stats.values, 3);
+        assert_eq!(stats.string_bytes, 7);
+        assert_eq!(stats.count(ValueKind::ANY), 2);
+        assert_eq!(stats.max_depth, 0);
+
+        let fault = crate::result_to_string::<()>(&Err(Fault::new(4, "nope"))).unwrap();
+        let stats = inspect(&fault).unwrap();
+        assert_eq!(stats.count(ValueKind::STRUCT), 1);
+        assert_eq!(stats.members, 2);
+    }
+
+    #[test]
+    fn inspect_errors() {
+        assert!(inspect("").is_err());
+        assert!(inspect("<methodResponse><params>").is_err());
+        assert!(inspect("<value><int>1</string></value>").is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use quick_xml::{events::Event, name::QName, Reader};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::error::DecodingError;
+use crate::util::{ParseState, ReaderExt, ValueDeserializer};
+use crate::{Fault, ParseOptions, Result};
+
+/// An iterator over the elements of a response whose param is an `<array>`, converting one
+/// element at a time. Returned by [`response_stream`](crate::response_stream) and
+/// [`ParseOptions::response_stream`].
+///
+/// Only the element being converted is held in memory, so a response with thousands of records
+/// can be processed without collecting them in a `Vec` first. The body itself must still be in
+/// memory.
+///
+/// The iterator ends after the last element, or after the first error. The end of the document
+/// is checked once the last element has been read.
+pub struct ResponseStream<'a, T> {
+    reader: Reader<&'a [u8]>,
+    state: ParseState<'a>,
+    done: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'a, T> ResponseStream<'a, T>
+where
+    T: DeserializeOwned,
+{
+    /// Reads up to the first element. A fault is returned as [`Error::Fault`](crate::Error::Fault).
+    pub(crate) fn new(input: &'a str, options: &'a ParseOptions) -> Result<Self> {
+        let mut reader = Reader::from_str(input);
+        reader.expand_empty_elements(true);
+        reader.trim_text(true);
+        let state = ParseState::new(options);
+
+        state.expect_root(&mut reader, QName(b"methodResponse"))?;
+        match reader.read_event().map_err(DecodingError::from)? {
+            Event::Start(e) if e.name() == QName(b"params") => {
+                reader.expect_tag(QName(b"param"))?;
+                reader.expect_tag(QName(b"value"))?;
+                state.count_value()?;
+                reader.expect_tag(QName(b"array"))?;
+                reader.expect_tag(QName(b"data"))?;
+            }
+            Event::Start(e) if e.name() == QName(b"fault") => {
+                reader.expect_tag(QName(b"value"))?;
+                let fault = Fault::deserialize(ValueDeserializer::new(&mut reader, &state)?)?;
+                return Err(fault.into());
+            }
+            e => return Err(DecodingError::UnexpectedEvent(format!("{:?}", e)).into()),
+        }
+
+        Ok(ResponseStream {
+            reader,
+            state,
+            done: false,
+            _marker: PhantomData,
+        })
+    }
+
+    fn next_element(&mut self) -> Result<Option<T>> {
+        match self.reader.read_event().map_err(DecodingError::from)? {
+            Event::Start(e) if e.name() == QName(b"value") => {
+                let deserializer = ValueDeserializer::new(&mut self.reader, &self.state)?;
+                Ok(Some(T::deserialize(deserializer)?))
+            }
+            Event::End(e) if e.name() == QName(b"data") => {
+                let ends = ["array", "value", "param", "params", "methodResponse"];
+                for end in ends.iter() {
+                    self.reader
+                        .read_to_end(QName(end.as_bytes()))
+                        .map_err(DecodingError::from)?;
+                }
+                Ok(None)
+            }
+            e => Err(DecodingError::UnexpectedEvent(format!("{:?}", e)).into()),
+        }
+    }
+}
+
+impl<'a, T> Iterator for ResponseStream<'a, T>
+where
+    T: DeserializeOwned,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let ret = self.next_element().transpose();
+        self.done = !matches!(ret, Some(Ok(_)));
+        ret
+    }
+}
+
+impl<'a, T> fmt::Debug for ResponseStream<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseStream")
+            .field("position", &self.reader.buffer_position())
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use crate::{Error, Fault, Value};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Record {
+        id: i32,
+    }
+
+    fn records(ids: &[i32]) -> String {
+        let values = ids
+            .iter()
+            .map(|&id| {
+                let mut record = Value::Nil;
+                record.entry("id").or_insert(id);
+                record
+            })
+            .collect();
+        crate::response_to_string(vec![Value::Array(values)].into_iter()).unwrap()
+    }
+
+    #[test]
+    fn stream_elements() {
+        let body = records(&[1, 2, 3]);
+        let ids: Vec<i32> = crate::response_stream::<Record>(&body)
+            .unwrap()
+            .map(|record| record.unwrap().id)
+            .collect();
+        assert_eq!(ids, [1, 2, 3]);
+
+        let body = records(&[]);
+        assert_eq!(crate::response_stream::<Record>(&body).unwrap().count(), 0);
+
+        let body = "<?xml version=\"1.0\"?>\n<methodResponse><params><param><value><array>\
+            <data>\n<value><i4>1</i4></value>\n<value>two</value><value><i4>3</i4></value>\
+            </data></array></value></param></params></methodResponse>";
+        let mut stream = crate::response_stream::<i32>(body).unwrap();
+        assert_eq!(stream.next().unwrap().unwrap(), 1);
+        assert!(stream.next().unwrap().is_err());
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn stream_errors() {
+        let body = crate::result_to_string::<()>(&Err(Fault::new(3, "gone"))).unwrap();
+        let err = crate::response_stream::<i32>(&body).unwrap_err();
+        assert!(matches!(err, Error::Fault(Fault { fault_code: 3, .. })));
+
+        let body = crate::response_to_string(vec![Value::Int(1)].into_iter()).unwrap();
+        assert!(crate::response_stream::<i32>(&body).is_err());
+
+        let body = records(&[1]);
+        let truncated = &body[..body.len() - "</methodResponse>".len()];
+        let mut stream = crate::response_stream::<Record>(truncated).unwrap();
+        assert_eq!(stream.next().unwrap().unwrap(), Record { id: 1 });
+        assert!(stream.next().unwrap().is_err());
+    }
+}
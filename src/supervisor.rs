@@ -0,0 +1,417 @@
+//! A typed client for [supervisord's XML-RPC interface].
+//!
+//! [`Supervisor`] wraps a [`BlockingClient`] with a method for each call of the `supervisor`
+//! namespace which controls processes and reads their logs. Failed calls are returned as
+//! [`Error::Fault`](crate::Error::Fault) with one of the [`codes`] supervisord uses.
+//!
+//! ```no_run
+//! use serde_xmlrpc::supervisor::{codes, Supervisor};
+//! use serde_xmlrpc::Error;
+//!
+//! let supervisor = Supervisor::new("http://localhost:9001/RPC2");
+//! for process in supervisor.get_all_process_info().unwrap() {
+//!     println!("{}: {}", process.name, process.statename);
+//! }
+//!
+//! match supervisor.start_process("worker", true) {
+//!     Err(Error::Fault(fault)) if fault.fault_code == codes::ALREADY_STARTED => {}
+//!     result => {
+//!         result.unwrap();
+//!     }
+//! }
+//!
+//! let tail = supervisor.tail_process_stdout_log("worker", 0, 4096).unwrap();
+//! print!("{}", String::from_utf8_lossy(&tail.bytes));
+//! ```
+//!
+//! [supervisord's XML-RPC interface]: http://supervisord.org/api.html
+
+use std::fmt;
+
+use serde::de::{self, DeserializeOwned, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::client::BlockingClient;
+use crate::Result;
+
+/// The fault codes supervisord returns.
+pub mod codes {
+    pub const UNKNOWN_METHOD: i32 = 1;
+    pub const INCORRECT_PARAMETERS: i32 = 2;
+    pub const BAD_ARGUMENTS: i32 = 3;
+    pub const SIGNATURE_UNSUPPORTED: i32 = 4;
+    /// supervisord is shutting down.
+    pub const SHUTDOWN_STATE: i32 = 6;
+    /// No process or group has the given name.
+    pub const BAD_NAME: i32 = 10;
+    pub const BAD_SIGNAL: i32 = 11;
+    pub const NO_FILE: i32 = 20;
+    pub const NOT_EXECUTABLE: i32 = 21;
+    pub const FAILED: i32 = 30;
+    /// The process exited before it could be considered started.
+    pub const ABNORMAL_TERMINATION: i32 = 40;
+    pub const SPAWN_ERROR: i32 = 50;
+    pub const ALREADY_STARTED: i32 = 60;
+    pub const NOT_RUNNING: i32 = 70;
+    pub const SUCCESS: i32 = 80;
+    pub const ALREADY_ADDED: i32 = 90;
+    pub const STILL_RUNNING: i32 = 91;
+    pub const CANT_REREAD: i32 = 92;
+}
+
+/// The state of supervisord itself, as returned by `supervisor.getState`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct State {
+    /// `2` when fatal, `1` when running, `0` when restarting and `-1` when shutting down.
+    pub statecode: i32,
+    /// The name of the state, such as `RUNNING`.
+    pub statename: String,
+}
+
+/// A process and its state, as returned by `supervisor.getProcessInfo`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessInfo {
+    pub name: String,
+    pub group: String,
+    /// A summary of the state, such as `pid 123, uptime 0:01:00`.
+    pub description: String,
+    /// When the process was last started, in seconds since the epoch, or `0` if never.
+    pub start: i32,
+    /// When the process last stopped, in seconds since the epoch, or `0` if never.
+    pub stop: i32,
+    /// The time on the server, in seconds since the epoch.
+    pub now: i32,
+    /// The state code, such as `20` for running.
+    pub state: i32,
+    /// The name of the state, such as `RUNNING`.
+    pub statename: String,
+    /// Why the process couldn't be spawned, if it couldn't.
+    pub spawnerr: String,
+    pub exitstatus: i32,
+    pub stdout_logfile: String,
+    pub stderr_logfile: String,
+    /// The process ID, or `0` if the process isn't running.
+    pub pid: i32,
+}
+
+/// The outcome for one process of starting or stopping many, such as with
+/// `supervisor.startAllProcesses`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessStatus {
+    pub name: String,
+    pub group: String,
+    /// One of [`codes`], [`codes::SUCCESS`] if the process started or stopped.
+    pub status: i32,
+    pub description: String,
+}
+
+/// A chunk of a log, as returned by `supervisor.tailProcessStdoutLog`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogTail {
+    /// The bytes read. supervisord sends them as a `<string>`, but `<base64>` is read too.
+    pub bytes: Vec<u8>,
+    /// The offset to read the next chunk from.
+    pub offset: i32,
+    /// Whether the log grew by more than the length asked for since `offset`, so some of it was
+    /// skipped.
+    pub overflow: bool,
+}
+
+impl<'de> Deserialize<'de> for LogTail {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (LogBytes(bytes), offset, overflow) = Deserialize::deserialize(deserializer)?;
+        Ok(LogTail {
+            bytes,
+            offset,
+            overflow,
+        })
+    }
+}
+
+/// Log text, from either a `<string>` or `<base64>`.
+struct LogBytes(Vec<u8>);
+
+impl<'de> Deserialize<'de> for LogBytes {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(LogBytesVisitor)
+    }
+}
+
+struct LogBytesVisitor;
+
+impl<'de> Visitor<'de> for LogBytesVisitor {
+    type Value = LogBytes;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string or base64 log")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<Self::Value, E> {
+        Ok(LogBytes(v.as_bytes().to_vec()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> std::result::Result<Self::Value, E> {
+        Ok(LogBytes(v.into_bytes()))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> std::result::Result<Self::Value, E> {
+        Ok(LogBytes(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> std::result::Result<Self::Value, E> {
+        Ok(LogBytes(v))
+    }
+}
+
+/// Controls a supervisord instance over XML-RPC.
+#[derive(Clone, Debug)]
+pub struct Supervisor {
+    client: BlockingClient,
+}
+
+impl Supervisor {
+    /// Creates a client for the supervisord at `url`, usually `http://localhost:9001/RPC2`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Supervisor::with_client(BlockingClient::new(url))
+    }
+
+    /// Creates a client which makes its calls with `client`, for example to set a timeout or to
+    /// connect to supervisord's Unix socket:
+    ///
+    /// ```no_run
+    /// use serde_xmlrpc::client::BlockingClient;
+    /// use serde_xmlrpc::supervisor::Supervisor;
+    ///
+    /// let client = BlockingClient::unix("/var/run/supervisor.sock", "/RPC2");
+    /// let supervisor = Supervisor::with_client(client);
+    /// ```
+    pub fn with_client(client: BlockingClient) -> Self {
+        Supervisor { client }
+    }
+
+    fn call<P: Serialize, R: DeserializeOwned>(&self, method: &str, params: P) -> Result<R> {
+        self.client.call(&format!("supervisor.{}", method), params)
+    }
+
+    /// Returns the version of the API, such as `3.0`.
+    pub fn get_api_version(&self) -> Result<String> {
+        self.call("getAPIVersion", [(); 0])
+    }
+
+    /// Returns the version of supervisord.
+    pub fn get_supervisor_version(&self) -> Result<String> {
+        self.call("getSupervisorVersion", [(); 0])
+    }
+
+    /// Returns the state of supervisord.
+    pub fn get_state(&self) -> Result<State> {
+        self.call("getState", [(); 0])
+    }
+
+    /// Returns the process ID of supervisord.
+    pub fn get_pid(&self) -> Result<i32> {
+        self.call("getPID", [(); 0])
+    }
+
+    /// Returns the process called `name`, which may be given as `group:name`.
+    pub fn get_process_info(&self, name: &str) -> Result<ProcessInfo> {
+        self.call("getProcessInfo", (name,))
+    }
+
+    /// Returns every process.
+    pub fn get_all_process_info(&self) -> Result<Vec<ProcessInfo>> {
+        self.call("getAllProcessInfo", [(); 0])
+    }
+
+    /// Starts the process called `name`, or every process in a group with `group:*`. With `wait`,
+    /// the call returns once the process is considered started.
+    pub fn start_process(&self, name: &str, wait: bool) -> Result<bool> {
+        self.call("startProcess", (name, wait))
+    }
+
+    /// Starts every process, returning the outcome for each.
+    pub fn start_all_processes(&self, wait: bool) -> Result<Vec<ProcessStatus>> {
+        self.call("startAllProcesses", (wait,))
+    }
+
+    /// Stops the process called `name`, or every process in a group with `group:*`. With `wait`,
+    /// the call returns once the process has stopped.
+    pub fn stop_process(&self, name: &str, wait: bool) -> Result<bool> {
+        self.call("stopProcess", (name, wait))
+    }
+
+    /// Stops every process, returning the outcome for each.
+    pub fn stop_all_processes(&self, wait: bool) -> Result<Vec<ProcessStatus>> {
+        self.call("stopAllProcesses", (wait,))
+    }
+
+    /// Sends `signal`, such as `HUP` or `15`, to the process called `name`.
+    pub fn signal_process(&self, name: &str, signal: &str) -> Result<bool> {
+        self.call("signalProcess", (name, signal))
+    }
+
+    /// Reads `length` bytes of the process's stdout log from `offset`. A negative `offset` counts
+    /// from the end of the log, with a `length` of `0`.
+    pub fn read_process_stdout_log(&self, name: &str, offset: i32, length: i32) -> Result<String> {
+        self.call("readProcessStdoutLog", (name, offset, length))
+    }
+
+    /// Reads `length` bytes of the process's stderr log from `offset`, the same as
+    /// [`read_process_stdout_log`](Supervisor::read_process_stdout_log).
+    pub fn read_process_stderr_log(&self, name: &str, offset: i32, length: i32) -> Result<String> {
+        self.call("readProcessStderrLog", (name, offset, length))
+    }
+
+    /// Reads up to `length` bytes of the process's stdout log which were written after
+    /// `offset`. Pass the [`LogTail::offset`] returned to follow the log.
+    pub fn tail_process_stdout_log(&self, name: &str, offset: i32, length: i32) -> Result<LogTail> {
+        self.call("tailProcessStdoutLog", (name, offset, length))
+    }
+
+    /// Reads the process's stderr log from `offset`, the same as
+    /// [`tail_process_stdout_log`](Supervisor::tail_process_stdout_log).
+    pub fn tail_process_stderr_log(&self, name: &str, offset: i32, length: i32) -> Result<LogTail> {
+        self.call("tailProcessStderrLog", (name, offset, length))
+    }
+
+    /// Clears the stdout and stderr logs of the process called `name`.
+    pub fn clear_process_logs(&self, name: &str) -> Result<bool> {
+        self.call("clearProcessLogs", (name,))
+    }
+
+    /// Rereads the configuration, returning the names of the groups which were added, changed
+    /// and removed. The changes aren't applied until the groups are added or removed.
+    pub fn reload_config(&self) -> Result<(Vec<String>, Vec<String>, Vec<String>)> {
+        let [changes]: [(Vec<String>, Vec<String>, Vec<String>); 1] =
+            self.call("reloadConfig", [(); 0])?;
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+    use crate::{Error, Fault, Value};
+
+    /// Serves a single request with the XML-RPC response `body`, returning the URL to call and a
+    /// handle which yields the request that was received.
+    fn serve(body: String) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/RPC2", listener.local_addr().unwrap());
+
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !String::from_utf8_lossy(&request).ends_with("</methodCall>") {
+                let len = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..len]);
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/xml\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        (url, handle)
+    }
+
+    fn respond(value: Value) -> String {
+        crate::response_to_string(std::iter::once(value)).unwrap()
+    }
+
+    #[test]
+    fn process_info() {
+        let body = r#"<methodResponse><params><param><value><array><data><value><struct>
+            <member><name>name</name><value><string>worker</string></value></member>
+            <member><name>group</name><value><string>workers</string></value></member>
+            <member><name>description</name><value><string>pid 42, uptime 0:00:05</string></value></member>
+            <member><name>start</name><value><int>1700000000</int></value></member>
+            <member><name>stop</name><value><int>0</int></value></member>
+            <member><name>now</name><value><int>1700000005</int></value></member>
+            <member><name>state</name><value><int>20</int></value></member>
+            <member><name>statename</name><value><string>RUNNING</string></value></member>
+            <member><name>spawnerr</name><value><string></string></value></member>
+            <member><name>exitstatus</name><value><int>0</int></value></member>
+            <member><name>logfile</name><value><string>/var/log/worker.log</string></value></member>
+            <member><name>stdout_logfile</name><value><string>/var/log/worker.log</string></value></member>
+            <member><name>stderr_logfile</name><value><string></string></value></member>
+            <member><name>pid</name><value><int>42</int></value></member>
+            </struct></value></data></array></value></param></params></methodResponse>"#;
+        let (url, server) = serve(body.to_string());
+
+        let processes = Supervisor::new(url).get_all_process_info().unwrap();
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].name, "worker");
+        assert_eq!(processes[0].statename, "RUNNING");
+        assert_eq!(processes[0].pid, 42);
+        assert!(server
+            .join()
+            .unwrap()
+            .contains("<methodName>supervisor.getAllProcessInfo</methodName>"));
+    }
+
+    #[test]
+    fn start_fault() {
+        let body = crate::result_to_string::<()>(&Err(Fault::new(
+            codes::ALREADY_STARTED,
+            "ALREADY_STARTED: worker",
+        )));
+        let (url, server) = serve(body.unwrap());
+
+        let err = Supervisor::new(url)
+            .start_process("worker", true)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Fault(Fault {
+                fault_code: codes::ALREADY_STARTED,
+                ..
+            })
+        ));
+        assert!(server.join().unwrap().ends_with(
+            "<methodName>supervisor.startProcess</methodName><params>\
+             <param><value><string>worker</string></value></param>\
+             <param><value><boolean>1</boolean></value></param>\
+             </params></methodCall>"
+        ));
+    }
+
+    #[test]
+    fn tail_logs() {
+        for bytes in [Value::from("line 1\n"), Value::Base64(b"line 1\n".to_vec())].iter() {
+            let body = respond(Value::Array(vec![
+                bytes.clone(),
+                Value::Int(7),
+                Value::Bool(false),
+            ]));
+            let (url, server) = serve(body);
+
+            let tail = Supervisor::new(url)
+                .tail_process_stdout_log("worker", 0, 1024)
+                .unwrap();
+            assert_eq!(
+                tail,
+                LogTail {
+                    bytes: b"line 1\n".to_vec(),
+                    offset: 7,
+                    overflow: false,
+                }
+            );
+            server.join().unwrap();
+        }
+    }
+}
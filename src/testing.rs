@@ -0,0 +1,224 @@
+//! [`proptest`](https://docs.rs/proptest) strategies for generating [`Value`] trees, to
+//! property-test handlers against the kind of payloads they'll see over XML-RPC.
+//!
+//! Generated values are ones a document can carry: strings only hold characters which are
+//! allowed in XML, and member names are identifiers such as `faultCode`.
+//!
+//! ```
+//! use proptest::test_runner::TestRunner;
+//! use serde_xmlrpc::testing::ValueStrategy;
+//! use serde_xmlrpc::{Value, ValueKind};
+//!
+//! fn sum(value: &Value) -> i64 {
+//!     value.walk().filter_map(|(_, v)| v.as_i64()).sum()
+//! }
+//!
+//! let numbers = ValueStrategy::new().kinds(ValueKind::INT | ValueKind::ARRAY);
+//! TestRunner::default()
+//!     .run(&numbers, |value| {
+//!         sum(&value);
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! ```
+
+use proptest::prelude::*;
+use proptest::strategy::{BoxedStrategy, NewTree, Strategy, Union, ValueTree};
+use proptest::test_runner::TestRunner;
+
+use crate::{Map, Value, ValueKind, XmlRpcDateTime};
+
+/// A strategy generating [`Value`]s, with settings for how deep they nest and which types they
+/// use.
+#[derive(Clone, Debug)]
+pub struct ValueStrategy {
+    max_depth: u32,
+    max_len: usize,
+    kinds: ValueKind,
+}
+
+impl ValueStrategy {
+    /// Generates any type, nested at most 4 deep with at most 8 elements or members at each
+    /// level.
+    pub fn new() -> Self {
+        ValueStrategy {
+            max_depth: 4,
+            max_len: 8,
+            kinds: ValueKind::ANY,
+        }
+    }
+
+    /// Sets how deep arrays and structs nest.
+    pub fn max_depth(mut self, depth: u32) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Sets the maximum number of elements in an array or members in a struct, characters in a
+    /// string and bytes of base64 data.
+    pub fn max_len(mut self, len: usize) -> Self {
+        self.max_len = len;
+        self
+    }
+
+    /// Sets the types to generate. Arrays and structs only contain these types as well.
+    /// [`Value::Raw`] is never generated.
+    ///
+    /// # Panics
+    ///
+    /// Generating values panics if `kinds` is [`ValueKind::NONE`].
+    pub fn kinds(mut self, kinds: ValueKind) -> Self {
+        self.kinds = kinds;
+        self
+    }
+
+    fn build(&self) -> BoxedStrategy<Value> {
+        let kinds = self.kinds;
+        let len = 0..=self.max_len;
+        let mut leaves: Vec<BoxedStrategy<Value>> = Vec::new();
+        let mut add = |kind: ValueKind, strategy: BoxedStrategy<Value>| {
+            if kinds.contains(kind) {
+                leaves.push(strategy);
+            }
+        };
+
+        add(ValueKind::INT, any::<i32>().prop_map(Value::Int).boxed());
+        add(
+            ValueKind::INT64,
+            any::<i64>().prop_map(Value::Int64).boxed(),
+        );
+        add(ValueKind::BOOL, any::<bool>().prop_map(Value::Bool).boxed());
+        add(
+            ValueKind::STRING,
+            text(len.clone()).prop_map(Value::String).boxed(),
+        );
+        add(
+            ValueKind::DOUBLE,
+            any::<f64>().prop_map(Value::Double).boxed(),
+        );
+        add(
+            ValueKind::FLOAT,
+            any::<f32>().prop_map(Value::Float).boxed(),
+        );
+        add(
+            ValueKind::DATETIME,
+            datetime().prop_map(Value::DateTime).boxed(),
+        );
+        add(
+            ValueKind::BASE64,
+            prop::collection::vec(any::<u8>(), len.clone())
+                .prop_map(Value::Base64)
+                .boxed(),
+        );
+        add(ValueKind::NIL, Just(Value::Nil).boxed());
+        // Empty containers end the recursion when no scalar types are allowed.
+        add(ValueKind::STRUCT, Just(Value::Struct(Map::new())).boxed());
+        add(ValueKind::ARRAY, Just(Value::Array(Vec::new())).boxed());
+
+        let leaf = Union::new(leaves);
+        if !kinds.intersects(ValueKind::STRUCT | ValueKind::ARRAY) {
+            return leaf.boxed();
+        }
+
+        let max_len = self.max_len;
+        let desired_size = (max_len as u32).saturating_pow(self.max_depth.min(3));
+        leaf.prop_recursive(self.max_depth, desired_size, max_len as u32, move |inner| {
+            let mut containers = Vec::new();
+            if kinds.contains(ValueKind::STRUCT) {
+                containers.push(
+                    prop::collection::vec(
+                        ("[a-zA-Z_][a-zA-Z0-9_]{0,15}", inner.clone()),
+                        0..=max_len,
+                    )
+                    .prop_map(|members| Value::Struct(members.into_iter().collect()))
+                    .boxed(),
+                );
+            }
+            if kinds.contains(ValueKind::ARRAY) {
+                containers.push(
+                    prop::collection::vec(inner, 0..=max_len)
+                        .prop_map(Value::Array)
+                        .boxed(),
+                );
+            }
+            Union::new(containers)
+        })
+        .boxed()
+    }
+}
+
+impl Default for ValueStrategy {
+    fn default() -> Self {
+        ValueStrategy::new()
+    }
+}
+
+impl Strategy for ValueStrategy {
+    type Tree = Box<dyn ValueTree<Value = Value>>;
+    type Value = Value;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        self.build().new_tree(runner)
+    }
+}
+
+/// Generates any [`Value`] with the default settings of [`ValueStrategy`].
+pub fn any_value() -> ValueStrategy {
+    ValueStrategy::new()
+}
+
+/// Generates strings of characters which are allowed in XML.
+fn text(len: std::ops::RangeInclusive<usize>) -> impl Strategy<Value = String> {
+    prop::collection::vec(any::<char>(), len).prop_map(|chars| {
+        chars
+            .into_iter()
+            .filter(|&c| crate::value::is_xml_char(c))
+            .collect()
+    })
+}
+
+/// Generates dates/times with every field in range, though not necessarily existing dates such
+/// as February 31st.
+fn datetime() -> impl Strategy<Value = XmlRpcDateTime> {
+    (
+        (0..=9999, 1u32..=12, 1u32..=31),
+        (0u32..=23, 0u32..=59, 0u32..=60, 0u32..=999),
+        -(24 * 60 - 1)..24 * 60,
+    )
+        .prop_map(
+            |((year, month, day), (hour, minute, second, millisecond), offset_minutes)| {
+                XmlRpcDateTime {
+                    year,
+                    month,
+                    day,
+                    hour,
+                    minute,
+                    second,
+                    millisecond,
+                    offset_minutes,
+                }
+            },
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParseOptions, WriteOptions};
+
+    proptest! {
+        /// Writing a parsed value reproduces the document it was parsed from.
+        #[test]
+        fn roundtrip(value in any_value()) {
+            let options = WriteOptions::default();
+            let body = options.value_to_string(value).unwrap();
+            let parsed = ParseOptions::lenient().value_from_str(&body).unwrap();
+            prop_assert_eq!(options.value_to_string(parsed).unwrap(), body);
+        }
+
+        #[test]
+        fn kinds(value in ValueStrategy::new().kinds(ValueKind::INT | ValueKind::ARRAY)) {
+            prop_assert!(value.walk().all(|(_, v)| v.matches(ValueKind::INT | ValueKind::ARRAY)));
+        }
+    }
+}
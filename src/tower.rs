@@ -0,0 +1,247 @@
+//! Running XML-RPC calls through [`tower`](https://docs.rs/tower) middleware.
+//!
+//! [`XmlRpcLayer`] turns a service which handles [`MethodCall`]s into one which handles request
+//! bodies. Middleware placed between the layer and the inner service sees each call's method name
+//! and params as data, so it can log calls, check which methods a client may call or rate limit
+//! them per method without knowing anything about XML.
+//!
+//! [`Dispatcher`] is such a service, as is `AsyncDispatcher`:
+//!
+//! ```
+//! use serde_xmlrpc::server::Dispatcher;
+//! use serde_xmlrpc::tower::XmlRpcLayer;
+//! use serde_xmlrpc::Fault;
+//! use tower_layer::Layer;
+//!
+//! let mut dispatcher = Dispatcher::new();
+//! dispatcher.register("add", |a: i32, b: i32| Ok::<_, Fault>(a + b));
+//!
+//! // Any middleware taking a `MethodCall` could wrap the dispatcher here.
+//! let service = XmlRpcLayer::new().layer(dispatcher);
+//! ```
+
+use std::convert::TryFrom;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::server::{parse_error, Dispatcher};
+use crate::{Document, MethodCall, MethodResponse, ParseOptions, WriteOptions};
+
+/// The error type of an [`XmlRpcService`], either the inner service's error or a response which
+/// couldn't be written.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A future returned by an [`XmlRpcService`], resolving to the response body.
+pub type ResponseFuture = Pin<Box<dyn Future<Output = Result<String, BoxError>> + Send>>;
+
+/// Wraps a service handling [`MethodCall`]s in an [`XmlRpcService`].
+#[derive(Clone, Debug, Default)]
+pub struct XmlRpcLayer {
+    parse_options: ParseOptions,
+    write_options: WriteOptions,
+}
+
+impl XmlRpcLayer {
+    pub fn new() -> Self {
+        XmlRpcLayer::default()
+    }
+
+    /// Sets the options request bodies are parsed with.
+    pub fn parse_options(mut self, options: ParseOptions) -> Self {
+        self.parse_options = options;
+        self
+    }
+
+    /// Sets the options response bodies are written with.
+    pub fn write_options(mut self, options: WriteOptions) -> Self {
+        self.write_options = options;
+        self
+    }
+}
+
+impl<S> Layer<S> for XmlRpcLayer {
+    type Service = XmlRpcService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        XmlRpcService {
+            inner,
+            parse_options: self.parse_options.clone(),
+            write_options: self.write_options.clone(),
+        }
+    }
+}
+
+/// A service which parses request bodies into [`MethodCall`]s for an inner service and writes
+/// the [`MethodResponse`]s it returns.
+///
+/// Requests are any bytes, such as a `String` or `Vec<u8>`, and responses are `String`s. A body
+/// which isn't a valid `<methodCall>` is answered with the fault picked by
+/// [`Fault::from_request_error`](crate::Fault::from_request_error) without calling the inner
+/// service, the same as [`Dispatcher::handle`] does.
+#[derive(Clone, Debug)]
+pub struct XmlRpcService<S> {
+    inner: S,
+    parse_options: ParseOptions,
+    write_options: WriteOptions,
+}
+
+impl<S> XmlRpcService<S> {
+    /// Returns the inner service.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, B> Service<B> for XmlRpcService<S>
+where
+    S: Service<MethodCall, Response = MethodResponse>,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+    B: AsRef<[u8]>,
+{
+    type Response = String;
+    type Error = BoxError;
+    type Future = ResponseFuture;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, body: B) -> Self::Future {
+        let write_options = self.write_options.clone();
+        let call = self
+            .parse_options
+            .document_from_slice(body.as_ref())
+            .and_then(MethodCall::try_from);
+
+        let response = match call {
+            Ok(call) => self.inner.call(call),
+            Err(err) => {
+                let body = write_options.document_to_string(&parse_error(err).into());
+                return Box::pin(async move { body.map_err(Into::into) });
+            }
+        };
+
+        Box::pin(async move {
+            let response = Document::from(response.await.map_err(Into::into)?);
+            Ok(write_options.document_to_string(&response)?)
+        })
+    }
+}
+
+/// Never fails, since failures are turned into faults.
+impl Service<MethodCall> for Dispatcher {
+    type Response = MethodResponse;
+    type Error = std::convert::Infallible;
+    type Future = std::future::Ready<Result<MethodResponse, Self::Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, call: MethodCall) -> Self::Future {
+        std::future::ready(Ok(Dispatcher::call(self, call)))
+    }
+}
+
+/// Never fails, since failures are turned into faults.
+#[cfg(feature = "async")]
+impl Service<MethodCall> for crate::server::AsyncDispatcher {
+    type Response = MethodResponse;
+    type Error = std::convert::Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<MethodResponse, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, call: MethodCall) -> Self::Future {
+        let response = crate::server::AsyncDispatcher::call(self, call);
+        Box::pin(async move { Ok(response.await) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::util::block_on;
+    use crate::{Fault, Value};
+
+    /// Middleware which only lets through calls to some methods.
+    struct Allow<S> {
+        inner: S,
+        methods: BTreeSet<&'static str>,
+    }
+
+    impl<S> Service<MethodCall> for Allow<S>
+    where
+        S: Service<MethodCall, Response = MethodResponse>,
+        S::Future: Send + 'static,
+    {
+        type Response = MethodResponse;
+        type Error = S::Error;
+        type Future = Pin<Box<dyn Future<Output = Result<MethodResponse, S::Error>> + Send>>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+            self.inner.poll_ready(cx)
+        }
+
+        fn call(&mut self, call: MethodCall) -> Self::Future {
+            if self.methods.contains(call.method_name.as_str()) {
+                Box::pin(self.inner.call(call))
+            } else {
+                let fault = Fault::new(403, format!("{} is not allowed", call.method_name));
+                Box::pin(async move { Ok(MethodResponse::Fault(fault)) })
+            }
+        }
+    }
+
+    fn call<S: Service<String, Response = String, Error = BoxError>>(
+        service: &mut S,
+        method: &str,
+        params: Vec<Value>,
+    ) -> MethodResponse {
+        let body = crate::request_to_string(method, params).unwrap();
+        block_on(service.call(body)).unwrap().parse().unwrap()
+    }
+
+    #[test]
+    fn layer() {
+        let mut dispatcher = Dispatcher::new();
+        dispatcher
+            .register("add", |a: i32, b: i32| Ok::<_, Fault>(a + b))
+            .register("shutdown", || Ok::<_, Fault>(()));
+        let allow = Allow {
+            inner: dispatcher,
+            methods: vec!["add"].into_iter().collect(),
+        };
+        let mut service = XmlRpcLayer::new().layer(allow);
+
+        assert_eq!(
+            call(&mut service, "add", vec![1.into(), 2.into()]),
+            MethodResponse::Success(vec![Value::Int(3)].into())
+        );
+        assert!(matches!(
+            call(&mut service, "shutdown", vec![]),
+            MethodResponse::Fault(Fault {
+                fault_code: 403,
+                ..
+            })
+        ));
+
+        let response = block_on(service.call("<methodCall>".to_string())).unwrap();
+        assert!(matches!(
+            response.parse::<MethodResponse>().unwrap(),
+            MethodResponse::Fault(Fault {
+                fault_code: -32700,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,173 @@
+//! Spans and events for calls with the `tracing` feature. Without it, these do nothing.
+//!
+//! Each call gets a `call` span at debug level with the method name and param count, under the
+//! target `serde_xmlrpc::client` or `serde_xmlrpc::server`. Inside it, events record the size of
+//! the request and response bodies and how the call ended, with its duration in microseconds.
+//! Faults are logged at debug level, other failures at warn level. A call refused by the quota
+//! of its method gets a `quota exceeded` event at warn level naming the limit.
+
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+use crate::Result;
+
+/// The span of one call.
+#[derive(Clone)]
+pub(crate) struct CallSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "tracing")]
+    start: Instant,
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+impl CallSpan {
+    /// Starts the span of a call made by a client.
+    #[cfg(any(feature = "client", feature = "blocking"))]
+    pub(crate) fn client(method: &str, params: usize) -> Self {
+        CallSpan {
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!(target: "serde_xmlrpc::client", "call", method, params),
+            #[cfg(feature = "tracing")]
+            start: Instant::now(),
+        }
+    }
+
+    /// Starts the span of a call handled by a dispatcher.
+    #[cfg(feature = "server")]
+    pub(crate) fn server(method: &str, params: usize) -> Self {
+        CallSpan {
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!(target: "serde_xmlrpc::server", "call", method, params),
+            #[cfg(feature = "tracing")]
+            start: Instant::now(),
+        }
+    }
+
+    /// Records the size of the request body.
+    pub(crate) fn request(&self, bytes: usize) {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(parent: &self.span, request_bytes = bytes, "request");
+    }
+
+    /// Records the size of the response body.
+    pub(crate) fn response(&self, bytes: usize) {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(parent: &self.span, response_bytes = bytes, "response");
+    }
+
+    /// Records the result of the call as seen by a client.
+    pub(crate) fn finish<T>(&self, result: &Result<T>) {
+        #[cfg(feature = "tracing")]
+        {
+            let duration_us = self.start.elapsed().as_micros() as u64;
+            match result {
+                Ok(_) => tracing::debug!(parent: &self.span, duration_us, "call succeeded"),
+                Err(crate::Error::Fault(fault)) => tracing::debug!(
+                    parent: &self.span,
+                    duration_us,
+                    fault_code = fault.fault_code,
+                    fault_string = %fault.fault_string,
+                    "call returned a fault"
+                ),
+                Err(err) => {
+                    tracing::warn!(parent: &self.span, duration_us, error = %err, "call failed")
+                }
+            }
+        }
+    }
+
+    /// Records the response of a dispatcher.
+    #[cfg(feature = "server")]
+    pub(crate) fn finish_response(&self, response: &crate::MethodResponse) {
+        match response {
+            crate::MethodResponse::Success(_) => self.finish(&Ok(())),
+            crate::MethodResponse::Fault(fault) => {
+                self.finish::<()>(&Err(crate::Error::Fault(fault.clone())))
+            }
+        }
+    }
+}
+
+/// Records a call of `method` being refused for breaking the `limit` of its quota.
+#[cfg(feature = "server")]
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn quota_exceeded(method: &str, limit: &'static str) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(target: "serde_xmlrpc::server", method, limit, "quota exceeded");
+}
+
+#[cfg(all(test, feature = "tracing", feature = "server"))]
+mod tests {
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    use crate::server::Dispatcher;
+    use crate::Fault;
+
+    /// Records the fields of every span and event as `name=value` lines.
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    struct Fields(Vec<String>);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut fields = Fields(Vec::new());
+            span.record(&mut fields);
+            let line = format!("{} {}", span.metadata().target(), fields.0.join(" "));
+            self.0.lock().unwrap().push(line);
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields(Vec::new());
+            event.record(&mut fields);
+            self.0.lock().unwrap().push(fields.0.join(" "));
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn dispatcher_spans() {
+        let mut dispatcher = Dispatcher::new();
+        dispatcher.register("div", |a: i32, b: i32| {
+            a.checked_div(b)
+                .ok_or_else(|| Fault::new(1, "division by zero"))
+        });
+
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let request = crate::request_to_string("div", vec![1.into(), 0.into()]).unwrap();
+            dispatcher.handle(&request).unwrap();
+        });
+
+        let lines = recorder.0.lock().unwrap();
+        assert_eq!(lines[0], "serde_xmlrpc::server method=\"div\" params=2");
+        assert!(lines[1].starts_with("message=request request_bytes="));
+        assert!(lines[2].starts_with("message=call returned a fault duration_us="));
+        assert!(lines[2].ends_with("fault_code=1 fault_string=division by zero"));
+        assert!(lines[3].starts_with("message=response response_bytes="));
+    }
+}
@@ -0,0 +1,82 @@
+use std::borrow::Cow;
+
+use quick_xml::{events::Event, Reader};
+
+use crate::error::DecodingError;
+use crate::Result;
+
+/// Decodes a document to UTF-8 using the encoding named in its XML declaration. Documents without
+/// a declared encoding are expected to be UTF-8, as the XML spec requires.
+///
+/// Other encodings need the `encodings` feature. Labels are resolved the same way browsers do, so
+/// `ISO-8859-1` is decoded as its superset Windows-1252.
+pub(crate) fn decode(input: &[u8]) -> Result<Cow<'_, str>> {
+    let input = input.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(input);
+
+    match declared_encoding(input) {
+        Some(label) if !is_utf8_label(&label) => decode_with(&label, input),
+        _ => Ok(Cow::Borrowed(
+            std::str::from_utf8(input).map_err(DecodingError::from)?,
+        )),
+    }
+}
+
+/// Reads the `encoding` attribute of the XML declaration, if there is one. The declaration is
+/// ASCII in every encoding we can decode, so it can be read before knowing the encoding.
+fn declared_encoding(input: &[u8]) -> Option<String> {
+    match Reader::from_reader(input).read_event() {
+        Ok(Event::Decl(decl)) => decl
+            .encoding()
+            .and_then(|label| label.ok())
+            .map(|label| String::from_utf8_lossy(&label).trim().to_owned()),
+        _ => None,
+    }
+}
+
+fn is_utf8_label(label: &str) -> bool {
+    ["utf-8", "utf8", "us-ascii", "ascii"]
+        .iter()
+        .any(|utf8| label.eq_ignore_ascii_case(utf8))
+}
+
+#[cfg(feature = "encodings")]
+fn decode_with<'a>(label: &str, input: &'a [u8]) -> Result<Cow<'a, str>> {
+    let encoding = encoding_rs::Encoding::for_label(label.as_bytes())
+        .ok_or_else(|| DecodingError::UnsupportedEncoding(label.into()))?;
+
+    Ok(encoding
+        .decode_without_bom_handling_and_without_replacement(input)
+        .ok_or_else(|| DecodingError::EncodingError(encoding.name().into()))?)
+}
+
+#[cfg(not(feature = "encodings"))]
+fn decode_with<'a>(label: &str, _input: &'a [u8]) -> Result<Cow<'a, str>> {
+    Err(DecodingError::UnsupportedEncoding(label.into()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decode;
+
+    #[test]
+    fn utf8_documents() {
+        let input = "<?xml version=\"1.0\" encoding=\"UTF-8\"?><value>caf\u{e9}</value>";
+        assert_eq!(decode(input.as_bytes()).unwrap(), input);
+        assert_eq!(decode(b"\xEF\xBB\xBF<value/>").unwrap(), "<value/>");
+        assert!(decode(b"<value>caf\xE9</value>").is_err());
+    }
+
+    #[test]
+    fn latin1_documents() {
+        let input = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><value>caf\xE9 \x80</value>";
+        let decoded = decode(input);
+
+        if cfg!(feature = "encodings") {
+            assert!(decoded
+                .unwrap()
+                .ends_with("<value>caf\u{e9} \u{20ac}</value>"));
+        } else {
+            assert!(decoded.is_err());
+        }
+    }
+}
@@ -1,10 +1,14 @@
+use std::collections::{HashMap, HashSet};
+
 use base64::prelude::*;
 use quick_xml::{events::Event, name::QName, Reader, Writer};
 use serde::forward_to_deserialize_any;
 
 use crate::error::DecodingError;
-use crate::util::{ReaderExt, WriterExt};
-use crate::{Error, Result};
+use crate::util::{ParseState, ReaderExt, WriterExt};
+use crate::value::de::MapKeyDeserializer;
+use crate::value::VALUE_TOKEN;
+use crate::{DuplicateMembers, Error, Result, WriteOptions};
 
 use super::{ValueDeserializer, ValueSerializer};
 
@@ -14,14 +18,15 @@ where
     W: std::io::Write,
 {
     writer: &'a mut Writer<W>,
+    options: &'a WriteOptions,
 }
 
 impl<'a, W> MapSerializer<'a, W>
 where
     W: std::io::Write,
 {
-    pub fn new(writer: &'a mut Writer<W>) -> Result<Self> {
-        let ret = MapSerializer { writer };
+    pub fn new(writer: &'a mut Writer<W>, options: &'a WriteOptions) -> Result<Self> {
+        let ret = MapSerializer { writer, options };
         ret.writer.write_start_tag("value")?;
         ret.writer.write_start_tag("struct")?;
         Ok(ret)
@@ -40,7 +45,7 @@ where
         T: ?Sized + serde::Serialize,
     {
         self.writer.write_start_tag("member")?;
-        key.serialize(MapKeySerializer::new(self.writer))?;
+        key.serialize(MapKeySerializer::new(self.writer, self.options))?;
         Ok(())
     }
 
@@ -48,7 +53,7 @@ where
     where
         T: ?Sized + serde::Serialize,
     {
-        value.serialize(ValueSerializer::new(self.writer))?;
+        value.serialize(ValueSerializer::new(self.writer, self.options))?;
         self.writer.write_end_tag("member")?;
         Ok(())
     }
@@ -108,14 +113,15 @@ where
     W: std::io::Write,
 {
     writer: &'a mut Writer<W>,
+    options: &'a WriteOptions,
 }
 
 impl<'a, W> MapKeySerializer<'a, W>
 where
     W: std::io::Write,
 {
-    fn new(writer: &'a mut Writer<W>) -> Self {
-        MapKeySerializer { writer }
+    fn new(writer: &'a mut Writer<W>, options: &'a WriteOptions) -> Self {
+        MapKeySerializer { writer, options }
     }
 }
 
@@ -185,7 +191,8 @@ where
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok> {
-        self.writer.write_tag("name", v)
+        let name = self.options.member_name(v);
+        self.writer.write_tag("name", name, self.options.escape)
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
@@ -216,16 +223,16 @@ where
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
     ) -> Result<Self::Ok> {
-        Err(key_must_be_a_string())
+        self.serialize_str(variant)
     }
 
-    fn serialize_newtype_struct<T>(self, _name: &'static str, _value: &T) -> Result<Self::Ok>
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Self::Ok>
     where
         T: ?Sized + serde::Serialize,
     {
-        Err(key_must_be_a_string())
+        value.serialize(self)
     }
 
     fn serialize_newtype_variant<T>(
@@ -293,13 +300,130 @@ fn key_must_be_a_string() -> Error {
 #[doc(hidden)]
 pub struct MapDeserializer<'a, 'r> {
     reader: &'a mut Reader<&'r [u8]>,
+    state: &'a ParseState<'a>,
     end: &'a [u8],
+    /// Member names read so far, for [`DuplicateMembers::Error`] and
+    /// [`DuplicateMembers::KeepFirst`].
+    seen: HashSet<String>,
+    /// Member names which appear more than once, found by looking ahead for
+    /// [`DuplicateMembers::KeepLast`] and [`DuplicateMembers::Collect`].
+    repeated: HashMap<String, Repeated<'r>>,
+    /// The values of the member whose key was just read, for [`DuplicateMembers::Collect`].
+    collected: Option<Vec<Reader<&'r [u8]>>>,
+}
+
+/// A member name which appears more than once in a struct.
+struct Repeated<'r> {
+    /// How many of the members are left.
+    count: usize,
+    /// Readers positioned at each of the members' values, only kept for
+    /// [`DuplicateMembers::Collect`].
+    values: Vec<Reader<&'r [u8]>>,
 }
 
 impl<'a, 'r> MapDeserializer<'a, 'r> {
-    pub fn new(reader: &'a mut Reader<&'r [u8]>, end: &'a [u8]) -> Self {
-        MapDeserializer { reader, end }
+    /// `into_struct` is whether the members are read into a struct rather than a map. Maps
+    /// replace a duplicate member's earlier value by themselves, structs reject it.
+    pub fn new(
+        reader: &'a mut Reader<&'r [u8]>,
+        state: &'a ParseState<'a>,
+        end: &'a [u8],
+        into_struct: bool,
+    ) -> Self {
+        let repeated = match state.options.duplicate_members {
+            DuplicateMembers::KeepLast if into_struct => repeated_members(reader, state, false),
+            DuplicateMembers::Collect => repeated_members(reader, state, true),
+            _ => HashMap::new(),
+        };
+
+        MapDeserializer {
+            reader,
+            state,
+            end,
+            seen: HashSet::new(),
+            repeated,
+            collected: None,
+        }
     }
+
+    /// Whether the member `name` should be skipped under the duplicate member policy.
+    fn skip_member(&mut self, name: &str) -> Result<bool> {
+        match self.state.options.duplicate_members {
+            DuplicateMembers::PassThrough => Ok(false),
+            DuplicateMembers::Error if !self.seen.insert(name.to_owned()) => {
+                Err(DecodingError::DuplicateMember(name.to_owned()).into())
+            }
+            DuplicateMembers::Error => Ok(false),
+            DuplicateMembers::KeepFirst => Ok(!self.seen.insert(name.to_owned())),
+            DuplicateMembers::KeepLast => match self.repeated.get_mut(name) {
+                Some(repeated) if repeated.count > 1 => {
+                    repeated.count -= 1;
+                    Ok(true)
+                }
+                _ => Ok(false),
+            },
+            DuplicateMembers::Collect => match self.repeated.get_mut(name) {
+                // The first of the members stands in for all of them.
+                Some(repeated) if !repeated.values.is_empty() => {
+                    self.collected = Some(std::mem::take(&mut repeated.values));
+                    Ok(false)
+                }
+                Some(_) => Ok(true),
+                None => Ok(false),
+            },
+        }
+    }
+}
+
+/// Reads a `<name>` and the text in it, after the `<member>` start tag.
+fn read_member_name(reader: &mut Reader<&[u8]>, state: &ParseState) -> Result<String> {
+    reader.expect_tag(QName(b"name"))?;
+    let strict = state.options.strict;
+    let name = reader.read_text_content("".into(), QName(b"name"), !strict)?;
+    Ok(if strict {
+        name.into_owned()
+    } else {
+        name.trim().to_owned()
+    })
+}
+
+/// Finds the member names which appear more than once in the rest of the struct `reader` is in,
+/// on a copy of it. Anything unexpected ends the search, since reading the members for real
+/// reports it.
+fn repeated_members<'r>(
+    reader: &Reader<&'r [u8]>,
+    state: &ParseState,
+    keep_values: bool,
+) -> HashMap<String, Repeated<'r>> {
+    let mut reader = reader.clone();
+    let mut members: HashMap<String, Repeated<'r>> = HashMap::new();
+
+    while let Ok(Event::Start(ref e)) = reader.read_event() {
+        if e.name() != QName(b"member") {
+            break;
+        }
+        let name = match read_member_name(&mut reader, state) {
+            Ok(name) => state
+                .options
+                .renamed_field(&name)
+                .map_or(name, str::to_owned),
+            Err(_) => break,
+        };
+        let member = members.entry(name).or_insert(Repeated {
+            count: 0,
+            values: Vec::new(),
+        });
+        member.count += 1;
+        if keep_values {
+            member.values.push(reader.clone());
+        }
+        if reader.read_to_end(QName(b"member")).is_err() {
+            break;
+        }
+    }
+
+    members.retain(|_, member| member.count > 1);
+    members
 }
 
 impl<'de, 'a, 'r> serde::de::MapAccess<'de> for MapDeserializer<'a, 'r> {
@@ -309,24 +433,38 @@ impl<'de, 'a, 'r> serde::de::MapAccess<'de> for MapDeserializer<'a, 'r> {
     where
         T: serde::de::DeserializeSeed<'de>,
     {
-        match self.reader.read_event() {
-            // The base case is that we found a closing tag for the tag we were
-            // looking for.
-            Ok(Event::End(ref e)) if e.name() == QName(self.end) => Ok(None),
-
-            // If we got a member start tag, we know there's a key and value
-            // coming.
-            Ok(Event::Start(ref e)) if e.name() == QName(b"member") => {
-                self.reader.expect_tag(QName(b"name"))?;
-                Ok(Some(seed.deserialize(MapKeyDeserializer::new(
-                    self.reader,
-                    b"name",
-                ))?))
+        loop {
+            match self.reader.read_event() {
+                // The base case is that we found a closing tag for the tag we were
+                // looking for.
+                Ok(Event::End(ref e)) if e.name() == QName(self.end) => return Ok(None),
+
+                // If we got a member start tag, we know there's a key and value
+                // coming.
+                Ok(Event::Start(ref e)) if e.name() == QName(b"member") => {
+                    let mut name = read_member_name(self.reader, self.state)?;
+                    self.state.check_text(&name)?;
+                    if let Some(field) = self.state.options.renamed_field(&name) {
+                        name = field.to_owned();
+                    }
+
+                    if self.skip_member(&name)? {
+                        self.reader
+                            .read_to_end(QName(b"member"))
+                            .map_err(DecodingError::from)?;
+                        continue;
+                    }
+                    return Ok(Some(seed.deserialize(MapKeyDeserializer::new(name))?));
+                }
+
+                // Any other event or error is unexpected and is an actual error.
+                Ok(e) => {
+                    return Err(
+                        DecodingError::UnexpectedEvent(format!("map key read: {:?}", e)).into(),
+                    )
+                }
+                Err(e) => return Err(DecodingError::from(e).into()),
             }
-
-            // Any other event or error is unexpected and is an actual error.
-            Ok(e) => Err(DecodingError::UnexpectedEvent(format!("map key read: {:?}", e)).into()),
-            Err(e) => Err(DecodingError::from(e).into()),
         }
     }
 
@@ -334,9 +472,20 @@ impl<'de, 'a, 'r> serde::de::MapAccess<'de> for MapDeserializer<'a, 'r> {
     where
         T: serde::de::DeserializeSeed<'de>,
     {
+        if let Some(values) = self.collected.take() {
+            let ret = seed.deserialize(RepeatedDeserializer {
+                values: values.into_iter(),
+                state: self.state,
+            });
+            self.reader
+                .read_to_end(QName(b"member"))
+                .map_err(DecodingError::from)?;
+            return ret;
+        }
+
         let ret = match self.reader.read_event() {
             Ok(Event::Start(ref e)) if e.name() == QName(b"value") => {
-                Ok(seed.deserialize(ValueDeserializer::new(self.reader)?)?)
+                Ok(seed.deserialize(ValueDeserializer::new(self.reader, self.state)?)?)
             }
             Ok(e) => Err(DecodingError::UnexpectedEvent(format!("map value read: {:?}", e)).into()),
             Err(e) => Err(DecodingError::from(e).into()),
@@ -350,36 +499,66 @@ impl<'de, 'a, 'r> serde::de::MapAccess<'de> for MapDeserializer<'a, 'r> {
     }
 }
 
-#[doc(hidden)]
-pub struct MapKeyDeserializer<'a, 'r> {
-    reader: &'a mut Reader<&'r [u8]>,
-    end: &'a [u8],
-}
-
-impl<'a, 'r> MapKeyDeserializer<'a, 'r> {
-    pub fn new(reader: &'a mut Reader<&'r [u8]>, end: &'a [u8]) -> Self {
-        MapKeyDeserializer { reader, end }
-    }
+/// Hands the values of a member which appears more than once to serde as a sequence, for
+/// [`DuplicateMembers::Collect`].
+struct RepeatedDeserializer<'a, 'r> {
+    values: std::vec::IntoIter<Reader<&'r [u8]>>,
+    state: &'a ParseState<'a>,
 }
 
-impl<'de, 'a, 'r> serde::Deserializer<'de> for MapKeyDeserializer<'a, 'r> {
+impl<'de, 'a, 'r> serde::Deserializer<'de> for RepeatedDeserializer<'a, 'r> {
     type Error = Error;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: serde::de::Visitor<'de>,
     {
-        visitor.visit_string(
-            self.reader
-                .read_text(QName(self.end))
-                .map_err(DecodingError::from)?
-                .into(),
-        )
+        visitor.visit_seq(self)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        if name == VALUE_TOKEN {
+            let state = self.state;
+            return state.reading_values(|| self.deserialize_any(visitor));
+        }
+        self.deserialize_any(visitor)
     }
 
     forward_to_deserialize_any!(
         bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
-        byte_buf unit unit_struct newtype_struct seq tuple
-        tuple_struct map struct enum identifier ignored_any option
+        byte_buf unit unit_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
     );
 }
+
+impl<'de, 'a, 'r> serde::de::SeqAccess<'de> for RepeatedDeserializer<'a, 'r> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        match self.values.next() {
+            Some(mut reader) => {
+                reader.expect_tag(QName(b"value"))?;
+                let value = seed.deserialize(ValueDeserializer::new(&mut reader, self.state)?)?;
+                Ok(Some(value))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len())
+    }
+}
@@ -1,22 +1,244 @@
+use std::borrow::Cow;
+use std::cell::Cell;
+
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::name::QName;
 use quick_xml::{Reader, Writer};
 
 use crate::error::{DecodingError, EncodingError, Result};
+use crate::{Error, EscapePolicy, ParseOptions, Value, WriteOptions};
 
+mod encoding;
 mod map;
 mod seq;
 mod value;
 
+pub(crate) use encoding::decode;
+
+pub(crate) use crate::datetime::parse_datetime;
+pub(crate) use crate::options::scalar_text;
 pub use map::{MapDeserializer, MapSerializer};
 pub use seq::{SeqDeserializer, SeqSerializer};
+pub(crate) use value::{
+    decode_base64, is_spec_double, parse_bool, parse_double, parse_int, string_text, value_xml_len,
+    write_value, EXTENSIONS_NS,
+};
 pub use value::{Deserializer as ValueDeserializer, Serializer as ValueSerializer};
 
-pub(crate) trait ReaderExt {
+/// State shared by every deserializer working on the same document, used to enforce the limits
+/// in [`ParseOptions`].
+pub(crate) struct ParseState<'a> {
+    pub(crate) options: &'a ParseOptions,
+    depth: Cell<usize>,
+    values: Cell<usize>,
+    // serde_transcode reports deserializer errors through the serializer, which loses their type.
+    // Limit errors are kept here so they can be returned as is.
+    limit_error: Cell<Option<DecodingError>>,
+    reading_value: Cell<bool>,
+}
+
+impl<'a> ParseState<'a> {
+    pub(crate) fn new(options: &'a ParseOptions) -> Self {
+        ParseState {
+            options,
+            depth: Cell::new(0),
+            values: Cell::new(0),
+            limit_error: Cell::new(None),
+            reading_value: Cell::new(false),
+        }
+    }
+
+    /// Reads the contents of a `<value>` into a [`Value`].
+    pub(crate) fn read_value(&self, reader: &mut Reader<&[u8]>) -> Result<Value> {
+        let deserializer = ValueDeserializer::new(reader, self)?;
+        let serializer = crate::value::Serializer::with_options(&VALUE_OPTIONS);
+        self.reading_values(|| serde_transcode::transcode(deserializer, serializer))
+            .map_err(|err| self.limit_error.take().map_or(err, Error::from))
+    }
+
+    /// Runs `f` with [`ParseState::reading_value`] set.
+    pub(crate) fn reading_values<T>(&self, f: impl FnOnce() -> T) -> T {
+        let outer = self.reading_value.replace(true);
+        let ret = f();
+        self.reading_value.set(outer);
+        ret
+    }
+
+    /// Whether the document is being read into a [`Value`] rather than a user's type, in which
+    /// case dateTime values are handed over marked with [`DATETIME_TOKEN`] so they don't end up
+    /// as strings.
+    ///
+    /// [`DATETIME_TOKEN`]: crate::value::DATETIME_TOKEN
+    pub(crate) fn reading_value(&self) -> bool {
+        self.reading_value.get()
+    }
+
+    fn limit_exceeded(&self, err: fn(usize) -> DecodingError, max: usize) -> Error {
+        self.limit_error.set(Some(err(max)));
+        err(max).into()
+    }
+
+    /// Reads up to the start of the root element, skipping the XML declaration. A `<!DOCTYPE>`
+    /// is an error unless the options allow it, and even then its entities are never expanded.
+    pub(crate) fn read_root<'r>(&self, reader: &mut Reader<&'r [u8]>) -> Result<BytesStart<'r>> {
+        loop {
+            match reader.read_event().map_err(DecodingError::from)? {
+                Event::Decl(_) => continue,
+                Event::DocType(_) if self.options.allow_doctype => continue,
+                Event::DocType(_) => return Err(DecodingError::DoctypeNotAllowed.into()),
+                Event::Start(e) => return Ok(e),
+                e => return Err(DecodingError::UnexpectedEvent(format!("{:?}", e)).into()),
+            }
+        }
+    }
+
+    /// Reads up to the start of the root element like [`ParseState::read_root`], which must be
+    /// `tag`.
+    pub(crate) fn expect_root(&self, reader: &mut Reader<&[u8]>, tag: QName) -> Result<()> {
+        let root = self.read_root(reader)?;
+        if root.name() != tag {
+            return Err(DecodingError::UnexpectedTag(
+                String::from_utf8_lossy(root.name().into_inner()).into(),
+                String::from_utf8_lossy(tag.into_inner()).into(),
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Records entering an array or struct. The returned guard records leaving it again when
+    /// dropped.
+    pub(crate) fn enter(&self) -> Result<DepthGuard<'_>> {
+        let depth = self.depth.get() + 1;
+        if let Some(max) = self.options.max_depth {
+            if depth > max {
+                return Err(self.limit_exceeded(DecodingError::DepthLimitExceeded, max));
+            }
+        }
+
+        self.depth.set(depth);
+        Ok(DepthGuard(&self.depth))
+    }
+
+    /// Records reading a `<value>`.
+    pub(crate) fn count_value(&self) -> Result<()> {
+        let values = self.values.get() + 1;
+        if let Some(max) = self.options.max_values {
+            if values > max {
+                return Err(self.limit_exceeded(DecodingError::ValueLimitExceeded, max));
+            }
+        }
+
+        self.values.set(values);
+        Ok(())
+    }
+
+    pub(crate) fn check_text(&self, text: &str) -> Result<()> {
+        match self.options.max_text_len {
+            Some(max) if text.len() > max => {
+                Err(self.limit_exceeded(DecodingError::TextLimitExceeded, max))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// `<ex:float>` values are handed over as `f32`, which should stay a float when read into a Value.
+static VALUE_OPTIONS: WriteOptions = WriteOptions::lenient().float_extension(true);
+
+/// Reads the XML of a [`RawValue`] straight into `visitor`, as a [`Value`] would be read if
+/// `reading_value` is set.
+///
+/// [`RawValue`]: crate::RawValue
+pub(crate) fn visit_raw<'de, V>(
+    raw: &crate::RawValue,
+    reading_value: bool,
+    visitor: V,
+) -> Result<V::Value>
+where
+    V: serde::de::Visitor<'de>,
+{
+    use serde::Deserializer as _;
+
+    let mut reader = Reader::from_str(raw.as_str());
+    reader.expand_empty_elements(true);
+    reader.trim_text(true);
+    let options = ParseOptions::lenient();
+    let state = ParseState::new(&options);
+
+    state.expect_root(&mut reader, QName(b"value"))?;
+    let deserializer = ValueDeserializer::new(&mut reader, &state)?;
+    if reading_value {
+        state.reading_values(|| deserializer.deserialize_any(visitor))
+    } else {
+        deserializer.deserialize_any(visitor)
+    }
+}
+
+/// Runs a future to completion on the current thread, parking it until the future is woken.
+/// This is all the tests of the async APIs need, as they don't do any I/O through a runtime.
+#[cfg(all(
+    test,
+    any(
+        feature = "async",
+        feature = "axum",
+        feature = "tower",
+        feature = "client"
+    )
+))]
+pub(crate) fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+    use std::thread::{self, Thread};
+
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Arc::new(Unpark(thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(ret) => return ret,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+pub(crate) struct DepthGuard<'a>(&'a Cell<usize>);
+
+impl Drop for DepthGuard<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() - 1);
+    }
+}
+
+pub(crate) trait ReaderExt<'r> {
     fn expect_tag(&mut self, end: QName) -> Result<()>;
+
+    /// Reads text up to the closing `end` tag, unescaping entities and joining any CDATA sections
+    /// with the text around them. `text` is what has already been read of the string.
+    fn read_string(&mut self, text: Cow<'r, str>, end: QName) -> Result<Cow<'r, str>> {
+        self.read_text_content(text, end, false)
+    }
+
+    /// Like `read_string`, but when `flatten` is set child elements are allowed and their text is
+    /// included as if the tags weren't there.
+    fn read_text_content(
+        &mut self,
+        text: Cow<'r, str>,
+        end: QName,
+        flatten: bool,
+    ) -> Result<Cow<'r, str>>;
 }
 
-impl<'a> ReaderExt for Reader<&'a [u8]> {
+impl<'r> ReaderExt<'r> for Reader<&'r [u8]> {
     fn expect_tag(&mut self, end: QName) -> Result<()> {
         loop {
             match self.read_event() {
@@ -52,17 +274,81 @@ impl<'a> ReaderExt for Reader<&'a [u8]> {
 
         Ok(())
     }
+
+    fn read_text_content(
+        &mut self,
+        mut text: Cow<'r, str>,
+        end: QName,
+        flatten: bool,
+    ) -> Result<Cow<'r, str>> {
+        // Whitespace is significant in strings, so trimming is turned off until the end tag. All
+        // of our readers trim text otherwise.
+        self.trim_text(false);
+        let mut depth = 0;
+        let ret = loop {
+            let piece = match self.read_event() {
+                Ok(Event::Text(e)) => e.unescape().map_err(DecodingError::from)?,
+                Ok(Event::CData(e)) => match e.into_inner() {
+                    Cow::Borrowed(b) => {
+                        Cow::Borrowed(std::str::from_utf8(b).map_err(DecodingError::from)?)
+                    }
+                    Cow::Owned(b) => Cow::Owned(String::from_utf8(b).map_err(DecodingError::from)?),
+                },
+                Ok(Event::Comment(_)) => continue,
+                Ok(Event::Start(_)) if flatten => {
+                    depth += 1;
+                    continue;
+                }
+                Ok(Event::End(_)) if depth > 0 => {
+                    depth -= 1;
+                    continue;
+                }
+                Ok(Event::End(e)) if e.name() == end => break Ok(text),
+                Ok(e) => {
+                    break Err(DecodingError::UnexpectedEvent(format!(
+                        "{:?}, expected text in {}",
+                        e,
+                        String::from_utf8_lossy(end.into_inner())
+                    ))
+                    .into())
+                }
+                Err(e) => break Err(DecodingError::from(e).into()),
+            };
+
+            if text.is_empty() {
+                text = piece;
+            } else {
+                text.to_mut().push_str(&piece);
+            }
+        };
+        self.trim_text(true);
+
+        ret
+    }
 }
 
 pub(crate) trait WriterExt {
     // High level functions
-    fn write_tag(&mut self, tag: &str, text: &str) -> Result<()> {
+    fn write_tag(&mut self, tag: &str, text: &str, escape: EscapePolicy) -> Result<()> {
         self.write_start_tag(tag)?;
-        self.write_text(text)?;
+        self.write_text(text, escape)?;
         self.write_end_tag(tag)?;
         Ok(())
     }
 
+    /// Writes a tag containing text, as a CDATA section if the options call for it.
+    fn write_string_tag(&mut self, tag: &str, text: &str, options: &WriteOptions) -> Result<()> {
+        match options.cdata_threshold {
+            Some(threshold) if count_escapes(text, options.escape) >= threshold => {
+                // A CDATA section can't contain its own terminator, so any `]]>` is split across
+                // two sections.
+                let cdata = format!("<![CDATA[{}]]>", text.replace("]]>", "]]]]><![CDATA[>"));
+                self.write_safe_tag(tag, &cdata)
+            }
+            _ => self.write_tag(tag, text, options.escape),
+        }
+    }
+
     fn write_safe_tag(&mut self, tag: &str, text: &str) -> Result<()> {
         self.write_start_tag(tag)?;
         self.write_safe_text(text)?;
@@ -74,8 +360,88 @@ pub(crate) trait WriterExt {
     fn write_decl(&mut self) -> Result<()>;
     fn write_start_tag(&mut self, tag: &str) -> Result<()>;
     fn write_end_tag(&mut self, tag: &str) -> Result<()>;
-    fn write_text(&mut self, text: &str) -> Result<()>;
     fn write_safe_text(&mut self, text: &str) -> Result<()>;
+
+    fn write_text(&mut self, text: &str, escape: EscapePolicy) -> Result<()> {
+        self.write_safe_text(&escape_text(text, escape))
+    }
+}
+
+/// Escapes text content according to the given policy. Text without anything to escape is
+/// returned as is, and otherwise the runs between escaped characters are copied over whole.
+fn escape_text(text: &str, policy: EscapePolicy) -> Cow<'_, str> {
+    let mut positions = escape_positions(text.as_bytes(), policy).peekable();
+    if positions.peek().is_none() {
+        return Cow::Borrowed(text);
+    }
+
+    let mut ret = String::with_capacity(text.len() + text.len() / 8);
+    let mut last = 0;
+    for i in positions {
+        ret.push_str(&text[last..i]);
+        ret.push_str(match text.as_bytes()[i] {
+            b'<' => "&lt;",
+            b'>' => "&gt;",
+            b'&' => "&amp;",
+            b'\'' => "&apos;",
+            _ => "&quot;",
+        });
+        last = i + 1;
+    }
+    ret.push_str(&text[last..]);
+
+    Cow::Owned(ret)
+}
+
+/// The length of text once written with [`WriterExt::write_string_tag`], without the tags.
+fn string_text_len(text: &str, options: &WriteOptions) -> usize {
+    match options.cdata_threshold {
+        Some(threshold) if count_escapes(text, options.escape) >= threshold => {
+            "<![CDATA[]]>".len() * (1 + text.matches("]]>").count()) + text.len()
+        }
+        _ => escaped_len(text, options.escape),
+    }
+}
+
+/// The length of text once escaped according to the given policy.
+fn escaped_len(text: &str, policy: EscapePolicy) -> usize {
+    let extra: usize = escape_positions(text.as_bytes(), policy)
+        .map(|i| match text.as_bytes()[i] {
+            b'<' | b'>' => "&lt;".len() - 1,
+            b'&' => "&amp;".len() - 1,
+            _ => "&quot;".len() - 1,
+        })
+        .sum();
+    text.len() + extra
+}
+
+/// Counts the characters which would be escaped according to the given policy.
+fn count_escapes(text: &str, policy: EscapePolicy) -> usize {
+    escape_positions(text.as_bytes(), policy).count()
+}
+
+/// Finds the characters to escape according to the given policy, in order. The searches use
+/// `memchr`, which checks many bytes at once, since most text has nothing to escape at all.
+///
+/// [`EscapePolicy::Minimal`] only escapes a `>` when it would otherwise close a `]]>` sequence,
+/// which isn't allowed in XML text.
+fn escape_positions(text: &[u8], policy: EscapePolicy) -> impl Iterator<Item = usize> + '_ {
+    let quoted: &[u8] = if policy == EscapePolicy::Full {
+        text
+    } else {
+        &[]
+    };
+    let mut markup = memchr::memchr3_iter(b'<', b'>', b'&', text).peekable();
+    let mut quotes = memchr::memchr2_iter(b'\'', b'"', quoted).peekable();
+
+    std::iter::from_fn(move || match (markup.peek(), quotes.peek()) {
+        (Some(m), Some(q)) if q < m => quotes.next(),
+        (Some(_), _) => markup.next(),
+        (None, _) => quotes.next(),
+    })
+    .filter(move |&i| {
+        policy != EscapePolicy::Minimal || text[i] != b'>' || text[..i].ends_with(b"]]")
+    })
 }
 
 impl<W> WriterExt for Writer<W>
@@ -99,14 +465,47 @@ where
             .map_err(EncodingError::from)?;
         Ok(())
     }
-    fn write_text(&mut self, text: &str) -> Result<()> {
-        self.write_event(Event::Text(BytesText::new(text)))
-            .map_err(EncodingError::from)?;
-        Ok(())
-    }
     fn write_safe_text(&mut self, text: &str) -> Result<()> {
         self.write_event(Event::Text(BytesText::from_escaped(text)))
             .map_err(EncodingError::from)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_matches_quick_xml() {
+        let texts = [
+            "",
+            "plain text",
+            "<a href=\"x\">Tom & Jerry's</a>",
+            "&&<<>>''\"\"",
+            "ünïcödé > ascii",
+            "]]> and ]> and ]]]>",
+        ];
+        for text in texts.iter() {
+            assert_eq!(
+                escape_text(text, EscapePolicy::Full),
+                quick_xml::escape::escape(text)
+            );
+            assert_eq!(
+                escape_text(text, EscapePolicy::Text),
+                quick_xml::escape::partial_escape(text)
+            );
+        }
+
+        assert!(matches!(
+            escape_text("plain text", EscapePolicy::Full),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(
+            escape_text("a > b ]]> <&", EscapePolicy::Minimal),
+            "a > b ]]&gt; &lt;&amp;"
+        );
+        assert_eq!(count_escapes("]]> '", EscapePolicy::Minimal), 1);
+        assert_eq!(count_escapes("]]> '", EscapePolicy::Full), 2);
+    }
+}
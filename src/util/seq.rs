@@ -2,8 +2,8 @@ use quick_xml::Reader;
 use quick_xml::{events::Event, name::QName, Writer};
 
 use crate::error::DecodingError;
-use crate::util::{ReaderExt, WriterExt};
-use crate::{Error, Result};
+use crate::util::{ParseState, ReaderExt, WriterExt};
+use crate::{Error, Result, WriteOptions};
 
 use super::{ValueDeserializer, ValueSerializer};
 
@@ -13,14 +13,15 @@ where
     W: std::io::Write,
 {
     writer: &'a mut Writer<W>,
+    options: &'a WriteOptions,
 }
 
 impl<'a, W> SeqSerializer<'a, W>
 where
     W: std::io::Write,
 {
-    pub fn new(writer: &'a mut Writer<W>) -> Result<Self> {
-        let ret = SeqSerializer { writer };
+    pub fn new(writer: &'a mut Writer<W>, options: &'a WriteOptions) -> Result<Self> {
+        let ret = SeqSerializer { writer, options };
         ret.writer.write_start_tag("value")?;
         ret.writer.write_start_tag("array")?;
         ret.writer.write_start_tag("data")?;
@@ -39,7 +40,7 @@ where
     where
         T: ?Sized + serde::Serialize,
     {
-        value.serialize(ValueSerializer::new(self.writer))
+        value.serialize(ValueSerializer::new(self.writer, self.options))
     }
 
     fn end(self) -> Result<Self::Ok> {
@@ -110,6 +111,7 @@ where
 #[doc(hidden)]
 pub struct SeqDeserializer<'a, 'r> {
     reader: &'a mut Reader<&'r [u8]>,
+    state: &'a ParseState<'a>,
     end: QName<'a>,
     end_maybe: Option<QName<'a>>,
 }
@@ -117,11 +119,13 @@ pub struct SeqDeserializer<'a, 'r> {
 impl<'a, 'r> SeqDeserializer<'a, 'r> {
     pub fn new(
         reader: &'a mut Reader<&'r [u8]>,
+        state: &'a ParseState<'a>,
         end: QName<'a>,
         end_maybe: Option<QName<'a>>,
     ) -> Result<Self> {
         let ret = SeqDeserializer {
             reader,
+            state,
             end,
             end_maybe,
         };
@@ -147,7 +151,7 @@ impl<'de, 'a, 'r> serde::de::SeqAccess<'de> for SeqDeserializer<'a, 'r> {
                 Ok(None)
             }
             Ok(Event::Start(ref e)) if e.name() == QName(b"value") => Ok(Some(
-                seed.deserialize(ValueDeserializer::new(self.reader)?)?,
+                seed.deserialize(ValueDeserializer::new(self.reader, self.state)?)?,
             )),
             Ok(_) => Err(DecodingError::UnexpectedEvent("one of value".to_string()).into()),
             Err(e) => Err(DecodingError::from(e).into()),
@@ -5,11 +5,14 @@ use quick_xml::{
     Reader, Writer,
 };
 use serde::forward_to_deserialize_any;
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 
 use crate::error::{DecodingError, EncodingError};
-use crate::util::{ReaderExt, WriterExt};
-use crate::{Error, Result};
+use crate::util::{parse_datetime, scalar_text, ParseState, ReaderExt, WriterExt};
+use crate::value::{
+    RawValue, BIGDECIMAL_TOKEN, BIGINTEGER_TOKEN, DATETIME_TOKEN, RAW_TOKEN, VALUE_TOKEN,
+};
+use crate::{Error, ParseOptions, Result, Value, WriteOptions};
 
 use super::{MapDeserializer, MapSerializer};
 use super::{SeqDeserializer, SeqSerializer};
@@ -17,13 +20,203 @@ use super::{SeqDeserializer, SeqSerializer};
 #[doc(hidden)]
 pub struct Deserializer<'a, 'r> {
     pub(crate) reader: &'a mut Reader<&'r [u8]>,
+    state: &'a ParseState<'a>,
+    /// Whether a `<struct>` is read into a struct rather than a map, see [`MapDeserializer::new`].
+    into_struct: bool,
 }
 
 impl<'a, 'r> Deserializer<'a, 'r> {
-    pub fn new(reader: &'a mut Reader<&'r [u8]>) -> Result<Self> {
-        let ret = Deserializer { reader };
+    pub fn new(reader: &'a mut Reader<&'r [u8]>, state: &'a ParseState<'a>) -> Result<Self> {
+        let ret = Deserializer {
+            reader,
+            state,
+            into_struct: false,
+        };
         Ok(ret)
     }
+
+    /// Reads the rest of a `<value>` of an unknown type as a [`RawValue`], exactly as it is
+    /// written in `rest`, the input left before its type's start tag. See
+    /// [`ParseOptions::keep_unknown`].
+    ///
+    /// This is kept out of `deserialize_any` so its locals don't grow the stack of every nested
+    /// value.
+    #[inline(never)]
+    fn read_unknown<'de, V>(self, rest: &'r [u8], name: QName<'_>, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.reader.read_to_end(name).map_err(DecodingError::from)?;
+        self.reader
+            .read_to_end(QName(b"value"))
+            .map_err(DecodingError::from)?;
+        let len = rest.len() - self.reader.get_ref().len();
+        let inner = std::str::from_utf8(&rest[..len]).map_err(DecodingError::from)?;
+        self.state.check_text(inner)?;
+        let raw = format!("<value>{}", inner);
+        let token = std::iter::once((RAW_TOKEN, raw));
+        visitor.visit_map(serde::de::value::MapDeserializer::<_, Error>::new(token))
+    }
+}
+
+/// Trims the text of a string value if the options call for it, see
+/// [`ParseOptions::trim_strings`].
+pub(crate) fn string_text<'t>(text: &'t str, options: &ParseOptions) -> &'t str {
+    if options.trim_strings {
+        text.trim()
+    } else {
+        text
+    }
+}
+
+/// Parses the text of an `<int>`, `<i4>` or `<i8>` tag. The spec allows an optional sign and
+/// leading zeros, but no whitespace. Strict mode also requires `<int>` and `<i4>` values to fit
+/// in 32 bits.
+pub(crate) fn parse_int(tag: QName, text: &str, options: &ParseOptions) -> Result<i64> {
+    let text = scalar_text(text, options);
+    let val: i64 = text.parse().map_err(DecodingError::from)?;
+
+    if options.strict && tag != QName(b"i8") && i32::try_from(val).is_err() {
+        return Err(DecodingError::IntDecodeError(text.into()).into());
+    }
+
+    Ok(val)
+}
+
+/// The tag an integer is written in: `<int>` where it fits in 32 bits and `<i8>` otherwise, so
+/// that strict parsers still read it.
+fn int_tag(fits_i32: bool) -> &'static str {
+    if fits_i32 {
+        "int"
+    } else {
+        "i8"
+    }
+}
+
+/// Parses the text of a `<boolean>` tag. The spec only allows `1` and `0`, lenient mode also
+/// accepts `true` and `false` in any case, as some Python and PHP servers write them.
+pub(crate) fn parse_bool(text: &str, options: &ParseOptions) -> Result<bool> {
+    match scalar_text(text, options) {
+        "1" => Ok(true),
+        "0" => Ok(false),
+        word if !options.strict && word.eq_ignore_ascii_case("true") => Ok(true),
+        word if !options.strict && word.eq_ignore_ascii_case("false") => Ok(false),
+        _ => Err(DecodingError::BooleanDecodeError(text.into()).into()),
+    }
+}
+
+/// Parses the text of a `<double>` or `<ex:float>` tag. Strict mode only accepts the decimal
+/// notation allowed by the spec: an optional sign, digits, and an optional fractional part.
+pub(crate) fn parse_double<F>(text: &str, options: &ParseOptions) -> Result<F>
+where
+    F: std::str::FromStr<Err = std::num::ParseFloatError> + Into<f64> + Copy,
+{
+    let text = scalar_text(text, options);
+    let invalid = || Error::from(DecodingError::DoubleDecodeError(text.into()));
+    if options.strict && !is_spec_double(text) && !(options.allow_non_finite && is_non_finite(text))
+    {
+        return Err(invalid());
+    }
+
+    let val: F = text.parse().map_err(DecodingError::from)?;
+    if !options.allow_non_finite && !val.into().is_finite() {
+        return Err(invalid());
+    }
+    Ok(val)
+}
+
+/// Whether `text` is one of the ways Python and Rust write NaN and infinity.
+fn is_non_finite(text: &str) -> bool {
+    let word = text.strip_prefix(&['+', '-'][..]).unwrap_or(text);
+    ["nan", "inf", "infinity"]
+        .iter()
+        .any(|w| word.eq_ignore_ascii_case(w))
+}
+
+/// The text of a `<double>` or `<ex:float>`. The spec has no way to write NaN or infinity, so
+/// they are written the way Python does if the options allow it.
+fn double_text<F>(v: F, options: &WriteOptions) -> Result<String>
+where
+    F: std::fmt::Display + Into<f64> + Copy,
+{
+    let wide: f64 = v.into();
+    if wide.is_finite() {
+        // The spec doesn't allow exponent notation. Display for floats always writes the full
+        // decimal expansion, so this is safe even for very large or small numbers.
+        return Ok(v.to_string());
+    }
+    if !options.allow_non_finite {
+        return Err(EncodingError::Unrepresentable(format!("non-finite double {}", v)).into());
+    }
+
+    Ok(match wide {
+        _ if wide.is_nan() => "nan".to_string(),
+        _ if wide > 0.0 => "inf".to_string(),
+        _ => "-inf".to_string(),
+    })
+}
+
+/// Decodes the text of a `<base64>` tag, appending to the output buffer in chunks rather than
+/// first building a copy of the input. Whitespace is skipped, since many implementations wrap
+/// base64 data into lines.
+pub(crate) fn decode_base64(text: &str) -> Result<Vec<u8>> {
+    let mut ret = Vec::with_capacity(text.len() / 4 * 3);
+    let mut chunk = [0u8; BASE64_DECODE_CHUNK];
+    let mut len = 0;
+
+    for b in text.bytes().filter(|b| !b.is_ascii_whitespace()) {
+        chunk[len] = b;
+        len += 1;
+
+        if len == chunk.len() {
+            BASE64_STANDARD
+                .decode_vec(&chunk[..], &mut ret)
+                .map_err(DecodingError::from)?;
+            len = 0;
+        }
+    }
+
+    if len > 0 {
+        BASE64_STANDARD
+            .decode_vec(&chunk[..len], &mut ret)
+            .map_err(DecodingError::from)?;
+    }
+
+    Ok(ret)
+}
+
+/// Writes `data` as base64 text, encoding it in chunks straight into the writer.
+fn write_base64<W>(writer: &mut Writer<W>, data: &[u8]) -> Result<()>
+where
+    W: std::io::Write,
+{
+    let mut buf = [0u8; BASE64_ENCODE_CHUNK / 3 * 4];
+    for chunk in data.chunks(BASE64_ENCODE_CHUNK) {
+        let len = BASE64_STANDARD
+            .encode_slice(chunk, &mut buf)
+            .expect("buffer fits an encoded chunk");
+        let text = std::str::from_utf8(&buf[..len]).expect("base64 is always ASCII");
+        writer.write_safe_text(text)?;
+    }
+
+    Ok(())
+}
+
+// Both sizes need to line up with base64 groups (4 characters or 3 bytes) so chunks can be
+// processed independently without padding in the middle.
+const BASE64_DECODE_CHUNK: usize = 4096;
+const BASE64_ENCODE_CHUNK: usize = 3072;
+
+pub(crate) fn is_spec_double(text: &str) -> bool {
+    let unsigned = text.strip_prefix(|c| c == '+' || c == '-').unwrap_or(text);
+    let (int, frac) = match unsigned.find('.') {
+        Some(idx) => (&unsigned[..idx], &unsigned[idx + 1..]),
+        None => (unsigned, ""),
+    };
+
+    !(int.is_empty() && frac.is_empty())
+        && int.bytes().all(|b| b.is_ascii_digit())
+        && frac.bytes().all(|b| b.is_ascii_digit())
 }
 
 impl<'de, 'a, 'r> serde::Deserializer<'de> for Deserializer<'a, 'r> {
@@ -34,14 +227,31 @@ impl<'de, 'a, 'r> serde::Deserializer<'de> for Deserializer<'a, 'r> {
     where
         V: serde::de::Visitor<'de>,
     {
+        self.state.count_value()?;
+
+        // What is left of the input, to take unknown elements from as they are.
+        let rest: &'r [u8] = self.reader.get_ref();
+
         let ret = match self.reader.read_event() {
             // If we got text, this is a String value. This is an edge case
             // because it's valid to have a string value without the inner
             // "string" tag.
-            Ok(Event::Text(e)) => visitor.visit_str::<Self::Error>(
-                e.unescape()
-                    .map_err(DecodingError::from)?.as_ref(),
-            )?,
+            Ok(Event::Text(e)) => {
+                let text = e.unescape().map_err(DecodingError::from)?;
+                let text = self.reader.read_string(text, QName(b"value"))?;
+                self.state.check_text(&text)?;
+                return visitor.visit_str(string_text(&text, self.state.options));
+            }
+
+            // CDATA is text as well. The reader doesn't trim it like it does text, but
+            // `trim_strings` does, so a string reads the same whether or not it was sent as CDATA.
+            Ok(Event::CData(e)) => {
+                let text = String::from_utf8(e.into_inner().into_owned())
+                    .map_err(DecodingError::from)?;
+                let text = self.reader.read_string(text.into(), QName(b"value"))?;
+                self.state.check_text(&text)?;
+                return visitor.visit_str(string_text(&text, self.state.options));
+            }
 
             // Alternatively, if we got the matching end tag, this is an empty
             // string value. Note that we need to return early here so the end
@@ -55,7 +265,7 @@ impl<'de, 'a, 'r> serde::Deserializer<'de> for Deserializer<'a, 'r> {
                         .read_text(e.name())
                         .map_err(DecodingError::from)?;
 
-                    let val: i64 = text.parse().map_err(DecodingError::from)?;
+                    let val = parse_int(e.name(), &text, self.state.options)?;
 
                     if let Ok(val) = val.try_into() {
                         visitor.visit_i8::<Self::Error>(val)?
@@ -73,19 +283,13 @@ impl<'de, 'a, 'r> serde::Deserializer<'de> for Deserializer<'a, 'r> {
                         .reader
                         .read_text(e.name())
                         .map_err(DecodingError::from)?;
-                    match text.as_ref() {
-                        "1" => visitor.visit_bool::<Self::Error>(true),
-                        "0" => visitor.visit_bool::<Self::Error>(false),
-                        _ => return Err(DecodingError::BooleanDecodeError(text.into_owned()).into()),
-                    }?
+                    visitor.visit_bool::<Self::Error>(parse_bool(&text, self.state.options)?)?
                 }
 
                 QName(b"string") => {
-                    visitor.visit_str::<Self::Error>(
-                        self.reader
-                            .read_text(e.name())
-                            .map_err(DecodingError::from)?.as_ref(),
-                    )?
+                    let text = self.reader.read_string("".into(), e.name())?;
+                    self.state.check_text(&text)?;
+                    visitor.visit_str::<Self::Error>(string_text(&text, self.state.options))?
                 }
 
                 QName(b"double") => {
@@ -93,15 +297,57 @@ impl<'de, 'a, 'r> serde::Deserializer<'de> for Deserializer<'a, 'r> {
                         .reader
                         .read_text(e.name())
                         .map_err(DecodingError::from)?;
-                    visitor.visit_f64::<Self::Error>(text.parse().map_err(DecodingError::from)?)?
+                    visitor.visit_f64::<Self::Error>(parse_double(&text, self.state.options)?)?
+                }
+
+                QName(b"ex:float") => {
+                    let text = self
+                        .reader
+                        .read_text(e.name())
+                        .map_err(DecodingError::from)?;
+                    visitor.visit_f32::<Self::Error>(parse_double(&text, self.state.options)?)?
+                }
+
+                // Numbers which don't fit any type of ours are read as text, or kept as they are
+                // when reading a Value, so no precision is lost.
+                QName(b"ex:bigdecimal") | QName(b"ex:biginteger") => {
+                    let text = self
+                        .reader
+                        .read_text(e.name())
+                        .map_err(DecodingError::from)?;
+                    let text = quick_xml::escape::unescape(&text)
+                        .map_err(|e| DecodingError::from(quick_xml::Error::from(e)))?;
+                    let text = scalar_text(&text, self.state.options);
+                    self.state.check_text(text)?;
+
+                    if self.state.reading_value() {
+                        let tag = e.local_name().into_inner();
+                        let tag = std::str::from_utf8(tag).map_err(DecodingError::from)?;
+                        let raw = RawValue::extension(tag, text).into_string();
+                        let token = std::iter::once((RAW_TOKEN, raw));
+                        visitor.visit_map(serde::de::value::MapDeserializer::<_, Error>::new(token))?
+                    } else {
+                        visitor.visit_str::<Self::Error>(text)?
+                    }
                 }
 
                 QName(b"dateTime.iso8601") => {
-                    visitor.visit_str::<Self::Error>(
-                        self.reader
-                            .read_text(e.name())
-                            .map_err(DecodingError::from)?.as_ref(),
-                    )?
+                    let text = self
+                        .reader
+                        .read_text(e.name())
+                        .map_err(DecodingError::from)?;
+                    parse_datetime(&text, self.state.options)?;
+
+                    if self.state.reading_value() {
+                        let token = std::iter::once((DATETIME_TOKEN, text.as_ref()));
+                        visitor.visit_map(serde::de::value::MapDeserializer::<_, Error>::new(token))?
+                    } else {
+                        visitor
+                            .visit_str::<Self::Error>(text.as_ref())
+                            .map_err(|err| {
+                                err.relabel_invalid_type(|| format!("dateTime.iso8601 {:?}", text))
+                            })?
+                    }
                 }
 
                 QName(b"base64") => {
@@ -109,15 +355,18 @@ impl<'de, 'a, 'r> serde::Deserializer<'de> for Deserializer<'a, 'r> {
                         .reader
                         .read_text(e.name())
                         .map_err(DecodingError::from)?;
-                    visitor.visit_byte_buf::<Self::Error>(
-                       BASE64_STANDARD.decode(text.as_ref()).map_err(DecodingError::from)?,
-                    )?
+                    self.state.check_text(&text)?;
+                    visitor.visit_byte_buf::<Self::Error>(decode_base64(&text)?)?
                 }
 
-                QName(b"struct") => visitor.visit_map(MapDeserializer::new(self.reader, b"struct"))?,
+                QName(b"struct") => {
+                    let _depth = self.state.enter()?;
+                    visitor.visit_map(MapDeserializer::new(self.reader, self.state, b"struct", self.into_struct))?
+                }
 
                 QName(b"array") => {
-                    visitor.visit_seq(SeqDeserializer::new(self.reader, QName(b"data"), Some(QName(b"array")))?)?
+                    let _depth = self.state.enter()?;
+                    visitor.visit_seq(SeqDeserializer::new(self.reader, self.state, QName(b"data"), Some(QName(b"array")))?)?
                 }
 
                 QName(b"nil") => {
@@ -127,6 +376,13 @@ impl<'de, 'a, 'r> serde::Deserializer<'de> for Deserializer<'a, 'r> {
                     visitor.visit_unit::<Self::Error>()?
                 }
 
+                // Types we don't know are kept byte for byte when asked to, see
+                // ParseOptions::keep_unknown. The contents aren't checked beyond their tags
+                // matching up.
+                _ if self.state.options.keep_unknown && self.state.reading_value() => {
+                    return self.read_unknown(rest, e.name(), visitor)
+                }
+
                 _ => {
                     return Err(DecodingError::UnexpectedTag(
                         String::from_utf8_lossy(e.name().into_inner()).into(),
@@ -164,30 +420,210 @@ impl<'de, 'a, 'r> serde::Deserializer<'de> for Deserializer<'a, 'r> {
         Ok(ret)
     }
 
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        // Look ahead on a copy of the reader, since the element has to be read again by
+        // deserialize_any either way. A nil is handed to the visitor as a unit, which serde reads
+        // as `None`.
+        let mut peek = self.reader.clone();
+        match peek.read_event() {
+            Ok(Event::Start(ref e)) if e.name() == QName(b"nil") => self.deserialize_any(visitor),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        // A Value somewhere in the user's type, which should keep dateTimes as they are.
+        if name == VALUE_TOKEN {
+            let state = self.state;
+            return state.reading_values(|| self.deserialize_any(visitor));
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V>(
+        mut self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.into_struct = true;
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        // Members and elements nobody reads, such as fields a struct doesn't have, are skipped
+        // without decoding. Their tags still have to match up, but their contents aren't checked
+        // and don't count against the depth or text limits.
+        self.state.count_value()?;
+        self.reader
+            .read_to_end(QName(b"value"))
+            .map_err(DecodingError::from)?;
+        visitor.visit_unit()
+    }
+
     forward_to_deserialize_any!(
         bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
-        byte_buf unit unit_struct newtype_struct seq tuple
-        tuple_struct map struct enum identifier ignored_any option
+        byte_buf unit unit_struct seq tuple
+        tuple_struct map enum identifier
     );
 }
 
+/// Writes a [`Value`] directly rather than by transcoding it, since serde has no way to represent
+/// a `<dateTime.iso8601>`.
+pub(crate) fn write_value<W>(
+    writer: &mut Writer<W>,
+    value: &Value,
+    options: &WriteOptions,
+) -> Result<()>
+where
+    W: std::io::Write,
+{
+    use serde::Serializer as _;
+
+    match value {
+        Value::Int(v) => Serializer::new(writer, options).serialize_i32(*v),
+        Value::Int64(v) => Serializer::new(writer, options).serialize_i64(*v),
+        Value::Bool(v) => Serializer::new(writer, options).serialize_bool(*v),
+        Value::String(v) => Serializer::new(writer, options).serialize_str(v),
+        Value::Double(v) => Serializer::new(writer, options).serialize_f64(*v),
+        Value::Float(v) => Serializer::new(writer, options).write_ex_float(*v),
+        Value::Base64(v) => Serializer::new(writer, options).serialize_bytes(v),
+        Value::Nil => Serializer::new(writer, options).serialize_unit(),
+        Value::DateTime(v) => {
+            writer.write_start_tag("value")?;
+            writer.write_safe_tag("dateTime.iso8601", &options.datetime.format(v))?;
+            writer.write_end_tag("value")
+        }
+        Value::Struct(v) => {
+            writer.write_start_tag("value")?;
+            writer.write_start_tag("struct")?;
+            for (name, v) in v {
+                writer.write_start_tag("member")?;
+                writer.write_tag("name", name, options.escape)?;
+                write_value(writer, v, options)?;
+                writer.write_end_tag("member")?;
+            }
+            writer.write_end_tag("struct")?;
+            writer.write_end_tag("value")
+        }
+        Value::Array(v) => {
+            writer.write_start_tag("value")?;
+            writer.write_start_tag("array")?;
+            writer.write_start_tag("data")?;
+            for v in v {
+                write_value(writer, v, options)?;
+            }
+            writer.write_end_tag("data")?;
+            writer.write_end_tag("array")?;
+            writer.write_end_tag("value")
+        }
+        Value::Raw(v) => writer.write_safe_text(v.as_str()),
+    }
+}
+
+/// The length of `value` once written by [`write_value`] with `options`, worked out without
+/// writing it.
+pub(crate) fn value_xml_len(value: &Value, options: &WriteOptions) -> usize {
+    /// The length of a tag holding text of length `len`.
+    fn tag(name: &str, len: usize) -> usize {
+        2 * name.len() + "<></>".len() + len
+    }
+
+    /// The length of a double or float as written by `double_text`.
+    fn double_len<F>(v: F) -> usize
+    where
+        F: std::fmt::Display + Into<f64> + Copy,
+    {
+        let wide: f64 = v.into();
+        if wide.is_finite() {
+            v.to_string().len()
+        } else if wide.is_nan() || wide > 0.0 {
+            "nan".len()
+        } else {
+            "-inf".len()
+        }
+    }
+
+    let inner = match value {
+        Value::Int(v) => tag("int", v.to_string().len()),
+        Value::Int64(v) => tag(int_tag(i32::try_from(*v).is_ok()), v.to_string().len()),
+        Value::Bool(_) => tag("boolean", 1),
+        Value::String(v) => tag("string", super::string_text_len(v, options)),
+        Value::Double(v) => tag("double", double_len(*v)),
+        Value::Float(v) => {
+            let namespace = " xmlns:ex=\"\"".len() + EXTENSIONS_NS.len();
+            tag("ex:float", double_len(*v)) + namespace
+        }
+        Value::Base64(v) => tag("base64", v.len().div_ceil(3) * 4),
+        Value::Nil => "<nil/>".len(),
+        Value::DateTime(v) => tag("dateTime.iso8601", options.datetime.format(v).len()),
+        Value::Struct(v) => {
+            let members: usize = v
+                .iter()
+                .map(|(name, v)| {
+                    let name = tag("name", super::escaped_len(name, options.escape));
+                    tag("member", name + value_xml_len(v, options))
+                })
+                .sum();
+            tag("struct", members)
+        }
+        Value::Array(v) => {
+            let values = v.iter().map(|v| value_xml_len(v, options)).sum();
+            tag("array", tag("data", values))
+        }
+        Value::Raw(v) => return v.as_str().len(),
+    };
+    tag("value", inner)
+}
+
 #[doc(hidden)]
 pub struct Serializer<'a, W>
 where
     W: std::io::Write,
 {
     writer: &'a mut Writer<W>,
+    options: &'a WriteOptions,
 }
 
 impl<'a, W> Serializer<'a, W>
 where
     W: std::io::Write,
 {
-    pub fn new(writer: &'a mut Writer<W>) -> Self {
-        Serializer { writer }
+    pub fn new(writer: &'a mut Writer<W>, options: &'a WriteOptions) -> Self {
+        Serializer { writer, options }
+    }
+
+    /// Writes an `<ex:float>`, declaring the `ex` prefix on the element itself so the value
+    /// stands alone wherever it ends up in the document.
+    fn write_ex_float(self, v: f32) -> Result<()> {
+        let text = double_text(v, self.options)?;
+        let start = BytesStart::new("ex:float").with_attributes([("xmlns:ex", EXTENSIONS_NS)]);
+        self.writer.write_start_tag("value")?;
+        self.writer
+            .write_event(Event::Start(start))
+            .map_err(EncodingError::from)?;
+        self.writer.write_safe_text(&text)?;
+        self.writer.write_end_tag("ex:float")?;
+        self.writer.write_end_tag("value")?;
+        Ok(())
     }
 }
 
+/// The namespace of Apache XML-RPC's extension types.
+pub(crate) const EXTENSIONS_NS: &str = "http://ws.apache.org/xmlrpc/namespaces/extensions";
+
 impl<'a, W> serde::Serializer for Serializer<'a, W>
 where
     W: std::io::Write,
@@ -225,7 +661,8 @@ where
 
     fn serialize_i64(self, v: i64) -> Result<Self::Ok> {
         self.writer.write_start_tag("value")?;
-        self.writer.write_safe_tag("int", &v.to_string())?;
+        self.writer
+            .write_safe_tag(int_tag(i32::try_from(v).is_ok()), &v.to_string())?;
         self.writer.write_end_tag("value")?;
         Ok(())
     }
@@ -244,40 +681,47 @@ where
 
     fn serialize_u64(self, v: u64) -> Result<Self::Ok> {
         self.writer.write_start_tag("value")?;
-        self.writer.write_safe_tag("int", &v.to_string())?;
+        self.writer
+            .write_safe_tag(int_tag(i32::try_from(v).is_ok()), &v.to_string())?;
         self.writer.write_end_tag("value")?;
         Ok(())
     }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok> {
+        if self.options.float_extension {
+            return self.write_ex_float(v);
+        }
         self.serialize_f64(v as f64)
     }
 
     fn serialize_f64(self, v: f64) -> Result<Self::Ok> {
+        let text = double_text(v, self.options)?;
         self.writer.write_start_tag("value")?;
-        self.writer.write_safe_tag("double", &v.to_string())?;
+        self.writer.write_safe_tag("double", &text)?;
         self.writer.write_end_tag("value")?;
         Ok(())
     }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok> {
         self.writer.write_start_tag("value")?;
-        self.writer.write_tag("string", &v.to_string())?;
+        self.writer
+            .write_string_tag("string", &v.to_string(), self.options)?;
         self.writer.write_end_tag("value")?;
         Ok(())
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok> {
         self.writer.write_start_tag("value")?;
-        self.writer.write_tag("string", v)?;
+        self.writer.write_string_tag("string", v, self.options)?;
         self.writer.write_end_tag("value")?;
         Ok(())
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
         self.writer.write_start_tag("value")?;
-        self.writer
-            .write_safe_tag("base64", &BASE64_STANDARD.encode(v))?;
+        self.writer.write_start_tag("base64")?;
+        write_base64(self.writer, v)?;
+        self.writer.write_end_tag("base64")?;
         self.writer.write_end_tag("value")?;
         Ok(())
     }
@@ -315,10 +759,24 @@ where
         self.serialize_unit()
     }
 
-    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Self::Ok>
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Self::Ok>
     where
         T: ?Sized + serde::Serialize,
     {
+        // Raw XML, dateTimes from a Value and big numbers are written like the Value they convert
+        // to.
+        if [
+            RAW_TOKEN,
+            DATETIME_TOKEN,
+            BIGDECIMAL_TOKEN,
+            BIGINTEGER_TOKEN,
+        ]
+        .contains(&name)
+        {
+            let value = crate::value::Serializer::with_options(self.options)
+                .serialize_newtype_struct(name, value)?;
+            return write_value(self.writer, &value, self.options);
+        }
         value.serialize(self)
     }
 
@@ -336,7 +794,7 @@ where
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
-        Self::SerializeSeq::new(self.writer)
+        Self::SerializeSeq::new(self.writer, self.options)
     }
 
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
@@ -362,7 +820,7 @@ where
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
-        Self::SerializeMap::new(self.writer)
+        Self::SerializeMap::new(self.writer, self.options)
     }
 
     fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
@@ -391,7 +849,9 @@ where
     reader.trim_text(true);
 
     reader.expect_tag(QName(b"value"))?;
-    let deserializer = Deserializer::new(&mut reader)?;
+    let options = ParseOptions::default();
+    let state = ParseState::new(&options);
+    let deserializer = Deserializer::new(&mut reader, &state)?;
     T::deserialize(deserializer)
 }
 
@@ -402,7 +862,8 @@ where
     T: ?Sized + serde::Serialize,
 {
     let mut writer = Writer::new(Vec::new());
-    let ser = Serializer::new(&mut writer);
+    let options = WriteOptions::default();
+    let ser = Serializer::new(&mut writer, &options);
     val.serialize(ser)?;
     Ok(String::from_utf8(writer.into_inner()).map_err(DecodingError::from)?)
 }
@@ -419,6 +880,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_from_str() {
         let x: i32 = from_str("<value><int>42</int></value>").unwrap();
         assert_eq!(x, 42);
@@ -438,6 +900,60 @@ mod tests {
         );
     }
 
+    /// Untagged enums and options are read by looking at which element the value has, the same
+    /// way as from a parsed `Value`.
+    #[test]
+    fn test_self_describing() {
+        use crate::{from_value, value_from_str};
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        #[serde(untagged)]
+        enum Reply {
+            Flag(bool),
+            Count(i64),
+            Ratio(f64),
+            Text(String),
+            List(Vec<Reply>),
+            Named { code: i32, detail: Option<String> },
+            Empty(()),
+        }
+
+        let cases = [
+            ("<boolean>1</boolean>", Reply::Flag(true)),
+            ("<i8>5000000000</i8>", Reply::Count(5_000_000_000)),
+            ("<double>0.5</double>", Reply::Ratio(0.5)),
+            ("plain", Reply::Text("plain".into())),
+            (
+                "<array><data><value><int>1</int></value><value>a</value></data></array>",
+                Reply::List(vec![Reply::Count(1), Reply::Text("a".into())]),
+            ),
+            (
+                "<struct><member><name>code</name><value><int>4</int></value></member></struct>",
+                Reply::Named {
+                    code: 4,
+                    detail: None,
+                },
+            ),
+            ("<nil/>", Reply::Empty(())),
+        ];
+        for (xml, expected) in cases.iter() {
+            let xml = format!("<value>{}</value>", xml);
+            assert_eq!(&from_str::<Reply>(&xml).unwrap(), expected, "{}", xml);
+            let value = value_from_str(&xml).unwrap();
+            assert_eq!(&from_value::<Reply>(value).unwrap(), expected, "{}", xml);
+        }
+
+        let x: Option<i32> = from_str("<value><int>42</int></value>").unwrap();
+        assert_eq!(x, Some(42));
+        let x: Option<i32> = from_str("<value><nil/></value>").unwrap();
+        assert_eq!(x, None);
+        let x: Vec<Option<String>> = from_str(
+            "<value><array><data><value>a</value><value><nil/></value><value></value></data></array></value>",
+        )
+        .unwrap();
+        assert_eq!(x, vec![Some("a".into()), None, Some("".into())]);
+    }
+
     #[test]
     fn test_to_string() {
         assert_eq!(&to_string(&42).unwrap(), "<value><int>42</int></value>");
@@ -459,4 +975,158 @@ mod tests {
             "<value><struct><member><name>hello</name><value><string>world</string></value></member></struct></value>",
         )
     }
+
+    /// Doubles are written in plain decimal notation, however large or small.
+    #[test]
+    fn double_to_string() {
+        assert_eq!(
+            &to_string(&1e21).unwrap(),
+            "<value><double>1000000000000000000000</double></value>"
+        );
+        assert_eq!(
+            &to_string(&1.5e-7).unwrap(),
+            "<value><double>0.00000015</double></value>"
+        );
+    }
+
+    #[test]
+    fn raw_value_to_string() {
+        let raw = crate::RawValue::new("<value><ex:i1>7</ex:i1></value>").unwrap();
+        assert_eq!(
+            &to_string(&vec![raw]).unwrap(),
+            "<value><array><data><value><ex:i1>7</ex:i1></value></data></array></value>"
+        );
+    }
+
+    #[test]
+    fn test_value_xml_len() {
+        use crate::{DateTimeFormat, EscapePolicy, Value, WriteOptions};
+
+        let mut record = Value::Nil;
+        record
+            .entry("name & 'id'")
+            .or_insert("<Tom> & \"Jerry\" ]]>");
+        record
+            .entry("when")
+            .or_insert(Value::DateTime("1998-07-17T14:08:55".parse().unwrap()));
+        record.entry("data").or_insert(vec![1u8, 2, 3, 4]);
+        let values = vec![
+            record,
+            Value::Int(-42),
+            Value::Int64(1 << 40),
+            Value::Bool(true),
+            Value::Double(1.5e-7),
+            Value::Double(f64::NEG_INFINITY),
+            Value::Float(0.1),
+            Value::Nil,
+            Value::from(""),
+            Value::Raw(crate::RawValue::new("<value><ex:i1>7</ex:i1></value>").unwrap()),
+        ];
+        let value = Value::Array(values);
+
+        let options = [
+            WriteOptions::default(),
+            WriteOptions::default().escape(EscapePolicy::Minimal),
+            WriteOptions::default().cdata_threshold(Some(2)),
+            WriteOptions::default().datetime_format(DateTimeFormat::EXTENDED),
+        ];
+        for options in options.iter() {
+            let xml = options.value_to_string(value.clone()).unwrap();
+            assert_eq!(super::value_xml_len(&value, options), xml.len(), "{}", xml);
+        }
+        assert_eq!(
+            value.estimated_xml_len(),
+            crate::value_to_string(value.clone()).unwrap().len()
+        );
+    }
+
+    /// Integers which don't fit in 32 bits are written as `<i8>`, so strict parsers read them back.
+    #[test]
+    fn test_write_i8() {
+        use crate::{ParseOptions, ValidateOptions, Value, WriteOptions};
+
+        let args = vec![
+            Value::Int64(5_000_000_000),
+            Value::Int64(i64::MIN),
+            Value::Int(-7),
+        ];
+        let body = WriteOptions::strict()
+            .request_to_string("m", args.clone())
+            .unwrap();
+        assert!(body.contains("<i8>5000000000</i8>"), "{}", body);
+        assert!(body.contains("<int>-7</int>"), "{}", body);
+
+        let (_, params) = ParseOptions::strict().request_from_str(&body).unwrap();
+        assert_eq!(params, args);
+        let violations = ValidateOptions::default()
+            .allow_i8(true)
+            .validate_request(&body);
+        assert!(violations.is_empty(), "{:?}", violations);
+
+        assert_eq!(
+            to_string(&u64::MAX).unwrap(),
+            "<value><i8>18446744073709551615</i8></value>"
+        );
+        assert_eq!(
+            to_string(&3_000_000_000u32).unwrap(),
+            "<value><i8>3000000000</i8></value>"
+        );
+        assert_eq!(to_string(&-3i64).unwrap(), "<value><int>-3</int></value>");
+    }
+
+    #[test]
+    fn test_map_keys() {
+        use std::collections::BTreeMap;
+
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+        struct Port(u16);
+
+        let x: BTreeMap<Port, String> = from_str(
+            "<value><struct><member><name>80</name><value>http</value></member></struct></value>",
+        )
+        .unwrap();
+        assert_eq!(x.get(&Port(80)).map(String::as_str), Some("http"));
+
+        assert_eq!(
+            &to_string(&x).unwrap(),
+            "<value><struct><member><name>80</name><value><string>http</string></value></member></struct></value>",
+        );
+    }
+
+    #[test]
+    fn test_ignored_any() {
+        let nested = "<value><array><data><value><struct><member><name>hello</name>\
+            <value>inner</value></member></struct></value><value><int>x</int></value>\
+            </data></array></value>";
+        let xml = format!(
+            "<value><struct><member><name>skipped</name><value><array><data>{}</data></array>\
+             </value></member><member><name>hello</name><value>world</value></member>\
+             </struct></value>",
+            nested.repeat(100)
+        );
+        let x: Test = from_str(&xml).unwrap();
+        assert_eq!(x.hello, "world");
+
+        let x: (i32, serde::de::IgnoredAny) = from_str(
+            "<value><array><data><value><int>1</int></value><value><nil/></value></data></array></value>",
+        )
+        .unwrap();
+        assert_eq!(x.0, 1);
+
+        let xml = "<value><struct><member><name>skipped</name><value><array></value></array>\
+            </member><member><name>hello</name><value>world</value></member></struct></value>";
+        assert!(from_str::<Test>(xml).is_err());
+    }
+
+    #[test]
+    fn test_base64_chunks() {
+        use crate::{value_from_str, value_to_string, Value};
+
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let xml = value_to_string(Value::Base64(data.clone())).unwrap();
+        assert_eq!(value_from_str(&xml).unwrap(), Value::Base64(data));
+
+        let x = value_from_str("<value><base64>aGVsbG8g\r\n d29y\nbGQ=</base64></value>").unwrap();
+        assert_eq!(x.as_bytes(), Some(&b"hello world"[..]));
+    }
 }
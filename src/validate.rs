@@ -0,0 +1,615 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use crate::util::{decode_base64, is_spec_double, parse_datetime};
+use crate::{ParseOptions, MAX_SAFE_DEPTH};
+
+/// Options for checking documents against the [XML-RPC specification], such as in conformance
+/// tests against other implementations.
+///
+/// Parsing stops at the first problem and lenient parsing lets many of them through. Validation
+/// instead reports every violation of the spec it finds along with its position, so a test can
+/// show everything a peer gets wrong at once. By default only what the spec describes is
+/// accepted, and the extensions this crate understands can be allowed one by one.
+///
+/// ```
+/// use serde_xmlrpc::ValidateOptions;
+///
+/// let body = "<methodResponse><params><param><value><nil/></value></param></params>\
+///     </methodResponse>";
+/// let violations = serde_xmlrpc::validate_response(body);
+/// assert_eq!(violations.len(), 1);
+/// assert_eq!(violations[0].position, 38);
+///
+/// assert!(ValidateOptions::default().allow_nil(true).validate_response(body).is_empty());
+/// ```
+///
+/// [XML-RPC specification]: http://xmlrpc.com/spec.md
+#[derive(Clone, Debug, Default)]
+pub struct ValidateOptions {
+    pub(crate) allow_nil: bool,
+    pub(crate) allow_i8: bool,
+    pub(crate) allow_float: bool,
+}
+
+impl ValidateOptions {
+    /// Allows `<nil/>` values. They are not allowed by default.
+    pub fn allow_nil(mut self, allow: bool) -> Self {
+        self.allow_nil = allow;
+        self
+    }
+
+    /// Allows 64-bit `<i8>` values. They are not allowed by default.
+    pub fn allow_i8(mut self, allow: bool) -> Self {
+        self.allow_i8 = allow;
+        self
+    }
+
+    /// Allows `<ex:float>` values, see [`WriteOptions::float_extension`]. They are not allowed by
+    /// default.
+    ///
+    /// [`WriteOptions::float_extension`]: crate::WriteOptions::float_extension
+    pub fn allow_float(mut self, allow: bool) -> Self {
+        self.allow_float = allow;
+        self
+    }
+
+    /// Checks the body of a request with these options. See
+    /// [`validate_request`](crate::validate_request).
+    pub fn validate_request(&self, input: &str) -> Vec<Violation> {
+        let mut validator = Validator::new(self);
+        if let Some(root) = validator.read(input, "methodCall") {
+            validator.check_call(&root);
+        }
+        validator.violations
+    }
+
+    /// Checks the body of a response with these options. See
+    /// [`validate_response`](crate::validate_response).
+    pub fn validate_response(&self, input: &str) -> Vec<Violation> {
+        let mut validator = Validator::new(self);
+        if let Some(root) = validator.read(input, "methodResponse") {
+            validator.check_response(&root);
+        }
+        validator.violations
+    }
+}
+
+/// A way in which a document doesn't follow the XML-RPC specification.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// The byte offset in the document of the element or text at fault.
+    pub position: usize,
+    /// What is wrong, such as `<i4> value 4294967296 doesn't fit in 32 bits`.
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for Violation {}
+
+/// How deeply elements may be nested, which leaves room for [`MAX_SAFE_DEPTH`] arrays or structs
+/// inside a document's params. Each of them takes three elements, such as
+/// `<value><array><data>`.
+const MAX_ELEMENT_DEPTH: usize = 3 * MAX_SAFE_DEPTH + 4;
+
+/// An element of the document, read in full before checking it.
+pub(crate) struct Element {
+    pub(crate) name: String,
+    pub(crate) position: usize,
+    pub(crate) children: Vec<Element>,
+    /// The element's own text, unescaped and including CDATA sections.
+    pub(crate) text: String,
+    /// Where the first text that isn't whitespace starts, if there is any.
+    pub(crate) text_position: Option<usize>,
+}
+
+impl Element {
+    fn new(name: String, position: usize) -> Self {
+        Element {
+            name,
+            position,
+            children: Vec::new(),
+            text: String::new(),
+            text_position: None,
+        }
+    }
+}
+
+struct Validator<'a> {
+    options: &'a ValidateOptions,
+    violations: Vec<Violation>,
+}
+
+impl<'a> Validator<'a> {
+    fn new(options: &'a ValidateOptions) -> Self {
+        Validator {
+            options,
+            violations: Vec::new(),
+        }
+    }
+
+    fn violation(&mut self, position: usize, message: String) {
+        self.violations.push(Violation { position, message });
+    }
+
+    /// Reads the document into a tree of elements, returning its root if it is well-formed XML
+    /// and is a `root` element.
+    fn read(&mut self, input: &str, root: &str) -> Option<Element> {
+        read_tree(input, &[root], false, &mut self.violations)
+    }
+
+    fn check_call(&mut self, call: &Element) {
+        self.no_text(call);
+        match call.children.as_slice() {
+            [name] | [name, _] if name.name == "methodName" => {
+                self.check_method_name(name);
+                if let Some(params) = call.children.get(1) {
+                    self.check_params(params, false);
+                }
+            }
+            _ => self.violation(
+                call.position,
+                "<methodCall> must contain a <methodName>, optionally followed by <params>".into(),
+            ),
+        }
+    }
+
+    fn check_method_name(&mut self, name: &Element) {
+        self.no_children(name);
+        let valid = |c: char| c.is_ascii_alphanumeric() || "_.:/".contains(c);
+        if name.text.is_empty() || !name.text.chars().all(valid) {
+            let message = format!("invalid method name {:?}", name.text);
+            self.violation(name.position, message);
+        }
+    }
+
+    fn check_response(&mut self, response: &Element) {
+        self.no_text(response);
+        match response.children.as_slice() {
+            [params] if params.name == "params" => self.check_params(params, true),
+            [fault] if fault.name == "fault" => self.check_fault(fault),
+            _ => self.violation(
+                response.position,
+                "<methodResponse> must contain either <params> or <fault>".into(),
+            ),
+        }
+    }
+
+    /// Checks `<params>`, of which a response must have exactly one.
+    fn check_params(&mut self, params: &Element, response: bool) {
+        if params.name != "params" {
+            return self.unexpected(params, "methodCall");
+        }
+
+        self.no_text(params);
+        let mut count = 0;
+        for param in &params.children {
+            if param.name != "param" {
+                self.unexpected(param, "params");
+            } else if let Some(value) = self.only_child(param, "value") {
+                count += 1;
+                self.check_value(value);
+            }
+        }
+
+        if response && count != 1 {
+            let message = format!("a response must have exactly one <param>, found {}", count);
+            self.violation(params.position, message);
+        }
+    }
+
+    fn check_fault(&mut self, fault: &Element) {
+        let value = match self.only_child(fault, "value") {
+            Some(value) => value,
+            None => return,
+        };
+        self.check_value(value);
+
+        let members = match value.children.as_slice() {
+            [s] if s.name == "struct" => s.children.as_slice(),
+            _ => &[],
+        };
+        let member_type = |name: &str| {
+            members
+                .iter()
+                .find_map(|member| match member.children.as_slice() {
+                    [n, v] if n.text == name => Some(match v.children.first() {
+                        Some(t) => t.name.as_str(),
+                        None => "string",
+                    }),
+                    _ => None,
+                })
+        };
+
+        let code = matches!(member_type("faultCode"), Some("int") | Some("i4"));
+        if members.len() != 2 || !code || member_type("faultString") != Some("string") {
+            let message =
+                "a fault must be a struct with an int faultCode and a string faultString".into();
+            self.violation(value.position, message);
+        }
+    }
+
+    fn check_value(&mut self, value: &Element) {
+        // A value without a type is a string.
+        if let Some((typed, rest)) = value.children.split_first() {
+            self.no_text(value);
+            self.check_typed(typed);
+            for other in rest {
+                self.unexpected(other, "value");
+            }
+        }
+    }
+
+    /// Checks the element giving a value its type, such as `<int>`.
+    fn check_typed(&mut self, typed: &Element) {
+        let allowed = match typed.name.as_str() {
+            "nil" => self.options.allow_nil,
+            "i8" => self.options.allow_i8,
+            "ex:float" => self.options.allow_float,
+            _ => true,
+        };
+        if !allowed {
+            let message = format!("<{}> is an extension which isn't allowed", typed.name);
+            return self.violation(typed.position, message);
+        }
+
+        let text = typed.text.as_str();
+        let valid = match typed.name.as_str() {
+            "i4" | "int" => match text.parse::<i64>() {
+                Ok(int) if i32::try_from(int).is_err() => {
+                    let message = format!("<{}> value {} doesn't fit in 32 bits", typed.name, int);
+                    self.violation(typed.position, message);
+                    true
+                }
+                Ok(_) => true,
+                Err(_) => false,
+            },
+            "i8" => text.parse::<i64>().is_ok(),
+            "boolean" => text == "0" || text == "1",
+            "string" => true,
+            "double" | "ex:float" => is_spec_double(text),
+            "dateTime.iso8601" => parse_datetime(text, &ParseOptions::strict()).is_ok(),
+            "base64" => decode_base64(text).is_ok(),
+            "nil" => text.is_empty(),
+            "struct" => return self.check_struct(typed),
+            "array" => return self.check_array(typed),
+            _ => {
+                let message = format!("unknown type <{}>", typed.name);
+                return self.violation(typed.position, message);
+            }
+        };
+
+        self.no_children(typed);
+        if !valid {
+            let message = format!("invalid <{}> value {:?}", typed.name, text);
+            self.violation(typed.position, message);
+        }
+    }
+
+    fn check_struct(&mut self, s: &Element) {
+        self.no_text(s);
+        for member in &s.children {
+            if member.name != "member" {
+                self.unexpected(member, "struct");
+                continue;
+            }
+
+            self.no_text(member);
+            match member.children.as_slice() {
+                [name, value] if name.name == "name" && value.name == "value" => {
+                    self.no_children(name);
+                    self.check_value(value);
+                }
+                _ => self.violation(
+                    member.position,
+                    "<member> must contain a <name> followed by a <value>".into(),
+                ),
+            }
+        }
+    }
+
+    fn check_array(&mut self, array: &Element) {
+        let data = match self.only_child(array, "data") {
+            Some(data) => data,
+            None => return,
+        };
+
+        self.no_text(data);
+        for value in &data.children {
+            if value.name == "value" {
+                self.check_value(value);
+            } else {
+                self.unexpected(value, "data");
+            }
+        }
+    }
+
+    /// Returns the one child of `element`, which must be a `name` element.
+    fn only_child<'e>(&mut self, element: &'e Element, name: &str) -> Option<&'e Element> {
+        self.no_text(element);
+        let mut found = None;
+        for child in &element.children {
+            if child.name == name && found.is_none() {
+                found = Some(child);
+            } else {
+                self.unexpected(child, &element.name);
+            }
+        }
+
+        if found.is_none() {
+            let message = format!("<{}> is missing <{}>", element.name, name);
+            self.violation(element.position, message);
+        }
+        found
+    }
+
+    fn unexpected(&mut self, child: &Element, parent: &str) {
+        let message = format!("unexpected <{}> in <{}>", child.name, parent);
+        self.violation(child.position, message);
+    }
+
+    fn no_children(&mut self, element: &Element) {
+        for child in &element.children {
+            self.unexpected(child, &element.name);
+        }
+    }
+
+    fn no_text(&mut self, element: &Element) {
+        if let Some(position) = element.text_position {
+            let message = format!("unexpected text in <{}>", element.name);
+            self.violation(position, message);
+        }
+    }
+}
+
+/// Reads the document into a tree of elements, returning its root if it is well-formed XML and
+/// is one of the `roots` elements. A `<!DOCTYPE>` is a violation unless `allow_doctype` is set.
+pub(crate) fn read_tree(
+    input: &str,
+    roots: &[&str],
+    allow_doctype: bool,
+    violations: &mut Vec<Violation>,
+) -> Option<Element> {
+    let mut violation = |position: usize, message: String| {
+        violations.push(Violation { position, message });
+    };
+    let mut reader = Reader::from_str(input);
+    reader.expand_empty_elements(true);
+    let mut stack: Vec<Element> = Vec::new();
+    let mut document: Option<Element> = None;
+
+    loop {
+        let position = reader.buffer_position();
+        let event = match reader.read_event() {
+            Ok(event) => event,
+            Err(err) => {
+                violation(reader.buffer_position(), format!("malformed XML: {}", err));
+                return None;
+            }
+        };
+
+        let text = match event {
+            Event::Start(e) => {
+                if stack.is_empty() && document.is_some() {
+                    violation(position, "more than one root element".into());
+                    return None;
+                }
+                if stack.len() == MAX_ELEMENT_DEPTH {
+                    let message = format!("elements nested more than {} deep", stack.len());
+                    violation(position, message);
+                    return None;
+                }
+                let name = String::from_utf8_lossy(e.name().into_inner()).into_owned();
+                stack.push(Element::new(name, position));
+                continue;
+            }
+            Event::End(_) => {
+                let element = stack.pop().expect("end tags are matched with start tags");
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => document = Some(element),
+                }
+                continue;
+            }
+            Event::Text(e) => match e.unescape() {
+                Ok(text) => text.into_owned(),
+                Err(err) => {
+                    violation(position, format!("malformed XML: {}", err));
+                    return None;
+                }
+            },
+            Event::CData(e) => match String::from_utf8(e.into_inner().into_owned()) {
+                Ok(text) => text,
+                Err(err) => {
+                    violation(position, format!("malformed UTF-8: {}", err));
+                    return None;
+                }
+            },
+            Event::DocType(_) if allow_doctype => continue,
+            Event::DocType(_) => {
+                let message = "document type declarations are not allowed".into();
+                violation(position, message);
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+
+        match stack.last_mut() {
+            Some(element) => {
+                if element.text_position.is_none() && !text.trim().is_empty() {
+                    element.text_position = Some(position);
+                }
+                element.text.push_str(&text);
+            }
+            None if !text.trim().is_empty() => {
+                violation(position, "text outside the root element".into());
+            }
+            None => {}
+        }
+    }
+
+    if let Some(element) = stack.first() {
+        let message = format!("unexpected end of document in <{}>", element.name);
+        violation(input.len(), message);
+        return None;
+    }
+
+    match document {
+        Some(element) if roots.contains(&element.name.as_str()) => Some(element),
+        Some(element) => {
+            let message = format!(
+                "expected <{}>, found <{}>",
+                roots.join("> or <"),
+                element.name
+            );
+            violation(element.position, message);
+            None
+        }
+        None => {
+            violation(input.len(), format!("missing <{}>", roots.join("> or <")));
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(violations: Vec<Violation>) -> Vec<String> {
+        violations.into_iter().map(|v| v.message).collect()
+    }
+
+    #[test]
+    fn spec_documents() {
+        let call = r#"<?xml version="1.0"?>
+            <methodCall>
+                <methodName>examples.getStateName</methodName>
+                <params><param><value><i4>41</i4></value></param></params>
+            </methodCall>"#;
+        assert_eq!(crate::validate_request(call), vec![]);
+
+        let response = r#"<?xml version="1.0"?>
+            <methodResponse>
+                <params><param><value><string>South Dakota</string></value></param></params>
+            </methodResponse>"#;
+        assert_eq!(crate::validate_response(response), vec![]);
+
+        let fault = r#"<?xml version="1.0"?>
+            <methodResponse><fault><value><struct>
+                <member><name>faultCode</name><value><int>4</int></value></member>
+                <member><name>faultString</name><value>Too many parameters.</value></member>
+            </struct></value></fault></methodResponse>"#;
+        assert_eq!(crate::validate_response(fault), vec![]);
+
+        let values = "<methodCall><methodName>a</methodName><params><param><value><array><data>\
+            <value><boolean>1</boolean></value><value><double>-12.5</double></value>\
+            <value><dateTime.iso8601>19980717T14:08:55</dateTime.iso8601></value>\
+            <value><base64>eW91IGNhbid0IHJlYWQgdGhpcyE=</base64></value><value/>\
+            </data></array></value></param></params></methodCall>";
+        assert_eq!(crate::validate_request(values), vec![]);
+    }
+
+    #[test]
+    fn violations() {
+        let response = "<methodResponse><params>\
+            <param><value><i4>4294967296</i4></value></param>\
+            <param><value><double>1e3</double><int>1</int></value></param>\
+            </params></methodResponse>";
+        assert_eq!(
+            crate::validate_response(response),
+            vec![
+                Violation {
+                    position: 38,
+                    message: "<i4> value 4294967296 doesn't fit in 32 bits".into(),
+                },
+                Violation {
+                    position: 87,
+                    message: "invalid <double> value \"1e3\"".into(),
+                },
+                Violation {
+                    position: 107,
+                    message: "unexpected <int> in <value>".into(),
+                },
+                Violation {
+                    position: 16,
+                    message: "a response must have exactly one <param>, found 2".into(),
+                },
+            ]
+        );
+
+        let call = "<methodCall><methodName>a b</methodName><params><param><value>\
+            <struct><member><value><i8>1</i8></value></member></struct>\
+            </value></param></params></methodCall>";
+        assert_eq!(
+            messages(crate::validate_request(call)),
+            vec![
+                "invalid method name \"a b\"",
+                "<member> must contain a <name> followed by a <value>",
+            ]
+        );
+
+        let fault = "<methodResponse><fault><value><struct>\
+            <member><name>faultCode</name><value>4</value></member>\
+            </struct></value></fault></methodResponse>";
+        assert_eq!(
+            messages(crate::validate_response(fault)),
+            vec!["a fault must be a struct with an int faultCode and a string faultString"]
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(
+            messages(crate::validate_request("<methodResponse/>")),
+            vec!["expected <methodCall>, found <methodResponse>"]
+        );
+        assert_eq!(
+            messages(crate::validate_request("<methodCall><methodName>")),
+            vec!["unexpected end of document in <methodCall>"]
+        );
+        assert_eq!(crate::validate_request("<methodCall></params>").len(), 1);
+
+        let deep = "<value><array><data>".repeat(MAX_SAFE_DEPTH + 2);
+        let call = format!(
+            "<methodCall><methodName>a</methodName><params><param>{}",
+            deep
+        );
+        assert_eq!(
+            messages(crate::validate_request(&call)),
+            vec![format!(
+                "elements nested more than {} deep",
+                MAX_ELEMENT_DEPTH
+            )]
+        );
+    }
+
+    #[test]
+    fn extensions() {
+        let response = |value: &str| {
+            format!(
+                "<methodResponse><params><param><value>{}</value></param></params></methodResponse>",
+                value
+            )
+        };
+        let all = ValidateOptions::default()
+            .allow_nil(true)
+            .allow_i8(true)
+            .allow_float(true);
+
+        for value in ["<nil/>", "<i8>4294967296</i8>", "<ex:float>1.5</ex:float>"].iter() {
+            assert_eq!(crate::validate_response(&response(value)).len(), 1);
+            assert_eq!(all.validate_response(&response(value)), vec![]);
+        }
+        assert_eq!(all.validate_response(&response("<nil>x</nil>")).len(), 1);
+    }
+}
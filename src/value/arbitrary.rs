@@ -0,0 +1,113 @@
+//! [`Arbitrary`] implementations for fuzzing and property tests.
+//!
+//! Generated values are ones a document can carry, so they can be written and parsed back:
+//!
+//! * Arrays and structs nest at most [`MAX_DEPTH`] deep, within the default limits.
+//! * Strings only hold characters which are allowed in XML, and member names don't start or end
+//!   with whitespace, which parsers trim.
+//!
+//! Anything else is left to chance, such as doubles being NaN or infinite.
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use super::{Map, Value};
+use crate::XmlRpcDateTime;
+
+/// How deep generated arrays and structs nest.
+const MAX_DEPTH: usize = 8;
+
+impl<'a> Arbitrary<'a> for Value {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        arbitrary_value(u, 0)
+    }
+}
+
+fn arbitrary_value(u: &mut Unstructured<'_>, depth: usize) -> Result<Value> {
+    // Only scalars once the data runs out or the maximum depth is reached.
+    let variants = if depth < MAX_DEPTH && !u.is_empty() {
+        11
+    } else {
+        9
+    };
+
+    Ok(match u.choose_index(variants)? {
+        0 => Value::Int(u.arbitrary()?),
+        1 => Value::Int64(u.arbitrary()?),
+        2 => Value::Bool(u.arbitrary()?),
+        3 => Value::String(arbitrary_text(u)?),
+        4 => Value::Double(u.arbitrary()?),
+        5 => Value::Float(u.arbitrary()?),
+        6 => Value::DateTime(u.arbitrary()?),
+        7 => Value::Base64(u.arbitrary()?),
+        8 => Value::Nil,
+        9 => {
+            let mut members = Map::new();
+            for _ in 0..u.arbitrary_len::<(String, u8)>()? {
+                let name = arbitrary_text(u)?.trim().to_string();
+                members.insert(name, arbitrary_value(u, depth + 1)?);
+            }
+            Value::Struct(members)
+        }
+        _ => {
+            let mut values = Vec::new();
+            for _ in 0..u.arbitrary_len::<u8>()? {
+                values.push(arbitrary_value(u, depth + 1)?);
+            }
+            Value::Array(values)
+        }
+    })
+}
+
+/// Generates a string without the characters XML doesn't allow, such as most control characters.
+fn arbitrary_text(u: &mut Unstructured<'_>) -> Result<String> {
+    let text: String = u.arbitrary()?;
+    Ok(text.chars().filter(|&c| super::is_xml_char(c)).collect())
+}
+
+/// Generates dates/times with every field in range, though not necessarily existing dates such
+/// as February 31st.
+impl<'a> Arbitrary<'a> for XmlRpcDateTime {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(XmlRpcDateTime {
+            year: u.int_in_range(0..=9999)?,
+            month: u.int_in_range(1..=12)?,
+            day: u.int_in_range(1..=31)?,
+            hour: u.int_in_range(0..=23)?,
+            minute: u.int_in_range(0..=59)?,
+            second: u.int_in_range(0..=60)?,
+            millisecond: u.int_in_range(0..=999)?,
+            offset_minutes: u.int_in_range(-(24 * 60 - 1)..=24 * 60 - 1)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParseOptions, WriteOptions};
+
+    /// Writing a parsed value reproduces the document it was parsed from. The first write isn't
+    /// compared, since parsing loses the parts of a value the document can't express, such as
+    /// whether a small integer was an `Int64`.
+    #[test]
+    fn arbitrary_roundtrip() {
+        let options = WriteOptions::default();
+        // A xorshift generator, to get the same inputs on every run.
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next_byte = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        };
+
+        for _ in 0..256 {
+            let data: Vec<u8> = (0..512).map(|_| next_byte()).collect();
+            let value = Value::arbitrary(&mut Unstructured::new(&data)).unwrap();
+
+            let body = options.value_to_string(value).unwrap();
+            let parsed = ParseOptions::lenient().value_from_str(&body).unwrap();
+            assert!(options.value_to_string(parsed).unwrap() == body, "{}", body);
+        }
+    }
+}
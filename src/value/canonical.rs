@@ -0,0 +1,109 @@
+use core::convert::TryFrom;
+
+use super::Value;
+
+impl Value {
+    /// Rewrites the value into a canonical form, so that values which mean the same compare equal
+    /// and are written as the same XML, such as before hashing or signing them or comparing them
+    /// in tests:
+    ///
+    /// * Struct members are sorted by name, which only changes anything with the
+    ///   `preserve_order` feature.
+    /// * A [`Value::Int64`] which fits in 32 bits becomes a [`Value::Int`].
+    /// * Every NaN becomes the same NaN.
+    /// * Dates are converted to UTC with [`XmlRpcDateTime::to_utc`].
+    /// * A [`Value::Raw`] holding one of the standard types is replaced by the value it holds.
+    ///   Extension types are kept as they are. Without the `std` feature, which is needed to
+    ///   parse them, every raw value is kept.
+    ///
+    /// [`WriteOptions::canonical`](crate::WriteOptions::canonical) does this while writing.
+    ///
+    /// ```
+    /// use serde_xmlrpc::{Value, XmlRpcDateTime};
+    ///
+    /// let local: XmlRpcDateTime = "1998-07-17T16:08:55+02:00".parse().unwrap();
+    /// let mut a = Value::Nil;
+    /// a.entry("id").or_insert(Value::Int64(7));
+    /// a.entry("at").or_insert(local);
+    ///
+    /// let utc: XmlRpcDateTime = "19980717T14:08:55Z".parse().unwrap();
+    /// let mut b = Value::Nil;
+    /// b.entry("at").or_insert(utc);
+    /// b.entry("id").or_insert(7);
+    ///
+    /// assert_ne!(a, b);
+    /// a.canonicalize();
+    /// b.canonicalize();
+    /// assert_eq!(a, b);
+    /// ```
+    ///
+    /// [`XmlRpcDateTime::to_utc`]: crate::XmlRpcDateTime::to_utc
+    pub fn canonicalize(&mut self) {
+        match self {
+            Value::Struct(members) => {
+                members.sort_keys();
+                members.values_mut().for_each(Value::canonicalize);
+            }
+            Value::Array(values) => values.iter_mut().for_each(Value::canonicalize),
+            Value::Int64(v) => {
+                if let Ok(v) = i32::try_from(*v) {
+                    *self = Value::Int(v);
+                }
+            }
+            Value::Double(v) if v.is_nan() => *v = f64::NAN,
+            Value::Float(v) if v.is_nan() => *v = f32::NAN,
+            Value::DateTime(dt) => *dt = dt.to_utc(),
+            #[cfg(feature = "std")]
+            Value::Raw(raw) => match crate::value_from_str(raw.as_str()) {
+                Ok(Value::Raw(_)) | Err(_) => {}
+                Ok(mut value) => {
+                    value.canonicalize();
+                    *self = value;
+                }
+            },
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Map, RawValue, Value, XmlRpcDateTime};
+
+    #[test]
+    fn canonicalize() {
+        let dt: XmlRpcDateTime = "2024-01-01T00:30:00+01:00".parse().unwrap();
+        let members = vec![
+            ("b", Value::Double(-f64::NAN)),
+            ("a", Value::Int64(1 << 40)),
+            ("c", Value::Array(vec![Value::Int64(-1), Value::DateTime(dt)])),
+            (
+                "d",
+                Value::Raw(RawValue::new("<value><struct><member><name>z</name><value><i8>2</i8></value></member></struct></value>").unwrap()),
+            ),
+            (
+                "e",
+                Value::Raw(RawValue::new("<value><ex:i1>7</ex:i1></value>").unwrap()),
+            ),
+        ];
+        let mut value = Value::Struct(
+            members
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect::<Map<_, _>>(),
+        );
+        value.canonicalize();
+
+        let keys: Vec<_> = value.as_struct().unwrap().keys().cloned().collect();
+        assert_eq!(keys, ["a", "b", "c", "d", "e"]);
+        assert_eq!(value.pointer("/a"), Some(&Value::Int64(1 << 40)));
+        assert_eq!(value.pointer("/b"), Some(&Value::Double(f64::NAN)));
+        assert_eq!(value.pointer("/c/0"), Some(&Value::Int(-1)));
+        assert_eq!(
+            value.pointer("/c/1").and_then(Value::as_datetime),
+            Some("2023-12-31T23:30:00Z".parse().unwrap())
+        );
+        assert_eq!(value.pointer("/d/z"), Some(&Value::Int(2)));
+        assert!(matches!(value.pointer("/e"), Some(Value::Raw(_))));
+    }
+}
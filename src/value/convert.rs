@@ -0,0 +1,233 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use super::{Map, Value, ValueKind};
+use crate::error::DecodingError;
+use crate::XmlRpcDateTime;
+
+/// A type with its own conversion to and from [`Value`], for types which don't implement serde's
+/// traits or need a different representation over XML-RPC.
+///
+/// Implementations can be used as params with [`Values::param`] and read back with
+/// [`Values::get`], and containers of them (`Vec<T>`, `Option<T>` and maps with string keys)
+/// convert element by element.
+///
+/// ```
+/// use serde_xmlrpc::{Value, Values, XmlRpcType};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Celsius(f64);
+///
+/// impl XmlRpcType for Celsius {
+///     fn to_value(&self) -> Value {
+///         Value::Double(self.0)
+///     }
+///
+///     fn from_value(value: &Value) -> serde_xmlrpc::Result<Self> {
+///         f64::from_value(value).map(Celsius)
+///     }
+/// }
+///
+/// let params = Values::new().param(&Celsius(21.5)).param(&"kitchen".to_string());
+/// assert_eq!(params.get::<Celsius>(0).unwrap(), Celsius(21.5));
+/// assert!(params.get::<Celsius>(1).is_err());
+/// ```
+///
+/// [`Values::param`]: crate::Values::param
+/// [`Values::get`]: crate::Values::get
+pub trait XmlRpcType: Sized {
+    /// Converts `self` into a `Value`.
+    fn to_value(&self) -> Value;
+
+    /// Converts a `Value` back into `Self`, failing if it has the wrong type.
+    fn from_value(value: &Value) -> crate::Result<Self>;
+}
+
+pub(super) fn invalid_type(value: &Value, expected: ValueKind) -> crate::Error {
+    DecodingError::InvalidType {
+        found: value.kind().to_string(),
+        expected: expected.to_string(),
+    }
+    .into()
+}
+
+impl XmlRpcType for Value {
+    fn to_value(&self) -> Value {
+        self.clone()
+    }
+
+    fn from_value(value: &Value) -> crate::Result<Self> {
+        Ok(value.clone())
+    }
+}
+
+impl XmlRpcType for i32 {
+    fn to_value(&self) -> Value {
+        Value::Int(*self)
+    }
+
+    fn from_value(value: &Value) -> crate::Result<Self> {
+        match *value {
+            Value::Int(i) => Ok(i),
+            _ => Err(invalid_type(value, ValueKind::INT)),
+        }
+    }
+}
+
+/// Reads either integer width, since a 64-bit value may have been sent as an `<int>`.
+impl XmlRpcType for i64 {
+    fn to_value(&self) -> Value {
+        Value::Int64(*self)
+    }
+
+    fn from_value(value: &Value) -> crate::Result<Self> {
+        match *value {
+            Value::Int(i) => Ok(i.into()),
+            Value::Int64(i) => Ok(i),
+            _ => Err(invalid_type(value, ValueKind::INTEGER)),
+        }
+    }
+}
+
+impl XmlRpcType for bool {
+    fn to_value(&self) -> Value {
+        Value::Bool(*self)
+    }
+
+    fn from_value(value: &Value) -> crate::Result<Self> {
+        match *value {
+            Value::Bool(b) => Ok(b),
+            _ => Err(invalid_type(value, ValueKind::BOOL)),
+        }
+    }
+}
+
+impl XmlRpcType for f64 {
+    fn to_value(&self) -> Value {
+        Value::Double(*self)
+    }
+
+    fn from_value(value: &Value) -> crate::Result<Self> {
+        match *value {
+            Value::Double(d) => Ok(d),
+            Value::Float(f) => Ok(f.into()),
+            _ => Err(invalid_type(value, ValueKind::DOUBLE | ValueKind::FLOAT)),
+        }
+    }
+}
+
+impl XmlRpcType for String {
+    fn to_value(&self) -> Value {
+        Value::String(self.clone())
+    }
+
+    fn from_value(value: &Value) -> crate::Result<Self> {
+        match value {
+            Value::String(s) => Ok(s.clone()),
+            _ => Err(invalid_type(value, ValueKind::STRING)),
+        }
+    }
+}
+
+impl XmlRpcType for XmlRpcDateTime {
+    fn to_value(&self) -> Value {
+        Value::DateTime(*self)
+    }
+
+    fn from_value(value: &Value) -> crate::Result<Self> {
+        match *value {
+            Value::DateTime(dt) => Ok(dt),
+            _ => Err(invalid_type(value, ValueKind::DATETIME)),
+        }
+    }
+}
+
+/// `None` is written as `<nil/>`.
+impl<T: XmlRpcType> XmlRpcType for Option<T> {
+    fn to_value(&self) -> Value {
+        match self {
+            Some(v) => v.to_value(),
+            None => Value::Nil,
+        }
+    }
+
+    fn from_value(value: &Value) -> crate::Result<Self> {
+        match value {
+            Value::Nil => Ok(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: XmlRpcType> XmlRpcType for Vec<T> {
+    fn to_value(&self) -> Value {
+        Value::Array(self.iter().map(T::to_value).collect())
+    }
+
+    fn from_value(value: &Value) -> crate::Result<Self> {
+        match value {
+            Value::Array(values) => values.iter().map(T::from_value).collect(),
+            _ => Err(invalid_type(value, ValueKind::ARRAY)),
+        }
+    }
+}
+
+impl<T: XmlRpcType> XmlRpcType for Map<String, T> {
+    fn to_value(&self) -> Value {
+        Value::Struct(
+            self.iter()
+                .map(|(k, v)| (k.clone(), v.to_value()))
+                .collect(),
+        )
+    }
+
+    fn from_value(value: &Value) -> crate::Result<Self> {
+        match value {
+            Value::Struct(members) => members
+                .iter()
+                .map(|(k, v)| Ok((k.clone(), T::from_value(v)?)))
+                .collect(),
+            _ => Err(invalid_type(value, ValueKind::STRUCT)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn containers() {
+        let values = vec![Some(1), None, Some(3)];
+        let value = values.to_value();
+        assert_eq!(
+            value,
+            Value::Array(vec![Value::Int(1), Value::Nil, Value::Int(3)])
+        );
+        assert_eq!(Vec::<Option<i32>>::from_value(&value).unwrap(), values);
+
+        let err = Vec::<i32>::from_value(&value).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "decoding error: invalid type: nil, expected int"
+        );
+    }
+
+    #[test]
+    fn integer_widths() {
+        assert_eq!(i64::from_value(&Value::Int(7)).unwrap(), 7);
+        assert!(i32::from_value(&Value::Int64(7)).is_err());
+    }
+
+    #[test]
+    fn float_widths() {
+        use std::convert::TryFrom;
+
+        for value in [Value::Double(0.5), Value::Float(0.5)] {
+            assert_eq!(value.as_f64(), Some(0.5));
+            assert_eq!(f64::from_value(&value).unwrap(), 0.5);
+            assert_eq!(f64::try_from(value).unwrap(), 0.5);
+        }
+        assert!(f64::try_from(Value::Int(1)).is_err());
+    }
+}
@@ -1,17 +1,169 @@
-use std::collections::BTreeMap;
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
 
-use serde::de::Visitor;
+use serde::de::{IntoDeserializer, Unexpected, Visitor};
 use serde::forward_to_deserialize_any;
 
-use crate::{Error, Result, Value};
+#[cfg(feature = "std")]
+use super::{RawValue, RAW_TOKEN};
+use super::{DATETIME_TOKEN, VALUE_TOKEN};
+use crate::error::DecodingError;
+use crate::{Error, Map, ParseOptions, Result, Value};
+
+/// Reads a value from any self-describing format. Integers become [`Value::Int`] when they fit in
+/// 32 bits and [`Value::Int64`] otherwise, sequences become arrays, and maps become structs with
+/// their keys as member names.
+///
+/// Deserializers from this crate hand dateTime values over as they are, except inside a
+/// `#[serde(flatten)]` field or an untagged enum: serde reads those before knowing their type, so
+/// dateTimes arrive as strings.
+impl<'de> serde::Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(VALUE_TOKEN, ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        formatter.write_str("an XML-RPC value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> core::result::Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> core::result::Result<Value, E> {
+        Ok(match i32::try_from(v) {
+            Ok(v) => Value::Int(v),
+            Err(_) => Value::Int64(v),
+        })
+    }
+
+    fn visit_u64<E>(self, v: u64) -> core::result::Result<Value, E>
+    where
+        E: serde::de::Error,
+    {
+        match i64::try_from(v) {
+            Ok(v) => self.visit_i64(v),
+            Err(_) => Err(E::invalid_value(Unexpected::Unsigned(v), &self)),
+        }
+    }
+
+    fn visit_f32<E>(self, v: f32) -> core::result::Result<Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> core::result::Result<Value, E> {
+        Ok(Value::Double(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> core::result::Result<Value, E> {
+        Ok(Value::String(v.to_owned()))
+    }
+
+    fn visit_string<E>(self, v: String) -> core::result::Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> core::result::Result<Value, E> {
+        Ok(Value::Base64(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> core::result::Result<Value, E> {
+        Ok(Value::Base64(v))
+    }
+
+    fn visit_none<E>(self) -> core::result::Result<Value, E> {
+        Ok(Value::Nil)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> core::result::Result<Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        serde::Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_unit<E>(self) -> core::result::Result<Value, E> {
+        Ok(Value::Nil)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> core::result::Result<Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> core::result::Result<Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> core::result::Result<Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        let mut members = Map::new();
+        while let Some((name, value)) = map.next_entry::<String, Value>()? {
+            members.insert(name, value);
+        }
+
+        if let (1, Some(Value::String(text))) = (members.len(), members.get(DATETIME_TOKEN)) {
+            return crate::datetime::parse_datetime(text, &ParseOptions::lenient())
+                .map(Value::DateTime)
+                .map_err(serde::de::Error::custom);
+        }
+        #[cfg(feature = "std")]
+        if let (1, Some(Value::String(xml))) = (members.len(), members.get(RAW_TOKEN)) {
+            return RawValue::new(xml.as_str())
+                .map(Value::Raw)
+                .map_err(serde::de::Error::custom);
+        }
+        Ok(Value::Struct(members))
+    }
+}
 
 pub struct Deserializer {
     val: Value,
+    /// Whether errors from nested values get the path to them, see [`Value::extract`].
+    track_path: bool,
+    /// Whether a [`Value`] is being deserialized, in which case dateTimes are handed over marked
+    /// with [`DATETIME_TOKEN`] rather than as strings.
+    reading_value: bool,
 }
 
 impl Deserializer {
     pub fn from_value(input: Value) -> Self {
-        Deserializer { val: input }
+        Deserializer {
+            val: input,
+            track_path: false,
+            reading_value: false,
+        }
+    }
+
+    pub(crate) fn tracking_path(input: Value) -> Self {
+        Deserializer {
+            val: input,
+            track_path: true,
+            reading_value: false,
+        }
     }
 }
 
@@ -28,17 +180,35 @@ impl<'de> serde::Deserializer<'de> for Deserializer {
             Value::Bool(v) => visitor.visit_bool(v),
             Value::String(v) => visitor.visit_string(v),
             Value::Double(v) => visitor.visit_f64(v),
-            Value::DateTime(v) => visitor.visit_string(v.to_string()),
-            Value::Base64(v) => visitor.visit_bytes(v.as_slice()),
+            Value::Float(v) => visitor.visit_f32(v),
+            Value::DateTime(v) if self.reading_value => {
+                let token = core::iter::once((DATETIME_TOKEN, v.to_string()));
+                visitor.visit_map(serde::de::value::MapDeserializer::new(token))
+            }
+            Value::DateTime(v) => {
+                let text = v.to_string();
+                visitor.visit_str(&text).map_err(|err: Error| {
+                    err.relabel_invalid_type(|| format!("dateTime.iso8601 {:?}", text))
+                })
+            }
+            Value::Base64(v) => visitor.visit_byte_buf(v),
             Value::Struct(v) => {
-                let map_deserializer = MapDeserializer::new(v);
+                let map_deserializer = MapDeserializer::new(v, self.track_path, self.reading_value);
                 visitor.visit_map(map_deserializer)
             }
             Value::Array(v) => {
-                let seq_deserializer = SeqDeserializer::new(v);
+                let seq_deserializer = SeqDeserializer::new(v, self.track_path, self.reading_value);
                 visitor.visit_seq(seq_deserializer)
             }
-            Value::Nil => visitor.visit_none(),
+            // Option targets are handled by deserialize_option, so anything else which sees a
+            // nil is expecting a unit.
+            Value::Nil => visitor.visit_unit(),
+            #[cfg(feature = "std")]
+            Value::Raw(ref raw) => crate::util::visit_raw(raw, self.reading_value, visitor),
+            #[cfg(not(feature = "std"))]
+            Value::Raw(_) => Err(serde::de::Error::custom(
+                "reading a raw value needs the std feature",
+            )),
         }
     }
 
@@ -53,21 +223,100 @@ impl<'de> serde::Deserializer<'de> for Deserializer {
         }
     }
 
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.val {
+            // Structs can be filled positionally from an array, which is how a params list is
+            // passed to from_values. Elements past the last field are an error rather than being
+            // dropped, since they usually mean the struct doesn't match the method.
+            Value::Array(v) => {
+                let len = v.len();
+                let mut seq_deserializer =
+                    SeqDeserializer::new(v, self.track_path, self.reading_value);
+                let ret = visitor.visit_seq(&mut seq_deserializer)?;
+                if seq_deserializer.iter.len() == 0 {
+                    Ok(ret)
+                } else {
+                    let expected = format!("struct {} with {} elements", name, fields.len());
+                    Err(serde::de::Error::invalid_length(len, &expected.as_str()))
+                }
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.val {
+            // Like structs, tuples don't drop extra elements.
+            Value::Array(v) => {
+                let found = v.len();
+                let mut seq_deserializer =
+                    SeqDeserializer::new(v, self.track_path, self.reading_value);
+                let ret = visitor.visit_seq(&mut seq_deserializer)?;
+                if seq_deserializer.iter.len() == 0 {
+                    Ok(ret)
+                } else {
+                    let expected = format!("a tuple of size {}", len);
+                    Err(serde::de::Error::invalid_length(found, &expected.as_str()))
+                }
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if name == VALUE_TOKEN {
+            return Deserializer {
+                reading_value: true,
+                ..self
+            }
+            .deserialize_any(visitor);
+        }
+        self.deserialize_any(visitor)
+    }
+
     forward_to_deserialize_any!(
         bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
-        byte_buf unit unit_struct newtype_struct seq tuple
-        tuple_struct map struct enum identifier ignored_any
+        byte_buf unit unit_struct seq
+        tuple_struct map enum identifier ignored_any
     );
 }
 
 struct SeqDeserializer {
-    iter: std::vec::IntoIter<Value>,
+    iter: alloc::vec::IntoIter<Value>,
+    track_path: bool,
+    reading_value: bool,
+    index: usize,
 }
 
 impl SeqDeserializer {
-    fn new(vec: Vec<Value>) -> Self {
+    fn new(vec: Vec<Value>, track_path: bool, reading_value: bool) -> Self {
         SeqDeserializer {
             iter: vec.into_iter(),
+            track_path,
+            reading_value,
+            index: 0,
+        }
+    }
+
+    fn nested(&self, value: Value) -> Deserializer {
+        Deserializer {
+            val: value,
+            track_path: self.track_path,
+            reading_value: self.reading_value,
         }
     }
 }
@@ -79,23 +328,42 @@ impl<'de> serde::de::SeqAccess<'de> for SeqDeserializer {
     where
         T: serde::de::DeserializeSeed<'de>,
     {
-        match self.iter.next() {
-            Some(value) => seed.deserialize(Deserializer::from_value(value)).map(Some),
-            None => Ok(None),
+        let value = match self.iter.next() {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        let index = self.index;
+        self.index += 1;
+        let deserializer = self.nested(value);
+        if !self.track_path {
+            return seed.deserialize(deserializer).map(Some);
         }
+        seed.deserialize(deserializer)
+            .map(Some)
+            .map_err(|err| err.at_path(&index.to_string()))
     }
 }
 
 struct MapDeserializer {
-    iter: <BTreeMap<String, Value> as IntoIterator>::IntoIter,
+    iter: <Map<String, Value> as IntoIterator>::IntoIter,
     value: Option<Value>,
+    /// The escaped name of the current member, only kept when tracking paths.
+    key: Option<String>,
+    reading_value: bool,
 }
 
 impl MapDeserializer {
-    fn new(map: BTreeMap<String, Value>) -> Self {
+    fn new(map: Map<String, Value>, track_path: bool, reading_value: bool) -> Self {
         MapDeserializer {
             iter: map.into_iter(),
             value: None,
+            key: if track_path {
+                Some(String::new())
+            } else {
+                None
+            },
+            reading_value,
         }
     }
 }
@@ -107,23 +375,43 @@ impl<'de> serde::de::MapAccess<'de> for MapDeserializer {
     where
         T: serde::de::DeserializeSeed<'de>,
     {
-        match self.iter.next() {
-            Some((key, value)) => {
-                self.value = Some(value);
-                seed.deserialize(Deserializer::from_value(Value::String(key)))
-                    .map(Some)
+        let (key, value) = match self.iter.next() {
+            Some(member) => member,
+            None => return Ok(None),
+        };
+
+        self.value = Some(value);
+        let path = match self.key {
+            Some(ref mut path) => {
+                *path = super::pointer::escape(&key);
+                path
             }
-            None => Ok(None),
-        }
+            None => return seed.deserialize(MapKeyDeserializer::new(key)).map(Some),
+        };
+        seed.deserialize(MapKeyDeserializer::new(key))
+            .map(Some)
+            .map_err(|err| err.at_path(path))
     }
 
     fn next_value_seed<T>(&mut self, seed: T) -> Result<T::Value>
     where
         T: serde::de::DeserializeSeed<'de>,
     {
-        match self.value.take() {
-            Some(value) => seed.deserialize(Deserializer::from_value(value)),
-            None => Err(serde::de::Error::custom("value is missing")),
+        let value = match self.value.take() {
+            Some(value) => value,
+            None => return Err(serde::de::Error::custom("value is missing")),
+        };
+
+        let deserializer = Deserializer {
+            val: value,
+            track_path: self.key.is_some(),
+            reading_value: self.reading_value,
+        };
+        match self.key {
+            Some(ref path) => seed
+                .deserialize(deserializer)
+                .map_err(|err| err.at_path(path)),
+            None => seed.deserialize(deserializer),
         }
     }
 
@@ -135,6 +423,97 @@ impl<'de> serde::de::MapAccess<'de> for MapDeserializer {
     }
 }
 
+/// Deserializes a struct member name. Names are always strings on the wire, but maps keyed by
+/// other types (integers, booleans, unit enum variants) are parsed back out of that string.
+pub(crate) struct MapKeyDeserializer {
+    key: String,
+}
+
+impl MapKeyDeserializer {
+    pub(crate) fn new(key: String) -> Self {
+        MapKeyDeserializer { key }
+    }
+}
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                visitor.$visit(self.key.parse().map_err(DecodingError::from)?)
+            }
+        )*
+    };
+}
+
+impl<'de> serde::Deserializer<'de> for MapKeyDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_string(self.key)
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.key.as_str() {
+            "1" | "true" => visitor.visit_bool(true),
+            "0" | "false" => visitor.visit_bool(false),
+            _ => Err(DecodingError::BooleanDecodeError(self.key).into()),
+        }
+    }
+
+    deserialize_parsed_key! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(self.key.into_deserializer())
+    }
+
+    forward_to_deserialize_any!(
+        char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    );
+}
+
 #[cfg(test)]
 mod test {
     use serde::Deserialize;
@@ -150,12 +529,12 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::useless_conversion)]
     fn test_serde() {
-        use std::collections::BTreeMap;
         use std::iter::FromIterator;
 
         use super::Deserializer;
-        use crate::Value;
+        use crate::{Map, Value};
 
         let x = Value::Int(42);
         let y = i32::deserialize(Deserializer::from_value(x)).unwrap();
@@ -165,7 +544,7 @@ mod test {
         let y: Vec<String> = Vec::deserialize(Deserializer::from_value(x)).unwrap();
         assert_eq!(y, vec!["hello world".to_string()]);
 
-        let x = Value::Struct(BTreeMap::from_iter(
+        let x = Value::Struct(Map::from_iter(
             vec![("hello".to_string(), Value::String("world".to_string()))].into_iter(),
         ));
         let y = Test::deserialize(Deserializer::from_value(x)).unwrap();
@@ -176,17 +555,17 @@ mod test {
             },
         );
 
-        let x = Value::Struct(BTreeMap::new());
+        let x = Value::Struct(Map::new());
         let y = Test2::deserialize(Deserializer::from_value(x)).unwrap();
         assert_eq!(y, Test2 { val: None },);
 
-        let x = Value::Struct(BTreeMap::from_iter(
+        let x = Value::Struct(Map::from_iter(
             vec![("val".to_string(), Value::Nil)].into_iter(),
         ));
         let y = Test2::deserialize(Deserializer::from_value(x)).unwrap();
         assert_eq!(y, Test2 { val: None },);
 
-        let x = Value::Struct(BTreeMap::from_iter(
+        let x = Value::Struct(Map::from_iter(
             vec![("val".to_string(), Value::String("hello".to_string()))].into_iter(),
         ));
         let y = Test2::deserialize(Deserializer::from_value(x)).unwrap();
@@ -197,4 +576,152 @@ mod test {
             },
         );
     }
+
+    /// A `Value` inside another type keeps dateTimes and base64 data, whether it is read from a
+    /// document or another `Value`.
+    #[test]
+    fn value_fields() {
+        use crate::{from_value, response_from_str, to_value, Value, XmlRpcDateTime};
+
+        #[derive(Deserialize, serde::Serialize, Debug, PartialEq)]
+        struct Event {
+            name: String,
+            payload: Value,
+        }
+
+        let body = "<methodResponse><params><param><value><struct>\
+            <member><name>name</name><value>boot</value></member>\
+            <member><name>payload</name><value><array><data>\
+            <value><dateTime.iso8601>19980717T14:08:55</dateTime.iso8601></value>\
+            <value><i8>5000000000</i8></value><value><int>1</int></value><value><nil/></value>\
+            </data></array></value></member>\
+            </struct></value></param></params></methodResponse>";
+        let dt: XmlRpcDateTime = "19980717T14:08:55".parse().unwrap();
+        let payload = Value::Array(vec![
+            Value::DateTime(dt),
+            Value::Int64(5_000_000_000),
+            Value::Int(1),
+            Value::Nil,
+        ]);
+
+        let event: Event = response_from_str(body).unwrap();
+        assert_eq!(event.payload, payload);
+
+        let val = to_value(&event).unwrap();
+        assert_eq!(val.pointer("/payload"), Some(&payload));
+        assert_eq!(from_value::<Event>(val).unwrap(), event);
+
+        // Unsigned integers too big for an i8 can't be values.
+        let err = Value::deserialize(serde::de::value::U64Deserializer::<crate::Error>::new(
+            u64::MAX,
+        ));
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_map_keys() {
+        use std::collections::HashMap;
+        use std::iter::FromIterator;
+
+        use super::Deserializer;
+        use crate::{Map, Value};
+
+        #[derive(Deserialize, Debug, PartialEq, Eq, Hash)]
+        enum Color {
+            Red,
+        }
+
+        let x = Value::Struct(Map::from_iter([
+            ("1".to_string(), Value::Bool(true)),
+            ("-2".to_string(), Value::Bool(false)),
+        ]));
+        let y = HashMap::<i32, bool>::deserialize(Deserializer::from_value(x)).unwrap();
+        assert_eq!(y, HashMap::from_iter([(1, true), (-2, false)]));
+
+        let x = Value::Struct(Map::from_iter([("Red".to_string(), Value::Int(1))]));
+        let y = HashMap::<Color, i32>::deserialize(Deserializer::from_value(x)).unwrap();
+        assert_eq!(y, HashMap::from_iter([(Color::Red, 1)]));
+
+        let x = Value::Struct(Map::from_iter([("nope".to_string(), Value::Int(1))]));
+        assert!(HashMap::<u8, i32>::deserialize(Deserializer::from_value(x)).is_err());
+    }
+
+    #[test]
+    fn test_positional_struct() {
+        use crate::{from_values, Value};
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Params {
+            user: String,
+            flags: i32,
+            #[serde(default)]
+            comment: Option<String>,
+        }
+
+        let params: Params = from_values(vec![Value::from("bob"), Value::Int(3)]).unwrap();
+        assert_eq!(
+            params,
+            Params {
+                user: "bob".to_string(),
+                flags: 3,
+                comment: None,
+            }
+        );
+
+        let params: Params =
+            from_values(vec![Value::from("bob"), Value::Int(3), Value::from("hi")]).unwrap();
+        assert_eq!(params.comment.as_deref(), Some("hi"));
+
+        assert!(from_values::<Params>(vec![Value::from("bob")]).is_err());
+        let err = from_values::<Params>(vec![
+            Value::from("bob"),
+            Value::Int(3),
+            Value::Nil,
+            Value::Int(4),
+        ])
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "decoding error: serde: invalid length 4, expected struct Params with 3 elements"
+        );
+    }
+
+    #[test]
+    fn extract_paths() {
+        use std::collections::BTreeMap;
+
+        use crate::error::DecodingError;
+        use crate::{from_value, Error, Value};
+
+        let mut value = Value::Nil;
+        value
+            .entry("a/b")
+            .or_insert(Value::Nil)
+            .entry("items")
+            .or_insert(vec![Value::Int(1), Value::from("two")]);
+
+        let err = value
+            .extract::<BTreeMap<String, BTreeMap<String, Vec<i32>>>>()
+            .unwrap_err();
+        match err {
+            Error::DecodingError(DecodingError::AtPath { path, source }) => {
+                assert_eq!(path, "/a~1b/items/1");
+                assert!(matches!(*source, DecodingError::InvalidType { .. }));
+            }
+            err => panic!("expected a path, got {:?}", err),
+        }
+
+        // Only extract adds paths.
+        let err = from_value::<BTreeMap<String, BTreeMap<String, Vec<i32>>>>(value).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::DecodingError(DecodingError::InvalidType { .. })
+        ));
+
+        assert!(Value::from("x")
+            .extract::<i32>()
+            .unwrap_err()
+            .to_string()
+            .ends_with("expected i32"));
+    }
 }
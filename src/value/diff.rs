@@ -0,0 +1,160 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::pointer::escape;
+use super::Value;
+
+/// A difference between two values, found by [`diff`](crate::diff).
+///
+/// Each difference has the path of the value it's about, a [JSON Pointer] as for
+/// [`Value::pointer`].
+///
+/// [JSON Pointer]: https://www.rfc-editor.org/rfc/rfc6901
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffEntry {
+    /// A struct member or array element which is only in the new value.
+    Added { path: String, value: Value },
+    /// A struct member or array element which is only in the old value.
+    Removed { path: String, value: Value },
+    /// A value which is different in the new value, or has a different type.
+    Changed {
+        path: String,
+        old: Value,
+        new: Value,
+    },
+}
+
+impl DiffEntry {
+    /// The path of the value which was added, removed or changed.
+    pub fn path(&self) -> &str {
+        match self {
+            DiffEntry::Added { path, .. }
+            | DiffEntry::Removed { path, .. }
+            | DiffEntry::Changed { path, .. } => path,
+        }
+    }
+}
+
+/// Writes the difference on one line, such as `changed /port: Int(80) -> Int(8080)`.
+impl fmt::Display for DiffEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffEntry::Added { path, value } => write!(f, "added {}: {:?}", path, value),
+            DiffEntry::Removed { path, value } => write!(f, "removed {}: {:?}", path, value),
+            DiffEntry::Changed { path, old, new } => {
+                write!(f, "changed {}: {:?} -> {:?}", path, old, new)
+            }
+        }
+    }
+}
+
+pub(crate) fn diff(old: &Value, new: &Value) -> Vec<DiffEntry> {
+    let mut entries = Vec::new();
+    diff_at(&mut String::new(), old, new, &mut entries);
+    entries
+}
+
+fn diff_at(path: &mut String, old: &Value, new: &Value, entries: &mut Vec<DiffEntry>) {
+    let len = path.len();
+    match (old, new) {
+        (Value::Struct(old), Value::Struct(new)) => {
+            for (key, old) in old {
+                path.push('/');
+                path.push_str(&escape(key));
+                match new.get(key) {
+                    Some(new) => diff_at(path, old, new, entries),
+                    None => entries.push(DiffEntry::Removed {
+                        path: path.clone(),
+                        value: old.clone(),
+                    }),
+                }
+                path.truncate(len);
+            }
+            for (key, new) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+                entries.push(DiffEntry::Added {
+                    path: format!("{}/{}", path, escape(key)),
+                    value: new.clone(),
+                });
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for (idx, old) in old.iter().enumerate() {
+                path.push_str(&format!("/{}", idx));
+                match new.get(idx) {
+                    Some(new) => diff_at(path, old, new, entries),
+                    None => entries.push(DiffEntry::Removed {
+                        path: path.clone(),
+                        value: old.clone(),
+                    }),
+                }
+                path.truncate(len);
+            }
+            for (idx, new) in new.iter().enumerate().skip(old.len()) {
+                entries.push(DiffEntry::Added {
+                    path: format!("{}/{}", path, idx),
+                    value: new.clone(),
+                });
+            }
+        }
+        (old, new) if old != new => entries.push(DiffEntry::Changed {
+            path: path.clone(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_values() {
+        let mut old = Value::Nil;
+        old.entry("host").or_insert("localhost");
+        old.entry("port").or_insert(80);
+        old.entry("a/b").or_insert(true);
+        old.entry("tags")
+            .or_insert(vec![Value::from("x"), Value::from("y")]);
+
+        let mut new = Value::Nil;
+        new.entry("host").or_insert("localhost");
+        new.entry("port").or_insert("80");
+        new.entry("tags").or_insert(vec![Value::from("z")]);
+        new.entry("debug").or_insert(Value::Nil);
+
+        let entries = diff(&old, &new);
+        let mut paths: Vec<_> = entries.iter().map(DiffEntry::path).collect();
+        paths.sort_unstable();
+        assert_eq!(paths, ["/a~1b", "/debug", "/port", "/tags/0", "/tags/1"]);
+        assert!(entries.contains(&DiffEntry::Changed {
+            path: "/port".into(),
+            old: Value::Int(80),
+            new: Value::from("80"),
+        }));
+        assert!(entries.contains(&DiffEntry::Removed {
+            path: "/tags/1".into(),
+            value: Value::from("y"),
+        }));
+        assert!(entries.contains(&DiffEntry::Added {
+            path: "/debug".into(),
+            value: Value::Nil,
+        }));
+
+        assert!(diff(&old, &old).is_empty());
+        assert_eq!(
+            diff(&Value::Int(1), &Value::Array(vec![]))[0].to_string(),
+            "changed : Int(1) -> Array([])"
+        );
+        assert_eq!(
+            diff(&Value::Array(vec![]), &Value::Array(vec![Value::Nil])),
+            [DiffEntry::Added {
+                path: "/0".into(),
+                value: Value::Nil,
+            }]
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use super::map::MapEntry;
+use super::Value;
+
+/// A member of a [`Value::Struct`] which may or may not exist yet, returned by [`Value::entry`].
+pub struct Entry<'a>(pub(super) MapEntry<'a>);
+
+impl<'a> Entry<'a> {
+    /// Returns the name of the member.
+    pub fn key(&self) -> &str {
+        self.0.key()
+    }
+
+    /// Inserts `default` if the member doesn't exist, then returns the member.
+    pub fn or_insert(self, default: impl Into<Value>) -> &'a mut Value {
+        self.0.or_insert(default.into())
+    }
+
+    /// Inserts the result of `default` if the member doesn't exist, then returns the member.
+    pub fn or_insert_with<F, V>(self, default: F) -> &'a mut Value
+    where
+        F: FnOnce() -> V,
+        V: Into<Value>,
+    {
+        self.0.or_insert_with(|| default().into())
+    }
+
+    /// Calls `f` with the member if it exists.
+    pub fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut Value),
+    {
+        Entry(self.0.and_modify(f))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Value;
+
+    #[test]
+    fn entry() {
+        let mut value = Value::Nil;
+        value.entry("count").or_insert(0);
+        for _ in 0..2 {
+            value
+                .entry("count")
+                .and_modify(|v| *v = Value::Int(v.as_i32().unwrap() + 1))
+                .or_insert(0);
+        }
+        value.entry("tags").or_insert_with(Vec::<Value>::new);
+
+        let members = value.as_struct().unwrap();
+        assert_eq!(members["count"], Value::Int(2));
+        assert_eq!(members["tags"], Value::Array(vec![]));
+        assert_eq!(value.entry("tags").key(), "tags");
+    }
+
+    #[test]
+    #[should_panic(expected = "entry called on int value")]
+    fn entry_on_scalar() {
+        Value::Int(1).entry("count");
+    }
+}
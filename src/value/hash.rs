@@ -0,0 +1,125 @@
+use core::hash::{Hash, Hasher};
+use core::mem;
+
+use super::Value;
+
+/// Compares values by their contents, with floating point numbers compared by bit pattern rather
+/// than numerically. This makes the comparison an equivalence relation, so `Value` is `Eq` and
+/// can be used as a `HashMap` key or deduplicated in a `HashSet`:
+///
+/// * A NaN equals a NaN with the same bit pattern, such as `f64::NAN` equals itself.
+/// * `0.0` and `-0.0` are different values.
+/// * A `Value::Double` never equals a `Value::Float`, nor a `Value::Int` a `Value::Int64`, even
+///   if they hold the same number.
+/// * Structs are equal if they have the same members, in any order.
+///
+/// ```
+/// use std::collections::HashSet;
+///
+/// use serde_xmlrpc::Value;
+///
+/// assert_eq!(Value::Double(f64::NAN), Value::Double(f64::NAN));
+/// assert_ne!(Value::Double(0.0), Value::Double(-0.0));
+///
+/// let calls: HashSet<Value> = vec![Value::from(1.5), Value::from(1.5), Value::from("a")]
+///     .into_iter()
+///     .collect();
+/// assert_eq!(calls.len(), 2);
+/// ```
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Int64(a), Value::Int64(b)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Double(a), Value::Double(b)) => a.to_bits() == b.to_bits(),
+            (Value::Float(a), Value::Float(b)) => a.to_bits() == b.to_bits(),
+            (Value::DateTime(a), Value::DateTime(b)) => a == b,
+            (Value::Base64(a), Value::Base64(b)) => a == b,
+            (Value::Struct(a), Value::Struct(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => a == b,
+            (Value::Nil, Value::Nil) => true,
+            (Value::Raw(a), Value::Raw(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value {}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Value::Int(i) => i.hash(state),
+            Value::Int64(i) => i.hash(state),
+            Value::Bool(b) => b.hash(state),
+            Value::String(s) => s.hash(state),
+            Value::Double(d) => d.to_bits().hash(state),
+            Value::Float(f) => f.to_bits().hash(state),
+            Value::DateTime(dt) => dt.hash(state),
+            Value::Base64(data) => data.hash(state),
+            Value::Struct(members) => {
+                members.len().hash(state);
+
+                // Equal structs can have their members in different orders, so hash them in key
+                // order.
+                #[cfg(feature = "preserve_order")]
+                let members = {
+                    let mut sorted: Vec<_> = members.iter().collect();
+                    sorted.sort_unstable_by_key(|(key, _)| *key);
+                    sorted
+                };
+
+                for (key, value) in members {
+                    key.hash(state);
+                    value.hash(state);
+                }
+            }
+            Value::Array(values) => values.hash(state),
+            Value::Nil => {}
+            Value::Raw(raw) => raw.hash(state),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+
+    use super::*;
+    use crate::Map;
+
+    fn hash(value: &Value) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn struct_order() {
+        let a: Map<String, Value> = vec![("a".to_string(), 1.into()), ("b".to_string(), 2.into())]
+            .into_iter()
+            .collect();
+        let b: Map<String, Value> = vec![("b".to_string(), 2.into()), ("a".to_string(), 1.into())]
+            .into_iter()
+            .collect();
+        let (a, b) = (Value::Struct(a), Value::Struct(b));
+
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        assert_ne!(hash(&a), hash(&Value::Struct(Map::new())));
+    }
+
+    #[test]
+    fn floats() {
+        let nan = Value::Double(f64::NAN);
+        assert_eq!(nan, nan.clone());
+        assert_eq!(hash(&nan), hash(&nan.clone()));
+        assert_ne!(Value::Double(1.0), Value::Float(1.0));
+
+        let dt = Value::DateTime("2019-02-11T23:56:41".parse().unwrap());
+        assert_eq!(hash(&dt), hash(&dt.clone()));
+    }
+}
@@ -0,0 +1,110 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::Value;
+
+impl Value {
+    /// Returns an iterator over the elements of an array, which is empty if `self` isn't an
+    /// array.
+    pub fn iter(&self) -> core::slice::Iter<'_, Value> {
+        match self {
+            Value::Array(values) => values.iter(),
+            _ => [].iter(),
+        }
+    }
+
+    /// Returns an iterator over the names and values of the members of a struct, which is empty
+    /// if `self` isn't a struct.
+    pub fn members(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.as_struct()
+            .into_iter()
+            .flat_map(|members| members.iter().map(|(k, v)| (k.as_str(), v)))
+    }
+
+    /// Returns an iterator which visits `self` and every value nested in it, depth-first and in
+    /// document order. Each value comes with its path, a [JSON Pointer] which finds it again
+    /// with [`pointer`](Value::pointer). `self` has the empty path.
+    ///
+    /// ```
+    /// use serde_xmlrpc::Value;
+    ///
+    /// let mut value = Value::Nil;
+    /// value.entry("tags").or_insert(vec![Value::from("a")]);
+    /// value.entry("user").or_insert(Value::Nil).entry("password").or_insert("hunter2");
+    ///
+    /// let paths: Vec<String> = value.walk().map(|(path, _)| path).collect();
+    /// assert_eq!(paths, ["", "/tags", "/tags/0", "/user", "/user/password"]);
+    /// ```
+    ///
+    /// [JSON Pointer]: https://www.rfc-editor.org/rfc/rfc6901
+    pub fn walk(&self) -> Walk<'_> {
+        Walk {
+            stack: vec![(String::new(), self)],
+        }
+    }
+}
+
+/// An iterator over a value and all values nested in it, returned by [`Value::walk`].
+#[derive(Clone, Debug)]
+pub struct Walk<'a> {
+    /// The values still to visit, with the next one last.
+    stack: Vec<(String, &'a Value)>,
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = (String, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, value) = self.stack.pop()?;
+
+        match value {
+            Value::Array(values) => {
+                for (idx, child) in values.iter().enumerate().rev() {
+                    self.stack.push((format!("{}/{}", path, idx), child));
+                }
+            }
+            Value::Struct(members) => {
+                for (key, child) in members.iter().rev() {
+                    let key = super::pointer::escape(key);
+                    self.stack.push((format!("{}/{}", path, key), child));
+                }
+            }
+            _ => {}
+        }
+
+        Some((path, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Value;
+
+    #[test]
+    fn iterators() {
+        let array = Value::Array(vec![Value::Int(1), Value::Int(2)]);
+        assert_eq!(array.iter().count(), 2);
+        assert_eq!(array.members().count(), 0);
+
+        let mut value = Value::Nil;
+        value.entry("a/b").or_insert(array);
+        value
+            .entry("c")
+            .or_insert(Value::Nil)
+            .entry("d")
+            .or_insert(true);
+        assert_eq!(value.iter().count(), 0);
+        assert_eq!(
+            value.members().map(|(k, _)| k).collect::<Vec<_>>(),
+            ["a/b", "c"]
+        );
+
+        for (path, walked) in value.walk() {
+            assert_eq!(value.pointer(&path), Some(walked));
+        }
+        let paths: Vec<String> = value.walk().map(|(path, _)| path).collect();
+        assert_eq!(paths, ["", "/a~1b", "/a~1b/0", "/a~1b/1", "/c", "/c/d"]);
+    }
+}
@@ -0,0 +1,145 @@
+//! Conversions between [`Value`] and [`serde_json::Value`].
+//!
+//! JSON has no date or binary types, so converting to JSON writes `Value::DateTime` as its
+//! ISO 8601 text and `Value::Base64` as a standard base64 string. Converting back never produces
+//! either variant: those strings stay `Value::String`.
+//!
+//! Integers become `Value::Int` when they fit in 32 bits and `Value::Int64` otherwise. Numbers
+//! which fit in neither, and non-finite doubles going the other way, can't be represented and
+//! result in an error.
+
+use std::convert::TryFrom;
+
+use base64::prelude::*;
+use serde_json::Number;
+
+use crate::error::EncodingError;
+use crate::{Error, Value};
+
+impl TryFrom<serde_json::Value> for Value {
+    type Error = Error;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        Ok(match value {
+            serde_json::Value::Null => Value::Nil,
+            serde_json::Value::Bool(b) => Value::Bool(b),
+            serde_json::Value::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    match i32::try_from(i) {
+                        Ok(i) => Value::Int(i),
+                        Err(_) => Value::Int64(i),
+                    }
+                } else if n.is_u64() {
+                    return Err(EncodingError::Unrepresentable(format!(
+                        "integer {} is too large",
+                        n
+                    ))
+                    .into());
+                } else {
+                    // Anything which isn't an integer is representable as an f64.
+                    Value::Double(n.as_f64().unwrap_or_default())
+                }
+            }
+            serde_json::Value::String(s) => Value::String(s),
+            serde_json::Value::Array(a) => Value::Array(
+                a.into_iter()
+                    .map(Value::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            serde_json::Value::Object(o) => Value::Struct(
+                o.into_iter()
+                    .map(|(k, v)| Ok((k, Value::try_from(v)?)))
+                    .collect::<Result<_, Error>>()?,
+            ),
+        })
+    }
+}
+
+impl TryFrom<Value> for serde_json::Value {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Value::Int(i) => serde_json::Value::from(i),
+            Value::Int64(i) => serde_json::Value::from(i),
+            Value::Bool(b) => serde_json::Value::Bool(b),
+            Value::String(s) => serde_json::Value::String(s),
+            Value::Double(d) => Number::from_f64(d)
+                .map(serde_json::Value::Number)
+                .ok_or_else(|| {
+                    EncodingError::Unrepresentable(format!("double {} is not finite", d))
+                })?,
+            Value::Float(f) => serde_json::Value::try_from(Value::Double(f.into()))?,
+            Value::DateTime(dt) => serde_json::Value::String(dt.to_string()),
+            Value::Base64(data) => serde_json::Value::String(BASE64_STANDARD.encode(data)),
+            Value::Struct(map) => serde_json::Value::Object(
+                map.into_iter()
+                    .map(|(k, v)| Ok((k, serde_json::Value::try_from(v)?)))
+                    .collect::<Result<_, Error>>()?,
+            ),
+            Value::Array(a) => serde_json::Value::Array(
+                a.into_iter()
+                    .map(serde_json::Value::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Nil => serde_json::Value::Null,
+            Value::Raw(raw) => match crate::value_from_str(raw.as_str())? {
+                // Extension types which are kept as XML, such as `<ex:bigdecimal>`, become their
+                // text.
+                Value::Raw(raw) => serde_json::Value::String(crate::from_value(Value::Raw(raw))?),
+                value => serde_json::Value::try_from(value)?,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use serde_json::json;
+
+    use crate::Value;
+
+    #[test]
+    fn json_roundtrip() {
+        let json = json!({
+            "name": "rosout",
+            "count": 3,
+            "big": 8589934592i64,
+            "ratio": 0.5,
+            "flags": [true, null],
+        });
+
+        let value = Value::try_from(json.clone()).unwrap();
+        let members = value.as_struct().unwrap();
+        assert_eq!(members["count"], Value::Int(3));
+        assert_eq!(members["big"], Value::Int64(8589934592));
+        assert_eq!(
+            members["flags"],
+            Value::Array(vec![Value::Bool(true), Value::Nil])
+        );
+
+        assert_eq!(serde_json::Value::try_from(value).unwrap(), json);
+    }
+
+    #[test]
+    fn json_lossy_types() {
+        let value = Value::Base64(b"hello".to_vec());
+        assert_eq!(
+            serde_json::Value::try_from(value).unwrap(),
+            json!("aGVsbG8=")
+        );
+
+        assert!(serde_json::Value::try_from(Value::Double(f64::NAN)).is_err());
+        assert!(Value::try_from(json!(u64::MAX)).is_err());
+
+        let value = crate::value_from_str(
+            "<value><ex:biginteger xmlns:ex=\"urn:x\">18446744073709551616</ex:biginteger></value>",
+        );
+        assert_eq!(
+            serde_json::Value::try_from(value.unwrap()).unwrap(),
+            json!("18446744073709551616")
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use core::fmt;
+use core::ops::{BitOr, BitOrAssign};
+
+/// A set of XML-RPC value types, used to check a [`Value`](crate::Value) against several
+/// acceptable types at once.
+///
+/// ```
+/// use serde_xmlrpc::{Value, ValueKind};
+///
+/// let accepted = ValueKind::INTEGER | ValueKind::STRING;
+/// assert!(Value::Int64(42).matches(accepted));
+/// assert!(Value::from("42").matches(accepted));
+/// assert!(!Value::Double(42.0).matches(accepted));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ValueKind(u16);
+
+impl ValueKind {
+    /// No types at all.
+    pub const NONE: ValueKind = ValueKind(0);
+    /// A 32-bit integer (`<int>` or `<i4>`).
+    pub const INT: ValueKind = ValueKind(1 << 0);
+    /// A 64-bit integer (`<i8>`).
+    pub const INT64: ValueKind = ValueKind(1 << 1);
+    /// A boolean (`<boolean>`).
+    pub const BOOL: ValueKind = ValueKind(1 << 2);
+    /// A string (`<string>`).
+    pub const STRING: ValueKind = ValueKind(1 << 3);
+    /// A double (`<double>`).
+    pub const DOUBLE: ValueKind = ValueKind(1 << 4);
+    /// A date/time (`<dateTime.iso8601>`).
+    pub const DATETIME: ValueKind = ValueKind(1 << 5);
+    /// Binary data (`<base64>`).
+    pub const BASE64: ValueKind = ValueKind(1 << 6);
+    /// A struct (`<struct>`).
+    pub const STRUCT: ValueKind = ValueKind(1 << 7);
+    /// An array (`<array>`).
+    pub const ARRAY: ValueKind = ValueKind(1 << 8);
+    /// The nil extension (`<nil/>`).
+    pub const NIL: ValueKind = ValueKind(1 << 9);
+    /// A single precision float (Apache's `<ex:float>` extension).
+    pub const FLOAT: ValueKind = ValueKind(1 << 10);
+    /// Pre-serialized XML ([`Value::Raw`](crate::Value::Raw)), whose type isn't known.
+    pub const RAW: ValueKind = ValueKind(1 << 11);
+
+    /// Either integer width.
+    pub const INTEGER: ValueKind = ValueKind(Self::INT.0 | Self::INT64.0);
+    /// Any number, integer or floating point.
+    pub const NUMBER: ValueKind = ValueKind(Self::INTEGER.0 | Self::DOUBLE.0 | Self::FLOAT.0);
+    /// Any value which isn't a struct or an array.
+    pub const SCALAR: ValueKind = ValueKind(
+        Self::NUMBER.0
+            | Self::BOOL.0
+            | Self::STRING.0
+            | Self::DATETIME.0
+            | Self::BASE64.0
+            | Self::NIL.0,
+    );
+    /// Every type.
+    pub const ANY: ValueKind =
+        ValueKind(Self::SCALAR.0 | Self::STRUCT.0 | Self::ARRAY.0 | Self::RAW.0);
+
+    const NAMES: [(ValueKind, &'static str); 12] = [
+        (Self::INT, "int"),
+        (Self::INT64, "i8"),
+        (Self::BOOL, "boolean"),
+        (Self::STRING, "string"),
+        (Self::DOUBLE, "double"),
+        (Self::DATETIME, "dateTime.iso8601"),
+        (Self::BASE64, "base64"),
+        (Self::STRUCT, "struct"),
+        (Self::ARRAY, "array"),
+        (Self::NIL, "nil"),
+        (Self::FLOAT, "ex:float"),
+        (Self::RAW, "raw"),
+    ];
+
+    /// Returns the XML-RPC tag name of a single type, or `None` if `self` holds several types or
+    /// none.
+    pub(crate) fn name(self) -> Option<&'static str> {
+        Self::NAMES
+            .iter()
+            .find(|(kind, _)| *kind == self)
+            .map(|(_, name)| *name)
+    }
+
+    /// Returns `true` if every type in `other` is also in `self`.
+    pub const fn contains(self, other: ValueKind) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns `true` if `self` and `other` have at least one type in common.
+    pub const fn intersects(self, other: ValueKind) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Returns `true` if the set contains no types.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the types in either set.
+    pub const fn union(self, other: ValueKind) -> ValueKind {
+        ValueKind(self.0 | other.0)
+    }
+
+    /// Returns the types in `self` which aren't in `other`.
+    pub const fn difference(self, other: ValueKind) -> ValueKind {
+        ValueKind(self.0 & !other.0)
+    }
+}
+
+impl BitOr for ValueKind {
+    type Output = ValueKind;
+
+    fn bitor(self, other: ValueKind) -> ValueKind {
+        self.union(other)
+    }
+}
+
+impl BitOrAssign for ValueKind {
+    fn bitor_assign(&mut self, other: ValueKind) {
+        *self = self.union(other);
+    }
+}
+
+/// Writes the XML-RPC tag names of the contained types separated by `|`, such as `int|string`.
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+        for (kind, name) in Self::NAMES.iter() {
+            if self.contains(*kind) {
+                if !first {
+                    f.write_str("|")?;
+                }
+                f.write_str(name)?;
+                first = false;
+            }
+        }
+
+        if first {
+            f.write_str("none")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ValueKind({})", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ValueKind;
+
+    #[test]
+    fn set_operations() {
+        let kinds = ValueKind::INT | ValueKind::STRING;
+        assert!(kinds.contains(ValueKind::INT));
+        assert!(!kinds.contains(ValueKind::INTEGER));
+        assert!(kinds.intersects(ValueKind::INTEGER));
+        assert!(ValueKind::ANY.contains(ValueKind::SCALAR | ValueKind::STRUCT));
+        assert!(ValueKind::NONE.is_empty());
+        assert_eq!(kinds.difference(ValueKind::INTEGER), ValueKind::STRING);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(ValueKind::INTEGER.to_string(), "int|i8");
+        assert_eq!(
+            (ValueKind::NIL | ValueKind::STRUCT).to_string(),
+            "struct|nil"
+        );
+        assert_eq!(ValueKind::NONE.to_string(), "none");
+    }
+
+    #[test]
+    fn type_names() {
+        use crate::{Map, RawValue, Value};
+
+        let values = [
+            Value::Int(0),
+            Value::Int64(0),
+            Value::Bool(false),
+            Value::String(String::new()),
+            Value::Double(0.0),
+            Value::Float(0.0),
+            Value::DateTime("2019-02-11T23:56:41".parse().unwrap()),
+            Value::Base64(vec![]),
+            Value::Struct(Map::new()),
+            Value::Array(vec![]),
+            Value::Nil,
+            Value::Raw(RawValue::new("<value/>").unwrap()),
+        ];
+        for value in values.iter() {
+            assert_eq!(value.type_name(), value.kind().to_string());
+        }
+        assert_eq!(Value::Float(0.0).type_name(), "ex:float");
+        assert_eq!(
+            Value::Raw(RawValue::new("<value/>").unwrap()).type_name(),
+            "raw"
+        );
+    }
+}
@@ -0,0 +1,433 @@
+use alloc::string::String;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+use core::iter::{FromIterator, FusedIterator};
+use core::marker::PhantomData;
+use core::ops::{Index, IndexMut};
+
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use super::{Entry, Value};
+
+#[cfg(not(feature = "preserve_order"))]
+type MapImpl<K, V> = alloc::collections::BTreeMap<K, V>;
+#[cfg(feature = "preserve_order")]
+type MapImpl<K, V> = indexmap::IndexMap<K, V>;
+
+#[cfg(not(feature = "preserve_order"))]
+pub(super) type MapEntry<'a> = alloc::collections::btree_map::Entry<'a, String, Value>;
+#[cfg(feature = "preserve_order")]
+pub(super) type MapEntry<'a> = indexmap::map::Entry<'a, String, Value>;
+
+/// The map of member names to values backing [`Value::Struct`].
+///
+/// By default members are ordered by name. Enabling the `preserve_order` feature keeps them in
+/// the order they were parsed or inserted instead, so re-serialized structs match the original
+/// document. The API is the same either way, so code written against one order keeps compiling
+/// when another crate in the build turns the feature on.
+///
+/// Only `String` keys are supported, like `serde_json::Map`.
+pub struct Map<K, V> {
+    map: MapImpl<K, V>,
+}
+
+impl<V> Map<String, V> {
+    /// Makes an empty map.
+    pub fn new() -> Self {
+        Map {
+            map: MapImpl::new(),
+        }
+    }
+
+    /// Makes an empty map with room for at least `capacity` members. The capacity is only a
+    /// hint, and is ignored without `preserve_order`.
+    pub fn with_capacity(capacity: usize) -> Self {
+        #[cfg(not(feature = "preserve_order"))]
+        let _ = capacity;
+        Map {
+            #[cfg(not(feature = "preserve_order"))]
+            map: MapImpl::new(),
+            #[cfg(feature = "preserve_order")]
+            map: MapImpl::with_capacity(capacity),
+        }
+    }
+
+    /// Removes all members.
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+
+    /// Returns the value of the member called `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        String: Borrow<Q>,
+        Q: ?Sized + Ord + Eq + Hash,
+    {
+        self.map.get(key)
+    }
+
+    /// Returns the name and value of the member called `key`.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&String, &V)>
+    where
+        String: Borrow<Q>,
+        Q: ?Sized + Ord + Eq + Hash,
+    {
+        self.map.get_key_value(key)
+    }
+
+    /// Returns a mutable reference to the value of the member called `key`.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        String: Borrow<Q>,
+        Q: ?Sized + Ord + Eq + Hash,
+    {
+        self.map.get_mut(key)
+    }
+
+    /// Returns whether there is a member called `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        String: Borrow<Q>,
+        Q: ?Sized + Ord + Eq + Hash,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// Inserts a member, returning the value it replaced if there already was a member called
+    /// `key`. A replaced member keeps its place in the order.
+    pub fn insert(&mut self, key: String, value: V) -> Option<V> {
+        self.map.insert(key, value)
+    }
+
+    /// Removes the member called `key` and returns its value. The members after it keep their
+    /// order.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        String: Borrow<Q>,
+        Q: ?Sized + Ord + Eq + Hash,
+    {
+        #[cfg(not(feature = "preserve_order"))]
+        return self.map.remove(key);
+        #[cfg(feature = "preserve_order")]
+        return self.map.shift_remove(key);
+    }
+
+    /// Removes the member called `key` and returns its name and value. The members after it keep
+    /// their order.
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(String, V)>
+    where
+        String: Borrow<Q>,
+        Q: ?Sized + Ord + Eq + Hash,
+    {
+        #[cfg(not(feature = "preserve_order"))]
+        return self.map.remove_entry(key);
+        #[cfg(feature = "preserve_order")]
+        return self.map.shift_remove_entry(key);
+    }
+
+    /// Keeps only the members for which `keep` returns `true`.
+    pub fn retain<F>(&mut self, keep: F)
+    where
+        F: FnMut(&String, &mut V) -> bool,
+    {
+        self.map.retain(keep)
+    }
+
+    /// Orders the members by name. They already are without `preserve_order`.
+    pub fn sort_keys(&mut self) {
+        #[cfg(feature = "preserve_order")]
+        self.map.sort_keys()
+    }
+
+    /// Returns the number of members.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns whether there are no members.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Iterates over the names and values of the members, in order.
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter(self.map.iter())
+    }
+
+    /// Iterates over the names and mutable values of the members, in order.
+    pub fn iter_mut(&mut self) -> IterMut<'_, V> {
+        IterMut(self.map.iter_mut())
+    }
+
+    /// Iterates over the member names, in order.
+    pub fn keys(&self) -> Keys<'_, V> {
+        Keys(self.map.keys())
+    }
+
+    /// Iterates over the member values, in order.
+    pub fn values(&self) -> Values<'_, V> {
+        Values(self.map.values())
+    }
+
+    /// Iterates over mutable references to the member values, in order.
+    pub fn values_mut(&mut self) -> ValuesMut<'_, V> {
+        ValuesMut(self.map.values_mut())
+    }
+}
+
+impl Map<String, Value> {
+    /// Returns the member called `key`, which may or may not exist yet, to insert or modify it in
+    /// place. See [`Value::entry`].
+    pub fn entry(&mut self, key: impl Into<String>) -> Entry<'_> {
+        Entry(self.map.entry(key.into()))
+    }
+}
+
+impl<V> Default for Map<String, V> {
+    fn default() -> Self {
+        Map::new()
+    }
+}
+
+impl<K: Clone, V: Clone> Clone for Map<K, V> {
+    fn clone(&self) -> Self {
+        Map {
+            map: self.map.clone(),
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Map<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+/// Maps are equal if they have the same members, in any order.
+impl<V: PartialEq> PartialEq for Map<String, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<V: Eq> Eq for Map<String, V> {}
+
+impl<Q, V> Index<&Q> for Map<String, V>
+where
+    String: Borrow<Q>,
+    Q: ?Sized + Ord + Eq + Hash,
+{
+    type Output = V;
+
+    /// Returns the value of the member called `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such member.
+    fn index(&self, key: &Q) -> &V {
+        &self.map[key]
+    }
+}
+
+impl<Q, V> IndexMut<&Q> for Map<String, V>
+where
+    String: Borrow<Q>,
+    Q: ?Sized + Ord + Eq + Hash,
+{
+    /// Returns a mutable reference to the value of the member called `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such member.
+    fn index_mut(&mut self, key: &Q) -> &mut V {
+        self.get_mut(key).expect("no member with this name")
+    }
+}
+
+impl<V> FromIterator<(String, V)> for Map<String, V> {
+    fn from_iter<I: IntoIterator<Item = (String, V)>>(iter: I) -> Self {
+        Map {
+            map: MapImpl::from_iter(iter),
+        }
+    }
+}
+
+impl<V> Extend<(String, V)> for Map<String, V> {
+    fn extend<I: IntoIterator<Item = (String, V)>>(&mut self, iter: I) {
+        self.map.extend(iter)
+    }
+}
+
+impl<V> IntoIterator for Map<String, V> {
+    type Item = (String, V);
+    type IntoIter = IntoIter<V>;
+
+    fn into_iter(self) -> IntoIter<V> {
+        IntoIter(self.map.into_iter())
+    }
+}
+
+impl<'a, V> IntoIterator for &'a Map<String, V> {
+    type Item = (&'a String, &'a V);
+    type IntoIter = Iter<'a, V>;
+
+    fn into_iter(self) -> Iter<'a, V> {
+        self.iter()
+    }
+}
+
+impl<'a, V> IntoIterator for &'a mut Map<String, V> {
+    type Item = (&'a String, &'a mut V);
+    type IntoIter = IterMut<'a, V>;
+
+    fn into_iter(self) -> IterMut<'a, V> {
+        self.iter_mut()
+    }
+}
+
+impl<V: Serialize> Serialize for Map<String, V> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(self)
+    }
+}
+
+impl<'de, V: Deserialize<'de>> Deserialize<'de> for Map<String, V> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct MapVisitor<V>(PhantomData<V>);
+
+        impl<'de, V: Deserialize<'de>> Visitor<'de> for MapVisitor<V> {
+            type Value = Map<String, V>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a map")
+            }
+
+            fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut map = Map::new();
+                while let Some((key, value)) = access.next_entry()? {
+                    map.insert(key, value);
+                }
+                Ok(map)
+            }
+        }
+
+        deserializer.deserialize_map(MapVisitor(PhantomData))
+    }
+}
+
+/// Implements the iterator traits for one of the iterator wrappers below by forwarding to the
+/// iterator of the backing map.
+macro_rules! delegate_iterator {
+    ($name:ident $(<$lt:lifetime>)?, $item:ty) => {
+        impl<$($lt,)? V> Iterator for $name<$($lt,)? V> {
+            type Item = $item;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                self.0.next()
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.0.size_hint()
+            }
+        }
+
+        impl<$($lt,)? V> DoubleEndedIterator for $name<$($lt,)? V> {
+            fn next_back(&mut self) -> Option<Self::Item> {
+                self.0.next_back()
+            }
+        }
+
+        impl<$($lt,)? V> ExactSizeIterator for $name<$($lt,)? V> {
+            fn len(&self) -> usize {
+                self.0.len()
+            }
+        }
+
+        impl<$($lt,)? V> FusedIterator for $name<$($lt,)? V> {}
+    };
+}
+
+#[cfg(not(feature = "preserve_order"))]
+use alloc::collections::btree_map as imp;
+#[cfg(feature = "preserve_order")]
+use indexmap::map as imp;
+
+/// An iterator over the members of a [`Map`], see [`Map::iter`].
+pub struct Iter<'a, V>(imp::Iter<'a, String, V>);
+delegate_iterator!(Iter<'a>, (&'a String, &'a V));
+
+/// An iterator over the members of a [`Map`] with mutable values, see [`Map::iter_mut`].
+pub struct IterMut<'a, V>(imp::IterMut<'a, String, V>);
+delegate_iterator!(IterMut<'a>, (&'a String, &'a mut V));
+
+/// An owning iterator over the members of a [`Map`].
+pub struct IntoIter<V>(imp::IntoIter<String, V>);
+delegate_iterator!(IntoIter, (String, V));
+
+/// An iterator over the member names of a [`Map`], see [`Map::keys`].
+pub struct Keys<'a, V>(imp::Keys<'a, String, V>);
+delegate_iterator!(Keys<'a>, &'a String);
+
+/// An iterator over the member values of a [`Map`], see [`Map::values`].
+pub struct Values<'a, V>(imp::Values<'a, String, V>);
+delegate_iterator!(Values<'a>, &'a V);
+
+/// An iterator over mutable references to the member values of a [`Map`], see
+/// [`Map::values_mut`].
+pub struct ValuesMut<'a, V>(imp::ValuesMut<'a, String, V>);
+delegate_iterator!(ValuesMut<'a>, &'a mut V);
+
+#[cfg(test)]
+mod tests {
+    use super::Map;
+    use crate::Value;
+
+    #[test]
+    fn same_api_in_either_order() {
+        let mut map: Map<String, Value> = vec![
+            ("b".to_string(), Value::Int(2)),
+            ("a".to_string(), Value::Int(1)),
+            ("c".to_string(), Value::Int(3)),
+        ]
+        .into_iter()
+        .collect();
+
+        #[cfg(not(feature = "preserve_order"))]
+        let expected = ["a", "b", "c"];
+        #[cfg(feature = "preserve_order")]
+        let expected = ["b", "a", "c"];
+        assert_eq!(map.keys().collect::<Vec<_>>(), expected);
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(map["a"], Value::Int(1));
+        map["a"] = Value::Int(10);
+        assert_eq!(map.get("a"), Some(&Value::Int(10)));
+        assert_eq!(map.remove("b"), Some(Value::Int(2)));
+        assert!(!map.contains_key("b"));
+        map.entry("d").or_insert(4);
+        map.retain(|_, v| *v != Value::Int(3));
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["a", "d"]);
+
+        map.insert("0".to_string(), Value::Nil);
+        map.sort_keys();
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["0", "a", "d"]);
+        assert_eq!(
+            map.iter().next_back(),
+            Some((&"d".to_string(), &Value::Int(4)))
+        );
+
+        let other: Map<String, Value> = map.clone().into_iter().rev().collect();
+        assert_eq!(map, other);
+    }
+}
@@ -0,0 +1,91 @@
+use super::Value;
+
+impl Value {
+    /// Merges `other` into `self`, such as to lay a configuration override over its defaults.
+    ///
+    /// * Two structs are merged member by member. Members only in `other` are added and members
+    ///   in both are merged recursively.
+    /// * Two arrays are concatenated, with the elements of `other` last.
+    /// * In any other case, including values of different types, `other` replaces `self`. A
+    ///   [`Value::Nil`] in `other` replaces too, so it can't be used to keep a value.
+    ///
+    /// ```
+    /// use serde_xmlrpc::Value;
+    ///
+    /// let mut config = Value::Nil;
+    /// config.entry("name").or_insert("rosout");
+    /// config.entry("limits").or_insert(Value::Nil).entry("depth").or_insert(64);
+    /// config.entry("topics").or_insert(vec![Value::from("/a")]);
+    ///
+    /// let mut overlay = Value::Nil;
+    /// overlay.entry("limits").or_insert(Value::Nil).entry("rate").or_insert(10);
+    /// overlay.entry("topics").or_insert(vec![Value::from("/b")]);
+    /// overlay.entry("name").or_insert(1);
+    ///
+    /// config.merge(overlay);
+    /// assert_eq!(config.pointer("/name"), Some(&Value::Int(1)));
+    /// assert_eq!(config.pointer("/limits/depth"), Some(&Value::Int(64)));
+    /// assert_eq!(config.pointer("/limits/rate"), Some(&Value::Int(10)));
+    /// assert_eq!(config.pointer("/topics/1"), Some(&Value::from("/b")));
+    /// ```
+    pub fn merge(&mut self, other: Value) {
+        match (self, other) {
+            (Value::Struct(members), Value::Struct(others)) => {
+                for (key, other) in others {
+                    match members.get_mut(&key) {
+                        Some(member) => member.merge(other),
+                        None => {
+                            members.insert(key, other);
+                        }
+                    }
+                }
+            }
+            (Value::Array(values), Value::Array(others)) => values.extend(others),
+            (this, other) => *this = other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Map, Value};
+
+    fn members(members: Vec<(&str, Value)>) -> Value {
+        Value::Struct(
+            members
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect::<Map<_, _>>(),
+        )
+    }
+
+    #[test]
+    fn merge() {
+        let mut value = members(vec![
+            ("a", members(vec![("x", 1.into()), ("y", 2.into())])),
+            ("b", vec![Value::Int(1)].into()),
+            ("c", "keep".into()),
+        ]);
+        value.merge(members(vec![
+            ("a", members(vec![("y", 3.into()), ("z", 4.into())])),
+            ("b", vec![Value::Int(2)].into()),
+            ("d", Value::Nil),
+        ]));
+
+        assert_eq!(
+            value,
+            members(vec![
+                (
+                    "a",
+                    members(vec![("x", 1.into()), ("y", 3.into()), ("z", 4.into())])
+                ),
+                ("b", vec![Value::Int(1), Value::Int(2)].into()),
+                ("c", "keep".into()),
+                ("d", Value::Nil),
+            ])
+        );
+
+        value.merge(Value::Int(1));
+        assert_eq!(value, Value::Int(1));
+    }
+}
@@ -1,13 +1,70 @@
-use std::{collections::BTreeMap, convert::TryFrom};
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
 
-use iso8601::DateTime;
+use crate::{ValueError, XmlRpcDateTime};
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+mod canonical;
+mod convert;
 pub mod de;
+mod diff;
+mod entry;
+mod hash;
+mod iter;
+#[cfg(feature = "json")]
+mod json;
+mod kind;
+mod map;
+mod merge;
+mod net;
+mod pointer;
+mod raw;
+#[cfg(feature = "std")]
+mod redact;
 pub mod ser;
+#[cfg(feature = "url")]
+mod url;
+#[cfg(feature = "uuid")]
+mod uuid;
 
+pub use convert::XmlRpcType;
 pub use de::Deserializer;
+pub(crate) use diff::diff;
+pub use diff::DiffEntry;
+pub use entry::Entry;
+pub use iter::Walk;
+pub use kind::ValueKind;
+pub use map::Map;
+#[cfg(feature = "std")]
+pub(crate) use pointer::{
+    escape as escape_pointer, index as pointer_index, tokens as pointer_tokens,
+};
+pub use raw::RawValue;
+pub(crate) use raw::RAW_TOKEN;
+#[cfg(feature = "std")]
+pub use redact::Redactor;
 pub use ser::Serializer;
 
+/// A dateTime read from a document reaches the [`Serializer`] as a map with this as its only key
+/// and the text as its value, since serde has no date type of its own. A [`Value::DateTime`]
+/// serializes itself as a newtype struct with this name around the text.
+pub(crate) const DATETIME_TOKEN: &str = "$serde_xmlrpc::private::DateTime";
+
+/// A [`Value`] deserializes itself as a newtype struct with this name, so deserializers from this
+/// crate know to hand dateTime values over marked with [`DATETIME_TOKEN`].
+pub(crate) const VALUE_TOKEN: &str = "$serde_xmlrpc::private::Value";
+
+/// Types serialized with the `decimal` and `bigint` modules are newtype structs with these names
+/// around their text, so serializers from this crate can write them as Apache XML-RPC's
+/// `<ex:bigdecimal>` and `<ex:biginteger>` extensions.
+#[cfg(feature = "std")]
+pub(crate) const BIGDECIMAL_TOKEN: &str = "$serde_xmlrpc::private::BigDecimal";
+#[cfg(feature = "std")]
+pub(crate) const BIGINTEGER_TOKEN: &str = "$serde_xmlrpc::private::BigInteger";
+
 /// Convert a `T` into `serde_xmlrpc::Value` which is an enum that can represent
 /// any valid JSON data.
 ///
@@ -29,11 +86,11 @@ pub fn to_value<T>(value: T) -> crate::Result<Value>
 where
     T: serde::Serialize,
 {
-    value.serialize(Serializer)
+    value.serialize(Serializer::new())
 }
 
 /// Represents any single valid xmlrpc "Value"
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub enum Value {
     /// A 32-bit signed integer (`<i4>` or `<int>`).
     Int(i32),
@@ -45,20 +102,27 @@ pub enum Value {
     String(String),
     /// A double-precision IEEE 754 floating point number (`<double>`).
     Double(f64),
+    /// A single-precision IEEE 754 floating point number (`<ex:float>`), an extension used by
+    /// Apache XML-RPC. `f32` values are only written this way when
+    /// [`WriteOptions::float_extension`](crate::WriteOptions::float_extension) is set.
+    Float(f32),
     /// An ISO 8601 formatted date/time value (`<dateTime.iso8601>`).
-    DateTime(DateTime),
+    DateTime(XmlRpcDateTime),
     /// Base64-encoded binary data (`<base64>`).
     Base64(Vec<u8>),
     /// A mapping of named values (`<struct>`).
-    Struct(BTreeMap<String, Value>),
+    Struct(Map<String, Value>),
     /// A list of arbitrary (heterogeneous) values (`<array>`).
     Array(Vec<Value>),
     /// The empty (Unit) value (`<nil/>`).
     Nil,
+    /// A `<value>` element written out as is, see [`RawValue`].
+    Raw(RawValue),
 }
 
 // Public API definitions
 impl Value {
+    /*
     /// Returns an inner struct or array value indexed by `index`.
     ///
     /// Returns `None` if the member doesn't exist or `self` is neither a struct nor an array.
@@ -66,12 +130,82 @@ impl Value {
     /// You can also use Rust's square-bracket indexing syntax to perform this operation if you want
     /// a default value instead of an `Option`. Refer to the top-level [examples](#examples) for
     /// details.
-    /*
     pub fn get<I: Index>(&self, index: I) -> Option<&Value> {
         index.get(self)
     }
     */
 
+    /// Returns `true` if the type of this `Value` is one of `kinds`.
+    ///
+    /// ```
+    /// use serde_xmlrpc::{Value, ValueKind};
+    ///
+    /// assert!(Value::Int(1).matches(ValueKind::NUMBER));
+    /// assert!(!Value::Nil.matches(ValueKind::NUMBER));
+    /// ```
+    pub fn matches(&self, kinds: ValueKind) -> bool {
+        kinds.contains(self.kind())
+    }
+
+    /// Returns the type of this `Value`, as a [`ValueKind`] holding exactly that type.
+    ///
+    /// ```
+    /// use serde_xmlrpc::{Value, ValueKind};
+    ///
+    /// let value = Value::Array(vec![]);
+    /// assert_eq!(value.kind(), ValueKind::ARRAY);
+    /// assert_eq!(value.type_name(), "array");
+    /// assert_eq!(
+    ///     format!("expected {}, got {}", ValueKind::INT, value.kind()),
+    ///     "expected int, got array"
+    /// );
+    /// ```
+    pub fn kind(&self) -> ValueKind {
+        match *self {
+            Value::Int(_) => ValueKind::INT,
+            Value::Int64(_) => ValueKind::INT64,
+            Value::Bool(_) => ValueKind::BOOL,
+            Value::String(_) => ValueKind::STRING,
+            Value::Double(_) => ValueKind::DOUBLE,
+            Value::Float(_) => ValueKind::FLOAT,
+            Value::DateTime(_) => ValueKind::DATETIME,
+            Value::Base64(_) => ValueKind::BASE64,
+            Value::Struct(_) => ValueKind::STRUCT,
+            Value::Array(_) => ValueKind::ARRAY,
+            Value::Nil => ValueKind::NIL,
+            Value::Raw(_) => ValueKind::RAW,
+        }
+    }
+
+    /// Returns the name of the XML-RPC element for this `Value`'s type, such as `"int"` or
+    /// `"dateTime.iso8601"`. This is the same as the [`Display`](std::fmt::Display) output of
+    /// [`kind`](Value::kind).
+    pub fn type_name(&self) -> &'static str {
+        self.kind()
+            .name()
+            .expect("every value is of exactly one kind")
+    }
+
+    /// Returns the length in bytes of this `Value` written as XML, worked out without writing it.
+    /// This is exact for [`value_to_string`](crate::value_to_string) with the default
+    /// [`WriteOptions`](crate::WriteOptions), and close for other options.
+    ///
+    /// A call or response adds its own elements around the values, which come to 15 bytes per
+    /// param and about 100 bytes more plus the method name, so this is enough to check a response
+    /// against a size limit or to size a buffer before writing it.
+    ///
+    /// ```
+    /// use serde_xmlrpc::Value;
+    ///
+    /// let value = Value::Array(vec![Value::from("a < b"), Value::Int(42)]);
+    /// let xml = serde_xmlrpc::value_to_string(value.clone()).unwrap();
+    /// assert_eq!(value.estimated_xml_len(), xml.len());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn estimated_xml_len(&self) -> usize {
+        crate::util::value_xml_len(self, &crate::WriteOptions::lenient())
+    }
+
     /// If the `Value` is a normal integer (`Value::Int`), returns associated value. Returns `None`
     /// otherwise.
     ///
@@ -118,12 +252,69 @@ impl Value {
     pub fn as_f64(&self) -> Option<f64> {
         match *self {
             Value::Double(d) => Some(d),
+            Value::Float(f) => Some(f.into()),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is an integer which fits in a `u32`, returns it. Returns `None` otherwise,
+    /// including for negative integers and floating point numbers.
+    ///
+    /// ```
+    /// use serde_xmlrpc::Value;
+    ///
+    /// assert_eq!(Value::Int64(4_000_000_000).as_u32(), Some(4_000_000_000));
+    /// assert_eq!(Value::Int(-1).as_u32(), None);
+    /// assert_eq!(Value::Int64(1 << 32).as_u32(), None);
+    /// ```
+    pub fn as_u32(&self) -> Option<u32> {
+        self.as_i64().and_then(|i| u32::try_from(i).ok())
+    }
+
+    /// If the `Value` is a non-negative integer, returns it. Returns `None` otherwise.
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_i64().and_then(|i| u64::try_from(i).ok())
+    }
+
+    /// If the `Value` is a floating point number, returns it as an `f32`. Returns `None`
+    /// otherwise.
+    ///
+    /// A `Value::Double` is rounded to the nearest `f32`, so it may lose precision, and one
+    /// outside the range of `f32` becomes infinite.
+    pub fn as_f32(&self) -> Option<f32> {
+        match *self {
+            Value::Double(d) => Some(d as f32),
+            Value::Float(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is a number, returns it as an `i64`. Returns `None` otherwise.
+    ///
+    /// Integers are returned as they are. Floating point numbers are truncated toward zero and
+    /// saturate at `i64::MIN` and `i64::MAX`, except for NaN, which returns `None`.
+    ///
+    /// ```
+    /// use serde_xmlrpc::Value;
+    ///
+    /// assert_eq!(Value::Int(7).as_i64_lossy(), Some(7));
+    /// assert_eq!(Value::Double(-2.9).as_i64_lossy(), Some(-2));
+    /// assert_eq!(Value::Double(1e300).as_i64_lossy(), Some(i64::MAX));
+    /// assert_eq!(Value::Double(f64::NAN).as_i64_lossy(), None);
+    /// assert_eq!(Value::from("7").as_i64_lossy(), None);
+    /// ```
+    pub fn as_i64_lossy(&self) -> Option<i64> {
+        match *self {
+            Value::Int(i) => Some(i64::from(i)),
+            Value::Int64(i) => Some(i),
+            Value::Double(d) if !d.is_nan() => Some(d as i64),
+            Value::Float(f) if !f.is_nan() => Some(f as i64),
             _ => None,
         }
     }
 
     /// If the `Value` is a date/time, returns associated value. Returns `None` otherwise.
-    pub fn as_datetime(&self) -> Option<DateTime> {
+    pub fn as_datetime(&self) -> Option<XmlRpcDateTime> {
         match *self {
             Value::DateTime(dt) => Some(dt),
             _ => None,
@@ -139,7 +330,7 @@ impl Value {
     }
 
     /// If the `Value` is a struct, returns associated map. Returns `None` otherwise.
-    pub fn as_struct(&self) -> Option<&BTreeMap<String, Value>> {
+    pub fn as_struct(&self) -> Option<&Map<String, Value>> {
         match *self {
             Value::Struct(ref map) => Some(map),
             _ => None,
@@ -153,9 +344,107 @@ impl Value {
             _ => None,
         }
     }
+
+    /// Returns the member `key` of a struct for in-place manipulation. A [`Value::Nil`] is
+    /// replaced by an empty struct first, so structs can be built up from nothing.
+    ///
+    /// ```
+    /// use serde_xmlrpc::Value;
+    ///
+    /// let mut config = Value::Nil;
+    /// config.entry("retries").or_insert(3);
+    /// config.entry("retries").and_modify(|v| *v = Value::Int(5)).or_insert(3);
+    /// assert_eq!(config.as_struct().unwrap()["retries"], Value::Int(5));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` is neither a struct nor nil.
+    pub fn entry(&mut self, key: impl Into<String>) -> Entry<'_> {
+        if let Value::Nil = self {
+            *self = Value::Struct(Map::new());
+        }
+
+        match self {
+            Value::Struct(members) => members.entry(key),
+            value => panic!("entry called on {} value", value.kind()),
+        }
+    }
+
+    /// Converts each element of an array into a `T` separately, so a malformed element only
+    /// fails its own entry rather than the whole array. Returns an error if `self` isn't an
+    /// array.
+    ///
+    /// ```
+    /// use serde_xmlrpc::Document;
+    ///
+    /// let body = r#"<?xml version="1.0"?>
+    /// <methodResponse><params><param><value><array><data>
+    ///   <value><int>1</int></value>
+    ///   <value><string>two</string></value>
+    ///   <value><int>3</int></value>
+    /// </data></array></value></param></params></methodResponse>"#;
+    ///
+    /// let mut params = body.parse::<Document>().unwrap().into_result().unwrap();
+    /// let rows = params.remove(0).elements_lenient::<i32>().unwrap();
+    /// assert_eq!(rows.len(), 3);
+    /// assert_eq!(rows[0].as_ref().unwrap(), &1);
+    /// assert!(rows[1].is_err());
+    /// assert_eq!(rows[2].as_ref().unwrap(), &3);
+    /// ```
+    pub fn elements_lenient<T>(self) -> crate::Result<Vec<crate::Result<T>>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        match self {
+            Value::Array(values) => Ok(values.into_iter().map(crate::from_value).collect()),
+            value => Err(convert::invalid_type(&value, ValueKind::ARRAY)),
+        }
+    }
+
+    /// Converts a copy of `self` into a `T` like [`from_value`](crate::from_value) does, for
+    /// pulling typed parts out of a dynamic value. If a value nested in `self` can't be
+    /// converted, the error is a [`DecodingError::AtPath`] with the path to it.
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_xmlrpc::Value;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct User {
+    ///     name: String,
+    ///     age: i32,
+    /// }
+    ///
+    /// let mut response = Value::Nil;
+    /// let user = response.entry("user").or_insert(Value::Nil);
+    /// user.entry("name").or_insert("ferris");
+    /// user.entry("age").or_insert("seven");
+    ///
+    /// let err = response.pointer("/user").unwrap().extract::<User>().err().unwrap();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "decoding error: invalid type: string \"seven\", expected i32 at /age"
+    /// );
+    /// ```
+    ///
+    /// [`DecodingError::AtPath`]: crate::DecodingError::AtPath
+    pub fn extract<T>(&self) -> crate::Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        T::deserialize(Deserializer::tracking_path(self.clone()))
+    }
 }
 
-// Conversions into and from Value
+/// Whether `c` is allowed in XML text, which excludes most control characters.
+#[cfg(any(feature = "arbitrary", feature = "testing"))]
+pub(crate) fn is_xml_char(c: char) -> bool {
+    !matches!(c, '\u{0}'..='\u{8}' | '\u{b}' | '\u{c}' | '\u{e}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}')
+}
+
+// Conversions into and from Value. Each `TryFrom` takes exactly one variant, so for example an
+// `Int64` doesn't convert to an `i32` even if it would fit.
 
 impl From<i32> for Value {
     fn from(other: i32) -> Self {
@@ -164,15 +453,49 @@ impl From<i32> for Value {
 }
 
 impl<'a> TryFrom<&'a Value> for i32 {
-    type Error = ();
+    type Error = ValueError;
     fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
         match value {
             Value::Int(i) => Ok(*i),
-            _ => Err(()),
+            _ => Err(ValueError::mismatch(ValueKind::INT, value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for i32 {
+    type Error = ValueError;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Int(i) => Ok(i),
+            _ => Err(ValueError::mismatch(ValueKind::INT, &value)),
         }
     }
 }
 
+impl From<i8> for Value {
+    fn from(other: i8) -> Self {
+        Value::Int(other.into())
+    }
+}
+
+impl From<i16> for Value {
+    fn from(other: i16) -> Self {
+        Value::Int(other.into())
+    }
+}
+
+impl From<u8> for Value {
+    fn from(other: u8) -> Self {
+        Value::Int(other.into())
+    }
+}
+
+impl From<u16> for Value {
+    fn from(other: u16) -> Self {
+        Value::Int(other.into())
+    }
+}
+
 impl From<i64> for Value {
     fn from(other: i64) -> Self {
         Value::Int64(other)
@@ -180,11 +503,21 @@ impl From<i64> for Value {
 }
 
 impl<'a> TryFrom<&'a Value> for &'a i64 {
-    type Error = ();
+    type Error = ValueError;
     fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
         match value {
             Value::Int64(i) => Ok(i),
-            _ => Err(()),
+            _ => Err(ValueError::mismatch(ValueKind::INT64, value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for i64 {
+    type Error = ValueError;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Int64(i) => Ok(i),
+            _ => Err(ValueError::mismatch(ValueKind::INT64, &value)),
         }
     }
 }
@@ -196,11 +529,21 @@ impl From<bool> for Value {
 }
 
 impl<'a> TryFrom<&'a Value> for &'a bool {
-    type Error = ();
+    type Error = ValueError;
     fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
         match value {
             Value::Bool(i) => Ok(i),
-            _ => Err(()),
+            _ => Err(ValueError::mismatch(ValueKind::BOOL, value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = ValueError;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Bool(i) => Ok(i),
+            _ => Err(ValueError::mismatch(ValueKind::BOOL, &value)),
         }
     }
 }
@@ -212,13 +555,23 @@ impl From<String> for Value {
 }
 
 impl<'a> TryFrom<&'a Value> for String {
-    type Error = ();
+    type Error = ValueError;
 
     fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
         if let Some(val) = value.as_str() {
             Ok(val.to_string())
         } else {
-            Err(())
+            Err(ValueError::mismatch(ValueKind::STRING, value))
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = ValueError;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(i) => Ok(i),
+            _ => Err(ValueError::mismatch(ValueKind::STRING, &value)),
         }
     }
 }
@@ -230,17 +583,24 @@ impl From<&str> for Value {
 }
 
 impl<'a> TryFrom<&'a Value> for &'a str {
-    type Error = ();
+    type Error = ValueError;
 
     fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
         if let Some(val) = value.as_str() {
             Ok(val)
         } else {
-            Err(())
+            Err(ValueError::mismatch(ValueKind::STRING, value))
         }
     }
 }
 
+/// Converts into a `Value::Double`, since `<ex:float>` is an extension few servers understand.
+impl From<f32> for Value {
+    fn from(other: f32) -> Self {
+        Value::Double(other.into())
+    }
+}
+
 impl From<f64> for Value {
     fn from(other: f64) -> Self {
         Value::Double(other)
@@ -248,27 +608,51 @@ impl From<f64> for Value {
 }
 
 impl<'a> TryFrom<&'a Value> for &'a f64 {
-    type Error = ();
+    type Error = ValueError;
     fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
         match value {
             Value::Double(i) => Ok(i),
-            _ => Err(()),
+            _ => Err(ValueError::mismatch(ValueKind::DOUBLE, value)),
         }
     }
 }
 
-impl From<DateTime> for Value {
-    fn from(other: DateTime) -> Self {
+impl TryFrom<Value> for f64 {
+    type Error = ValueError;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Double(i) => Ok(i),
+            Value::Float(f) => Ok(f64::from(f)),
+            _ => Err(ValueError::mismatch(
+                ValueKind::DOUBLE | ValueKind::FLOAT,
+                &value,
+            )),
+        }
+    }
+}
+
+impl From<XmlRpcDateTime> for Value {
+    fn from(other: XmlRpcDateTime) -> Self {
         Value::DateTime(other)
     }
 }
 
-impl<'a> TryFrom<&'a Value> for &'a DateTime {
-    type Error = ();
+impl<'a> TryFrom<&'a Value> for &'a XmlRpcDateTime {
+    type Error = ValueError;
     fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
         match value {
             Value::DateTime(i) => Ok(i),
-            _ => Err(()),
+            _ => Err(ValueError::mismatch(ValueKind::DATETIME, value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for XmlRpcDateTime {
+    type Error = ValueError;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::DateTime(i) => Ok(i),
+            _ => Err(ValueError::mismatch(ValueKind::DATETIME, &value)),
         }
     }
 }
@@ -280,27 +664,53 @@ impl From<Vec<Value>> for Value {
 }
 
 impl<'a> TryFrom<&'a Value> for &'a Vec<Value> {
-    type Error = ();
+    type Error = ValueError;
     fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
         match value {
             Value::Array(i) => Ok(i),
-            _ => Err(()),
+            _ => Err(ValueError::mismatch(ValueKind::ARRAY, value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for Vec<Value> {
+    type Error = ValueError;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Array(i) => Ok(i),
+            _ => Err(ValueError::mismatch(ValueKind::ARRAY, &value)),
         }
     }
 }
 
 impl From<BTreeMap<String, Value>> for Value {
     fn from(other: BTreeMap<String, Value>) -> Value {
+        Value::Struct(other.into_iter().collect())
+    }
+}
+
+impl From<Map<String, Value>> for Value {
+    fn from(other: Map<String, Value>) -> Value {
         Value::Struct(other)
     }
 }
 
-impl<'a> TryFrom<&'a Value> for &'a BTreeMap<String, Value> {
-    type Error = ();
+impl<'a> TryFrom<&'a Value> for &'a Map<String, Value> {
+    type Error = ValueError;
     fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
         match value {
             Value::Struct(i) => Ok(i),
-            _ => Err(()),
+            _ => Err(ValueError::mismatch(ValueKind::STRUCT, value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for Map<String, Value> {
+    type Error = ValueError;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Struct(i) => Ok(i),
+            _ => Err(ValueError::mismatch(ValueKind::STRUCT, &value)),
         }
     }
 }
@@ -312,11 +722,27 @@ impl From<Vec<u8>> for Value {
 }
 
 impl<'a> TryFrom<&'a Value> for &'a Vec<u8> {
-    type Error = ();
+    type Error = ValueError;
     fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
         match value {
             Value::Base64(i) => Ok(i),
-            _ => Err(()),
+            _ => Err(ValueError::mismatch(ValueKind::BASE64, value)),
         }
     }
 }
+
+impl TryFrom<Value> for Vec<u8> {
+    type Error = ValueError;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Base64(i) => Ok(i),
+            _ => Err(ValueError::mismatch(ValueKind::BASE64, &value)),
+        }
+    }
+}
+
+impl From<RawValue> for Value {
+    fn from(other: RawValue) -> Self {
+        Value::Raw(other)
+    }
+}
@@ -0,0 +1,126 @@
+//! Conversions between [`Value`] and the address types of [`std::net`].
+//!
+//! Addresses are written as a `<string>` in their usual notation, such as `10.0.0.1`, `::1` or
+//! `[::1]:9001`, the same as serde writes them in fields. Converting back parses the string, and
+//! the error names the text which didn't parse.
+
+use alloc::format;
+use alloc::string::ToString;
+use core::convert::TryFrom;
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+use super::convert::invalid_type;
+use crate::error::DecodingError;
+use crate::{Error, Value, ValueKind, XmlRpcType};
+
+macro_rules! impl_address {
+    ($($ty:ty => $expected:expr,)*) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(other: $ty) -> Self {
+                    Value::String(other.to_string())
+                }
+            }
+
+            impl<'a> TryFrom<&'a Value> for $ty {
+                type Error = Error;
+
+                fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+                    let text = value
+                        .as_str()
+                        .ok_or_else(|| invalid_type(value, ValueKind::STRING))?;
+                    text.parse().map_err(|_| {
+                        DecodingError::InvalidValue {
+                            found: format!("string {:?}", text),
+                            expected: $expected.to_string(),
+                        }
+                        .into()
+                    })
+                }
+            }
+
+            impl TryFrom<Value> for $ty {
+                type Error = Error;
+
+                fn try_from(value: Value) -> Result<Self, Self::Error> {
+                    <$ty>::try_from(&value)
+                }
+            }
+
+            impl XmlRpcType for $ty {
+                fn to_value(&self) -> Value {
+                    Value::from(*self)
+                }
+
+                fn from_value(value: &Value) -> crate::Result<Self> {
+                    <$ty>::try_from(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_address! {
+    IpAddr => "an IP address",
+    Ipv4Addr => "an IPv4 address",
+    Ipv6Addr => "an IPv6 address",
+    SocketAddr => "a socket address",
+    SocketAddrV4 => "an IPv4 socket address",
+    SocketAddrV6 => "an IPv6 socket address",
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[test]
+    fn address_conversions() {
+        let ip: IpAddr = "fe80::1".parse().unwrap();
+        assert_eq!(Value::from(ip).as_str(), Some("fe80::1"));
+        assert_eq!(IpAddr::try_from(Value::from(ip)).unwrap(), ip);
+
+        let addr = SocketAddr::from(([127, 0, 0, 1], 9001));
+        assert_eq!(Value::from(addr).as_str(), Some("127.0.0.1:9001"));
+        assert_eq!(SocketAddr::from_value(&Value::from(addr)).unwrap(), addr);
+
+        let err = Ipv4Addr::try_from(Value::from("::1")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "decoding error: invalid value: string \"::1\", expected an IPv4 address"
+        );
+        assert!(SocketAddr::try_from(Value::from("127.0.0.1")).is_err());
+        assert!(IpAddr::try_from(Value::Int(1)).is_err());
+    }
+
+    #[test]
+    fn address_fields() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Listener {
+            host: IpAddr,
+            bind: SocketAddr,
+        }
+
+        let listener = Listener {
+            host: "::1".parse().unwrap(),
+            bind: "[::1]:9001".parse().unwrap(),
+        };
+        let body = crate::value_to_string(crate::to_value(&listener).unwrap()).unwrap();
+        assert!(body.contains("<string>::1</string>"));
+        assert!(body.contains("<string>[::1]:9001</string>"));
+
+        let value = crate::value_from_str(&body).unwrap();
+        assert_eq!(crate::from_value::<Listener>(value).unwrap(), listener);
+
+        let response =
+            crate::response_to_string(crate::params_to_vec(&listener).unwrap().into_iter());
+        assert_eq!(
+            crate::response_from_str::<Listener>(&response.unwrap()).unwrap(),
+            listener
+        );
+
+        let bad = crate::value_from_str(&body.replace("::1<", "::zz<")).unwrap();
+        assert!(crate::from_value::<Listener>(bad).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use alloc::string::String;
+
+use super::Value;
+
+impl Value {
+    /// Looks up a value by a [JSON Pointer] such as `"/a/b/0"`, which names a struct member or
+    /// array index at each step. The empty pointer refers to `self`, and `~1` and `~0` stand for
+    /// `/` and `~` in member names.
+    ///
+    /// Returns `None` if the pointer doesn't start with `/`, if a member or index doesn't exist or
+    /// if a step goes into a value which is neither a struct nor an array.
+    ///
+    /// ```
+    /// use serde_xmlrpc::Value;
+    ///
+    /// let mut value = Value::Nil;
+    /// value.entry("a/b").or_insert(vec![Value::Int(1), Value::Int(2)]);
+    ///
+    /// assert_eq!(value.pointer("/a~1b/1"), Some(&Value::Int(2)));
+    /// assert_eq!(value.pointer("/a~1b/2"), None);
+    /// assert_eq!(value.pointer(""), Some(&value));
+    /// ```
+    ///
+    /// [JSON Pointer]: https://www.rfc-editor.org/rfc/rfc6901
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        let mut value = self;
+        for token in tokens(pointer)? {
+            value = match value {
+                Value::Struct(members) => members.get(&token)?,
+                Value::Array(values) => values.get(index(&token)?)?,
+                _ => return None,
+            };
+        }
+
+        Some(value)
+    }
+
+    /// Like [`pointer`](Value::pointer), but returns the value for modification.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        let mut value = self;
+        for token in tokens(pointer)? {
+            value = match value {
+                Value::Struct(members) => members.get_mut(&token)?,
+                Value::Array(values) => values.get_mut(index(&token)?)?,
+                _ => return None,
+            };
+        }
+
+        Some(value)
+    }
+}
+
+/// Splits a pointer into its unescaped reference tokens.
+pub(crate) fn tokens(pointer: &str) -> Option<impl Iterator<Item = String> + '_> {
+    let rest = match pointer {
+        "" => None,
+        pointer => Some(pointer.strip_prefix('/')?),
+    };
+
+    Some(
+        rest.into_iter()
+            .flat_map(|rest| rest.split('/'))
+            .map(|token| token.replace("~1", "/").replace("~0", "~")),
+    )
+}
+
+/// Escapes a struct member name for use as a step of a pointer.
+pub(crate) fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Parses an array index, which can't have leading zeros or a sign.
+pub(crate) fn index(token: &str) -> Option<usize> {
+    if token.starts_with('+') || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Value;
+
+    #[test]
+    fn pointer() {
+        let mut value = Value::Nil;
+        value
+            .entry("a")
+            .or_insert(Value::Nil)
+            .entry("b")
+            .or_insert(vec![Value::Int(1), Value::Int(2)]);
+        value.entry("m~n").or_insert(3);
+        value.entry("").or_insert(4);
+
+        assert_eq!(value.pointer("/a/b/0"), Some(&Value::Int(1)));
+        assert_eq!(value.pointer("/m~0n"), Some(&Value::Int(3)));
+        assert_eq!(value.pointer("/"), Some(&Value::Int(4)));
+        assert_eq!(value.pointer("a"), None);
+        assert_eq!(value.pointer("/a/b/01"), None);
+        assert_eq!(value.pointer("/a/b/+1"), None);
+        assert_eq!(value.pointer("/a/b/0/c"), None);
+
+        *value.pointer_mut("/a/b/1").unwrap() = Value::Int(5);
+        assert_eq!(value.pointer("/a/b/1"), Some(&Value::Int(5)));
+    }
+}
@@ -0,0 +1,231 @@
+use alloc::string::String;
+use core::fmt;
+
+#[cfg(feature = "std")]
+use quick_xml::events::Event;
+#[cfg(feature = "std")]
+use quick_xml::Reader;
+use serde::Serialize;
+
+#[cfg(feature = "std")]
+use crate::error::DecodingError;
+#[cfg(feature = "std")]
+use crate::Result;
+
+/// A `<value>` which serializes to its newtype's text instead of its fields, so both serializers
+/// can tell a [`RawValue`] apart from a string.
+pub(crate) const RAW_TOKEN: &str = "$serde_xmlrpc::private::RawValue";
+
+/// A pre-serialized `<value>` element, written into documents as is.
+///
+/// This is an escape hatch for peers which need constructs this crate doesn't model, such as
+/// another implementation's extension types, and a way to cache subtrees which are written over
+/// and over. The XML is checked to be a single well-formed `<value>` element when the `RawValue`
+/// is created, but isn't otherwise interpreted, so it is up to the caller that the peer
+/// understands it.
+///
+/// A `RawValue` can be used as a [`Value::Raw`](crate::Value::Raw) or as a field of a type which
+/// is serialized:
+///
+/// ```
+/// use serde_xmlrpc::{RawValue, Value};
+///
+/// let cached = serde_xmlrpc::value_to_string(vec![Value::from("big"), Value::from("list")]);
+/// let cached = RawValue::new(cached.unwrap()).unwrap();
+///
+/// let body = serde_xmlrpc::request_to_string("show", vec![Value::Raw(cached)]).unwrap();
+/// assert!(body.contains("<param><value><array><data><value><string>big</string>"));
+///
+/// let custom = RawValue::new("<value><ex:i1 xmlns:ex=\"urn:example\">7</ex:i1></value>");
+/// assert!(custom.is_ok());
+/// assert!(RawValue::new("<value><int>7</int>").is_err());
+/// ```
+///
+/// Reading a `Value::Raw` back, such as with [`from_value`](crate::from_value), parses the XML
+/// first, so it only succeeds for types this crate understands. Both checking and parsing the
+/// XML need the `std` feature.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RawValue(pub(crate) String);
+
+impl RawValue {
+    /// Checks that `xml` is a single well-formed `<value>` element, optionally surrounded by
+    /// whitespace, and wraps it.
+    #[cfg(feature = "std")]
+    pub fn new(xml: impl Into<String>) -> Result<Self> {
+        let xml = xml.into();
+        check_fragment(&xml)?;
+        Ok(RawValue(xml))
+    }
+
+    /// Returns the XML.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the XML, consuming the `RawValue`.
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    /// A `<value>` holding one of Apache XML-RPC's extension types, such as `<ex:bigdecimal>`.
+    #[cfg(feature = "std")]
+    pub(crate) fn extension(tag: &str, text: &str) -> Self {
+        RawValue(format!(
+            "<value><ex:{} xmlns:ex=\"{}\">{}</ex:{}></value>",
+            tag,
+            crate::util::EXTENSIONS_NS,
+            quick_xml::escape::escape(text),
+            tag
+        ))
+    }
+}
+
+impl fmt::Display for RawValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for RawValue {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_newtype_struct(RAW_TOKEN, &self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+fn check_fragment(xml: &str) -> Result<()> {
+    let mut reader = Reader::from_str(xml);
+    reader.expand_empty_elements(true);
+    let mut depth = 0usize;
+    let mut seen_root = false;
+
+    loop {
+        match reader.read_event().map_err(DecodingError::from)? {
+            Event::Start(e) if depth == 0 => {
+                if seen_root || e.name().as_ref() != b"value" {
+                    let found = String::from_utf8_lossy(e.name().into_inner()).into_owned();
+                    return Err(DecodingError::UnexpectedTag(found, "value".into()).into());
+                }
+                seen_root = true;
+                depth += 1;
+            }
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            // Unknown entities would break the document the value ends up in.
+            Event::Text(e) if depth > 0 => {
+                e.unescape().map_err(DecodingError::from)?;
+            }
+            Event::Text(e) if e.iter().all(u8::is_ascii_whitespace) => {}
+            Event::CData(_) | Event::Comment(_) if depth > 0 => {}
+            Event::Eof if seen_root && depth == 0 => return Ok(()),
+            Event::Eof => return Err(DecodingError::UnexpectedEOF("value".into()).into()),
+            e => return Err(DecodingError::UnexpectedEvent(format!("{:?}", e)).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+
+    use super::*;
+    use crate::Value;
+
+    #[test]
+    fn well_formed() {
+        assert!(RawValue::new("<value/>").is_ok());
+        assert!(RawValue::new("\n<value><![CDATA[<]]><!-- note --></value>\n").is_ok());
+
+        for xml in [
+            "",
+            "<int>1</int>",
+            "<value>1</value><value>2</value>",
+            "<value><int>1</value></int>",
+            "<value><int>1</int>",
+            "<value>&bogus;</value>",
+            "text<value/>",
+            "<?xml version=\"1.0\"?><value/>",
+        ]
+        .iter()
+        {
+            assert!(RawValue::new(*xml).is_err(), "{}", xml);
+        }
+    }
+
+    #[test]
+    fn written_as_is() {
+        #[derive(Serialize)]
+        struct Params {
+            cached: RawValue,
+        }
+
+        let raw = RawValue::new("<value><ex:i1>7</ex:i1></value>").unwrap();
+        let expected = "<value><struct><member><name>cached</name>\
+            <value><ex:i1>7</ex:i1></value></member></struct></value>";
+
+        let params = Params {
+            cached: raw.clone(),
+        };
+        assert_eq!(
+            crate::value_to_string(crate::to_value(&params).unwrap()).unwrap(),
+            expected
+        );
+        let mut members = crate::Map::new();
+        members.insert("cached".to_string(), Value::Raw(raw));
+        assert_eq!(
+            crate::value_to_string(Value::Struct(members)).unwrap(),
+            expected
+        );
+
+        let body = crate::request_to_string("a", crate::params_to_vec((params,)).unwrap());
+        assert!(body.unwrap().contains(expected));
+    }
+
+    #[test]
+    fn unknown_kept() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Params {
+            #[allow(dead_code)]
+            shape: Value,
+        }
+
+        let options = crate::ParseOptions::default().keep_unknown(true);
+        let unknown =
+            "<value>\n  <ex:shape kind=\"circle\"><r>1&amp;2</r><![CDATA[<]]></ex:shape>\n</value>";
+        let params = format!(
+            "<value><struct><member><name>shape</name>{}</member></struct></value>",
+            unknown
+        );
+        let body = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?><methodCall><methodName>draw</methodName>\
+            <params><param>{}</param><param><value><int>2</int></value></param></params></methodCall>",
+            params
+        );
+
+        let (name, args) = options.request_from_str(&body).unwrap();
+        assert_eq!(
+            args[0].pointer("/shape"),
+            Some(&Value::Raw(RawValue(unknown.into())))
+        );
+        assert_eq!(args[1], Value::Int(2));
+        assert_eq!(crate::request_to_string(&name, args).unwrap(), body);
+        assert_eq!(
+            crate::value_to_string(options.value_from_str(&params).unwrap()).unwrap(),
+            params
+        );
+
+        // Only values read into a Value are kept, and only when asked to.
+        assert!(crate::request_from_str(&body).is_err());
+        assert!(options.response_from_str::<Params>(&body).is_err());
+        assert!(options.value_from_str("<value><ex:shape></value>").is_err());
+    }
+
+    #[test]
+    fn read_back() {
+        let raw = Value::Raw(RawValue::new("<value><int>7</int></value>").unwrap());
+        assert_eq!(crate::from_value::<i32>(raw).unwrap(), 7);
+    }
+}
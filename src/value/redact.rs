@@ -0,0 +1,247 @@
+use super::pointer::tokens;
+use super::Value;
+use crate::{Document, Result};
+
+impl Value {
+    /// Returns a copy with sensitive values masked, such as before logging it. Each of `paths` is
+    /// either a struct member name, which is masked wherever it appears and compared ignoring
+    /// ASCII case, or a pointer as for [`pointer`](Value::pointer) starting with `/`, where `*`
+    /// matches any member or index. See [`Redactor`] for more control.
+    ///
+    /// ```
+    /// use serde_xmlrpc::Value;
+    ///
+    /// let mut value = Value::Nil;
+    /// value.entry("user").or_insert("admin");
+    /// value.entry("Password").or_insert("hunter2");
+    /// value.entry("keys").or_insert(vec![Value::from("k1"), Value::from("k2")]);
+    ///
+    /// let redacted = value.redacted(["password", "/keys/*"]);
+    /// assert_eq!(redacted.pointer("/user"), Some(&Value::from("admin")));
+    /// assert_eq!(redacted.pointer("/Password"), Some(&Value::from("[redacted]")));
+    /// assert_eq!(redacted.pointer("/keys/1"), Some(&Value::from("[redacted]")));
+    /// ```
+    pub fn redacted<I, S>(&self, paths: I) -> Value
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let redactor = paths.into_iter().fold(Redactor::new(), |redactor, path| {
+            let path = path.as_ref();
+            if path.starts_with('/') {
+                redactor.path(path)
+            } else {
+                redactor.member(path)
+            }
+        });
+
+        let mut value = self.clone();
+        redactor.redact(&mut value);
+        value
+    }
+}
+
+/// Masks sensitive values, such as passwords, in values and whole documents before they are
+/// logged.
+///
+/// Values are masked by struct member name wherever they appear, or by pointer. In a
+/// [`Document`], the params count as an array, so `/0/password` is the `password` member of the
+/// first param.
+///
+/// ```
+/// use serde_xmlrpc::Redactor;
+///
+/// let redactor = Redactor::new().member("password").path("/1");
+///
+/// let body = serde_xmlrpc::request_to_string(
+///     "login",
+///     vec!["admin".into(), "hunter2".into()],
+/// )
+/// .unwrap();
+/// let logged = redactor.redact_xml(&body).unwrap();
+/// assert!(logged.contains("<string>admin</string>"));
+/// assert!(!logged.contains("hunter2"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Redactor {
+    members: Vec<String>,
+    paths: Vec<Vec<String>>,
+    replacement: Value,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Redactor {
+            members: Vec::new(),
+            paths: Vec::new(),
+            replacement: Value::from("[redacted]"),
+        }
+    }
+}
+
+impl Redactor {
+    /// Creates a redactor which masks nothing yet, with `"[redacted]"` as the replacement.
+    pub fn new() -> Self {
+        Redactor::default()
+    }
+
+    /// Masks the values of struct members named `name`, compared ignoring ASCII case, wherever
+    /// they appear.
+    pub fn member(mut self, name: impl Into<String>) -> Self {
+        self.members.push(name.into());
+        self
+    }
+
+    /// Masks the value at `pointer`, as for [`Value::pointer`], where a `*` step matches any
+    /// member or index. Invalid pointers never match.
+    pub fn path(mut self, pointer: &str) -> Self {
+        if let Some(tokens) = tokens(pointer) {
+            self.paths.push(tokens.collect());
+        }
+        self
+    }
+
+    /// Sets the value masked values are replaced with.
+    pub fn replacement(mut self, replacement: impl Into<Value>) -> Self {
+        self.replacement = replacement.into();
+        self
+    }
+
+    /// Masks the values of `value` in place.
+    pub fn redact(&self, value: &mut Value) {
+        self.redact_at(&mut Vec::new(), value);
+    }
+
+    /// Masks the params of a call or response in place. Faults are left alone.
+    pub fn redact_document(&self, document: &mut Document) {
+        let params = match document {
+            Document::Call { params, .. } | Document::Response(params) => params,
+            Document::Fault(_) => return,
+        };
+
+        let mut path = Vec::new();
+        for (idx, param) in params.iter_mut().enumerate() {
+            path.push(idx.to_string());
+            self.redact_at(&mut path, param);
+            path.pop();
+        }
+    }
+
+    /// Parses a call or response, masks its params and writes it again, for logging traffic.
+    pub fn redact_xml(&self, xml: &str) -> Result<String> {
+        let mut document = xml.parse::<Document>()?;
+        self.redact_document(&mut document);
+        document.to_xml()
+    }
+
+    fn redact_at(&self, path: &mut Vec<String>, value: &mut Value) {
+        if self.matches_path(path) {
+            *value = self.replacement.clone();
+            return;
+        }
+
+        match value {
+            Value::Struct(members) => {
+                for (key, member) in members.iter_mut() {
+                    if self
+                        .members
+                        .iter()
+                        .any(|name| name.eq_ignore_ascii_case(key))
+                    {
+                        *member = self.replacement.clone();
+                        continue;
+                    }
+                    path.push(key.clone());
+                    self.redact_at(path, member);
+                    path.pop();
+                }
+            }
+            Value::Array(values) => {
+                for (idx, element) in values.iter_mut().enumerate() {
+                    path.push(idx.to_string());
+                    self.redact_at(path, element);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn matches_path(&self, path: &[String]) -> bool {
+        self.paths.iter().any(|pattern| {
+            pattern.len() == path.len()
+                && pattern
+                    .iter()
+                    .zip(path)
+                    .all(|(step, token)| step == "*" || step == token)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Fault, Values};
+
+    #[test]
+    fn redact() {
+        let mut value = Value::Nil;
+        value
+            .entry("auth")
+            .or_insert(Value::Nil)
+            .entry("PASSWORD")
+            .or_insert(Value::Nil)
+            .entry("nested")
+            .or_insert(1);
+        value
+            .entry("users")
+            .or_insert(vec![Value::from("a"), Value::from(vec![Value::from("b")])]);
+
+        let redacted = value.redacted(["password", "/users/*/0", "bogus"]);
+        assert_eq!(
+            redacted.pointer("/auth/PASSWORD"),
+            Some(&Value::from("[redacted]"))
+        );
+        assert_eq!(redacted.pointer("/users/0"), Some(&Value::from("a")));
+        assert_eq!(
+            redacted.pointer("/users/1/0"),
+            Some(&Value::from("[redacted]"))
+        );
+
+        let mut value = Value::from("secret");
+        Redactor::new()
+            .path("")
+            .replacement(Value::Nil)
+            .redact(&mut value);
+        assert_eq!(value, Value::Nil);
+        assert_eq!(Value::from(1).redacted(["x", "nope"]), Value::from(1));
+    }
+
+    #[test]
+    fn redact_documents() {
+        let redactor = Redactor::new().member("token").path("/1");
+        let mut session = Value::Nil;
+        session.entry("token").or_insert("abc");
+        session.entry("user").or_insert("admin");
+
+        let mut document = Document::Response(Values::from(vec![session, Value::from("pw")]));
+        redactor.redact_document(&mut document);
+        let params = match document {
+            Document::Response(params) => params,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            params[0].pointer("/token"),
+            Some(&Value::from("[redacted]"))
+        );
+        assert_eq!(params[0].pointer("/user"), Some(&Value::from("admin")));
+        assert_eq!(params[1], Value::from("[redacted]"));
+
+        let fault = crate::result_to_string::<()>(&Err(Fault::new(1, "token expired"))).unwrap();
+        assert!(redactor
+            .redact_xml(&fault)
+            .unwrap()
+            .contains("token expired"));
+        assert!(redactor.redact_xml("<value/>").is_err());
+    }
+}
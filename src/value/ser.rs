@@ -1,29 +1,73 @@
-use std::collections::BTreeMap;
+use alloc::borrow::ToOwned;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 
 use serde::Serialize;
 
+use super::{RawValue, DATETIME_TOKEN, RAW_TOKEN};
+#[cfg(feature = "std")]
+use super::{BIGDECIMAL_TOKEN, BIGINTEGER_TOKEN};
 use crate::error::EncodingError;
-use crate::{Error, Result, Value};
+use crate::{Error, Map, ParseOptions, Result, Value, WriteOptions};
 
-pub struct Serializer;
+static DEFAULT_OPTIONS: WriteOptions = WriteOptions::lenient();
 
-impl Serializer {
+/// Serializes the value as its contents, so other formats see plain numbers, strings, maps and
+/// sequences. A dateTime becomes its text in ISO 8601 extended format, and [`Value::Raw`] its XML.
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Value::Int(v) => serializer.serialize_i32(*v),
+            Value::Int64(v) => serializer.serialize_i64(*v),
+            Value::Bool(v) => serializer.serialize_bool(*v),
+            Value::String(v) => serializer.serialize_str(v),
+            Value::Double(v) => serializer.serialize_f64(*v),
+            Value::Float(v) => serializer.serialize_f32(*v),
+            Value::DateTime(v) => {
+                serializer.serialize_newtype_struct(DATETIME_TOKEN, &v.to_string())
+            }
+            Value::Base64(v) => serializer.serialize_bytes(v),
+            Value::Struct(v) => serializer.collect_map(v),
+            Value::Array(v) => serializer.collect_seq(v),
+            Value::Nil => serializer.serialize_unit(),
+            Value::Raw(v) => v.serialize(serializer),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Serializer<'o> {
+    options: &'o WriteOptions,
+}
+
+impl Serializer<'static> {
     pub fn new() -> Self {
-        Serializer {}
+        Serializer {
+            options: &DEFAULT_OPTIONS,
+        }
     }
 }
 
-impl serde::Serializer for Serializer {
+impl<'o> Serializer<'o> {
+    pub fn with_options(options: &'o WriteOptions) -> Self {
+        Serializer { options }
+    }
+}
+
+impl<'o> serde::Serializer for Serializer<'o> {
     type Error = Error;
     type Ok = Value;
 
-    type SerializeSeq = SerializeVec;
-    type SerializeTuple = SerializeVec;
-    type SerializeTupleStruct = SerializeVec;
-    type SerializeTupleVariant = SerializeVec;
-    type SerializeMap = SerializeMap;
-    type SerializeStruct = SerializeMap;
-    type SerializeStructVariant = SerializeMap;
+    type SerializeSeq = SerializeVec<'o>;
+    type SerializeTuple = SerializeVec<'o>;
+    type SerializeTupleStruct = SerializeVec<'o>;
+    type SerializeTupleVariant = SerializeVec<'o>;
+    type SerializeMap = SerializeMap<'o>;
+    type SerializeStruct = SerializeMap<'o>;
+    type SerializeStructVariant = SerializeMap<'o>;
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
         Ok(Value::Bool(v))
@@ -64,6 +108,9 @@ impl serde::Serializer for Serializer {
     }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok> {
+        if self.options.float_extension {
+            return Ok(Value::Float(v));
+        }
         Ok(Value::Double(v as f64))
     }
 
@@ -87,11 +134,11 @@ impl serde::Serializer for Serializer {
         Ok(Value::Nil)
     }
 
-    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Self::Ok>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        value.serialize(Serializer)
+        value.serialize(self)
     }
 
     fn serialize_unit(self) -> Result<Self::Ok> {
@@ -99,7 +146,7 @@ impl serde::Serializer for Serializer {
     }
 
     fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
-        Ok(Value::Struct(BTreeMap::new()))
+        Ok(Value::Struct(Map::new()))
     }
 
     fn serialize_unit_variant(
@@ -111,14 +158,26 @@ impl serde::Serializer for Serializer {
         self.serialize_unit()
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(self, _name: &'static str, value: &T) -> Result<Self::Ok>
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Self::Ok>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        value.serialize(Serializer)
+        match value.serialize(self)? {
+            Value::String(xml) if name == RAW_TOKEN => Ok(Value::Raw(RawValue(xml))),
+            Value::String(text) if name == DATETIME_TOKEN => Ok(Value::DateTime(
+                crate::datetime::parse_datetime(&text, &ParseOptions::lenient())?,
+            )),
+            #[cfg(feature = "std")]
+            Value::String(text) if self.options.big_number_extension => match name {
+                BIGDECIMAL_TOKEN => Ok(Value::Raw(RawValue::extension("bigdecimal", &text))),
+                BIGINTEGER_TOKEN => Ok(Value::Raw(RawValue::extension("biginteger", &text))),
+                _ => Ok(Value::String(text)),
+            },
+            value => Ok(value),
+        }
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
@@ -126,7 +185,7 @@ impl serde::Serializer for Serializer {
         _value: &T,
     ) -> Result<Self::Ok>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unimplemented!();
     }
@@ -137,6 +196,7 @@ impl serde::Serializer for Serializer {
 
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
         Ok(SerializeVec {
+            options: self.options,
             vec: Vec::with_capacity(len),
         })
     }
@@ -161,7 +221,8 @@ impl serde::Serializer for Serializer {
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
         Ok(SerializeMap {
-            map: BTreeMap::new(),
+            options: self.options,
+            map: Map::new(),
             next_key: None,
         })
     }
@@ -182,19 +243,21 @@ impl serde::Serializer for Serializer {
 }
 
 #[doc(hidden)]
-pub struct SerializeVec {
+pub struct SerializeVec<'o> {
+    options: &'o WriteOptions,
     vec: Vec<Value>,
 }
 
-impl serde::ser::SerializeSeq for SerializeVec {
+impl<'o> serde::ser::SerializeSeq for SerializeVec<'o> {
     type Ok = Value;
     type Error = Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        self.vec.push(value.serialize(Serializer)?);
+        self.vec
+            .push(value.serialize(Serializer::with_options(self.options))?);
         Ok(())
     }
 
@@ -203,13 +266,13 @@ impl serde::ser::SerializeSeq for SerializeVec {
     }
 }
 
-impl serde::ser::SerializeTuple for SerializeVec {
+impl<'o> serde::ser::SerializeTuple for SerializeVec<'o> {
     type Ok = Value;
     type Error = Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         serde::ser::SerializeSeq::serialize_element(self, value)
     }
@@ -219,13 +282,13 @@ impl serde::ser::SerializeTuple for SerializeVec {
     }
 }
 
-impl serde::ser::SerializeTupleStruct for SerializeVec {
+impl<'o> serde::ser::SerializeTupleStruct for SerializeVec<'o> {
     type Ok = Value;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         serde::ser::SerializeSeq::serialize_element(self, value)
     }
@@ -235,13 +298,13 @@ impl serde::ser::SerializeTupleStruct for SerializeVec {
     }
 }
 
-impl serde::ser::SerializeTupleVariant for SerializeVec {
+impl<'o> serde::ser::SerializeTupleVariant for SerializeVec<'o> {
     type Ok = Value;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         serde::ser::SerializeSeq::serialize_element(self, value)
     }
@@ -252,75 +315,64 @@ impl serde::ser::SerializeTupleVariant for SerializeVec {
 }
 
 #[doc(hidden)]
-pub struct SerializeMap {
-    map: BTreeMap<String, Value>,
+pub struct SerializeMap<'o> {
+    options: &'o WriteOptions,
+    map: Map<String, Value>,
     next_key: Option<String>,
 }
 
-impl serde::ser::SerializeMap for SerializeMap {
+impl<'o> serde::ser::SerializeMap for SerializeMap<'o> {
     type Ok = Value;
     type Error = Error;
 
-    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<()>
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        // We can only serialize keys if they can be converted to strings
-        match key.serialize(Serializer)? {
-            Value::Int(v) => {
-                self.next_key = Some(v.to_string());
-                Ok(())
-            }
-            Value::Int64(v) => {
-                self.next_key = Some(v.to_string());
-                Ok(())
-            }
-            Value::Bool(v) => {
-                self.next_key = Some(v.to_string());
-                Ok(())
-            }
-            Value::String(s) => {
-                self.next_key = Some(s);
-                Ok(())
-            }
-            Value::Double(v) => {
-                self.next_key = Some(v.to_string());
-                Ok(())
-            }
-            _ => Err(EncodingError::InvalidKeyType(
-                "int, int64, bool, string, char, or float".to_string(),
-            )
-            .into()),
-        }
+        let key = key.serialize(MapKeySerializer)?;
+        self.next_key = Some(self.options.member_name(&key).to_owned());
+        Ok(())
     }
 
-    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         let key = self
             .next_key
             .take()
             .expect("serialize_value called before serialize_key");
-        let value = value.serialize(Serializer)?;
+        let value = value.serialize(Serializer::with_options(self.options))?;
 
+        if self.options.strict && self.map.contains_key(&key) {
+            return Err(EncodingError::DuplicateMember(key).into());
+        }
         self.map.insert(key, value);
 
         Ok(())
     }
 
     fn end(self) -> Result<Value> {
+        if let (1, Some(Value::String(text))) = (self.map.len(), self.map.get(DATETIME_TOKEN)) {
+            let dt = crate::datetime::parse_datetime(text, &ParseOptions::lenient())?;
+            return Ok(Value::DateTime(dt));
+        }
+        #[cfg(feature = "std")]
+        if let (1, Some(Value::String(xml))) = (self.map.len(), self.map.get(RAW_TOKEN)) {
+            return Ok(Value::Raw(RawValue::new(xml.as_str())?));
+        }
+
         Ok(Value::Struct(self.map))
     }
 }
 
-impl serde::ser::SerializeStruct for SerializeMap {
+impl<'o> serde::ser::SerializeStruct for SerializeMap<'o> {
     type Ok = Value;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         serde::ser::SerializeMap::serialize_key(self, key)?;
         serde::ser::SerializeMap::serialize_value(self, value)
@@ -331,13 +383,13 @@ impl serde::ser::SerializeStruct for SerializeMap {
     }
 }
 
-impl serde::ser::SerializeStructVariant for SerializeMap {
+impl<'o> serde::ser::SerializeStructVariant for SerializeMap<'o> {
     type Ok = Value;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         serde::ser::SerializeMap::serialize_key(self, key)?;
         serde::ser::SerializeMap::serialize_value(self, value)
@@ -348,6 +400,176 @@ impl serde::ser::SerializeStructVariant for SerializeMap {
     }
 }
 
+/// Converts map keys into struct member names. Any scalar which has a sensible string form is
+/// accepted, so maps keyed by integers or newtypes around strings work as well.
+struct MapKeySerializer;
+
+fn key_must_be_a_string() -> Error {
+    EncodingError::InvalidKeyType("int, bool, string, char, float or unit variant".to_string())
+        .into()
+}
+
+impl serde::Serializer for MapKeySerializer {
+    type Ok = String;
+    type Error = Error;
+
+    type SerializeSeq = serde::ser::Impossible<String, Error>;
+    type SerializeTuple = serde::ser::Impossible<String, Error>;
+    type SerializeTupleStruct = serde::ser::Impossible<String, Error>;
+    type SerializeTupleVariant = serde::ser::Impossible<String, Error>;
+    type SerializeMap = serde::ser::Impossible<String, Error>;
+    type SerializeStruct = serde::ser::Impossible<String, Error>;
+    type SerializeStructVariant = serde::ser::Impossible<String, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_char(self, v: char) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_none(self) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit(self) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(key_must_be_a_string())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use serde::Serialize;
@@ -366,49 +588,93 @@ mod test {
 
     #[test]
     fn test_serde() {
-        use std::collections::BTreeMap;
         use std::iter::FromIterator;
 
-        use crate::Value;
+        use crate::{Map, Value};
 
         let x = Value::Int(42);
         let y: i32 = 42;
-        let y = y.serialize(Serializer).unwrap();
+        let y = y.serialize(Serializer::new()).unwrap();
         assert_eq!(y, x);
 
         let x = Value::Array(vec![Value::String("hello world".to_string())]);
         let y: Vec<String> = vec!["hello world".to_string()];
-        let y = y.serialize(Serializer).unwrap();
+        let y = y.serialize(Serializer::new()).unwrap();
         assert_eq!(y, x);
 
         let x = Value::Array(vec![Value::String("hello world".to_string())]);
         let y: Vec<String> = vec!["hello world".to_string()];
-        let y = y.serialize(Serializer).unwrap();
+        let y = y.serialize(Serializer::new()).unwrap();
         assert_eq!(y, x);
 
-        let x = Value::Struct(BTreeMap::from_iter(
-            vec![("hello".to_string(), Value::String("world".to_string()))].into_iter(),
-        ));
+        let x = Value::Struct(Map::from_iter(vec![(
+            "hello".to_string(),
+            Value::String("world".to_string()),
+        )]));
         let y = Test {
             hello: "world".to_string(),
         };
-        let y = y.serialize(Serializer).unwrap();
+        let y = y.serialize(Serializer::new()).unwrap();
         assert_eq!(y, x,);
 
-        let x = Value::Struct(BTreeMap::from_iter(
-            vec![("val".to_string(), Value::Nil)].into_iter(),
-        ));
+        let x = Value::Struct(Map::from_iter(vec![("val".to_string(), Value::Nil)]));
         let y = Test2 { val: None };
-        let y = y.serialize(Serializer).unwrap();
+        let y = y.serialize(Serializer::new()).unwrap();
         assert_eq!(y, x);
 
-        let x = Value::Struct(BTreeMap::from_iter(
-            vec![("val".to_string(), Value::String("hello".to_string()))].into_iter(),
-        ));
+        let x = Value::Struct(Map::from_iter(vec![(
+            "val".to_string(),
+            Value::String("hello".to_string()),
+        )]));
         let y = Test2 {
             val: Some("hello".to_string()),
         };
-        let y = y.serialize(Serializer).unwrap();
+        let y = y.serialize(Serializer::new()).unwrap();
         assert_eq!(y, x,);
     }
+
+    #[test]
+    fn test_map_keys() {
+        use std::collections::HashMap;
+
+        use crate::Value;
+
+        #[derive(Serialize, PartialEq, Eq, Hash)]
+        struct UserId(u64);
+
+        let mut map = HashMap::new();
+        map.insert(UserId(u64::MAX), true);
+        let y = map.serialize(Serializer::new()).unwrap();
+        assert_eq!(
+            y.as_struct().unwrap().get("18446744073709551615"),
+            Some(&Value::Bool(true))
+        );
+
+        let mut map = HashMap::new();
+        map.insert(vec![1], true);
+        assert!(map.serialize(Serializer::new()).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_members() {
+        use crate::error::{EncodingError, Error};
+        use crate::WriteOptions;
+
+        struct Dupes;
+
+        impl Serialize for Dupes {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_map(vec![("a", 1), ("a", 2)])
+            }
+        }
+
+        let y = Dupes.serialize(Serializer::new()).unwrap();
+        assert_eq!(y.as_struct().unwrap()["a"].as_i32(), Some(2));
+
+        let err = WriteOptions::strict().to_value(Dupes).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::EncodingError(EncodingError::DuplicateMember(ref key)) if key == "a"
+        ));
+    }
 }
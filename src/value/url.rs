@@ -0,0 +1,94 @@
+//! Conversions between [`Value`] and [`url::Url`].
+//!
+//! A URL is written as a `<string>` of its serialization, which is also what the `serde` support
+//! this feature enables writes. Reading it back parses the string, so relative URLs are an error.
+
+use std::convert::TryFrom;
+
+use ::url::Url;
+
+use super::convert::invalid_type;
+use crate::error::DecodingError;
+use crate::{Error, Value, ValueKind, XmlRpcType};
+
+impl From<Url> for Value {
+    fn from(other: Url) -> Self {
+        Value::String(other.into())
+    }
+}
+
+impl From<&Url> for Value {
+    fn from(other: &Url) -> Self {
+        Value::String(other.as_str().to_string())
+    }
+}
+
+impl<'a> TryFrom<&'a Value> for Url {
+    type Error = Error;
+
+    fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+        let text = value
+            .as_str()
+            .ok_or_else(|| invalid_type(value, ValueKind::STRING))?;
+        Url::parse(text).map_err(|err| {
+            DecodingError::InvalidValue {
+                found: format!("string {:?}", text),
+                expected: format!("a URL ({})", err),
+            }
+            .into()
+        })
+    }
+}
+
+impl TryFrom<Value> for Url {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Url::try_from(&value)
+    }
+}
+
+impl XmlRpcType for Url {
+    fn to_value(&self) -> Value {
+        Value::from(self)
+    }
+
+    fn from_value(value: &Value) -> crate::Result<Self> {
+        Url::try_from(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[test]
+    fn url_conversions() {
+        let url = Url::parse("https://example.com/RPC2?a=1&b=2").unwrap();
+        let value = Value::from(&url);
+        assert_eq!(value.as_str(), Some("https://example.com/RPC2?a=1&b=2"));
+        assert_eq!(Url::try_from(value).unwrap(), url);
+
+        assert!(Url::from_value(&Value::from("/RPC2")).is_err());
+        assert!(Url::try_from(Value::Int(1)).is_err());
+    }
+
+    #[test]
+    fn url_fields() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Link {
+            href: Url,
+        }
+
+        let link = Link {
+            href: Url::parse("http://example.com/a b").unwrap(),
+        };
+        let body = crate::value_to_string(crate::to_value(&link).unwrap()).unwrap();
+        assert!(body.contains("<string>http://example.com/a%20b</string>"));
+
+        let value = crate::value_from_str(&body).unwrap();
+        assert_eq!(crate::from_value::<Link>(value).unwrap(), link);
+    }
+}
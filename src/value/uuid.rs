@@ -0,0 +1,91 @@
+//! Conversions between [`Value`] and [`uuid::Uuid`].
+//!
+//! A UUID is written as a `<string>` in its hyphenated form, which is also what the `serde`
+//! support this feature enables writes. Any form `Uuid` parses is read back, such as with or
+//! without hyphens.
+
+use std::convert::TryFrom;
+
+use ::uuid::Uuid;
+
+use super::convert::invalid_type;
+use crate::error::DecodingError;
+use crate::{Error, Value, ValueKind, XmlRpcType};
+
+impl From<Uuid> for Value {
+    fn from(other: Uuid) -> Self {
+        Value::String(other.hyphenated().to_string())
+    }
+}
+
+impl<'a> TryFrom<&'a Value> for Uuid {
+    type Error = Error;
+
+    fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+        let text = value
+            .as_str()
+            .ok_or_else(|| invalid_type(value, ValueKind::STRING))?;
+        Uuid::parse_str(text).map_err(|_| {
+            DecodingError::InvalidValue {
+                found: format!("string {:?}", text),
+                expected: "a UUID".to_string(),
+            }
+            .into()
+        })
+    }
+}
+
+impl TryFrom<Value> for Uuid {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Uuid::try_from(&value)
+    }
+}
+
+impl XmlRpcType for Uuid {
+    fn to_value(&self) -> Value {
+        Value::from(*self)
+    }
+
+    fn from_value(value: &Value) -> crate::Result<Self> {
+        Uuid::try_from(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[test]
+    fn uuid_conversions() {
+        let id = Uuid::from_u128(0x67e5_5044_10b1_426f_9247_bb68_0e5f_e0c8);
+        let value = Value::from(id);
+        assert_eq!(value.as_str(), Some("67e55044-10b1-426f-9247-bb680e5fe0c8"));
+        assert_eq!(Uuid::try_from(value).unwrap(), id);
+
+        let simple = Value::from("67e5504410b1426f9247bb680e5fe0c8");
+        assert_eq!(Uuid::from_value(&simple).unwrap(), id);
+        assert!(Uuid::try_from(Value::from("67e55044")).is_err());
+        assert!(Uuid::try_from(Value::Int(1)).is_err());
+    }
+
+    #[test]
+    fn uuid_fields() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Session {
+            id: Uuid,
+        }
+
+        let session = Session {
+            id: Uuid::from_u128(1),
+        };
+        let body = crate::value_to_string(crate::to_value(&session).unwrap()).unwrap();
+        assert!(body.contains("<string>00000000-0000-0000-0000-000000000001</string>"));
+
+        let value = crate::value_from_str(&body).unwrap();
+        assert_eq!(crate::from_value::<Session>(value).unwrap(), session);
+    }
+}
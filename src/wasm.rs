@@ -0,0 +1,88 @@
+//! Functions for calling from JavaScript through [`wasm-bindgen`](https://docs.rs/wasm-bindgen).
+//!
+//! Params and results are plain JavaScript values, converted the same way as with
+//! `serde_json::Value`: see the `json` feature for the details. A front-end builds the request
+//! body, sends it with `fetch` and parses the response:
+//!
+//! ```js
+//! import { requestToString, responseFromString } from "serde_xmlrpc";
+//!
+//! const body = requestToString("add", [1, 2]);
+//! const response = await fetch("/RPC2", { method: "POST", body });
+//! try {
+//!     console.log(responseFromString(await response.text()));
+//! } catch (err) {
+//!     // Either a fault with `faultCode` and `faultString`, or an `Error` if the response
+//!     // couldn't be parsed.
+//! }
+//! ```
+
+use std::convert::TryFrom;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{Document, Error, Result, Value};
+
+/// Builds the body of a call to `method`. `params` must be an array.
+#[wasm_bindgen(js_name = requestToString)]
+pub fn request_to_string(method: &str, params: JsValue) -> std::result::Result<String, JsError> {
+    let params: serde_json::Value = serde_wasm_bindgen::from_value(params)?;
+    Ok(json_request_to_string(method, params)?)
+}
+
+/// Parses a response body. Returns the result if the response has a single param, as it should,
+/// and an array of them otherwise. A fault is thrown as an object with `faultCode` and
+/// `faultString`.
+#[wasm_bindgen(js_name = responseFromString)]
+pub fn response_from_str(body: &str) -> std::result::Result<JsValue, JsValue> {
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    match json_response_from_str(body) {
+        Ok(result) => Ok(result.serialize(&serializer)?),
+        Err(Error::Fault(fault)) => Err(fault.serialize(&serializer)?),
+        Err(err) => Err(JsError::from(err).into()),
+    }
+}
+
+fn json_request_to_string(method: &str, params: serde_json::Value) -> Result<String> {
+    let params = Vec::<Value>::try_from(Value::try_from(params)?)?;
+    crate::request_to_string(method, params)
+}
+
+fn json_response_from_str(body: &str) -> Result<serde_json::Value> {
+    let mut params = body.parse::<Document>()?.into_result()?.into_vec();
+    if params.len() == 1 {
+        serde_json::Value::try_from(params.remove(0))
+    } else {
+        serde_json::Value::try_from(Value::Array(params))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn json_roundtrip() {
+        let body = json_request_to_string("add", json!([1, 2])).unwrap();
+        assert!(body.contains("<methodName>add</methodName>"));
+        assert!(json_request_to_string("add", json!({ "a": 1 })).is_err());
+
+        let body =
+            crate::response_to_string(vec![json!({ "sum": 3 }).try_into().unwrap()].into_iter())
+                .unwrap();
+        assert_eq!(json_response_from_str(&body).unwrap(), json!({ "sum": 3 }));
+
+        let fault = Document::Fault(crate::Fault::new(4, "Too many parameters."))
+            .to_xml()
+            .unwrap();
+        assert!(matches!(
+            json_response_from_str(&fault),
+            Err(Error::Fault(crate::Fault { fault_code: 4, .. }))
+        ));
+    }
+}